@@ -0,0 +1,59 @@
+//! Benchmarks for parsing and section content extraction on a large document.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use treemd::{Document, parser, query};
+
+/// Build a ~2MB document with 200 chapters, each with nested sections
+/// containing paragraphs, lists, code blocks and tables.
+fn large_document() -> String {
+    let mut md = String::new();
+    for i in 1..=200 {
+        md.push_str(&format!(
+            "# Chapter {i}\n\nIntro with **bold**, `code` and a [link](https://example.com/{i}).\n\n"
+        ));
+        for j in 1..=5 {
+            md.push_str(&format!("## Section {i}.{j}\n\n"));
+            md.push_str(&"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(8));
+            md.push_str("\n\n- item one\n- [ ] task\n\n```rust\nfn main() {}\n```\n\n");
+            for k in 1..=3 {
+                md.push_str(&format!(
+                    "### Topic {i}.{j}.{k}\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n"
+                ));
+                md.push_str(&"Sed do eiusmod tempor incididunt ut labore. ".repeat(10));
+                md.push_str("\n\n");
+            }
+        }
+    }
+    md
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let md = large_document();
+    let doc = Document::parse(&md);
+
+    c.bench_function("parse_markdown", |b| {
+        b.iter(|| Document::parse(black_box(&md)))
+    });
+
+    c.bench_function("build_json_output", |b| {
+        b.iter(|| parser::build_json_output(black_box(&doc), None))
+    });
+
+    // A fresh document each time, so the section is parsed rather than cached
+    c.bench_function("section_content_single", |b| {
+        b.iter_batched(
+            || Document::parse(&md),
+            |doc| doc.section_content(black_box(1500)).map(|c| c.blocks.len()),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("query_headings", |b| {
+        b.iter(|| query::execute(black_box(&doc), ".h2 | .text").ok())
+    });
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
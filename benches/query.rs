@@ -0,0 +1,49 @@
+//! Benchmarks for running one query over many small documents.
+//!
+//! Compares parsing the query for every document against compiling it once.
+//! Run with `cargo bench --bench query`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use treemd::{Document, query};
+
+const QUERY: &str = ".h2 | select(.level == 2) | text | upper";
+
+/// 1000 small documents, each with a title, a few sections and a code block
+fn documents() -> Vec<Document> {
+    (1..=1000)
+        .map(|i| {
+            let mut md = format!("# Doc {i}\n\nIntro to document {i}.\n\n");
+            for j in 1..=4 {
+                md.push_str(&format!(
+                    "## Section {j}\n\nSome [link](https://example.com/{i}/{j}) text.\n\n"
+                ));
+            }
+            md.push_str("```rust\nfn main() {}\n```\n");
+            Document::parse(&md)
+        })
+        .collect()
+}
+
+fn bench_query(c: &mut Criterion) {
+    let docs = documents();
+
+    c.bench_function("query_1000_docs_parse_each", |b| {
+        b.iter(|| {
+            docs.iter()
+                .map(|doc| query::execute(doc, black_box(QUERY)).map(|r| r.len()))
+                .sum::<Result<usize, _>>()
+        })
+    });
+
+    let compiled = query::compile(QUERY).unwrap();
+    c.bench_function("query_1000_docs_compiled", |b| {
+        b.iter(|| {
+            docs.iter()
+                .map(|doc| query::execute_compiled(doc, black_box(&compiled)).map(|r| r.len()))
+                .sum::<Result<usize, _>>()
+        })
+    });
+}
+
+criterion_group!(benches, bench_query);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+//! Benchmarks for `selector | select(...)` on a large document.
+//!
+//! A `select` right after an element selector is fused with it: elements are
+//! tested as they are produced instead of being collected first. Wrapping the
+//! `select` in parentheses turns that off, which gives the baseline. Before
+//! timing, the allocations each form makes are printed.
+//! Run with `cargo bench --bench select`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use treemd::{Document, query};
+
+/// Counts allocations and the bytes they ask for
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Name, fused query and the same query unfused
+const QUERIES: [(&str, &str, &str); 2] = [
+    (
+        "headings",
+        ".h | select(.level == 3 and contains(\"9\"))",
+        ".h | (select(.level == 3 and contains(\"9\")))",
+    ),
+    (
+        "descendant_code",
+        ".h2 >> .code | select(.lang == \"rust\")",
+        ".h2 >> .code | (select(.lang == \"rust\"))",
+    ),
+];
+
+/// 200 sections with subsections, prose and code blocks
+fn document() -> Document {
+    let mut md = String::from("# Reference\n\n");
+    for i in 1..=200 {
+        md.push_str(&format!("## Section {i}\n\nIntro to section {i}.\n\n"));
+        for j in 1..=3 {
+            md.push_str(&format!(
+                "### Part {i}.{j}\n\nSome text about part {j}.\n\n"
+            ));
+        }
+        let lang = if i % 2 == 0 { "rust" } else { "sh" };
+        md.push_str(&format!("```{lang}\nexample {i}\n```\n\n"));
+    }
+    Document::parse(&md)
+}
+
+/// Allocations and bytes requested while running `query` once
+fn allocations(doc: &Document, query: &query::Query) -> (usize, usize) {
+    let (calls, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    query::execute_compiled(doc, query).unwrap();
+    (
+        ALLOCATIONS.load(Ordering::Relaxed) - calls,
+        BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+fn bench_select(c: &mut Criterion) {
+    let doc = document();
+
+    for (name, fused, unfused) in QUERIES {
+        let fused = query::compile(fused).unwrap();
+        let unfused = query::compile(unfused).unwrap();
+        assert_eq!(
+            query::execute_compiled(&doc, &fused).unwrap(),
+            query::execute_compiled(&doc, &unfused).unwrap()
+        );
+
+        let (calls, bytes) = allocations(&doc, &fused);
+        let (base_calls, base_bytes) = allocations(&doc, &unfused);
+        eprintln!(
+            "{name}: fused {calls} allocations ({bytes} bytes), \
+             unfused {base_calls} allocations ({base_bytes} bytes)"
+        );
+
+        c.bench_function(&format!("select_{name}_fused"), |b| {
+            b.iter(|| query::execute_compiled(&doc, black_box(&fused)).map(|r| r.len()))
+        });
+        c.bench_function(&format!("select_{name}_unfused"), |b| {
+            b.iter(|| query::execute_compiled(&doc, black_box(&unfused)).map(|r| r.len()))
+        });
+    }
+}
+
+criterion_group!(benches, bench_select);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "unstable-dynamic")]
 use clap_complete::engine::{ArgValueCompleter, CompletionCandidate, ValueCompleter};
@@ -20,18 +20,32 @@ use clap_complete::engine::{ArgValueCompleter, CompletionCandidate, ValueComplet
     treemd -s Installation doc.md # Extract section\n  \
     treemd --setup-completions    # Set up shell completions"
 )]
+#[command(group(
+    clap::ArgGroup::new("search")
+        .args(["grep", "grep_regex", "locate"])
+        .multiple(true)
+))]
 pub struct Cli {
     /// Markdown file to view (.md or .markdown), or '-' for stdin
     ///
     /// Path to the markdown file to open. Use '-' to read from stdin.
     /// If no file is specified and stdin is piped, input is read from stdin.
+    /// Several files can be given with --list or --count; each result is
+    /// prefixed with its file's path.
     ///
     /// Examples:
     ///   treemd README.md         # Open file
     ///   treemd -                 # Read from stdin
     ///   cat doc.md | treemd -l   # Pipe markdown
-    #[arg(add = markdown_file_completer())]
-    pub file: Option<PathBuf>,
+    ///   treemd -l docs/*.md      # List headings of several files
+    #[arg(value_name = "FILE", add = markdown_file_completer())]
+    pub files: Vec<PathBuf>,
+
+    /// Parse multiple files in parallel
+    ///
+    /// Results are still printed in the order the files were given.
+    #[arg(long = "parallel")]
+    pub parallel: bool,
 
     #[command(subcommand)]
     pub command: Option<Command>,
@@ -59,6 +73,14 @@ pub struct Cli {
     #[arg(long = "filter", value_name = "PATTERN")]
     pub filter: Option<String>,
 
+    /// Like --filter, but PATTERN is a regular expression
+    ///
+    /// Matching is case-sensitive; start the pattern with (?i) to ignore case.
+    ///
+    /// Example: --filter-regex '^v[0-9]+' CHANGELOG.md
+    #[arg(long = "filter-regex", value_name = "REGEX", conflicts_with = "filter")]
+    pub filter_regex: Option<String>,
+
     /// Show only headings at specific level (1-6)
     ///
     /// Filters headings by their level:
@@ -68,14 +90,111 @@ pub struct Cli {
     #[arg(short = 'L', long = "level", value_name = "LEVEL")]
     pub level: Option<usize>,
 
+    /// Sort --list output by heading text, level or text length
+    ///
+    /// Headings are listed in document order by default. Ties keep document
+    /// order. Applies to plain and --raw0 output.
+    ///
+    /// Example: -l --sort text glossary.md
+    #[arg(long = "sort", value_name = "KEY", requires = "list")]
+    pub sort: Option<HeadingSort>,
+
+    /// Reverse the order of --list output (after --sort, if given)
+    #[arg(long = "reverse", requires = "list")]
+    pub reverse: bool,
+
     /// Output format for --list and --tree modes
     ///
     /// Controls how headings are displayed:
-    ///   plain - Human-readable text (default)
-    ///   json  - JSON array for scripting/parsing
-    ///   tree  - Box-drawing tree structure
-    #[arg(short = 'o', long = "output", default_value = "plain")]
-    pub output: OutputFormat,
+    ///   plain    - Human-readable text (default)
+    ///   json     - JSON array for scripting/parsing
+    ///   tree     - Box-drawing tree structure
+    ///   markdown - Markdown re-serialized from the parsed document; with
+    ///              --section, the section normalized, with --list, just
+    ///              the heading lines
+    ///
+    /// Defaults to `output_format` in the config file, or plain.
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<OutputFormat>,
+
+    /// Tree drawing style for --tree (box, ascii)
+    ///
+    /// Defaults to `tree_style` in the config file, or box.
+    #[arg(long = "tree-style", value_name = "STYLE")]
+    pub tree_style: Option<TreeStyle>,
+
+    /// Number of spaces a tab expands to in the TUI
+    ///
+    /// Defaults to `tab_width` in the config file, or 4.
+    #[arg(long = "tab-width", value_name = "N")]
+    pub tab_width: Option<usize>,
+
+    /// How the focused H1/H2 title is drawn in the TUI content pane
+    ///
+    /// Defaults to `heading_style` in the config file, or plain.
+    #[arg(long = "heading-style", value_name = "STYLE")]
+    pub heading_style: Option<HeadingStyle>,
+
+    /// How headings are laid out in the TUI outline pane
+    ///
+    /// Press `O` in the TUI to cycle through the styles.
+    /// Defaults to `outline_style` in the config file, or indent.
+    #[arg(long = "outline-style", value_name = "STYLE")]
+    pub outline_style: Option<OutlineStyle>,
+
+    /// Blank columns on each side of the text in the TUI content pane
+    ///
+    /// Defaults to `padding` in the config file, or 0.
+    #[arg(long, value_name = "N")]
+    pub padding: Option<u16>,
+
+    /// Cap the width of content lines in the TUI, centering the column
+    ///
+    /// Defaults to `max_width` in the config file, or the full pane width.
+    #[arg(long = "max-width", value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub max_width: Option<u16>,
+
+    /// Start the TUI with every heading collapsed
+    #[arg(long = "collapse-all", conflicts_with_all = ["expand_all", "expand_level"])]
+    pub collapse_all: bool,
+
+    /// Start the TUI with every heading expanded (default)
+    #[arg(long = "expand-all", conflicts_with = "expand_level")]
+    pub expand_all: bool,
+
+    /// Start the TUI with headings expanded N levels deep
+    ///
+    /// 0 is the same as --collapse-all.
+    #[arg(long = "expand-level", value_name = "N")]
+    pub expand_level: Option<usize>,
+
+    /// Print long output directly instead of through a pager
+    ///
+    /// By default, --list and --tree output taller than the terminal is shown
+    /// in $PAGER (or `less -R`). Output is never paged when piped.
+    #[arg(long = "no-pager")]
+    pub no_pager: bool,
+
+    /// Enable mouse support in the TUI (default)
+    ///
+    /// Click an outline entry to jump to it, and scroll with the wheel.
+    #[arg(long = "mouse", overrides_with = "no_mouse")]
+    pub mouse: bool,
+
+    /// Disable mouse support in the TUI
+    ///
+    /// Mouse capture stops the terminal from selecting text with the mouse;
+    /// use this to keep native text selection. Can also be set with
+    /// `mouse = false` in the config file.
+    #[arg(long = "no-mouse", overrides_with = "mouse")]
+    pub no_mouse: bool,
+
+    /// Disable colors in the TUI
+    ///
+    /// Also enabled by the NO_COLOR environment variable or `no_color = true`
+    /// in the config file.
+    #[arg(long = "no-color")]
+    pub no_color: bool,
 
     /// Extract specific section by heading name
     ///
@@ -86,12 +205,270 @@ pub struct Cli {
     #[arg(short = 's', long = "section", value_name = "HEADING")]
     pub section: Option<String>,
 
+    /// Extract the first section whose content matches PATTERN
+    ///
+    /// Like --section, but finds the section by searching the text under each
+    /// heading instead of the heading name. PATTERN is a case-insensitive
+    /// regular expression; if it isn't a valid regex it is matched as a
+    /// plain substring.
+    ///
+    /// Example: --section-matching "--no-color" prints the section documenting that flag
+    #[arg(
+        long = "section-matching",
+        value_name = "PATTERN",
+        conflicts_with = "section"
+    )]
+    pub section_matching: Option<String>,
+
+    /// Search section content and show headings whose body matches
+    ///
+    /// Unlike --filter, which only matches heading text, this searches the text
+    /// under each heading (up to the next heading) and prints the heading
+    /// followed by the matching lines with their line numbers.
+    /// Matching is a case-insensitive substring search.
+    ///
+    /// Example: -g deprecated README.md
+    #[arg(short = 'g', long = "grep", value_name = "PATTERN")]
+    pub grep: Option<String>,
+
+    /// Like --grep, but PATTERN is a regular expression
+    ///
+    /// Example: --grep-regex 'v[0-9]+\.[0-9]+' CHANGELOG.md
+    #[arg(long = "grep-regex", value_name = "REGEX", conflicts_with = "grep")]
+    pub grep_regex: Option<String>,
+
+    /// With --grep, --grep-regex or --locate, also print N lines around each match
+    ///
+    /// Like grep -C: matching lines are marked with `:`, context lines with
+    /// `-`, and separate runs of lines are split by `--`. --grep keeps the
+    /// context inside the matching section.
+    ///
+    /// Example: -g deprecated -C 2 README.md
+    #[arg(short = 'C', long = "context", value_name = "N", requires = "search")]
+    pub context: Option<usize>,
+
+    /// List all code blocks with their language and line range
+    ///
+    /// Shorthand for the query `.code`; use --output json for details.
+    #[arg(long = "code", conflicts_with_all = ["links", "images"])]
+    pub code: bool,
+
+    /// List all links with their URL and type (anchor, relative, wikilink, external)
+    ///
+    /// Shorthand for the query `.link`.
+    #[arg(long = "links", conflicts_with = "images")]
+    pub links: bool,
+
+    /// List only external links (http, https, mailto, ...)
+    ///
+    /// Implies --links. Shorthand for the query `.link[external]`.
+    #[arg(long = "links-external-only", conflicts_with_all = ["code", "images"])]
+    pub links_external_only: bool,
+
+    /// List all images with their alt text and source
+    ///
+    /// Shorthand for the query `.img`.
+    #[arg(long = "images")]
+    pub images: bool,
+
+    /// Check links for broken targets and exit nonzero if any are found
+    ///
+    /// Anchor links must match a heading in the document, and relative links
+    /// and wikilinks must point to existing files (resolved next to FILE).
+    /// External links are listed but not requested unless --check-external
+    /// is given.
+    ///
+    /// Example: --check-links docs/README.md
+    #[arg(long = "check-links")]
+    pub check_links: bool,
+
+    /// With --check-links, also send HTTP HEAD requests to external links
+    ///
+    /// Needs treemd built with the `check-external` feature, which is off by
+    /// default.
+    #[arg(long = "check-external", requires = "check_links")]
+    pub check_external: bool,
+
+    /// Report headings that share a slug and exit nonzero if any do
+    ///
+    /// Only the first of them can be linked to by its plain anchor. Each
+    /// collision is listed with the line numbers of its headings.
+    ///
+    /// Example: --check-duplicates docs/README.md
+    #[arg(long = "check-duplicates", conflicts_with = "check_links")]
+    pub check_duplicates: bool,
+
+    /// Compare heading structure against an older version of the file
+    ///
+    /// Shows headings that were added, removed, renamed, moved to another
+    /// parent, or changed level between OLD and FILE. Headings are matched by
+    /// slug, with a fuzzy fallback to detect renames.
+    ///
+    /// Example: --diff old.md new.md
+    #[arg(long = "diff", value_name = "OLD")]
+    pub diff: Option<PathBuf>,
+
+    /// Count code toward word counts in JSON output
+    ///
+    /// By default, code blocks are left out of `wordCount` so reading-time
+    /// estimates stay realistic for code-heavy docs.
+    #[arg(long = "include-code", conflicts_with = "exclude_code")]
+    pub include_code: bool,
+
+    /// Leave inline code spans out of word counts too (not just code blocks)
+    #[arg(long = "exclude-code")]
+    pub exclude_code: bool,
+
+    /// Leave section content out of JSON output, keeping only the outline
+    ///
+    /// Skips parsing section bodies, which is much faster on large files.
+    /// Word counts then include code, since code blocks aren't parsed.
+    ///
+    /// Example: --list --output json --no-content big.md
+    #[arg(long = "no-content")]
+    pub no_content: bool,
+
+    /// Read treemd's own JSON output (`--list --output json`) instead of markdown
+    ///
+    /// The document is rebuilt from the JSON sections, so a pipeline can
+    /// transform the JSON and view or re-render the result. With no other
+    /// mode it opens in the TUI, or prints the rebuilt markdown when stdout
+    /// is not a terminal.
+    ///
+    /// Example: treemd -l -o json doc.md | jq ... | treemd --from-json --tree
+    #[arg(long = "from-json")]
+    pub from_json: bool,
+
+    /// Query each line of input as a string instead of parsing markdown
+    ///
+    /// Like jq's -R: the query runs once per line with the line as `.`, so
+    /// the string builtins work on any line-oriented text. With --slurp the
+    /// query runs once on an array of all the lines.
+    ///
+    /// Example: printf 'a\nb\n' | treemd -R -q 'upper'
+    #[arg(
+        short = 'R',
+        long = "raw-input",
+        requires = "query",
+        conflicts_with = "from_json"
+    )]
+    pub raw_input: bool,
+
+    /// With --raw-input, collect every line into one array
+    ///
+    /// Example: treemd -R --slurp -q 'count' notes.txt
+    #[arg(long = "slurp", requires = "raw_input")]
+    pub slurp: bool,
+
+    /// Show the start of each section's text under its heading in --list
+    ///
+    /// Prints up to N characters (default 80) of the section body as plain
+    /// text, with markdown stripped. Applies to plain output.
+    ///
+    /// Example: treemd -l --with-content=120 README.md
+    #[arg(
+        long = "with-content",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "80",
+        requires = "list"
+    )]
+    pub with_content: Option<usize>,
+
+    /// Print the anchor slug of each --list heading instead of its text
+    ///
+    /// Slugs are unique in the document: repeats are numbered the way GitHub
+    /// does (`usage`, `usage-1`). With --slugs=anchor, each is printed as a
+    /// `#slug` link target.
+    ///
+    /// Example: treemd -l -L 2 --slugs=anchor README.md
+    #[arg(
+        long = "slugs",
+        value_name = "FORM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "plain",
+        requires = "list",
+        conflicts_with_all = ["number", "raw0", "with_content"]
+    )]
+    pub slugs: Option<SlugForm>,
+
+    /// Print the document with every heading level shifted by N
+    ///
+    /// Positive N demotes headings (H1 becomes H2), negative N promotes them.
+    /// Levels are clamped to 1-6, with a note on stderr when that happens.
+    /// Only heading markers change; the rest is printed as written.
+    ///
+    /// Example: treemd --shift-headings 1 chapter.md >> book.md
+    #[arg(
+        long = "shift-headings",
+        value_name = "N",
+        allow_negative_numbers = true,
+        conflicts_with_all = ["query", "locate", "template", "interactive_query", "list", "tree"]
+    )]
+    pub shift_headings: Option<isize>,
+
+    /// Print only the document's front matter
+    ///
+    /// YAML (`---`) or TOML (`+++`) front matter is printed as written, or as
+    /// a JSON object with `-o json`. Documents without front matter print
+    /// nothing, or `{}` as JSON.
+    ///
+    /// Example: treemd --frontmatter -o json post.md
+    #[arg(
+        long = "frontmatter",
+        visible_alias = "frontmatter-only",
+        conflicts_with_all = ["query", "locate", "template", "shift_headings"]
+    )]
+    pub frontmatter: bool,
+
+    /// Prefix headings with hierarchical section numbers (1, 1.1, 1.2.1)
+    ///
+    /// Works with --list and --tree. Numbers follow the heading tree, so a
+    /// heading that skips a level is numbered as the next child of its parent.
+    #[arg(long = "number")]
+    pub number: bool,
+
     /// Count headings by level (shows statistics)
     ///
     /// Displays a summary showing how many headings exist at each level (h1-h6)
-    /// and the total count.
-    #[arg(long = "count")]
-    pub count: bool,
+    /// and the total count. `--count=unique` instead reports how many distinct
+    /// heading texts and slugs there are, to spot duplicates; `--count=total`
+    /// prints just the number of headings.
+    ///
+    /// Example: treemd --count=unique README.md
+    #[arg(
+        long = "count",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "levels"
+    )]
+    pub count: Option<CountMode>,
+
+    /// Show task-list progress for each section that has checkboxes
+    ///
+    /// Prints a progress bar with checked/total items per section (excluding
+    /// subsections), then the total for the document.
+    ///
+    /// Example: --tasks TODO.md
+    #[arg(long = "tasks")]
+    pub tasks: bool,
+
+    /// Exit with status 1 when nothing matches (for scripting/CI)
+    ///
+    /// Applies to --list, --tree and query (-q) modes. Nothing is printed to
+    /// stdout when the selection is empty; a short note goes to stderr.
+    /// Query results consisting only of nulls count as empty.
+    ///
+    /// Exit codes:
+    ///   0 - at least one match
+    ///   1 - no matches, or an error occurred
+    ///
+    /// Example: treemd -l --filter Security --fail-on-empty README.md
+    #[arg(long = "fail-on-empty")]
+    pub fail_on_empty: bool,
 
     /// Set up shell completions interactively
     ///
@@ -121,6 +498,30 @@ pub struct Cli {
     #[arg(long = "color-mode", value_name = "MODE")]
     pub color_mode: Option<ColorModeArg>,
 
+    /// Skip the terminal compatibility notice shown before the TUI starts
+    ///
+    /// The notice appears once on terminals with limited color support
+    /// (Terminal.app before macOS 26). The 256-color fallback still applies
+    /// unless --color-mode says otherwise.
+    #[arg(long = "ignore-term-warning")]
+    pub ignore_term_warning: bool,
+
+    /// Print the JSON Schema of `--list -o json` output
+    ///
+    /// Describes the document, section, block and inline element objects,
+    /// for validating or generating types from treemd's JSON.
+    ///
+    /// Example: treemd --schema > treemd.schema.json
+    #[arg(long = "schema")]
+    pub schema: bool,
+
+    /// Print a short sample document in each built-in theme
+    ///
+    /// Uses --color-mode (or the config file) to pick true color or the
+    /// 256-color palette. Prints nothing with --no-color.
+    #[arg(long = "theme-preview")]
+    pub theme_preview: bool,
+
     /// Query expression for selecting/filtering document elements
     ///
     /// Uses a jq-like syntax for navigating and extracting markdown structure.
@@ -157,10 +558,119 @@ pub struct Cli {
     ///   jsonl  - Line-delimited JSON
     ///   md     - Raw markdown
     ///   tree   - Tree structure
+    ///   raw0   - Plain values separated by NUL bytes (same as --raw0)
     ///
     /// Example: -q '.h2' --query-output json
     #[arg(long = "query-output", value_name = "FORMAT")]
     pub query_output: Option<String>,
+
+    /// Print file:line:column for each element matched by a query
+    ///
+    /// Runs the query and prints one grep-style location per matched element,
+    /// followed by its text. Columns count characters, not bytes. Results
+    /// without a source position (plain strings, numbers, ...) are skipped.
+    /// Suitable for editor quickfix lists and fuzzy finders.
+    ///
+    /// Example: treemd --locate '.h2[Deprecated]' README.md | fzf
+    #[arg(long = "locate", value_name = "EXPR", conflicts_with = "query")]
+    pub locate: Option<String>,
+
+    /// Open the TUI with the query bar running EXPR
+    ///
+    /// Results update as the query is edited; press `:` in the TUI to open
+    /// the query bar at any time.
+    ///
+    /// Example: treemd --interactive-query '.h2 | text' README.md
+    #[arg(
+        long = "interactive-query",
+        value_name = "EXPR",
+        conflicts_with_all = ["query", "locate", "template"]
+    )]
+    pub interactive_query: Option<String>,
+
+    /// Fill a template with query results and print it
+    ///
+    /// Each `{{ query }}` placeholder is replaced by the text of the query's
+    /// first result; other text is printed as-is. Pass `@FILE` to read the
+    /// template from a file. Honors --strict and --allow-env.
+    ///
+    /// Example: --template '{{ .h1 | text }} has {{ [.h2] | count }} sections'
+    #[arg(
+        long = "template",
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["query", "locate"]
+    )]
+    pub template: Option<String>,
+
+    /// Print at most N query results
+    ///
+    /// Extra results are dropped and a "… N more" note is printed to stderr,
+    /// so piping a broad query like '.h' doesn't flood the terminal.
+    ///
+    /// Example: -q '.h' --max-output 20
+    #[arg(long = "max-output", value_name = "N", requires = "query")]
+    pub max_output: Option<usize>,
+
+    /// Print only page N of the query results (1-based)
+    ///
+    /// Results are split into pages of --page-size and a "Page N/M" footer
+    /// follows them (on stderr for json/jsonl output). Applied after --flat
+    /// and before --max-output.
+    ///
+    /// Example: -q '.link | url' --page 2 --page-size 50
+    #[arg(long = "page", value_name = "N", requires = "query",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    pub page: Option<u64>,
+
+    /// Number of results per page for --page [default: 10]
+    #[arg(long = "page-size", value_name = "K", requires = "page",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    pub page_size: Option<u64>,
+
+    /// Flatten array results one level before printing
+    ///
+    /// Each element of an array result becomes its own result, so in json/jsonl
+    /// output every element is a separate value. Applied before --max-output.
+    ///
+    /// Example: -q '[.h2] | limit(3)' --flat --query-output jsonl
+    #[arg(long = "flat", requires = "query")]
+    pub flat: bool,
+
+    /// Separate plain values with NUL bytes instead of newlines
+    ///
+    /// For safe scripting over headings that contain spaces or newlines.
+    /// Works with --query and --list; there is no trailing newline.
+    ///
+    /// Example: -l --raw0 doc.md | xargs -0 -n1 echo
+    #[arg(long = "raw0", conflicts_with = "query_output")]
+    pub raw0: bool,
+
+    /// Error on unknown properties instead of returning null
+    ///
+    /// By default, accessing a property that doesn't exist (e.g. a typo
+    /// like `.levle`) yields null. In strict mode the query fails and
+    /// suggests the closest known property names.
+    ///
+    /// Example: -q '.h2 | .levle' --strict
+    #[arg(long = "strict")]
+    pub strict: bool,
+
+    /// Let queries read environment variables via `env` and `$ENV`, and the
+    /// clock via `now`
+    ///
+    /// Disabled by default so queries from untrusted sources can't read
+    /// secrets from the environment, and query output stays reproducible.
+    ///
+    /// Example: -q 'env("BASE_URL")' --allow-env
+    #[arg(long = "allow-env")]
+    pub allow_env: bool,
+
+    /// How query errors are reported on stderr
+    ///
+    ///   human - Diagnostic with the query and a marker under the error (default)
+    ///   json  - One JSON object with kind, message, span, suggestions, help and note
+    #[arg(long = "error-format", value_name = "FORMAT", default_value = "human")]
+    pub error_format: ErrorFormat,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -197,6 +707,79 @@ pub enum OutputFormat {
     Json,
     /// Tree format with box-drawing
     Tree,
+    /// Markdown re-serialized from the parsed content
+    Markdown,
+}
+
+impl Cli {
+    /// The single input file (the first one given), if any
+    pub fn file(&self) -> Option<&Path> {
+        self.files.first().map(PathBuf::as_path)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HeadingSort {
+    /// Alphabetically, ignoring case
+    Text,
+    /// By heading level, h1 first
+    Level,
+    /// By text length, shortest first
+    Length,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SlugForm {
+    /// The bare slug, e.g. `usage-1`
+    Plain,
+    /// A link target, e.g. `#usage-1`
+    Anchor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CountMode {
+    /// Headings at each level, and the total
+    Levels,
+    /// Distinct heading texts and slugs, and how many are duplicates
+    Unique,
+    /// Only the total number of headings
+    Total,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TreeStyle {
+    /// Unicode box-drawing characters
+    Box,
+    /// Plain ASCII characters
+    Ascii,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Readable diagnostic
+    Human,
+    /// Machine-readable JSON
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HeadingStyle {
+    /// Title only in the pane border
+    Plain,
+    /// Title underlined with a rule
+    Underline,
+    /// Title in a box
+    Boxed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutlineStyle {
+    /// Two spaces of indentation per level
+    Indent,
+    /// Box-drawing connectors
+    Tree,
+    /// No indentation
+    Compact,
 }
 
 #[cfg(feature = "unstable-dynamic")]
@@ -1,4 +1,7 @@
 mod commands;
+pub mod pager;
 pub mod setup;
 
-pub use commands::{Cli, ColorModeArg, OutputFormat};
+pub use commands::{
+    Cli, ColorModeArg, CountMode, ErrorFormat, HeadingSort, OutputFormat, SlugForm, TreeStyle,
+};
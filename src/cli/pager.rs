@@ -0,0 +1,85 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when `$PAGER` is not set (`-R` keeps ANSI colors)
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Decides whether output should go through a pager
+///
+/// Only pages when stdout is a terminal and the output is taller than it.
+/// If the terminal height is unknown, output is printed directly.
+pub fn should_page(is_tty: bool, line_count: usize, terminal_height: Option<usize>) -> bool {
+    is_tty && terminal_height.is_some_and(|height| line_count > height)
+}
+
+/// Gets the pager command line from `$PAGER`, falling back to `less -R`
+///
+/// An empty `$PAGER` disables paging.
+pub fn pager_command() -> Option<String> {
+    match env::var("PAGER") {
+        Ok(pager) if pager.trim().is_empty() => None,
+        Ok(pager) => Some(pager),
+        Err(_) => Some(DEFAULT_PAGER.to_string()),
+    }
+}
+
+/// Prints `text` to stdout, through the pager if it doesn't fit on screen
+///
+/// Falls back to plain printing if the pager can't be started.
+pub fn print_paged(text: &str, use_pager: bool) {
+    let terminal_height = crossterm::terminal::size()
+        .ok()
+        .map(|(_, rows)| rows as usize);
+    let is_tty = io::stdout().is_terminal();
+
+    if use_pager && should_page(is_tty, text.lines().count(), terminal_height) {
+        if let Some(pager) = pager_command() {
+            if spawn_pager(&pager, text).is_ok() {
+                return;
+            }
+        }
+    }
+
+    print!("{}", text);
+}
+
+/// Spawns the pager and writes `text` to its stdin, waiting for it to exit
+fn spawn_pager(pager: &str, text: &str) -> io::Result<()> {
+    let mut parts = pager.split_whitespace();
+    let program = parts.next().unwrap_or("less");
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_page() {
+        // Taller than the terminal
+        assert!(should_page(true, 100, Some(40)));
+        // Fits on screen
+        assert!(!should_page(true, 40, Some(40)));
+        assert!(!should_page(true, 3, Some(40)));
+        // Piped output is never paged
+        assert!(!should_page(false, 100, Some(40)));
+        // Unknown terminal size
+        assert!(!should_page(true, 100, None));
+    }
+}
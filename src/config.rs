@@ -14,6 +14,9 @@ pub struct Config {
 
     #[serde(default)]
     pub theme: CustomThemeConfig,
+
+    #[serde(default)]
+    pub defaults: DefaultsConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +37,105 @@ pub struct TerminalConfig {
     pub warned_terminal_app: bool,
 }
 
+/// Defaults for command-line options
+///
+/// Unset values fall back to built-in defaults; command-line flags override both.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DefaultsConfig {
+    /// Output format for --list and --tree (plain, json, tree)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<String>,
+    /// Tree drawing style for --tree (box, ascii)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tree_style: Option<String>,
+    /// Number of spaces a tab expands to in the TUI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab_width: Option<usize>,
+    /// Render the TUI without colors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_color: Option<bool>,
+    /// Capture the mouse in the TUI (click to select, wheel to scroll)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse: Option<bool>,
+    /// Banner for the focused H1/H2 in the TUI (plain, underline, boxed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading_style: Option<String>,
+    /// Layout of the TUI outline pane (indent, tree, compact)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline_style: Option<String>,
+    /// Blank columns inside the left and right borders of the TUI content pane
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u16>,
+    /// Widest the TUI content column gets; wider panes center it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u16>,
+}
+
+impl DefaultsConfig {
+    /// Overlay `overrides` (e.g. values from command-line flags) on these values
+    pub fn overridden_by(&self, overrides: &DefaultsConfig) -> DefaultsConfig {
+        DefaultsConfig {
+            output_format: overrides
+                .output_format
+                .clone()
+                .or_else(|| self.output_format.clone()),
+            tree_style: overrides
+                .tree_style
+                .clone()
+                .or_else(|| self.tree_style.clone()),
+            tab_width: overrides.tab_width.or(self.tab_width),
+            no_color: overrides.no_color.or(self.no_color),
+            mouse: overrides.mouse.or(self.mouse),
+            heading_style: overrides
+                .heading_style
+                .clone()
+                .or_else(|| self.heading_style.clone()),
+            outline_style: overrides
+                .outline_style
+                .clone()
+                .or_else(|| self.outline_style.clone()),
+            padding: overrides.padding.or(self.padding),
+            max_width: overrides.max_width.or(self.max_width),
+        }
+    }
+
+    pub fn output_format(&self) -> &str {
+        self.output_format.as_deref().unwrap_or("plain")
+    }
+
+    pub fn tree_style(&self) -> &str {
+        self.tree_style.as_deref().unwrap_or("box")
+    }
+
+    pub fn tab_width(&self) -> usize {
+        self.tab_width.unwrap_or(4)
+    }
+
+    pub fn no_color(&self) -> bool {
+        self.no_color.unwrap_or(false)
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse.unwrap_or(true)
+    }
+
+    pub fn heading_style(&self) -> &str {
+        self.heading_style.as_deref().unwrap_or("plain")
+    }
+
+    pub fn outline_style(&self) -> &str {
+        self.outline_style.as_deref().unwrap_or("indent")
+    }
+
+    pub fn padding(&self) -> u16 {
+        self.padding.unwrap_or(0)
+    }
+
+    pub fn max_width(&self) -> Option<u16> {
+        self.max_width
+    }
+}
+
 /// Custom theme color overrides
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CustomThemeConfig {
@@ -79,6 +181,8 @@ pub struct CustomThemeConfig {
     pub blockquote_fg: Option<ColorValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code_fence: Option<ColorValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_highlight: Option<ColorValue>,
 }
 
 /// Color value that can be specified in multiple formats
@@ -159,14 +263,29 @@ impl Config {
     }
 
     /// Load config from file, or return default if file doesn't exist
+    ///
+    /// An unreadable or malformed file is reported on stderr and the built-in
+    /// defaults are used instead.
     pub fn load() -> Self {
-        Self::config_path()
-            .and_then(|path| {
-                fs::read_to_string(&path)
-                    .ok()
-                    .and_then(|contents| toml::from_str(&contents).ok())
-            })
-            .unwrap_or_default()
+        let Some(path) = Self::config_path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!(
+                    "Warning: ignoring malformed config {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                eprintln!("Warning: could not read config {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
     }
 
     /// Save config to file
@@ -228,3 +347,44 @@ impl Config {
         self.save()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_precedence() {
+        let file: Config = toml::from_str(
+            r#"
+            [defaults]
+            output_format = "json"
+            tab_width = 8
+            padding = 2
+            "#,
+        )
+        .unwrap();
+        let cli = DefaultsConfig {
+            output_format: Some("tree".to_string()),
+            ..Default::default()
+        };
+
+        let merged = file.defaults.overridden_by(&cli);
+
+        // CLI beats the file
+        assert_eq!(merged.output_format(), "tree");
+        // File beats the built-in default
+        assert_eq!(merged.tab_width(), 8);
+        assert_eq!(merged.padding(), 2);
+        // Built-in defaults fill the rest
+        assert_eq!(merged.tree_style(), "box");
+        assert!(!merged.no_color());
+        assert_eq!(merged.max_width(), None);
+    }
+
+    #[test]
+    fn test_config_without_defaults_section() {
+        let config: Config = toml::from_str("[ui]\ntheme = \"Nord\"\n").unwrap();
+        assert_eq!(config.defaults, DefaultsConfig::default());
+        assert_eq!(config.defaults.output_format(), "plain");
+    }
+}
@@ -0,0 +1,219 @@
+//! Structural comparison of two documents' heading outlines.
+//!
+//! Headings are aligned by slug first. Headings left over on both sides are
+//! then paired up by fuzzy similarity to detect renames; anything still
+//! unmatched was added or removed.
+
+use crate::parser::Document;
+use crate::parser::content::slugify;
+use crate::query::levenshtein;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Minimum similarity (0.0-1.0) for two unmatched headings to count as a rename.
+const RENAME_THRESHOLD: f64 = 0.6;
+
+/// A single structural difference between two heading outlines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum HeadingChange {
+    /// Heading only present in the new document
+    Added { text: String, level: usize },
+    /// Heading only present in the old document
+    Removed { text: String, level: usize },
+    /// Heading whose text changed
+    Renamed {
+        from: String,
+        to: String,
+        level: usize,
+    },
+    /// Heading whose level changed
+    LevelChanged {
+        text: String,
+        from: usize,
+        to: usize,
+    },
+    /// Heading that now sits under a different parent
+    Moved {
+        text: String,
+        from_parent: Option<String>,
+        to_parent: Option<String>,
+    },
+}
+
+/// Compare the heading structure of `old` against `new`.
+///
+/// Removals are listed first in old-document order, followed by the other
+/// changes in new-document order.
+pub fn diff_headings(old: &Document, new: &Document) -> Vec<HeadingChange> {
+    let old_slugs: Vec<String> = old.headings().iter().map(|h| slugify(&h.text)).collect();
+    let new_slugs: Vec<String> = new.headings().iter().map(|h| slugify(&h.text)).collect();
+
+    // Pass 1: exact slug matches, pairing duplicates in document order
+    let mut by_slug: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (i, slug) in new_slugs.iter().enumerate() {
+        by_slug.entry(slug.as_str()).or_default().push_back(i);
+    }
+
+    let mut old_to_new: Vec<Option<usize>> = old_slugs
+        .iter()
+        .map(|slug| by_slug.get_mut(slug.as_str()).and_then(|q| q.pop_front()))
+        .collect();
+    let mut new_to_old: Vec<Option<usize>> = vec![None; new_slugs.len()];
+    for (o, n) in old_to_new.iter().enumerate() {
+        if let Some(n) = *n {
+            new_to_old[n] = Some(o);
+        }
+    }
+
+    // Pass 2: pair leftovers by similarity to detect renames
+    let mut renamed = vec![false; new_slugs.len()];
+    for o in 0..old_slugs.len() {
+        if old_to_new[o].is_some() {
+            continue;
+        }
+
+        let best = (0..new_slugs.len())
+            .filter(|&n| new_to_old[n].is_none())
+            .map(|n| (n, similarity(&old_slugs[o], &new_slugs[n])))
+            .filter(|&(_, score)| score >= RENAME_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((n, _)) = best {
+            old_to_new[o] = Some(n);
+            new_to_old[n] = Some(o);
+            renamed[n] = true;
+        }
+    }
+
+    let old_parents = old.heading_parents();
+    let new_parents = new.heading_parents();
+    let mut changes = Vec::new();
+
+    for (o, heading) in old.headings().iter().enumerate() {
+        if old_to_new[o].is_none() {
+            changes.push(HeadingChange::Removed {
+                text: heading.text.clone(),
+                level: heading.level,
+            });
+        }
+    }
+
+    for (n, heading) in new.headings().iter().enumerate() {
+        let Some(o) = new_to_old[n] else {
+            changes.push(HeadingChange::Added {
+                text: heading.text.clone(),
+                level: heading.level,
+            });
+            continue;
+        };
+        let old_heading = &old.headings()[o];
+
+        if renamed[n] {
+            changes.push(HeadingChange::Renamed {
+                from: old_heading.text.clone(),
+                to: heading.text.clone(),
+                level: heading.level,
+            });
+        }
+
+        if old_heading.level != heading.level {
+            changes.push(HeadingChange::LevelChanged {
+                text: heading.text.clone(),
+                from: old_heading.level,
+                to: heading.level,
+            });
+        } else if old_parents[o].and_then(|p| old_to_new[p]) != new_parents[n] {
+            // Compare parents through the alignment so a renamed parent isn't a move
+            changes.push(HeadingChange::Moved {
+                text: heading.text.clone(),
+                from_parent: old_parents[o].map(|p| old.headings()[p].text.clone()),
+                to_parent: new_parents[n].map(|p| new.headings()[p].text.clone()),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Normalized Levenshtein similarity between two strings (1.0 = identical).
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> Vec<HeadingChange> {
+        diff_headings(&Document::parse(old), &Document::parse(new))
+    }
+
+    #[test]
+    fn test_added_and_removed_headings() {
+        let changes = diff(
+            "# Guide\n## Install\n## Legacy API\n",
+            "# Guide\n## Install\n## Troubleshooting\n",
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                HeadingChange::Removed {
+                    text: "Legacy API".to_string(),
+                    level: 2
+                },
+                HeadingChange::Added {
+                    text: "Troubleshooting".to_string(),
+                    level: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_level_change_and_rename() {
+        let changes = diff(
+            "# Guide\n## Install\n## Configuration\n",
+            "# Guide\n### Install\n## Configurations\n",
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                HeadingChange::LevelChanged {
+                    text: "Install".to_string(),
+                    from: 2,
+                    to: 3
+                },
+                HeadingChange::Renamed {
+                    from: "Configuration".to_string(),
+                    to: "Configurations".to_string(),
+                    level: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_moved_heading() {
+        let changes = diff(
+            "# Guide\n## Setup\n### Docker\n## Usage\n",
+            "# Guide\n## Setup\n## Usage\n### Docker\n",
+        );
+
+        assert_eq!(
+            changes,
+            vec![HeadingChange::Moved {
+                text: "Docker".to_string(),
+                from_parent: Some("Setup".to_string()),
+                to_parent: Some("Usage".to_string()),
+            }]
+        );
+        assert!(diff("# A\n## B\n", "# A\n## B\n").is_empty());
+    }
+}
@@ -4,7 +4,7 @@
 //! Includes security limits to prevent denial-of-service via large inputs.
 
 use std::io::{self, BufRead, IsTerminal};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Maximum input size (100 MB) - prevents memory exhaustion attacks
 const MAX_INPUT_SIZE: usize = 100 * 1024 * 1024;
@@ -24,6 +24,14 @@ pub enum InputSource {
 pub enum InputError {
     Io(io::Error),
     Utf8Error,
+    /// A file isn't valid UTF-8; holds the byte offset of the first invalid sequence
+    InvalidUtf8(usize),
+    NotFound,
+    /// Symlink whose target doesn't exist
+    BrokenSymlink,
+    /// Path is a directory; holds a markdown file inside it, if there is one
+    IsDirectory(Option<PathBuf>),
+    PermissionDenied,
     EmptyInput,
     NoTty,
     InputTooLarge(usize),
@@ -35,6 +43,24 @@ impl std::fmt::Display for InputError {
         match self {
             InputError::Io(e) => write!(f, "I/O error: {}", e),
             InputError::Utf8Error => write!(f, "Invalid UTF-8 in input"),
+            InputError::InvalidUtf8(offset) => {
+                write!(
+                    f,
+                    "not valid UTF-8 (invalid byte sequence at byte {})",
+                    offset
+                )
+            }
+            InputError::NotFound => write!(f, "no such file"),
+            InputError::BrokenSymlink => write!(f, "symlink points to a file that doesn't exist"),
+            InputError::IsDirectory(Some(file)) => {
+                write!(f, "is a directory; did you mean {}?", file.display())
+            }
+            InputError::IsDirectory(None) => {
+                write!(f, "is a directory; did you mean a file inside it?")
+            }
+            InputError::PermissionDenied => {
+                write!(f, "permission denied (check the file's read permissions)")
+            }
             InputError::EmptyInput => write!(f, "Empty input provided"),
             InputError::NoTty => {
                 write!(f, "No file specified and stdin is not being piped")
@@ -117,6 +143,50 @@ pub fn read_stdin() -> Result<String, InputError> {
     Ok(buffer)
 }
 
+/// Read a markdown file, following symlinks
+///
+/// Unlike `std::fs::read_to_string`, failures say what is wrong with the
+/// path: missing, a dangling symlink, a directory, unreadable, or not UTF-8.
+pub fn read_file(path: &Path) -> Result<String, InputError> {
+    if path.is_dir() {
+        return Err(InputError::IsDirectory(markdown_file_in(path)));
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound if path.symlink_metadata().is_ok() => InputError::BrokenSymlink,
+        io::ErrorKind::NotFound => InputError::NotFound,
+        io::ErrorKind::PermissionDenied => InputError::PermissionDenied,
+        _ => InputError::Io(e),
+    })?;
+
+    String::from_utf8(bytes).map_err(|e| InputError::InvalidUtf8(e.utf8_error().valid_up_to()))
+}
+
+/// A markdown file in `dir` to suggest instead: its README, or else the
+/// first markdown file by name
+fn markdown_file_in(dir: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.extension().is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+                })
+        })
+        .collect();
+    files.sort();
+
+    let readme = files.iter().position(|p| {
+        p.file_stem()
+            .is_some_and(|stem| stem.eq_ignore_ascii_case("readme"))
+    });
+    match readme {
+        Some(i) => Some(files.swap_remove(i)),
+        None => files.into_iter().next(),
+    }
+}
+
 /// Determine input source based on arguments and stdin state
 ///
 /// Priority:
@@ -133,7 +203,7 @@ pub fn determine_input_source(file_path: Option<&Path>) -> Result<InputSource, I
         }
         Some(path) => {
             // File path provided
-            let content = std::fs::read_to_string(path).map_err(InputError::Io)?;
+            let content = read_file(path)?;
             Ok(InputSource::File(content))
         }
         None if is_stdin_piped() => {
@@ -151,15 +221,18 @@ pub fn determine_input_source(file_path: Option<&Path>) -> Result<InputSource, I
 /// Process input and return content ready for markdown parsing
 ///
 /// Supports:
-/// - Raw markdown (passed through)
+/// - Raw markdown, or a document with front matter (passed through)
 /// - Plain text (wrapped in markdown heading)
 pub fn process_input(source: InputSource) -> Result<String, Box<dyn std::error::Error>> {
     let content = match source {
         InputSource::File(c) | InputSource::Stdin(c) => c,
     };
 
-    // Check if content looks like markdown (has headings)
-    if content.trim_start().starts_with('#') || content.contains("\n#") {
+    // Check if content looks like markdown (has headings or front matter)
+    if content.trim_start().starts_with('#')
+        || content.contains("\n#")
+        || crate::parser::frontmatter::split_front_matter(&content).is_some()
+    {
         // Markdown content, pass through
         Ok(content)
     } else {
@@ -192,4 +265,50 @@ mod tests {
         assert!(result.starts_with("# Input\n\n"));
         assert!(result.contains("Just some plain text"));
     }
+
+    #[test]
+    fn test_read_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let missing = read_file(&dir.path().join("missing.md")).unwrap_err();
+        assert!(matches!(missing, InputError::NotFound));
+        assert_eq!(missing.to_string(), "no such file");
+
+        // Empty directory, then one with markdown files in it
+        assert!(matches!(
+            read_file(dir.path()),
+            Err(InputError::IsDirectory(None))
+        ));
+        std::fs::write(dir.path().join("guide.md"), "# Guide\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Readme\n").unwrap();
+        let err = read_file(dir.path()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "is a directory; did you mean {}?",
+                dir.path().join("README.md").display()
+            )
+        );
+
+        let latin1 = dir.path().join("latin1.md");
+        std::fs::write(&latin1, b"# Caf\xe9\n").unwrap();
+        assert!(matches!(
+            read_file(&latin1),
+            Err(InputError::InvalidUtf8(5))
+        ));
+
+        assert_eq!(
+            read_file(&dir.path().join("guide.md")).unwrap(),
+            "# Guide\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_file_broken_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("link.md");
+        std::os::unix::fs::symlink(dir.path().join("gone.md"), &link).unwrap();
+        assert!(matches!(read_file(&link), Err(InputError::BrokenSymlink)));
+    }
 }
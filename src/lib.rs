@@ -32,7 +32,7 @@
 //! "#;
 //!
 //! let doc = parse_markdown(markdown);
-//! println!("Found {} headings", doc.headings.len());
+//! println!("Found {} headings", doc.headings().len());
 //!
 //! // Filter headings by text
 //! let filtered = doc.filter_headings("method");
@@ -52,11 +52,23 @@
 /// Provides configuration management for theme choices, UI settings, and terminal preferences.
 pub mod config;
 
+/// Structural diff module for comparing heading outlines.
+///
+/// Reports headings that were added, removed, renamed, re-leveled or moved
+/// between two versions of a document.
+pub mod diff;
+
 /// Input handling module for stdin and file sources.
 ///
 /// Provides robust input reading, format detection, and tree output parsing.
 pub mod input;
 
+/// Link checking module for finding broken links.
+///
+/// Validates anchors against heading slugs and relative links against the
+/// filesystem, optionally requesting external URLs.
+pub mod linkcheck;
+
 /// Parser module for markdown documents.
 ///
 /// Provides functions to parse markdown files and content into structured documents.
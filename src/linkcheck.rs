@@ -0,0 +1,326 @@
+//! Validation of the links in a document.
+//!
+//! Anchors are checked against the document's heading slugs, and relative
+//! files and wikilinks against the filesystem next to the document. External
+//! links are only fetched on request (see [`check_external`]).
+//! [`duplicate_headings`] finds headings whose anchors collide.
+
+use crate::parser::content::slugify;
+use crate::parser::{Document, Link, LinkTarget, extract_links};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Outcome of checking a single link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum LinkStatus {
+    /// The target exists
+    Ok,
+    /// The target is missing, with a short explanation
+    Broken(String),
+    /// Not checked (external links without `--check-external`)
+    Unchecked,
+}
+
+/// A link together with the result of checking it.
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCheck {
+    /// Display text of the link
+    pub text: String,
+    /// The link target as written
+    pub target: String,
+    /// Link type: `anchor`, `relative`, `wikilink` or `external`
+    pub kind: &'static str,
+    /// 1-based line of the link in the document
+    pub line: usize,
+    #[serde(flatten)]
+    pub status: LinkStatus,
+}
+
+impl LinkCheck {
+    /// Whether the link was found to be broken.
+    pub fn is_broken(&self) -> bool {
+        matches!(self.status, LinkStatus::Broken(_))
+    }
+}
+
+/// Headings whose text gives the same slug.
+///
+/// Only the first of them can be linked to as `#slug`; the others get
+/// numbered anchors, which change whenever a heading is added before them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateSlug {
+    pub slug: String,
+    /// The colliding headings, in document order
+    pub headings: Vec<DuplicateHeading>,
+}
+
+/// One heading of a [`DuplicateSlug`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateHeading {
+    pub text: String,
+    /// 1-based line of the heading
+    pub line: usize,
+    /// Anchor the heading actually gets (`slug`, `slug-1`, ...)
+    pub anchor: String,
+}
+
+/// Check every link in `doc`, resolving files relative to `base_dir`.
+///
+/// External links are reported as [`LinkStatus::Unchecked`]; pass the result
+/// through [`check_external`] to request them.
+pub fn check_links(doc: &Document, base_dir: &Path) -> Vec<LinkCheck> {
+    let anchors = heading_anchors(doc);
+    // Slugs of other markdown files, parsed once per file
+    let mut file_anchors: HashMap<std::path::PathBuf, Option<HashSet<String>>> = HashMap::new();
+
+    extract_links(doc.content())
+        .into_iter()
+        .map(|link| {
+            let status = match &link.target {
+                LinkTarget::Anchor(anchor) => check_anchor(&anchors, anchor),
+                LinkTarget::RelativeFile { path, .. } if has_scheme(path) => LinkStatus::Unchecked,
+                LinkTarget::RelativeFile { path, anchor } => {
+                    let resolved = base_dir.join(path);
+                    if !resolved.exists() {
+                        LinkStatus::Broken("file not found".to_string())
+                    } else if let Some(anchor) = anchor {
+                        let slugs = file_anchors.entry(resolved.clone()).or_insert_with(|| {
+                            Document::parse_file(&resolved)
+                                .ok()
+                                .map(|d| heading_anchors(&d))
+                        });
+                        match slugs {
+                            Some(slugs) => check_anchor(slugs, anchor),
+                            None => LinkStatus::Ok,
+                        }
+                    } else {
+                        LinkStatus::Ok
+                    }
+                }
+                LinkTarget::WikiLink { target, .. } => {
+                    let candidates = [format!("{}.md", target), format!("{}.markdown", target)];
+                    let found = candidates
+                        .iter()
+                        .chain(std::iter::once(target))
+                        .any(|candidate| base_dir.join(candidate).exists());
+                    if found {
+                        LinkStatus::Ok
+                    } else {
+                        LinkStatus::Broken("file not found".to_string())
+                    }
+                }
+                LinkTarget::External(_) => LinkStatus::Unchecked,
+            };
+            to_check(doc, &link, status)
+        })
+        .collect()
+}
+
+/// Check unchecked external links with HTTP `HEAD` requests.
+///
+/// Links that don't answer with a success status (after redirects) within
+/// `timeout` are marked broken. Only `http` and `https` URLs are requested.
+#[cfg(feature = "check-external")]
+pub fn check_external(checks: &mut [LinkCheck], timeout: std::time::Duration) {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    for check in checks.iter_mut() {
+        let is_http = check.target.starts_with("http://") || check.target.starts_with("https://");
+        if check.status != LinkStatus::Unchecked || !is_http {
+            continue;
+        }
+
+        check.status = match agent.head(&check.target).call() {
+            Ok(response) if response.status().is_success() => LinkStatus::Ok,
+            Ok(response) => LinkStatus::Broken(format!("HTTP {}", response.status().as_u16())),
+            Err(e) => LinkStatus::Broken(e.to_string()),
+        };
+    }
+}
+
+fn to_check(doc: &Document, link: &Link, status: LinkStatus) -> LinkCheck {
+    let (target, kind) = match &link.target {
+        LinkTarget::Anchor(anchor) => (format!("#{}", anchor), "anchor"),
+        LinkTarget::RelativeFile { path, anchor } => {
+            let mut target = path.to_string_lossy().to_string();
+            if let Some(anchor) = anchor {
+                target.push('#');
+                target.push_str(anchor);
+            }
+            (target, "relative")
+        }
+        LinkTarget::WikiLink { target, .. } => (target.clone(), "wikilink"),
+        LinkTarget::External(url) => (url.clone(), "external"),
+    };
+
+    LinkCheck {
+        text: link.text.clone(),
+        target,
+        kind,
+        line: doc.line_at(link.offset),
+        status,
+    }
+}
+
+/// Find headings sharing a slug, in order of each slug's first heading.
+pub fn duplicate_headings(doc: &Document) -> Vec<DuplicateSlug> {
+    let mut groups: Vec<DuplicateSlug> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (heading, (slug, anchor)) in doc.headings().iter().zip(numbered_anchors(doc)) {
+        let group = *index.entry(slug.clone()).or_insert_with(|| {
+            groups.push(DuplicateSlug {
+                slug,
+                headings: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[group].headings.push(DuplicateHeading {
+            text: heading.text.clone(),
+            line: doc.line_at(heading.offset),
+            anchor,
+        });
+    }
+
+    groups.retain(|group| group.headings.len() > 1);
+    groups
+}
+
+fn check_anchor(anchors: &HashSet<String>, anchor: &str) -> LinkStatus {
+    // A bare `#` links to the top of the document
+    if anchor.is_empty() || anchors.contains(&anchor.to_lowercase()) {
+        LinkStatus::Ok
+    } else {
+        LinkStatus::Broken("no heading with this anchor".to_string())
+    }
+}
+
+/// Unique anchor slug of each heading, in document order.
+///
+/// Repeated slugs are numbered the way GitHub does (`usage`, `usage-1`),
+/// so each one links to its own heading.
+pub fn heading_slugs(doc: &Document) -> Vec<String> {
+    numbered_anchors(doc)
+        .into_iter()
+        .map(|(_, anchor)| anchor)
+        .collect()
+}
+
+/// Anchors for each heading.
+fn heading_anchors(doc: &Document) -> HashSet<String> {
+    numbered_anchors(doc)
+        .into_iter()
+        .map(|(_, anchor)| anchor)
+        .collect()
+}
+
+/// Slug and anchor of each heading, numbering repeated slugs (`usage`,
+/// `usage-1`, ...) the way GitHub does.
+fn numbered_anchors(doc: &Document) -> Vec<(String, String)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    doc.headings()
+        .iter()
+        .map(|heading| {
+            let slug = slugify(&heading.text);
+            let count = seen.entry(slug.clone()).or_insert(0);
+            *count += 1;
+            let anchor = if *count == 1 {
+                slug.clone()
+            } else {
+                format!("{}-{}", slug, *count - 1)
+            };
+            (slug, anchor)
+        })
+        .collect()
+}
+
+/// Whether a "relative" path is really a URL with another scheme, like `mailto:`.
+fn has_scheme(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(md: &str, base_dir: &Path) -> Vec<(String, LinkStatus)> {
+        check_links(&Document::parse(md), base_dir)
+            .into_iter()
+            .map(|check| (check.target, check.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_dangling_anchor() {
+        let md = "# Guide\n## Usage\n## Usage\n\n[a](#usage) [b](#usage-1) [c](#install)\n";
+        assert_eq!(
+            statuses(md, Path::new(".")),
+            vec![
+                ("#usage".to_string(), LinkStatus::Ok),
+                ("#usage-1".to_string(), LinkStatus::Ok),
+                (
+                    "#install".to_string(),
+                    LinkStatus::Broken("no heading with this anchor".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_headings() {
+        let doc = Document::parse("# Guide\n## Setup\n## Usage\n### Setup!\n");
+        let duplicates = duplicate_headings(&doc);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].slug, "setup");
+        let headings: Vec<_> = duplicates[0]
+            .headings
+            .iter()
+            .map(|h| (h.text.as_str(), h.line, h.anchor.as_str()))
+            .collect();
+        assert_eq!(headings, [("Setup", 2, "setup"), ("Setup!", 4, "setup-1")]);
+
+        assert!(duplicate_headings(&Document::parse("# A\n## B\n")).is_empty());
+    }
+
+    #[test]
+    fn test_broken_relative_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("guide.md"), "# Guide\n## Setup\n").unwrap();
+
+        let md = "[ok](guide.md) [anchor](guide.md#setup) [bad anchor](guide.md#nope) \
+                  [missing](missing.md) [mail](mailto:a@b.c) \
+                  [web](https://example.com)\n\n[[guide]] [[gone]]\n";
+        let results = statuses(md, dir.path());
+        let broken = |reason: &str| LinkStatus::Broken(reason.to_string());
+
+        assert_eq!(results[0], ("guide.md".to_string(), LinkStatus::Ok));
+        assert_eq!(results[1], ("guide.md#setup".to_string(), LinkStatus::Ok));
+        assert_eq!(
+            results[2],
+            (
+                "guide.md#nope".to_string(),
+                broken("no heading with this anchor")
+            )
+        );
+        assert_eq!(
+            results[3],
+            ("missing.md".to_string(), broken("file not found"))
+        );
+        assert!(results.contains(&("mailto:a@b.c".to_string(), LinkStatus::Unchecked)));
+        assert!(results.contains(&("https://example.com".to_string(), LinkStatus::Unchecked)));
+        assert!(results.contains(&("guide".to_string(), LinkStatus::Ok)));
+        assert!(results.contains(&("gone".to_string(), broken("file not found"))));
+    }
+}
@@ -31,9 +31,10 @@
 mod cli;
 
 use clap::Parser as ClapParser;
-use cli::{Cli, OutputFormat};
+use cli::{Cli, CountMode, ErrorFormat, HeadingSort, OutputFormat, SlugForm, TreeStyle};
 use color_eyre::Result;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::process;
 use treemd::{Document, parser};
 
@@ -69,6 +70,25 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Handle --schema (doesn't require input)
+    if args.schema {
+        let schema = treemd::parser::output::json_schema();
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        return Ok(());
+    }
+
+    // Handle --theme-preview (doesn't require input)
+    if args.theme_preview {
+        let config = treemd::Config::load();
+        if config.defaults.overridden_by(&cli_defaults(&args)).no_color() {
+            eprintln!("Colors are disabled; skipping theme preview");
+            return Ok(());
+        }
+        let caps = treemd::tui::TerminalCapabilities::detect();
+        print!("{}", treemd::tui::theme_preview(color_mode(&args, &config, &caps)));
+        return Ok(());
+    }
+
     // For TUI mode with piped stdin, we'll read stdin first, then open TUI
     // This allows elegant piping: tree | treemd
     //
@@ -80,8 +100,13 @@ fn main() -> Result<()> {
     //
     // This is the standard pattern used by: less, fzf, bat, etc.
 
+    // Several files: list or count headings per file
+    if args.files.len() > 1 {
+        return handle_batch_mode(&args);
+    }
+
     // Determine input source (file, stdin, or error)
-    let input_source = match treemd::input::determine_input_source(args.file.as_deref()) {
+    let input_source = match treemd::input::determine_input_source(args.file()) {
         Ok(source) => source,
         Err(treemd::input::InputError::NoTty) => {
             eprintln!("Error: markdown file argument is required");
@@ -94,7 +119,10 @@ fn main() -> Result<()> {
             std::process::exit(1);
         }
         Err(e) => {
-            eprintln!("Error reading input: {}", e);
+            match args.file().filter(|path| *path != std::path::Path::new("-")) {
+                Some(path) => eprintln!("Error: {}: {}", path.display(), e),
+                None => eprintln!("Error reading input: {}", e),
+            }
             process::exit(1);
         }
     };
@@ -102,33 +130,111 @@ fn main() -> Result<()> {
     // Check if stdin was piped (before consuming input_source)
     let stdin_was_piped = matches!(input_source, treemd::input::InputSource::Stdin(_));
 
-    // Process input (handles tree format conversion, markdown passthrough, etc.)
-    let markdown_content = match treemd::input::process_input(input_source) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error processing input: {}", e);
-            process::exit(1);
+    // Raw input: query lines of text, skipping markdown parsing
+    if args.raw_input {
+        let content = match input_source {
+            treemd::input::InputSource::File(c) | treemd::input::InputSource::Stdin(c) => c,
+        };
+        let lines = content.lines().map(|line| treemd::query::Value::String(line.to_string()));
+        let inputs = if args.slurp {
+            vec![treemd::query::Value::Array(lines.collect())]
+        } else {
+            lines.collect()
+        };
+        let query_str = args.query.as_deref().unwrap_or(".");
+        return handle_query_mode(&parser::parse_markdown(""), query_str, &args, Some(inputs));
+    }
+
+    let doc = if args.from_json {
+        // Rebuild the document from treemd JSON output
+        let json = match input_source {
+            treemd::input::InputSource::File(c) | treemd::input::InputSource::Stdin(c) => c,
+        };
+        match serde_json::from_str::<parser::DocumentOutput>(&json) {
+            Ok(output) => output.to_document(),
+            Err(e) => {
+                eprintln!("Error reading JSON document: {}", e);
+                process::exit(1);
+            }
         }
+    } else {
+        // Process input (handles tree format conversion, markdown passthrough, etc.)
+        let markdown_content = match treemd::input::process_input(input_source) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error processing input: {}", e);
+                process::exit(1);
+            }
+        };
+
+        // Parse the markdown content
+        parser::parse_markdown(&markdown_content)
     };
 
-    // Parse the markdown content
-    let doc = parser::parse_markdown(&markdown_content);
+    // Load configuration; command-line flags take precedence over its defaults
+    let mut config = treemd::Config::load();
+    let defaults = config.defaults.overridden_by(&cli_defaults(&args));
+
+    // Print the document with shifted headings
+    if let Some(by) = args.shift_headings {
+        let (markdown, clamped) = parser::markdown::shift_headings(&doc, by);
+        if clamped > 0 {
+            eprintln!(
+                "Warning: shifting by {} clamped {} heading{} to stay within levels 1-6",
+                by,
+                clamped,
+                if clamped == 1 { "" } else { "s" }
+            );
+        }
+        print!("{}", markdown);
+        return Ok(());
+    }
+
+    // Print only the front matter
+    if args.frontmatter {
+        print_front_matter(&doc, &resolve_output_format(&defaults));
+        return Ok(());
+    }
 
     // Handle query mode
     if let Some(ref query_str) = args.query {
-        return handle_query_mode(&doc, query_str, args.query_output.as_deref());
+        return handle_query_mode(&doc, query_str, &args, None);
+    }
+
+    // Handle template mode
+    if let Some(ref template) = args.template {
+        return handle_template_mode(&doc, template, &args);
+    }
+
+    // Handle locate mode
+    if let Some(ref query_str) = args.locate {
+        return handle_locate_mode(&doc, query_str, &args);
     }
 
     // If no flags, launch TUI
     if !args.list
         && !args.tree
-        && !args.count
+        && args.count.is_none()
+        && !args.tasks
         && args.section.is_none()
+        && args.section_matching.is_none()
+        && args.grep.is_none()
+        && args.grep_regex.is_none()
+        && args.diff.is_none()
+        && !args.code
+        && !args.links
+        && !args.links_external_only
+        && !args.images
+        && !args.check_links
+        && !args.check_duplicates
         && args.command.is_none()
         && !args.setup_completions
     {
-        // Load configuration
-        let mut config = treemd::Config::load();
+        // Rebuilt markdown goes straight to a pipe or file
+        if args.from_json && !std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+            print!("{}", doc.content());
+            return Ok(());
+        }
 
         // Apply theme override from CLI if provided
         if let Some(ref theme_name) = args.theme {
@@ -136,31 +242,13 @@ fn main() -> Result<()> {
         }
 
         // Detect terminal capabilities and determine color mode
-        // Priority: CLI args > config file > auto-detection
         let caps = treemd::tui::TerminalCapabilities::detect();
-        let color_mode = if let Some(ref mode_arg) = args.color_mode {
-            // CLI flag takes highest priority
-            use cli::ColorModeArg;
-            use treemd::tui::ColorMode;
-            match mode_arg {
-                ColorModeArg::Auto => caps.recommended_color_mode,
-                ColorModeArg::Rgb => ColorMode::Rgb,
-                ColorModeArg::Color256 => ColorMode::Indexed256,
-            }
-        } else {
-            // Check config file setting before falling back to auto-detection
-            use treemd::tui::ColorMode;
-            match config.terminal.color_mode.as_str() {
-                "rgb" => ColorMode::Rgb,
-                "256" => ColorMode::Indexed256,
-                // "auto" or any other value falls back to detection
-                _ => caps.recommended_color_mode,
-            }
-        };
+        let color_mode = color_mode(&args, &config, &caps);
 
-        // Show compatibility warning if needed (before TUI init)
+        // Show compatibility warning if needed (before TUI init), unless --ignore-term-warning
         // Skip the warning prompt if stdin was piped (already consumed)
-        if caps.should_warn && !config.terminal.warned_terminal_app {
+        let warn = caps.should_warn && !args.ignore_term_warning;
+        if warn && !config.terminal.warned_terminal_app {
             if let Some(warning) = caps.warning_message() {
                 eprintln!("\n{}\n", warning);
                 // Only wait for keypress if stdin is still available (not piped)
@@ -191,6 +279,9 @@ fn main() -> Result<()> {
         stdout().execute(EnterAlternateScreen).inspect_err(|_| {
             treemd::tui::tty::disable_raw_mode().ok();
         })?;
+        if defaults.mouse() {
+            stdout().execute(crossterm::event::EnableMouseCapture).ok();
+        }
 
         let backend = ratatui::backend::CrosstermBackend::new(stdout());
         let mut terminal = ratatui::Terminal::new(backend).inspect_err(|_| {
@@ -198,24 +289,56 @@ fn main() -> Result<()> {
         })?;
 
         // Get filename and path (use placeholders for stdin)
-        let (filename, file_path) = if let Some(ref file) = args.file {
+        let (filename, file_path) = if let Some(file) = args.file() {
             let name = file
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("stdin")
                 .to_string();
-            let path = file.canonicalize().unwrap_or_else(|_| file.clone());
+            let path = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
             (name, path)
         } else {
             // Stdin input
             ("stdin".to_string(), std::path::PathBuf::from("<stdin>"))
         };
 
-        let app = treemd::App::new(doc, filename, file_path, config, color_mode);
+        let expansion = if args.collapse_all {
+            treemd::tui::OutlineExpansion::CollapseAll
+        } else if let Some(depth) = args.expand_level {
+            treemd::tui::OutlineExpansion::Level(depth)
+        } else {
+            treemd::tui::OutlineExpansion::ExpandAll
+        };
+        let mut app = treemd::App::new(doc, filename, file_path, config, color_mode, expansion);
+        app.tab_width = defaults.tab_width();
+        app.no_color = defaults.no_color();
+        app.mouse = defaults.mouse();
+        app.padding = defaults.padding();
+        app.max_width = defaults.max_width();
+        app.heading_style = defaults.heading_style().parse().unwrap_or_else(|_| {
+            eprintln!(
+                "Warning: unknown heading style '{}', using plain",
+                defaults.heading_style()
+            );
+            treemd::tui::HeadingStyle::Plain
+        });
+        app.outline_style = defaults.outline_style().parse().unwrap_or_else(|_| {
+            eprintln!(
+                "Warning: unknown outline style '{}', using indent",
+                defaults.outline_style()
+            );
+            treemd::tui::OutlineStyle::Indent
+        });
+        if let Some(ref query) = args.interactive_query {
+            app.open_query_bar(Some(query));
+        }
         let result = treemd::tui::run(&mut terminal, app);
 
         // Cleanup terminal state
         use crossterm::terminal::LeaveAlternateScreen;
+        if defaults.mouse() {
+            stdout().execute(crossterm::event::DisableMouseCapture).ok();
+        }
         stdout().execute(LeaveAlternateScreen).ok();
         treemd::tui::tty::disable_raw_mode().ok();
 
@@ -223,46 +346,617 @@ fn main() -> Result<()> {
     }
 
     // Handle CLI commands
-    handle_cli_mode(&args, &doc);
+    handle_cli_mode(&args, &doc, &defaults);
     Ok(())
 }
 
-fn handle_cli_mode(args: &Cli, doc: &Document) {
-    // Apply filters
-    let headings: Vec<_> = if let Some(level) = args.level {
+/// Collect the config-file defaults that were given explicitly on the command line
+fn cli_defaults(args: &Cli) -> treemd::config::DefaultsConfig {
+    use clap::ValueEnum;
+
+    let name =
+        |value: Option<clap::builder::PossibleValue>| value.map(|v| v.get_name().to_string());
+
+    treemd::config::DefaultsConfig {
+        output_format: name(args.output.as_ref().and_then(|o| o.to_possible_value())),
+        tree_style: name(args.tree_style.and_then(|s| s.to_possible_value())),
+        tab_width: args.tab_width,
+        heading_style: name(args.heading_style.and_then(|s| s.to_possible_value())),
+        outline_style: name(args.outline_style.and_then(|s| s.to_possible_value())),
+        padding: args.padding,
+        max_width: args.max_width,
+        no_color: (args.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()))
+            .then_some(true),
+        mouse: if args.mouse {
+            Some(true)
+        } else if args.no_mouse {
+            Some(false)
+        } else {
+            None
+        },
+    }
+}
+
+/// Resolve the output format for --list/--tree from the merged defaults
+fn resolve_output_format(defaults: &treemd::config::DefaultsConfig) -> OutputFormat {
+    use clap::ValueEnum;
+
+    OutputFormat::from_str(defaults.output_format(), true).unwrap_or_else(|_| {
+        eprintln!(
+            "Warning: unknown output format '{}', using plain",
+            defaults.output_format()
+        );
+        OutputFormat::Plain
+    })
+}
+
+/// Headings selected by --level, --filter or --filter-regex (all headings otherwise)
+fn select_headings<'a>(args: &Cli, doc: &'a Document) -> Vec<&'a parser::Heading> {
+    let mut headings = if let Some(level) = args.level {
         doc.headings_at_level(level)
     } else if let Some(ref filter) = args.filter {
         doc.filter_headings(filter)
+    } else if let Some(ref pattern) = args.filter_regex {
+        match doc.filter_headings_regex(pattern) {
+            Ok(headings) => headings,
+            Err(e) => {
+                eprintln!("Invalid regex '{}': {}", pattern, e);
+                process::exit(1);
+            }
+        }
+    } else {
+        doc.headings().iter().collect()
+    };
+
+    // Stable sorts, so ties stay in document order
+    match args.sort {
+        Some(HeadingSort::Text) => headings.sort_by_cached_key(|h| h.text.to_lowercase()),
+        Some(HeadingSort::Level) => headings.sort_by_key(|h| h.level),
+        Some(HeadingSort::Length) => headings.sort_by_key(|h| h.text.chars().count()),
+        None => {}
+    }
+    if args.reverse {
+        headings.reverse();
+    }
+    headings
+}
+
+/// Run --list or --count over several files.
+///
+/// Plain output prefixes each result with its file's path (like grep with
+/// multiple files); JSON output is an object keyed by path. Results follow the
+/// order the files were given, even with --parallel.
+fn handle_batch_mode(args: &Cli) -> Result<()> {
+    if !args.list && args.count.is_none() {
+        eprintln!("Error: multiple files are only supported with --list or --count");
+        process::exit(1);
+    }
+
+    let config = treemd::Config::load();
+    let defaults = config.defaults.overridden_by(&cli_defaults(args));
+    let output = resolve_output_format(&defaults);
+
+    let parse = |path: &std::path::PathBuf| {
+        treemd::input::read_file(path).map(|content| parser::parse_markdown(&content))
+    };
+    let docs: Vec<Result<Document, treemd::input::InputError>> = if args.parallel {
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+        let chunk_size = args.files.len().div_ceil(workers);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = args
+                .files
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk.iter().map(parse).collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("parser thread panicked"))
+                .collect()
+        })
     } else {
-        doc.headings.iter().collect()
+        args.files.iter().map(parse).collect()
     };
 
+    let mut failed = false;
+    let mut matched = false;
+    let mut json = indexmap::IndexMap::new();
+
+    for (path, doc) in args.files.iter().zip(&docs) {
+        let path = path.display().to_string();
+        let doc = match doc {
+            Ok(doc) => doc,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        if let Some(mode) = args.count {
+            matched |= !doc.headings().is_empty();
+            match output {
+                OutputFormat::Json => {
+                    let mut counts = indexmap::IndexMap::new();
+                    match mode {
+                        CountMode::Levels => {
+                            for heading in doc.headings() {
+                                *counts.entry(format!("h{}", heading.level)).or_insert(0) += 1;
+                            }
+                            counts.sort_keys();
+                        }
+                        CountMode::Unique => {
+                            let (texts, slugs) = distinct_headings(doc);
+                            counts.insert("distinct_texts".to_string(), texts);
+                            counts.insert("distinct_slugs".to_string(), slugs);
+                        }
+                        CountMode::Total => {}
+                    }
+                    counts.insert("total".to_string(), doc.headings().len());
+                    json.insert(path, serde_json::json!(counts));
+                }
+                _ => {
+                    let text = format_heading_counts(doc, mode);
+                    for line in text.lines().filter(|l| !l.is_empty()) {
+                        println!("{}:{}", path, line);
+                    }
+                }
+            }
+        } else {
+            let headings = select_headings(args, doc);
+            matched |= !headings.is_empty();
+            match output {
+                OutputFormat::Json => {
+                    json.insert(path, serde_json::json!(headings));
+                }
+                _ => {
+                    for heading in headings {
+                        println!("{}:{} {}", path, "#".repeat(heading.level), heading.text);
+                    }
+                }
+            }
+        }
+    }
+
+    if matches!(output, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+
+    if failed || (args.fail_on_empty && !matched) {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn handle_cli_mode(args: &Cli, doc: &Document, defaults: &treemd::config::DefaultsConfig) {
+    use clap::ValueEnum;
+
+    let output = resolve_output_format(defaults);
+    let tree_style = TreeStyle::from_str(defaults.tree_style(), true).unwrap_or_else(|_| {
+        eprintln!(
+            "Warning: unknown tree style '{}', using box",
+            defaults.tree_style()
+        );
+        TreeStyle::Box
+    });
+
+    // Apply filters
+    let headings = select_headings(args, doc);
+
+    // With --fail-on-empty, an empty selection is an error (exit code 1)
+    if args.fail_on_empty {
+        let is_empty = if args.tree {
+            doc.headings().is_empty()
+        } else {
+            args.list && headings.is_empty()
+        };
+        if is_empty {
+            eprintln!("No matching headings");
+            process::exit(1);
+        }
+    }
+
+    // Structural diff against an older version of the document
+    if let Some(ref old_path) = args.diff {
+        let old = match parser::parse_file(old_path) {
+            Ok(old) => old,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", old_path.display(), e);
+                process::exit(1);
+            }
+        };
+        let changes = treemd::diff::diff_headings(&old, doc);
+        let color = !defaults.no_color() && std::io::IsTerminal::is_terminal(&std::io::stdout());
+        print_diff(&changes, &output, color);
+        return;
+    }
+
+    if args.check_links {
+        check_links(args, doc, &output);
+        return;
+    }
+
+    if args.check_duplicates {
+        check_duplicates(doc, &output);
+        return;
+    }
+
+    // Element listings (--code, --links, --images)
+    let element_query = if args.code {
+        Some(".code")
+    } else if args.links_external_only {
+        Some(".link[external]")
+    } else if args.links {
+        Some(".link")
+    } else if args.images {
+        Some(".img")
+    } else {
+        None
+    };
+    if let Some(query_str) = element_query {
+        print_elements(doc, query_str, &output, args.fail_on_empty);
+        return;
+    }
+
+    // Content search takes priority over heading listing
+    if args.grep.is_some() || args.grep_regex.is_some() {
+        grep_sections(args, doc, &output);
+        return;
+    }
+
     // Handle different modes
-    if args.count {
-        print_heading_counts(doc);
+    if let Some(mode) = args.count {
+        print_heading_counts(doc, mode);
+    } else if args.tasks {
+        print!("{}", format_task_progress(doc));
     } else if args.tree {
-        print_tree(doc, &args.output);
+        print_tree(doc, &output, tree_style, args.number, !args.no_pager);
     } else if let Some(ref section_name) = args.section {
-        extract_section(doc, section_name);
+        extract_section(doc, section_name, &output);
+    } else if let Some(ref pattern) = args.section_matching {
+        extract_section_matching(doc, pattern, &output);
     } else if args.list {
-        print_headings(&headings, &args.output, doc);
+        let code_words = if args.include_code {
+            parser::CodeWords::Include
+        } else if args.exclude_code {
+            parser::CodeWords::ExcludeAll
+        } else {
+            parser::CodeWords::ExcludeBlocks
+        };
+        let json_options = parser::JsonOptions {
+            code_words,
+            content: !args.no_content,
+        };
+        if let Some(form) = args.slugs {
+            print_slugs(&headings, doc, form);
+        } else {
+            // Section numbers by heading offset, prefixed to each text when printed
+            let numbers: Option<HashMap<usize, String>> = args.number.then(|| {
+                doc.headings()
+                    .iter()
+                    .map(|h| h.offset)
+                    .zip(doc.heading_numbers())
+                    .collect()
+            });
+            if args.raw0 {
+                print_raw0(&headings, numbers.as_ref());
+            } else {
+                print_headings(
+                    &headings,
+                    numbers.as_ref(),
+                    &output,
+                    doc,
+                    json_options,
+                    args.with_content,
+                    !args.no_pager,
+                );
+            }
+        }
     }
 }
 
-fn print_headings(headings: &[&parser::Heading], format: &OutputFormat, doc: &Document) {
+fn print_diff(changes: &[treemd::diff::HeadingChange], format: &OutputFormat, color: bool) {
+    use treemd::diff::HeadingChange;
+
+    if matches!(format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(changes).unwrap());
+        return;
+    }
+
+    if changes.is_empty() {
+        println!("No structural changes");
+        return;
+    }
+
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text
+        }
+    };
+    let heading = |level: usize, text: &str| format!("{} {}", "#".repeat(level), text);
+    let parent = |p: &Option<String>| p.as_deref().unwrap_or("(top level)").to_string();
+
+    let mut counts = [0usize; 5];
+    for change in changes {
+        let line = match change {
+            HeadingChange::Added { text, level } => {
+                counts[0] += 1;
+                paint("32", format!("+ {}", heading(*level, text)))
+            }
+            HeadingChange::Removed { text, level } => {
+                counts[1] += 1;
+                paint("31", format!("- {}", heading(*level, text)))
+            }
+            HeadingChange::Renamed { from, to, level } => {
+                counts[2] += 1;
+                paint(
+                    "33",
+                    format!("~ {} → {}", heading(*level, from), heading(*level, to)),
+                )
+            }
+            HeadingChange::LevelChanged { text, from, to } => {
+                counts[3] += 1;
+                paint("33", format!("~ {} (level {} → {})", text, from, to))
+            }
+            HeadingChange::Moved {
+                text,
+                from_parent,
+                to_parent,
+            } => {
+                counts[4] += 1;
+                paint(
+                    "36",
+                    format!(
+                        "> {} (moved from {} to {})",
+                        text,
+                        parent(from_parent),
+                        parent(to_parent)
+                    ),
+                )
+            }
+        };
+        println!("{}", line);
+    }
+
+    println!(
+        "\n{} added, {} removed, {} renamed, {} level changed, {} moved",
+        counts[0], counts[1], counts[2], counts[3], counts[4]
+    );
+}
+
+/// Report the status of every link, exiting with 1 if any are broken
+fn check_links(args: &Cli, doc: &Document, output: &OutputFormat) {
+    use treemd::linkcheck::{self, LinkStatus};
+
+    // Relative links resolve next to the file; stdin uses the working directory
+    let base_dir = match args.file() {
+        Some(file) if file.as_os_str() != "-" => file
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default(),
+        _ => std::path::PathBuf::from("."),
+    };
+
+    #[allow(unused_mut)]
+    let mut checks = linkcheck::check_links(doc, &base_dir);
+
+    if args.check_external {
+        #[cfg(feature = "check-external")]
+        linkcheck::check_external(&mut checks, std::time::Duration::from_secs(10));
+        #[cfg(not(feature = "check-external"))]
+        {
+            eprintln!("--check-external requires treemd to be built with the check-external feature");
+            process::exit(1);
+        }
+    }
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&checks).unwrap();
+            println!("{}", json);
+        }
+        OutputFormat::Plain | OutputFormat::Tree | OutputFormat::Markdown => {
+            for check in &checks {
+                let status = match &check.status {
+                    LinkStatus::Ok => "ok".to_string(),
+                    LinkStatus::Broken(reason) => format!("BROKEN ({})", reason),
+                    LinkStatus::Unchecked => "unchecked".to_string(),
+                };
+                println!("{}: {} {} [{}]", check.line, status, check.target, check.kind);
+            }
+
+            let broken = checks.iter().filter(|c| c.is_broken()).count();
+            println!("\n{} links, {} broken", checks.len(), broken);
+        }
+    }
+
+    if checks.iter().any(|c| c.is_broken()) {
+        process::exit(1);
+    }
+}
+
+/// Report headings sharing a slug, exiting with 1 if there are any
+fn check_duplicates(doc: &Document, output: &OutputFormat) {
+    let duplicates = treemd::linkcheck::duplicate_headings(doc);
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&duplicates).unwrap();
+            println!("{}", json);
+        }
+        OutputFormat::Plain | OutputFormat::Tree | OutputFormat::Markdown => {
+            for duplicate in &duplicates {
+                let lines: Vec<String> =
+                    duplicate.headings.iter().map(|h| h.line.to_string()).collect();
+                println!("#{}: lines {}", duplicate.slug, lines.join(", "));
+            }
+            if duplicates.is_empty() {
+                println!("No duplicate headings");
+            } else {
+                let s = if duplicates.len() == 1 { "" } else { "s" };
+                println!("\n{} duplicate slug{}", duplicates.len(), s);
+            }
+        }
+    }
+
+    if !duplicates.is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Print the elements matched by a built-in element query, one per line
+fn print_elements(doc: &Document, query_str: &str, output: &OutputFormat, fail_on_empty: bool) {
+    use treemd::query::{self, Value};
+
+    let elements = match query::execute(doc, query_str) {
+        Ok(elements) => elements,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if elements.is_empty() && fail_on_empty {
+        eprintln!("No results");
+        process::exit(1);
+    }
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::Value::Array(elements.iter().map(query::to_json).collect());
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputFormat::Plain | OutputFormat::Tree | OutputFormat::Markdown => {
+            for element in &elements {
+                match element {
+                    Value::Code(c) => println!(
+                        "{}-{}: {}",
+                        c.start_line,
+                        c.end_line,
+                        c.language.as_deref().unwrap_or("(none)")
+                    ),
+                    Value::Link(l) => println!(
+                        "{}: {} ({}) {}",
+                        l.line,
+                        l.url,
+                        l.link_type.as_str(),
+                        l.text
+                    ),
+                    Value::Image(i) => println!("{}: {} {}", i.line, i.src, i.alt),
+                    other => println!("{}", other.to_text()),
+                }
+            }
+        }
+    }
+}
+
+fn grep_sections(args: &Cli, doc: &Document, output: &OutputFormat) {
+    let context = args.context.unwrap_or(0);
+    let matches = if let Some(ref pattern) = args.grep_regex {
+        let re = match regex::Regex::new(pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                eprintln!("Invalid regex '{}': {}", pattern, e);
+                process::exit(1);
+            }
+        };
+        doc.grep_sections_with_context(|line| re.is_match(line), context)
+    } else {
+        let needle = args.grep.as_deref().unwrap_or_default().to_lowercase();
+        doc.grep_sections_with_context(|line| line.to_lowercase().contains(&needle), context)
+    };
+
+    if matches.is_empty() {
+        if args.fail_on_empty {
+            eprintln!("No matching sections");
+            process::exit(1);
+        }
+        return;
+    }
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&matches).unwrap();
+            println!("{}", json);
+        }
+        OutputFormat::Plain | OutputFormat::Tree | OutputFormat::Markdown => {
+            for section in &matches {
+                let heading = section.heading;
+                println!("{} {}", "#".repeat(heading.level), heading.text);
+                if section.context.is_empty() {
+                    for (line_number, text) in &section.lines {
+                        println!("  {}: {}", line_number, text.trim_end());
+                    }
+                    continue;
+                }
+
+                // Like grep -C: context lines use `-`, runs are split by `--`
+                for (idx, run) in section.context.iter().enumerate() {
+                    if idx > 0 {
+                        println!("  --");
+                    }
+                    for (line_number, text) in run {
+                        let mark = if section.lines.iter().any(|(n, _)| n == line_number) {
+                            ':'
+                        } else {
+                            '-'
+                        };
+                        println!("  {}{} {}", line_number, mark, text.trim_end());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn print_headings(
+    headings: &[&parser::Heading],
+    numbers: Option<&HashMap<usize, String>>,
+    format: &OutputFormat,
+    doc: &Document,
+    json_options: parser::JsonOptions,
+    preview_chars: Option<usize>,
+    use_pager: bool,
+) {
     match format {
         OutputFormat::Plain => {
+            // Find each heading's section by its offset
+            let indices: HashMap<usize, usize> = doc
+                .headings()
+                .iter()
+                .enumerate()
+                .map(|(idx, h)| (h.offset, idx))
+                .collect();
+            let mut text = String::new();
             for heading in headings {
                 let prefix = "#".repeat(heading.level);
-                println!("{} {}", prefix, heading.text);
+                text.push_str(&format!("{} {}\n", prefix, heading_label(heading, numbers)));
+                let preview = preview_chars
+                    .and_then(|max| doc.section_preview(indices[&heading.offset], max));
+                if let Some(preview) = preview.filter(|p| !p.is_empty()) {
+                    text.push_str(&format!("    {}\n", preview));
+                }
             }
+            cli::pager::print_paged(&text, use_pager);
         }
         OutputFormat::Json => {
             // Use new nested JSON output with markdown intelligence
-            let json_output = parser::build_json_output(doc, None);
+            let json_output = parser::build_json_output_with(doc, None, json_options);
             let json = serde_json::to_string_pretty(&json_output).unwrap();
             println!("{}", json);
         }
+        OutputFormat::Markdown => {
+            let lines: Vec<String> = headings
+                .iter()
+                .map(|h| format!("{} {}", "#".repeat(h.level), heading_label(h, numbers)))
+                .collect();
+            cli::pager::print_paged(&(lines.join("\n") + "\n"), use_pager);
+        }
         OutputFormat::Tree => {
             eprintln!("Use --tree for tree output");
             process::exit(1);
@@ -270,108 +964,480 @@ fn print_headings(headings: &[&parser::Heading], format: &OutputFormat, doc: &Do
     }
 }
 
-fn print_tree(doc: &Document, format: &OutputFormat) {
+/// Print the unique anchor slug of each heading, one per line
+fn print_slugs(headings: &[&parser::Heading], doc: &Document, form: SlugForm) {
+    // Slugs are numbered across the whole document, so match them up by offset
+    let slugs: HashMap<usize, String> = doc
+        .headings()
+        .iter()
+        .map(|h| h.offset)
+        .zip(treemd::linkcheck::heading_slugs(doc))
+        .collect();
+    let hash = if form == SlugForm::Anchor { "#" } else { "" };
+    for heading in headings {
+        println!("{}{}", hash, slugs[&heading.offset]);
+    }
+}
+
+/// Print heading texts separated by NUL bytes, with no trailing newline
+fn print_raw0(headings: &[&parser::Heading], numbers: Option<&HashMap<usize, String>>) {
+    let texts: Vec<Cow<str>> = headings.iter().map(|h| heading_label(h, numbers)).collect();
+    print!("{}", texts.join("\0"));
+}
+
+/// A heading's text, after its section number when `numbers` (keyed by
+/// offset) is given
+fn heading_label<'a>(
+    heading: &'a parser::Heading,
+    numbers: Option<&HashMap<usize, String>>,
+) -> Cow<'a, str> {
+    match numbers {
+        Some(numbers) => Cow::Owned(format!("{} {}", numbers[&heading.offset], heading.text)),
+        None => Cow::Borrowed(&heading.text),
+    }
+}
+
+fn print_tree(
+    doc: &Document,
+    format: &OutputFormat,
+    style: TreeStyle,
+    numbered: bool,
+    use_pager: bool,
+) {
     let tree = doc.build_tree();
 
     match format {
-        OutputFormat::Tree | OutputFormat::Plain => {
+        OutputFormat::Tree | OutputFormat::Plain | OutputFormat::Markdown => {
+            let mut text = String::new();
             for (i, node) in tree.iter().enumerate() {
                 let is_last = i == tree.len() - 1;
-                print!("{}", node.render_box_tree("", is_last));
+                let rendered = match (style, numbered) {
+                    (TreeStyle::Box, false) => node.render_box_tree("", is_last),
+                    (TreeStyle::Box, true) => node.render_numbered_box_tree("", is_last),
+                    (TreeStyle::Ascii, false) => node.render_ascii_tree("", is_last),
+                    (TreeStyle::Ascii, true) => node.render_numbered_ascii_tree("", is_last),
+                };
+                text.push_str(&rendered);
             }
+            cli::pager::print_paged(&text, use_pager);
         }
         OutputFormat::Json => {
             // For JSON, we'll serialize the flat headings list
             // (Tree serialization would need custom implementation)
-            let json = serde_json::to_string_pretty(&doc.headings).unwrap();
+            let json = serde_json::to_string_pretty(&doc.headings()).unwrap();
             println!("{}", json);
         }
     }
 }
 
-fn print_heading_counts(doc: &Document) {
+fn print_heading_counts(doc: &Document, mode: CountMode) {
+    print!("{}", format_heading_counts(doc, mode));
+}
+
+fn format_heading_counts(doc: &Document, mode: CountMode) -> String {
+    match mode {
+        CountMode::Levels => format_level_counts(doc),
+        CountMode::Unique => format_unique_counts(doc),
+        CountMode::Total => format!("{}\n", doc.headings().len()),
+    }
+}
+
+/// Number of distinct heading texts and distinct slugs. Headings whose texts
+/// differ only in case or punctuation share a slug, so they'd share an anchor.
+fn distinct_headings(doc: &Document) -> (usize, usize) {
+    let texts: HashSet<&str> = doc.headings().iter().map(|h| h.text.as_str()).collect();
+    let slugs: HashSet<String> = doc
+        .headings()
+        .iter()
+        .map(|h| parser::content::slugify(&h.text))
+        .collect();
+    (texts.len(), slugs.len())
+}
+
+fn format_unique_counts(doc: &Document) -> String {
+    let total = doc.headings().len();
+    let (texts, slugs) = distinct_headings(doc);
+    format!(
+        "Headings: {}\nDistinct texts: {} ({} duplicates)\nDistinct slugs: {} ({} duplicates)\n",
+        total,
+        texts,
+        total - texts,
+        slugs,
+        total - slugs
+    )
+}
+
+fn format_level_counts(doc: &Document) -> String {
     let mut counts: HashMap<usize, usize> = HashMap::new();
 
-    for heading in &doc.headings {
+    for heading in doc.headings() {
         *counts.entry(heading.level).or_insert(0) += 1;
     }
 
-    println!("Heading counts:");
+    let mut text = String::from("Heading counts:\n");
     for level in 1..=6 {
         if let Some(count) = counts.get(&level) {
             let prefix = "#".repeat(level);
-            println!("  {}: {}", prefix, count);
+            text.push_str(&format!("  {}: {}\n", prefix, count));
         }
     }
-    println!("\nTotal: {}", doc.headings.len());
+    text.push_str(&format!("\nTotal: {}\n", doc.headings().len()));
+    text
 }
 
-fn extract_section(doc: &Document, section_name: &str) {
-    let heading = match doc.find_heading(section_name) {
-        Some(h) => h,
-        None => {
-            eprintln!("Section '{}' not found", section_name);
-            process::exit(1);
+/// Print the document's front matter as written, or as a JSON object
+fn print_front_matter(doc: &Document, format: &OutputFormat) {
+    let front_matter = doc.front_matter();
+
+    if !matches!(format, OutputFormat::Json) {
+        if let Some(front_matter) = front_matter {
+            print!("{}", front_matter.raw);
+        }
+        return;
+    }
+
+    let value = match front_matter {
+        Some(front_matter) => match treemd::query::Value::from_front_matter(&front_matter) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("Error: invalid front matter: {}", e);
+                process::exit(1);
+            }
+        },
+        None => treemd::query::Value::Object(Default::default()),
+    };
+    let json = treemd::query::to_json(&value);
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+}
+
+/// Task-list progress per section, with an ASCII bar, and the document total
+fn format_task_progress(doc: &Document) -> String {
+    const BAR_WIDTH: usize = 20;
+
+    let progress = |done: usize, total: usize| {
+        let filled = (done * BAR_WIDTH + total / 2) / total;
+        format!(
+            "[{}{}] {}/{} ({}%)",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            done,
+            total,
+            (done * 100 + total / 2) / total
+        )
+    };
+
+    let mut text = String::new();
+    let (mut done, mut total) = (0, 0);
+    for (idx, heading) in doc.headings().iter().enumerate() {
+        let Some((section_done, section_total)) = doc.section_tasks(idx) else {
+            continue;
+        };
+        if section_total == 0 {
+            continue;
+        }
+        done += section_done;
+        total += section_total;
+        text.push_str(&format!(
+            "{} {}  {}\n",
+            "#".repeat(heading.level),
+            heading.text,
+            progress(section_done, section_total)
+        ));
+    }
+
+    if total == 0 {
+        return "No task lists found\n".to_string();
+    }
+    text.push_str(&format!("\nTotal: {}\n", progress(done, total)));
+    text
+}
+
+fn extract_section_matching(doc: &Document, pattern: &str, format: &OutputFormat) {
+    let found = match regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+    {
+        Ok(re) => doc.find_section_matching(|line| re.is_match(line)),
+        Err(_) => {
+            let needle = pattern.to_lowercase();
+            doc.find_section_matching(|line| line.to_lowercase().contains(&needle))
         }
     };
 
-    // Find the section in content
-    // This is a simple implementation - could be improved
-    let search = format!("{} {}", "#".repeat(heading.level), heading.text);
-    if let Some(start) = doc.content.find(&search) {
-        // Find next heading at same or higher level
-        let after = &doc.content[start..];
-        let section_level = heading.level;
+    match found {
+        Some((_, text)) => print_section(text, format),
+        None => {
+            eprintln!("No section content matches '{}'", pattern);
+            process::exit(1);
+        }
+    }
+}
 
-        // Find end of section
-        let end_pos = doc
-            .headings
-            .iter()
-            .skip_while(|h| h.text != heading.text)
-            .skip(1)
-            .find(|h| h.level <= section_level)
-            .and_then(|next_heading| {
-                let search = format!("{} {}", "#".repeat(next_heading.level), next_heading.text);
-                after.find(&search)
-            })
-            .unwrap_or(after.len());
+fn extract_section(doc: &Document, section_name: &str, format: &OutputFormat) {
+    match doc.find_section(section_name) {
+        Some((_, text)) => print_section(text, format),
+        None => {
+            eprintln!("Section '{}' not found", section_name);
+            process::exit(1);
+        }
+    }
+}
 
-        println!("{}", &after[..end_pos].trim());
+/// Print a section's markdown as written, or re-serialized from its parsed
+/// structure with `--output markdown`.
+fn print_section(text: &str, format: &OutputFormat) {
+    match format {
+        OutputFormat::Markdown => {
+            let output = parser::build_json_output(&parser::parse_markdown(text), None);
+            println!("{}", output.to_markdown().trim_end());
+        }
+        _ => println!("{}", text),
     }
 }
 
+/// Run a query on the document, or once per value of `raw_inputs` (--raw-input)
 fn handle_query_mode(
     doc: &Document,
     query_str: &str,
-    output_format: Option<&str>,
+    args: &Cli,
+    raw_inputs: Option<Vec<treemd::query::Value>>,
 ) -> Result<()> {
     use treemd::query::{self, OutputFormat};
 
     // Parse output format
-    let format = output_format
+    let format = args
+        .query_output
+        .as_deref()
         .map(|s| s.parse::<OutputFormat>())
         .transpose()
         .map_err(|e| {
             eprintln!("Error: {}", e);
             process::exit(1);
         })?
-        .unwrap_or(OutputFormat::Plain);
+        .unwrap_or(if args.raw0 {
+            OutputFormat::RawZero
+        } else {
+            OutputFormat::Plain
+        });
+
+    let error_format = args.error_format;
 
     // Execute query
-    match query::execute(doc, query_str) {
-        Ok(results) => {
+    let results = match query::parse(query_str) {
+        Ok(query) => {
+            let mut engine = query::engine(doc)
+                .strict(args.strict)
+                .allow_env(args.allow_env)
+                .terminal_width(terminal_width());
+            match raw_inputs {
+                None => engine.execute(&query),
+                Some(inputs) => inputs.into_iter().try_fold(Vec::new(), |mut results, input| {
+                    results.extend(engine.execute_with_input(&query, input)?);
+                    Ok(results)
+                }),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    match results {
+        Ok(mut results) => {
+            if args.fail_on_empty && results.iter().all(|v| matches!(v, query::Value::Null)) {
+                eprintln!("No results");
+                process::exit(1);
+            }
             if results.is_empty() {
                 // No results - exit silently like jq
                 return Ok(());
             }
+            if args.flat {
+                results = flatten_one_level(results);
+            }
+            let mut footer = None;
+            if let Some(page) = args.page {
+                let size = args.page_size.unwrap_or(10) as usize;
+                let (range, pages) = page_bounds(results.len(), page as usize, size);
+                if range.is_empty() {
+                    eprintln!("Error: page {} is out of range ({} pages)", page, pages);
+                    process::exit(1);
+                }
+                results = results.drain(range).collect();
+                footer = Some(format!("Page {}/{}", page, pages));
+            }
+            if let Some(max) = args.max_output {
+                if results.len() > max {
+                    eprintln!("… {} more", results.len() - max);
+                    results.truncate(max);
+                }
+            }
             let output = query::format_output(&results, format);
-            println!("{}", output);
+            if matches!(format, OutputFormat::RawZero) {
+                print!("{}", output);
+            } else {
+                println!("{}", output);
+            }
+            match footer {
+                Some(footer) if matches!(format, OutputFormat::Plain) => println!("{}", footer),
+                Some(footer) => eprintln!("{}", footer),
+                None => {}
+            }
             Ok(())
         }
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
+        Err(e) => report_query_error(&e, error_format),
+    }
+}
+
+/// Columns of the terminal stdout goes to, or `None` when it's redirected
+fn terminal_width() -> Option<usize> {
+    if !std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(columns, _)| usize::from(columns))
+}
+
+fn handle_template_mode(doc: &Document, template: &str, args: &Cli) -> Result<()> {
+    use treemd::query;
+
+    // `@FILE` reads the template from a file
+    let template = match template.strip_prefix('@') {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("Error reading template {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => template.to_string(),
+    };
+
+    let mut engine = query::engine(doc)
+        .strict(args.strict)
+        .allow_env(args.allow_env)
+        .terminal_width(terminal_width());
+    match query::render_template(&mut engine, &template) {
+        Ok(text) if text.ends_with('\n') => print!("{}", text),
+        Ok(text) => println!("{}", text),
+        Err(e) => report_query_error(&e, args.error_format),
+    }
+    Ok(())
+}
+
+/// Print a failed query's error in the requested format and exit.
+fn report_query_error(error: &treemd::query::QueryError, format: ErrorFormat) -> ! {
+    match format {
+        ErrorFormat::Human => eprintln!("{}", error),
+        ErrorFormat::Json => eprintln!("{}", error.to_json()),
+    }
+    process::exit(1);
+}
+
+/// Index range of 1-based `page` among `len` results split into pages of
+/// `size`, and the total page count. The range is empty past the last page.
+fn page_bounds(len: usize, page: usize, size: usize) -> (std::ops::Range<usize>, usize) {
+    let pages = len.div_ceil(size);
+    let start = page.saturating_sub(1).saturating_mul(size).min(len);
+    let end = start.saturating_add(size).min(len);
+    (start..end, pages)
+}
+
+/// Replace each array result with its elements; other results are kept as-is.
+fn flatten_one_level(results: Vec<treemd::query::Value>) -> Vec<treemd::query::Value> {
+    use treemd::query::Value;
+
+    results
+        .into_iter()
+        .flat_map(|value| match value {
+            Value::Array(items) => items,
+            other => vec![other],
+        })
+        .collect()
+}
+
+fn handle_locate_mode(doc: &Document, query_str: &str, args: &Cli) -> Result<()> {
+    use treemd::query;
+
+    let path = match args.file() {
+        Some(file) if file.as_os_str() != "-" => file.display().to_string(),
+        _ => "<stdin>".to_string(),
+    };
+
+    let results = match query::parse(query_str) {
+        Ok(query) => query::engine(doc)
+            .strict(args.strict)
+            .allow_env(args.allow_env)
+            .execute(&query),
+        Err(e) => Err(e),
+    };
+
+    match results {
+        Ok(results) => {
+            let mut locations: Vec<_> = results
+                .iter()
+                .filter_map(|v| v.offset().map(|offset| (doc.line_col(offset), v.to_text())))
+                .collect();
+            if args.fail_on_empty && locations.is_empty() {
+                eprintln!("No results");
+                process::exit(1);
+            }
+            let print_location = |(line, column): (usize, usize), text: &str| {
+                let text = text.lines().next().unwrap_or("");
+                println!("{}:{}:{}: {}", path, line, column, text);
+            };
+
+            let Some(context) = args.context else {
+                for (position, text) in &locations {
+                    print_location(*position, text);
+                }
+                return Ok(());
+            };
+
+            // Like grep -C: context lines use `-` separators, runs are split by `--`
+            locations.sort_by_key(|(position, _)| *position);
+            let mut lines: Vec<usize> = locations.iter().map(|((line, _), _)| *line).collect();
+            lines.dedup();
+            for (idx, run) in doc.line_context(&lines, context).into_iter().enumerate() {
+                if idx > 0 {
+                    println!("--");
+                }
+                for (line, text) in run {
+                    let here: Vec<_> = locations.iter().filter(|((l, _), _)| *l == line).collect();
+                    if here.is_empty() {
+                        println!("{}-{}- {}", path, line, text);
+                    }
+                    for (position, text) in here {
+                        print_location(*position, text);
+                    }
+                }
+            }
+            Ok(())
         }
+        Err(e) => report_query_error(&e, args.error_format),
+    }
+}
+
+/// Pick the color mode for themed output.
+///
+/// Priority: CLI args > config file > auto-detection
+fn color_mode(
+    args: &Cli,
+    config: &treemd::Config,
+    caps: &treemd::tui::TerminalCapabilities,
+) -> treemd::tui::ColorMode {
+    use cli::ColorModeArg;
+    use treemd::tui::ColorMode;
+
+    match args.color_mode {
+        // CLI flag takes highest priority
+        Some(ColorModeArg::Auto) => caps.recommended_color_mode,
+        Some(ColorModeArg::Rgb) => ColorMode::Rgb,
+        Some(ColorModeArg::Color256) => ColorMode::Indexed256,
+        // Check config file setting before falling back to auto-detection
+        None => match config.terminal.color_mode.as_str() {
+            "rgb" => ColorMode::Rgb,
+            "256" => ColorMode::Indexed256,
+            // "auto" or any other value falls back to detection
+            _ => caps.recommended_color_mode,
+        },
     }
 }
 
@@ -392,6 +1458,9 @@ ELEMENT SELECTORS
     .table          All tables
     .list           All lists
     .blockquote     All blockquotes
+    .details        All <details> blocks
+    .footnote       Footnote definitions ([^label]: ...)
+    .frontmatter    Front matter at the top (YAML or TOML)
 
 FILTERS & INDEXING
     .h2[Features]       Heading containing "Features" (fuzzy)
@@ -411,51 +1480,103 @@ PIPES
     .code | lang        Get code block languages
     .link | url         Get link URLs
 
+COMMAS
+    .link, .img         Outputs of both, in turn (comma binds looser than |)
+    (.link, .img) | url Group with parentheses to pipe both
+    [.link, .img]       Collect both into one array
+
 COLLECTION FUNCTIONS
     count, length       Count elements (alias: len, size)
     first, last         First/last element (alias: head)
-    limit(n), take(n)   First n elements
-    skip(n), drop(n)    Skip first n elements
+    limit(n), take(n)   First n elements (negative n: last -n)
+    skip(n), drop(n)    Skip first n elements (negative n: drop last -n)
     nth(n)              Get element at index
+    nth(n; f)           Nth output of f, without computing the rest
+    range(n)            Numbers 0..n-1 (also range(a; b), range(a; b; step))
     reverse             Reverse order
-    sort                Sort alphabetically
-    sort_by(key)        Sort by property
-    unique              Remove duplicates
+    sort                Sort (null, booleans, numbers, then strings)
+    sort_by(f)          Sort by a key expression or property name
+    unique              Remove duplicates (1 and "1" are distinct)
     flatten             Flatten nested arrays
-    group_by(key)       Group elements by key
-    min, max            Min/max numeric value
-    add                 Sum numbers or concat strings
+    group_by(f)         Array of groups, sorted by key (jq-compatible)
+    group_into(key)     Object of groups keyed by name (alias: group)
+    map_values(f)       Apply f to each object/array value, keeping keys
+    recurse, recurse(f) Recursively descend (or apply f repeatedly)
+    walk(f)             Apply f bottom-up to every nested value
+    paths               Paths to every nested value, as arrays of keys/indexes
+    min, max            Min/max numeric value (non-numbers are ignored)
+    add                 Sum numbers, or concat strings or arrays; project
+                        elements to numbers first: [.h | .level] | add
+    keys                Object keys, sorted alphabetically
+    keys_unsorted       Object keys in insertion order
 
 STRING FUNCTIONS
     text                Get text representation
     upper, lower        Case conversion
     trim                Strip whitespace
+    indent(n)           Prefix each non-blank line with n spaces
+    dedent              Strip leading whitespace common to all lines
     split(sep)          Split by separator
-    join(sep)           Join with separator
+    split(re; flags)    Split on regex (flags: "g", or any of "imsx")
+    splits(re)          Split on regex, one result per piece
+    scan(re)            Each regex match, or array of its groups if it has any
+    capture(re)         Object of named groups: "(?P<name>...)"
+    join(sep)           Join with separator (nested arrays are flattened once)
     replace(a, b)       Replace substring
+    sub(re; s)          Replace first regex match (\1 or $1 backreferences)
+    gsub(re; s)         Replace all regex matches
+                        Regex functions take optional flags last:
+                        i (ignore case), m (multiline ^/$), s (. matches
+                        newline), x (extended); sub(re; s; "g") replaces all
+                        (g is rejected by test and capture)
     slugify             URL-friendly slug
+    tojson, @json       Encode as JSON text
+    fromjson            Parse JSON text
+    base64, @base64     Encode as base64
+    base64d, @base64d   Decode base64
+    html_escape, @html  Escape <>&"' for HTML
+    uri_escape, @uri    Percent-encode for URLs (RFC 3986)
+    index(s), rindex(s) First/last position of s (or null)
+    indices(s)          All positions of s (overlapping)
+    explode, implode    String to/from array of codepoints
+    ascii               Codepoint (0-127) to character
     lines, words, chars Count lines/words/chars
 
 FILTER FUNCTIONS
     select(cond)        Keep if condition true (alias: where, filter)
-    contains(s)         Contains substring (alias: includes)
+    contains(x)         Contains substring, or subset for arrays/objects
+                        (alias: includes)
     startswith(s)       Starts with prefix
     endswith(s)         Ends with suffix
-    matches(regex)      Matches regex pattern
-    any, all            Check if any/all truthy
+    matches(re; flags)  Matches regex pattern (alias: test)
+    any, all            Check if any/all array elements or object values are truthy
+    any(f), all(f)      Check if f holds for any/all elements or object values
     not                 Negate boolean
+    has(k), in(x)       Object key / array index exists (in: reversed)
+
+ENVIRONMENT (requires --allow-env)
+    env("NAME")         Value of an environment variable, or null
+    env, $ENV           Object of all environment variables
+    now                 Current Unix time in seconds
+
+DATE FUNCTIONS
+    strftime(fmt)       Format Unix seconds (UTC) with %Y, %m, %d, %H, %M, %S,
+                        %a, %b, %j, %F, %T, ... e.g. strftime("%F")
 
 CONTENT FUNCTIONS
     content             Section content (for headings)
     md                  Raw markdown
     url, href, src      Get URL/link/image source
     lang                Code block language
+    shift_level(n)      Shift a heading (and its subheadings) by n levels
 
 AGGREGATION FUNCTIONS
     stats               Document statistics
     levels              Heading count by level
     langs               Code block count by language
     types               Link types count
+    task_progress       Checked/total tasks of a list or lists ({done, total, percent})
+    histogram([width])  Bar chart of an object of counts (e.g. langs | histogram)
 
 EXAMPLES
     # List all h2 headings
@@ -475,6 +1596,9 @@ EXAMPLES
     treemd -q '.h | where(contains("API"))' doc.md
     treemd -q '.h[API]' doc.md
 
+    # Combine conditions; in select, | binds tighter than and/or
+    treemd -q '.h | select(.level == 2 and .text | startswith("Use"))' doc.md
+
     # All Rust code blocks
     treemd -q '.code[rust]' doc.md
 
@@ -485,7 +1609,7 @@ EXAMPLES
     treemd -q '.h1[Features] > .h2' doc.md
 
     # Group headings by level
-    treemd -q '[.h] | group_by("level")' doc.md
+    treemd -q '[.h] | group_by(.level)' doc.md
 
     # Document statistics
     treemd -q '. | stats' doc.md
@@ -1,30 +1,85 @@
 //! Build nested JSON output from document structure
 
 use super::content::{parse_content, slugify};
-use super::document::{Document, HeadingNode};
+use super::document::{Document, Heading, HeadingNode};
 use super::output::*;
-use super::utils::get_heading_level;
+use std::ops::Range;
 use std::path::Path;
 
+/// Which code counts toward word counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodeWords {
+    /// Count every whitespace-separated token, code included
+    Include,
+    /// Skip code blocks, which would inflate reading-time estimates
+    #[default]
+    ExcludeBlocks,
+    /// Skip code blocks and inline code spans
+    ExcludeAll,
+}
+
+/// Options for [`build_json_output_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Which code counts toward word counts
+    pub code_words: CodeWords,
+    /// Parse and embed each section's content
+    ///
+    /// Without content, sections carry only their structure and nothing is
+    /// parsed, so word counts fall back to counting raw text (code included).
+    pub content: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            code_words: CodeWords::default(),
+            content: true,
+        }
+    }
+}
+
 /// Build complete JSON output with nested sections and markdown intelligence
 pub fn build_json_output(doc: &Document, source_path: Option<&Path>) -> DocumentOutput {
+    build_json_output_with(doc, source_path, JsonOptions::default())
+}
+
+/// Like [`build_json_output`], with control over word counts and content
+pub fn build_json_output_with(
+    doc: &Document,
+    source_path: Option<&Path>,
+    options: JsonOptions,
+) -> DocumentOutput {
     let tree = doc.build_tree();
+    let code_words = if options.content {
+        options.code_words
+    } else {
+        CodeWords::Include
+    };
 
     // Calculate metadata
     let max_depth = calculate_max_depth(&tree);
-    let word_count = count_words(&doc.content);
+    let word_count = match code_words {
+        CodeWords::Include => count_words(doc.content()),
+        _ => {
+            let heading_words: usize = doc.headings().iter().map(|h| count_words(&h.text)).sum();
+            heading_words + count_block_words(&parse_content(doc.content(), 1), code_words)
+        }
+    };
 
     let metadata = DocumentMetadata {
         source: source_path.map(|p| p.to_string_lossy().to_string()),
-        heading_count: doc.headings.len(),
+        heading_count: doc.headings().len(),
         max_depth,
         word_count,
     };
 
-    // Build sections with content
+    // Build sections with content; the tree is in document order, so a running
+    // index lines each node up with its entry in `doc.headings()`
+    let mut idx = 0;
     let sections = tree
         .iter()
-        .map(|node| build_section(node, &doc.content))
+        .map(|node| build_section(node, doc, &mut idx, code_words, options.content))
         .collect();
 
     DocumentOutput {
@@ -32,82 +87,78 @@ pub fn build_json_output(doc: &Document, source_path: Option<&Path>) -> Document
     }
 }
 
-fn build_section(node: &HeadingNode, full_content: &str) -> Section {
+fn build_section(
+    node: &HeadingNode,
+    doc: &Document,
+    idx: &mut usize,
+    code_words: CodeWords,
+    with_content: bool,
+) -> Section {
     let heading = &node.heading;
 
-    // Extract content for this section
-    let (raw_content, offset, line) = extract_section_content(heading, full_content);
-
-    // Parse content into blocks
-    let blocks = parse_content(&raw_content, line);
+    // Parsed content for this section (cached on the document)
+    let (bounds, line) = section_bounds(doc, heading);
+    let content = with_content.then(|| doc.section_content(*idx).cloned().unwrap_or_default());
+    *idx += 1;
 
     // Build child sections
     let children = node
         .children
         .iter()
-        .map(|child| build_section(child, full_content))
+        .map(|child| build_section(child, doc, idx, code_words, with_content))
         .collect();
 
+    let word_count = match (&content, code_words) {
+        (Some(content), CodeWords::ExcludeBlocks | CodeWords::ExcludeAll) => {
+            count_block_words(&content.blocks, code_words)
+        }
+        (Some(content), CodeWords::Include) => count_words(&content.raw),
+        (None, _) => count_words(doc.content()[bounds.clone()].trim()),
+    };
+
     Section {
         id: slugify(&heading.text),
         level: heading.level,
         title: heading.text.clone(),
+        inline: heading.inline.clone(),
         slug: slugify(&heading.text),
-        position: Position { line, offset },
-        content: Content {
-            raw: raw_content,
-            blocks,
+        position: Position {
+            line,
+            offset: bounds.start,
         },
+        word_count,
+        content,
         children,
     }
 }
 
-fn extract_section_content(
-    heading: &super::document::Heading,
-    full_content: &str,
-) -> (String, usize, usize) {
+/// Byte range of a section's own content and the line that content starts on.
+///
+/// The range runs from the end of the heading (past a setext underline) to
+/// the next heading of any level, since child sections are extracted
+/// separately.
+pub(super) fn section_bounds(doc: &Document, heading: &Heading) -> (Range<usize>, usize) {
     // Use stored byte offset for direct access
     let offset = heading.offset;
 
-    // Calculate line number from byte offset
-    let line = full_content[..offset].lines().count() + 1;
+    // Line number from byte offset, counting a partial line before an
+    // indented heading the way `str::lines` does
+    let mid_line = offset > 0 && !doc.content()[..offset].ends_with('\n');
+    let line = doc.line_at(offset) + usize::from(mid_line);
 
-    // Find content start (skip the heading line itself)
-    let after_heading = &full_content[offset..];
-    let content_start = after_heading.find('\n').map(|i| i + 1).unwrap_or(0);
-    let section_content = &after_heading[content_start..];
+    // Find content start (skip the heading's own lines)
+    let start = doc.heading_end(heading);
+    let heading_lines = doc.content()[offset..start].matches('\n').count();
 
     // Find next heading (any level, since children are extracted separately)
-    let end = find_next_heading(section_content);
-
-    (
-        section_content[..end].trim().to_string(),
-        offset + content_start,
-        line + 1,
-    )
-}
-
-fn find_next_heading(content: &str) -> usize {
-    let mut in_code_block = false;
-    let mut pos = 0;
-
-    for line in content.lines() {
-        // Track code block fences
-        if line.trim_start().starts_with("```") {
-            in_code_block = !in_code_block;
-        }
-
-        // Check for heading only if not in code block
-        if !in_code_block && get_heading_level(line).is_some() {
-            // For nested JSON output, stop at ANY heading (child sections are
-            // extracted separately and included in the children array)
-            return pos;
-        }
-
-        pos += line.len() + 1; // +1 for newline
-    }
+    let end = doc
+        .headings()
+        .iter()
+        .map(|h| h.offset)
+        .find(|&next| next >= start)
+        .unwrap_or(doc.content().len());
 
-    content.len()
+    (start..end, line + heading_lines)
 }
 
 fn calculate_max_depth(tree: &[HeadingNode]) -> usize {
@@ -120,3 +171,65 @@ fn calculate_max_depth(tree: &[HeadingNode]) -> usize {
 fn count_words(content: &str) -> usize {
     content.split_whitespace().count()
 }
+
+/// Count words in parsed blocks, skipping code as requested by `code_words`
+fn count_block_words(blocks: &[Block], code_words: CodeWords) -> usize {
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph { content, inline } => count_inline_words(content, inline, code_words),
+            Block::Code { content, .. } => match code_words {
+                CodeWords::Include => count_words(content),
+                _ => 0,
+            },
+            Block::List { items, .. } => count_list_words(items, code_words),
+            Block::Blockquote { blocks, .. } => count_block_words(blocks, code_words),
+            Block::Table { headers, rows, .. } => headers
+                .iter()
+                .chain(rows.iter().flatten())
+                .map(|cell| count_words(cell))
+                .sum(),
+            Block::Image { alt, .. } => count_words(alt),
+            Block::Details {
+                summary, blocks, ..
+            } => count_words(summary) + count_block_words(blocks, code_words),
+            Block::Footnote { blocks, .. } => count_block_words(blocks, code_words),
+            Block::HorizontalRule | Block::Html { .. } => 0,
+        })
+        .sum()
+}
+
+fn count_list_words(items: &[ListItem], code_words: CodeWords) -> usize {
+    items
+        .iter()
+        .map(|item| {
+            count_inline_words(&item.content, &item.inline, code_words)
+                + count_list_words(&item.children, code_words)
+        })
+        .sum()
+}
+
+/// Words in a paragraph or list item's text, without its raw HTML tags
+fn count_inline_words(content: &str, inline: &[InlineElement], code_words: CodeWords) -> usize {
+    let mut text = content.to_string();
+    for element in inline {
+        if let InlineElement::Html { raw } = element {
+            text = text.replacen(raw.as_str(), "", 1);
+        }
+    }
+    count_words(&text).saturating_sub(inline_code_words(inline, code_words))
+}
+
+/// Words inside inline code spans, when they should be left out of the count
+fn inline_code_words(inline: &[InlineElement], code_words: CodeWords) -> usize {
+    if code_words != CodeWords::ExcludeAll {
+        return 0;
+    }
+    inline
+        .iter()
+        .map(|element| match element {
+            InlineElement::Code { value } => count_words(value),
+            _ => 0,
+        })
+        .sum()
+}
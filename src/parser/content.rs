@@ -6,9 +6,19 @@ use super::output::{Alignment, Block, InlineElement, ListItem};
 use pulldown_cmark::{
     Alignment as CmarkAlignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd,
 };
+use std::ops::Range;
 
 /// Parse markdown content into structured blocks
 pub fn parse_content(markdown: &str, start_line: usize) -> Vec<Block> {
+    parse_content_spans(markdown, start_line)
+        .into_iter()
+        .map(|(block, _)| block)
+        .collect()
+}
+
+/// Parse markdown content into structured blocks, each with the byte range of
+/// `markdown` it was parsed from
+pub(crate) fn parse_content_spans(markdown: &str, start_line: usize) -> Vec<(Block, Range<usize>)> {
     // First, extract any <details> blocks and replace them with placeholders
     let (processed_markdown, details_blocks) = extract_details_blocks(markdown);
 
@@ -17,12 +27,14 @@ pub fn parse_content(markdown: &str, start_line: usize) -> Vec<Block> {
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
 
     let parser = Parser::new_ext(&processed_markdown, options);
     let mut blocks = Vec::new();
-    let mut state = ParserState::new(start_line);
+    let mut state = ParserState::new(&processed_markdown, start_line);
 
-    for event in parser {
+    for (event, range) in parser.into_offset_iter() {
+        state.range = range;
         process_event(event, &mut state, &mut blocks);
     }
 
@@ -31,7 +43,7 @@ pub fn parse_content(markdown: &str, start_line: usize) -> Vec<Block> {
 
     // Replace placeholders with actual Details blocks
     let mut final_blocks = Vec::new();
-    for block in blocks {
+    for (block, span) in blocks.into_iter().zip(state.spans) {
         if let Block::Paragraph { content, .. } = &block {
             // Check if this paragraph contains only the placeholder
             let trimmed = content.trim();
@@ -39,8 +51,8 @@ pub fn parse_content(markdown: &str, start_line: usize) -> Vec<Block> {
                 if let Some(index_str) = trimmed.strip_prefix("[DETAILS_BLOCK_") {
                     if let Some(index_str) = index_str.strip_suffix(']') {
                         if let Ok(index) = index_str.parse::<usize>() {
-                            if let Some(details_block) = details_blocks.get(index) {
-                                final_blocks.push(details_block.clone());
+                            if let Some(details) = details_blocks.get(index) {
+                                final_blocks.push((details.block.clone(), details.source.clone()));
                                 continue;
                             }
                         }
@@ -48,14 +60,36 @@ pub fn parse_content(markdown: &str, start_line: usize) -> Vec<Block> {
                 }
             }
         }
-        final_blocks.push(block);
+        let span =
+            source_offset(&details_blocks, span.start)..source_offset(&details_blocks, span.end);
+        final_blocks.push((block, span));
     }
 
     final_blocks
 }
 
+/// A `<details>` block taken out of the markdown before parsing
+struct ExtractedDetails {
+    block: Block,
+    /// Byte range of the block in the original markdown
+    source: Range<usize>,
+    /// Byte range of its placeholder in the text that gets parsed
+    placeholder: Range<usize>,
+}
+
+/// Map an offset in the text with placeholders back to the original markdown
+fn source_offset(details: &[ExtractedDetails], offset: usize) -> usize {
+    match details.iter().rev().find(|d| d.placeholder.start <= offset) {
+        Some(d) if offset < d.placeholder.end => {
+            d.source.start + (offset - d.placeholder.start).min(d.source.len())
+        }
+        Some(d) => offset - d.placeholder.end + d.source.end,
+        None => offset,
+    }
+}
+
 /// Extract <details> blocks from markdown and replace with placeholders
-fn extract_details_blocks(markdown: &str) -> (String, Vec<Block>) {
+fn extract_details_blocks(markdown: &str) -> (String, Vec<ExtractedDetails>) {
     let mut details_blocks = Vec::new();
     let mut result = String::new();
     let mut current_pos = 0;
@@ -66,6 +100,7 @@ fn extract_details_blocks(markdown: &str) -> (String, Vec<Block>) {
             // Find the end of the opening tag
             if let Some(tag_end) = markdown[current_pos..].find('>') {
                 let details_start = current_pos + tag_end + 1;
+                let open = has_open_attribute(&markdown[current_pos..current_pos + tag_end]);
 
                 // Find the matching </details> tag
                 if let Some(details_end_pos) = markdown[details_start..].find("</details>") {
@@ -114,18 +149,25 @@ fn extract_details_blocks(markdown: &str) -> (String, Vec<Block>) {
                         Vec::new()
                     };
 
+                    // Add placeholder
+                    let placeholder_start = result.len();
+                    result.push_str(&format!("\n[DETAILS_BLOCK_{}]\n", details_blocks.len()));
+
                     // Create the Details block
-                    details_blocks.push(Block::Details {
-                        summary,
-                        content: content_trimmed.to_string(),
-                        blocks: nested_blocks,
+                    let source_end = details_end + "</details>".len();
+                    details_blocks.push(ExtractedDetails {
+                        block: Block::Details {
+                            summary,
+                            content: content_trimmed.to_string(),
+                            blocks: nested_blocks,
+                            open,
+                        },
+                        source: current_pos..source_end,
+                        placeholder: placeholder_start..result.len(),
                     });
 
-                    // Add placeholder
-                    result.push_str(&format!("\n[DETAILS_BLOCK_{}]\n", details_blocks.len() - 1));
-
                     // Skip past the entire details block
-                    current_pos = details_end + "</details>".len();
+                    current_pos = source_end;
                     continue;
                 }
             }
@@ -143,28 +185,72 @@ fn extract_details_blocks(markdown: &str) -> (String, Vec<Block>) {
     (result, details_blocks)
 }
 
-struct ParserState {
+/// Byte ranges of the `<details>...</details>` blocks that `extract_details_blocks` replaces
+pub(crate) fn details_ranges(markdown: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = markdown[pos..].find("<details") {
+        let start = pos + found;
+        let Some(tag_end) = markdown[start..].find('>') else {
+            break;
+        };
+        let details_start = start + tag_end + 1;
+
+        match markdown[details_start..].find("</details>") {
+            Some(end_pos) => {
+                let end = details_start + end_pos + "</details>".len();
+                ranges.push(start..end);
+                pos = end;
+            }
+            None => pos = start + 1,
+        }
+    }
+
+    ranges
+}
+
+/// Check whether an opening `<details ...` tag (without the closing `>`) has an `open` attribute
+fn has_open_attribute(tag: &str) -> bool {
+    tag.trim_start_matches("<details")
+        .split_whitespace()
+        .filter_map(|attr| attr.split('=').next())
+        .any(|name| name.eq_ignore_ascii_case("open"))
+}
+
+/// A list being built, one per nesting level
+struct ListLevel {
+    ordered: bool,
+    /// Number to assign to the next item (ordered lists only)
+    next_number: Option<u64>,
+    items: Vec<ListItem>,
+}
+
+struct ParserState<'a> {
+    /// Markdown being parsed
+    source: &'a str,
     current_line: usize,
+    /// Source range of the event being processed
+    range: Range<usize>,
+    /// Source range of each emitted block, in step with the blocks
+    spans: Vec<Range<usize>>,
     paragraph_buffer: String,
     inline_buffer: Vec<InlineElement>,
-    list_items: Vec<ListItem>,
-    list_ordered: bool,
-    list_depth: usize,
-    item_depth: usize,
-    task_list_marker: Option<bool>,
-    saved_task_markers: Vec<Option<bool>>,
+    /// Open lists, outermost first
+    list_stack: Vec<ListLevel>,
+    /// Open list items, outermost first
+    item_stack: Vec<ListItem>,
     code_buffer: String,
     code_language: Option<String>,
     code_start_line: usize,
-    blockquote_buffer: String,
+    /// Nesting depth of the open blockquotes
+    blockquote_depth: usize,
     table_headers: Vec<String>,
     table_alignments: Vec<Alignment>,
     table_rows: Vec<Vec<String>>,
     current_row: Vec<String>,
     in_paragraph: bool,
-    in_list: bool,
     in_code: bool,
-    in_blockquote: bool,
     in_table: bool,
     in_strong: bool,
     in_emphasis: bool,
@@ -176,32 +262,32 @@ struct ParserState {
     image_in_link: bool,
     in_image: bool,
     saved_link_url: String,
+    html_buffer: String,
+    /// Label of the open footnote definition and the index of its first block
+    footnote: Option<(String, usize)>,
 }
 
-impl ParserState {
-    fn new(start_line: usize) -> Self {
+impl<'a> ParserState<'a> {
+    fn new(source: &'a str, start_line: usize) -> Self {
         Self {
+            source,
             current_line: start_line,
+            range: 0..0,
+            spans: Vec::new(),
             paragraph_buffer: String::new(),
             inline_buffer: Vec::new(),
-            list_items: Vec::new(),
-            list_ordered: false,
-            list_depth: 0,
-            item_depth: 0,
-            task_list_marker: None,
-            saved_task_markers: Vec::new(),
+            list_stack: Vec::new(),
+            item_stack: Vec::new(),
             code_buffer: String::new(),
             code_language: None,
             code_start_line: 0,
-            blockquote_buffer: String::new(),
+            blockquote_depth: 0,
             table_headers: Vec::new(),
             table_alignments: Vec::new(),
             table_rows: Vec::new(),
             current_row: Vec::new(),
             in_paragraph: false,
-            in_list: false,
             in_code: false,
-            in_blockquote: false,
             in_table: false,
             in_strong: false,
             in_emphasis: false,
@@ -213,73 +299,144 @@ impl ParserState {
             image_in_link: false,
             in_image: false,
             saved_link_url: String::new(),
+            html_buffer: String::new(),
+            footnote: None,
         }
     }
 
     fn finalize(&mut self, blocks: &mut Vec<Block>) {
         self.flush_paragraph(blocks);
-        self.flush_list(blocks);
+        while !self.list_stack.is_empty() {
+            self.flush_list(blocks);
+        }
         self.flush_code(blocks);
-        self.flush_blockquote(blocks);
         self.flush_table(blocks);
     }
 
+    /// Emit a block parsed from the current event's source range
+    fn emit(&mut self, blocks: &mut Vec<Block>, block: Block) {
+        blocks.push(block);
+        self.spans.push(self.range.clone());
+    }
+
     fn flush_paragraph(&mut self, blocks: &mut Vec<Block>) {
         if self.in_paragraph && !self.paragraph_buffer.is_empty() {
-            blocks.push(Block::Paragraph {
+            let block = Block::Paragraph {
                 content: self.paragraph_buffer.clone(),
                 inline: self.inline_buffer.clone(),
-            });
+            };
+            self.emit(blocks, block);
             self.paragraph_buffer.clear();
             self.inline_buffer.clear();
             self.in_paragraph = false;
         }
     }
 
+    fn in_list_item(&self) -> bool {
+        !self.item_stack.is_empty()
+    }
+
+    /// Move buffered text into the innermost open list item
+    fn flush_item_text(&mut self) {
+        if let Some(item) = self.item_stack.last_mut() {
+            item.content.push_str(&self.paragraph_buffer);
+            item.inline.append(&mut self.inline_buffer);
+            self.paragraph_buffer.clear();
+        }
+    }
+
+    fn start_list(&mut self, start_number: Option<u64>) {
+        // Text before a nested list belongs to the enclosing item
+        self.flush_item_text();
+        self.list_stack.push(ListLevel {
+            ordered: start_number.is_some(),
+            next_number: start_number,
+            items: Vec::new(),
+        });
+    }
+
+    fn start_item(&mut self) {
+        let number = self.list_stack.last_mut().and_then(|level| {
+            let number = level.next_number;
+            level.next_number = number.map(|n| n + 1);
+            number
+        });
+        self.paragraph_buffer.clear();
+        self.inline_buffer.clear();
+        self.item_stack.push(ListItem {
+            checked: None,
+            number,
+            content: String::new(),
+            inline: Vec::new(),
+            children: Vec::new(),
+        });
+    }
+
+    fn end_item(&mut self) {
+        self.flush_item_text();
+        if let Some(mut item) = self.item_stack.pop() {
+            item.content = item.content.trim_end().to_string();
+            if let Some(level) = self.list_stack.last_mut() {
+                level.items.push(item);
+            }
+        }
+    }
+
+    /// Close the innermost list, attaching it to its parent item or emitting a block
     fn flush_list(&mut self, blocks: &mut Vec<Block>) {
-        if self.in_list && !self.list_items.is_empty() {
-            blocks.push(Block::List {
-                ordered: self.list_ordered,
-                items: self.list_items.clone(),
-            });
-            self.list_items.clear();
-            self.in_list = false;
+        let Some(level) = self.list_stack.pop() else {
+            return;
+        };
+
+        if let Some(parent) = self.item_stack.last_mut() {
+            // An item can hold several lists, e.g. bullets then numbers
+            parent.children.extend(level.items);
+        } else if !level.items.is_empty() {
+            let block = Block::List {
+                ordered: level.ordered,
+                items: level.items,
+            };
+            self.emit(blocks, block);
         }
     }
 
     fn flush_code(&mut self, blocks: &mut Vec<Block>) {
         if self.in_code && !self.code_buffer.is_empty() {
-            blocks.push(Block::Code {
+            let block = Block::Code {
                 language: self.code_language.clone(),
                 content: self.code_buffer.trim_end().to_string(),
                 start_line: self.code_start_line,
                 end_line: self.current_line,
-            });
+            };
+            self.emit(blocks, block);
             self.code_buffer.clear();
             self.code_language = None;
             self.in_code = false;
         }
     }
 
+    /// Emit the blockquote that just closed, parsing its blocks from the
+    /// source with the quote markers removed
     fn flush_blockquote(&mut self, blocks: &mut Vec<Block>) {
-        if self.in_blockquote && !self.blockquote_buffer.is_empty() {
-            let nested_blocks = parse_content(&self.blockquote_buffer, self.current_line);
-            blocks.push(Block::Blockquote {
-                content: self.blockquote_buffer.clone(),
+        let content = strip_quote_markers(&self.source[self.range.clone()]);
+        if !content.trim().is_empty() {
+            let nested_blocks = parse_content(&content, self.current_line);
+            let block = Block::Blockquote {
+                content,
                 blocks: nested_blocks,
-            });
-            self.blockquote_buffer.clear();
-            self.in_blockquote = false;
+            };
+            self.emit(blocks, block);
         }
     }
 
     fn flush_table(&mut self, blocks: &mut Vec<Block>) {
         if self.in_table && !self.table_headers.is_empty() {
-            blocks.push(Block::Table {
+            let block = Block::Table {
                 headers: self.table_headers.clone(),
                 alignments: self.table_alignments.clone(),
                 rows: self.table_rows.clone(),
-            });
+            };
+            self.emit(blocks, block);
             self.table_headers.clear();
             self.table_alignments.clear();
             self.table_rows.clear();
@@ -290,6 +447,44 @@ impl ParserState {
         }
     }
 
+    /// Keep inline HTML as an element, and in the text so the paragraph's
+    /// raw content still reads as written
+    fn add_inline_html(&mut self, raw: &str) {
+        self.inline_buffer.push(InlineElement::Html {
+            raw: raw.to_string(),
+        });
+        self.paragraph_buffer.push_str(raw);
+    }
+
+    /// Keep a footnote reference as an element, written `[^label]` in the text
+    fn add_footnote_reference(&mut self, label: &str) {
+        let marker = format!("[^{}]", label);
+        if self.in_link || self.in_image {
+            self.link_text.push_str(&marker);
+        } else {
+            self.inline_buffer.push(InlineElement::FootnoteReference {
+                label: label.to_string(),
+            });
+            self.paragraph_buffer.push_str(&marker);
+        }
+    }
+
+    /// Close the open footnote definition, moving the blocks parsed since it
+    /// started into it
+    fn flush_footnote(&mut self, blocks: &mut Vec<Block>) {
+        self.flush_paragraph(blocks);
+        if let Some((label, start)) = self.footnote.take() {
+            let inner: Vec<Block> = blocks.drain(start.min(blocks.len())..).collect();
+            self.spans.truncate(blocks.len());
+            let block = Block::Footnote {
+                label,
+                content: super::markdown::render_markdown(&inner),
+                blocks: inner,
+            };
+            self.emit(blocks, block);
+        }
+    }
+
     fn add_inline_text(&mut self, text: &str) {
         if text.is_empty() {
             return;
@@ -322,14 +517,54 @@ impl ParserState {
     }
 }
 
+/// The text of a blockquote with one level of `>` markers removed. Lazy
+/// continuation lines, which have no marker, are kept as they are.
+fn strip_quote_markers(quote: &str) -> String {
+    quote
+        .lines()
+        .map(|line| match line.trim_start().strip_prefix('>') {
+            Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[allow(clippy::too_many_lines)]
 fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>) {
+    // A blockquote is parsed from its source once it closes
+    if state.blockquote_depth > 0 {
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => state.blockquote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => {
+                state.blockquote_depth -= 1;
+                if state.blockquote_depth == 0 {
+                    state.flush_blockquote(blocks);
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+
     match event {
         Event::Start(Tag::Paragraph) => {
+            // Separate paragraphs of a loose list item
+            if state.in_list_item() && !state.paragraph_buffer.is_empty() {
+                state.paragraph_buffer.push('\n');
+                state.inline_buffer.push(InlineElement::Text {
+                    value: "\n".to_string(),
+                });
+            }
             state.in_paragraph = true;
         }
         Event::End(TagEnd::Paragraph) => {
-            state.flush_paragraph(blocks);
+            if state.in_list_item() {
+                // Keep the text for the enclosing list item
+                state.in_paragraph = false;
+            } else {
+                state.flush_paragraph(blocks);
+            }
         }
         Event::Start(Tag::CodeBlock(kind)) => {
             state.in_code = true;
@@ -349,65 +584,24 @@ fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>)
             state.flush_code(blocks);
         }
         Event::Start(Tag::List(start_number)) => {
-            state.list_depth += 1;
-            // Only set list properties for the outermost list
-            if state.list_depth == 1 {
-                state.in_list = true;
-                state.list_ordered = start_number.is_some();
-            }
+            state.start_list(start_number);
         }
         Event::End(TagEnd::List(_)) => {
-            state.list_depth = state.list_depth.saturating_sub(1);
-            // Only flush when exiting the outermost list
-            if state.list_depth == 0 {
-                state.flush_list(blocks);
-            }
+            state.flush_list(blocks);
         }
         Event::Start(Tag::Item) => {
-            state.item_depth += 1;
-
-            // Save current task marker when entering nested item
-            if state.item_depth > 1 {
-                state.saved_task_markers.push(state.task_list_marker);
-                state.task_list_marker = None;
-            }
-
-            // Only clear buffer for root-level items
-            if state.item_depth == 1 {
-                state.paragraph_buffer.clear();
-                state.inline_buffer.clear();
-            }
+            state.start_item();
         }
         Event::End(TagEnd::Item) => {
-            // Restore saved task marker when exiting nested item
-            if state.item_depth > 1 {
-                if let Some(saved) = state.saved_task_markers.pop() {
-                    state.task_list_marker = saved;
-                }
-            }
-
-            // Only save items at root level (depth 1)
-            if state.item_depth == 1 {
-                state.list_items.push(ListItem {
-                    checked: state.task_list_marker,
-                    content: state.paragraph_buffer.clone(),
-                    inline: state.inline_buffer.clone(),
-                });
-                state.paragraph_buffer.clear();
-                state.inline_buffer.clear();
-                state.task_list_marker = None;
-            }
-            state.item_depth = state.item_depth.saturating_sub(1);
+            state.end_item();
         }
         Event::TaskListMarker(checked) => {
-            state.task_list_marker = Some(checked);
-            // Checkbox marker will be added when text is encountered (see Text event)
+            if let Some(item) = state.item_stack.last_mut() {
+                item.checked = Some(checked);
+            }
         }
         Event::Start(Tag::BlockQuote(_)) => {
-            state.in_blockquote = true;
-        }
-        Event::End(TagEnd::BlockQuote(_)) => {
-            state.flush_blockquote(blocks);
+            state.blockquote_depth = 1;
         }
         Event::Start(Tag::Table(alignments)) => {
             state.in_table = true;
@@ -541,7 +735,7 @@ fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>)
                     // Flush any pending blocks before adding block image
                     state.flush_paragraph(blocks);
 
-                    blocks.push(Block::Image {
+                    let block = Block::Image {
                         alt: state.link_text.clone(),
                         src: state.link_url.clone(),
                         title: if state.paragraph_buffer.is_empty() {
@@ -549,7 +743,8 @@ fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>)
                         } else {
                             Some(state.paragraph_buffer.clone())
                         },
-                    });
+                    };
+                    state.emit(blocks, block);
                 }
 
                 state.link_text.clear();
@@ -561,35 +756,14 @@ fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>)
         Event::Text(text) => {
             if state.in_code {
                 state.code_buffer.push_str(&text);
-            } else if state.in_blockquote {
-                state.blockquote_buffer.push_str(&text);
             } else if state.in_link || state.in_image {
                 state.link_text.push_str(&text);
             } else {
-                // Add indentation for nested list items
-                if state.in_list && state.item_depth > 1 {
-                    // Add newline and indentation before nested item text
-                    if !state.paragraph_buffer.is_empty() && !state.paragraph_buffer.ends_with('\n')
-                    {
-                        state.paragraph_buffer.push('\n');
-                    }
-                    // Add indentation based on depth
-                    let indent = "  ".repeat(state.item_depth - 1);
-                    state.paragraph_buffer.push_str(&indent);
-
-                    // Add checkbox marker if this is a task list item
-                    if let Some(checked) = state.task_list_marker {
-                        let marker = if checked { "[x] " } else { "[ ] " };
-                        state.paragraph_buffer.push_str(marker);
-                        // Clear the marker so it's only added once
-                        state.task_list_marker = None;
-                    }
-                }
                 state.add_inline_text(&text);
             }
         }
         Event::SoftBreak => {
-            if state.in_paragraph {
+            if state.in_paragraph || state.in_list_item() {
                 state.paragraph_buffer.push(' ');
                 state.inline_buffer.push(InlineElement::Text {
                     value: " ".to_string(),
@@ -606,7 +780,40 @@ fn process_event(event: Event, state: &mut ParserState, blocks: &mut Vec<Block>)
         }
         Event::Rule => {
             state.flush_paragraph(blocks);
-            blocks.push(Block::HorizontalRule);
+            state.emit(blocks, Block::HorizontalRule);
+        }
+        Event::Html(html) => {
+            state.html_buffer.push_str(&html);
+        }
+        Event::End(TagEnd::HtmlBlock) => {
+            let html = std::mem::take(&mut state.html_buffer);
+            let raw = html.trim_end();
+            if state.in_list_item() {
+                state.add_inline_html(raw);
+            } else if !raw.is_empty() {
+                state.flush_paragraph(blocks);
+                let block = Block::Html {
+                    raw: raw.to_string(),
+                };
+                state.emit(blocks, block);
+            }
+        }
+        Event::InlineHtml(html) => {
+            if state.in_link || state.in_image {
+                state.link_text.push_str(&html);
+            } else {
+                state.add_inline_html(&html);
+            }
+        }
+        Event::Start(Tag::FootnoteDefinition(label)) => {
+            state.flush_paragraph(blocks);
+            state.footnote = Some((label.to_string(), blocks.len()));
+        }
+        Event::End(TagEnd::FootnoteDefinition) => {
+            state.flush_footnote(blocks);
+        }
+        Event::FootnoteReference(label) => {
+            state.add_footnote_reference(&label);
         }
         Event::Start(Tag::Heading { .. }) => {
             // Clear buffers when entering a heading - we don't include headings in parsed blocks
@@ -642,3 +849,176 @@ pub fn slugify(text: &str) -> String {
         .collect::<Vec<_>>()
         .join("-")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_mixed_list() {
+        let md =
+            "3. First\n   - Bullet a\n   - Bullet b\n4. Second\n   1. Sub one\n   2. Sub two\n";
+        let blocks = parse_content(md, 0);
+        assert_eq!(blocks.len(), 1);
+
+        let Block::List { ordered, items } = &blocks[0] else {
+            panic!("expected list block");
+        };
+        assert!(ordered);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].number, Some(3));
+        assert_eq!(items[0].content, "First");
+        assert_eq!(items[0].children.len(), 2);
+        assert_eq!(items[0].children[0].content, "Bullet a");
+        assert_eq!(items[0].children[0].number, None);
+
+        assert_eq!(items[1].number, Some(4));
+        assert_eq!(items[1].content, "Second");
+        let numbers: Vec<_> = items[1].children.iter().map(|i| i.number).collect();
+        assert_eq!(numbers, vec![Some(1), Some(2)]);
+        assert_eq!(items[1].children[1].content, "Sub two");
+    }
+
+    #[test]
+    fn test_item_with_two_nested_lists() {
+        let md = "- Parent\n  - Bullet\n\n  1. Number\n- Next\n";
+        let blocks = parse_content(md, 0);
+
+        let Block::List { items, .. } = &blocks[0] else {
+            panic!("expected list block");
+        };
+        let children: Vec<_> = items[0]
+            .children
+            .iter()
+            .map(|i| i.content.as_str())
+            .collect();
+        assert_eq!(children, ["Bullet", "Number"]);
+        assert_eq!(items[0].children[1].number, Some(1));
+        assert_eq!(items[1].content, "Next");
+    }
+
+    #[test]
+    fn test_nested_task_list() {
+        let md = "- [ ] Parent\n  - [x] Child\n- Plain\n";
+        let blocks = parse_content(md, 0);
+
+        let Block::List { items, .. } = &blocks[0] else {
+            panic!("expected list block");
+        };
+        assert_eq!(items[0].checked, Some(false));
+        assert_eq!(items[0].content, "Parent");
+        assert_eq!(items[0].children[0].checked, Some(true));
+        assert_eq!(items[1].checked, None);
+    }
+
+    #[test]
+    fn test_inline_and_block_html_are_kept() {
+        let md = "Press <kbd>Ctrl</kbd>+C<br>\nto stop.\n\n<div align=\"center\">\n  <b>Hi</b>\n</div>\n";
+        let blocks = parse_content(md, 0);
+
+        let Block::Paragraph { content, inline } = &blocks[0] else {
+            panic!("expected paragraph");
+        };
+        let html: Vec<&str> = inline
+            .iter()
+            .filter_map(|e| match e {
+                InlineElement::Html { raw } => Some(raw.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(html, ["<kbd>", "</kbd>", "<br>"]);
+        assert!(inline.contains(&InlineElement::Text {
+            value: "Ctrl".to_string()
+        }));
+        assert_eq!(content, "Press <kbd>Ctrl</kbd>+C<br> to stop.");
+
+        let Block::Html { raw } = &blocks[1] else {
+            panic!("expected html block, got {:?}", blocks[1]);
+        };
+        assert_eq!(raw, "<div align=\"center\">\n  <b>Hi</b>\n</div>");
+
+        // Re-serializing keeps the HTML as written
+        let markdown = crate::parser::render_markdown(&blocks);
+        assert!(
+            markdown.starts_with("Press <kbd>Ctrl</kbd>+C<br>"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.ends_with("<div align=\"center\">\n  <b>Hi</b>\n</div>"));
+    }
+
+    #[test]
+    fn test_footnotes_link_references_to_definitions() {
+        let md = "Claim[^1] and another[^src].\n\n[^1]: First note.\n\n[^src]: Second note\n    with more.\n";
+        let blocks = parse_content(md, 0);
+        assert_eq!(blocks.len(), 3);
+
+        let Block::Paragraph { content, inline } = &blocks[0] else {
+            panic!("expected paragraph");
+        };
+        assert_eq!(content, "Claim[^1] and another[^src].");
+        let references: Vec<&str> = inline
+            .iter()
+            .filter_map(|e| match e {
+                InlineElement::FootnoteReference { label } => Some(label.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(references, ["1", "src"]);
+
+        let definitions: Vec<(&str, &str)> = blocks[1..]
+            .iter()
+            .map(|block| match block {
+                Block::Footnote { label, content, .. } => (label.as_str(), content.as_str()),
+                other => panic!("expected footnote, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            definitions,
+            [("1", "First note."), ("src", "Second note with more.")]
+        );
+
+        let markdown = crate::parser::render_markdown(&blocks);
+        assert!(markdown.contains("[^1]: First note."), "{}", markdown);
+        assert_eq!(parse_content(&markdown, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_details_open_attribute() {
+        let md = "<details open>\n<summary>Shown</summary>\n\nBody\n</details>\n\n<details>\n<summary>Hidden</summary>\n\nBody\n</details>\n";
+        let blocks = parse_content(md, 0);
+        assert_eq!(blocks.len(), 2);
+
+        let Block::Details { summary, open, .. } = &blocks[0] else {
+            panic!("expected details block");
+        };
+        assert_eq!(summary, "Shown");
+        assert!(*open);
+
+        let Block::Details { summary, open, .. } = &blocks[1] else {
+            panic!("expected details block");
+        };
+        assert_eq!(summary, "Hidden");
+        assert!(!*open);
+    }
+
+    #[test]
+    fn test_details_without_closing_bracket_is_left_as_text() {
+        let blocks = parse_content("<details open", 0);
+        assert!(!blocks.iter().any(|b| matches!(b, Block::Details { .. })));
+    }
+
+    #[test]
+    fn test_loose_list_keeps_item_text() {
+        let md = "1. One\n\n2. Two\n";
+        let blocks = parse_content(md, 0);
+        assert_eq!(blocks.len(), 1);
+
+        let Block::List { items, .. } = &blocks[0] else {
+            panic!("expected list block");
+        };
+        assert_eq!(items[0].content, "One");
+        assert_eq!(items[1].content, "Two");
+    }
+}
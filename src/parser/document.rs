@@ -3,18 +3,146 @@
 //! This module defines the core data structures for representing
 //! markdown documents and their heading hierarchy.
 
+use super::output::{Block, Content, InlineElement, is_plain_text};
 use indextree::{Arena, NodeId};
 use serde::Serialize;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
 
 /// A markdown document with its content and structure.
 ///
 /// Contains the original markdown content and a list of extracted headings.
+/// Line positions and parsed section content are computed on first use and
+/// cached; both fields are read-only so the caches can't go stale.
 #[derive(Debug, Clone)]
 pub struct Document {
-    pub content: String,
-    pub headings: Vec<Heading>,
+    content: String,
+    headings: Vec<Heading>,
+    /// Byte offset of the start of each line
+    line_starts: OnceLock<Vec<usize>>,
+    /// Parsed content of each heading's section, filled in by [`Document::section_content`]
+    sections: OnceLock<Vec<OnceLock<ParsedSection>>>,
+    /// Parsed content before the first heading
+    preamble: OnceLock<ParsedSection>,
 }
 
+/// Parsed content of part of a document, with the byte range of the document
+/// each block was parsed from
+#[derive(Debug, Clone)]
+struct ParsedSection {
+    content: Content,
+    spans: Vec<Range<usize>>,
+}
+
+impl ParsedSection {
+    /// Parse `doc.content[bounds]`, whose first line is line `line`
+    fn parse(doc: &Document, bounds: Range<usize>, line: usize) -> Self {
+        let body = &doc.content[bounds.clone()];
+        // Parse from the first non-blank line, keeping its indentation so an
+        // indented code block there stays one
+        let lead = body.len() - body.trim_start().len();
+        let start = bounds.start + body[..lead].rfind('\n').map_or(0, |i| i + 1);
+        let source = doc.content[start..bounds.end].trim_end();
+
+        let (blocks, spans) = super::content::parse_content_spans(source, line)
+            .into_iter()
+            .map(|(block, span)| (block, start + span.start..start + span.end))
+            .unzip();
+        let raw = body.trim().to_string();
+        ParsedSection {
+            content: Content { raw, blocks },
+            spans,
+        }
+    }
+}
+
+/// A section whose body matched a [`Document::grep_sections`] search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionMatch<'a> {
+    /// The heading that owns the matching section
+    pub heading: &'a Heading,
+    /// Matching lines as (1-based line number, line text)
+    pub lines: Vec<(usize, &'a str)>,
+    /// Runs of lines around the matches, context included, when searched with
+    /// [`Document::grep_sections_with_context`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<Vec<(usize, &'a str)>>,
+}
+
+/// A heading paired with its section body, yielded by [`Document::sections`].
+#[derive(Debug, Clone)]
+pub struct SectionView<'a> {
+    /// Index of the heading in [`Document::headings`]
+    pub index: usize,
+    pub heading: &'a Heading,
+    /// The section's own markdown, trimmed: from the line after the heading up
+    /// to the next heading of any level, so subsections are not included
+    pub body: &'a str,
+    /// Indexes of the direct subsections, as in [`Document::build_tree`]
+    pub children: Vec<usize>,
+    doc: &'a Document,
+}
+
+impl<'a> SectionView<'a> {
+    /// Parsed blocks of the body, via [`Document::section_content`].
+    pub fn content(&self) -> &'a Content {
+        self.doc
+            .section_content(self.index)
+            .expect("section index is in range")
+    }
+}
+
+/// Iterator over a document's sections in document order.
+///
+/// Created by [`Document::sections`]. Each section's body and children are
+/// found as it is reached; nothing is parsed until [`SectionView::content`].
+#[derive(Debug, Clone)]
+pub struct Sections<'a> {
+    doc: &'a Document,
+    next: usize,
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = SectionView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next;
+        let heading = self.doc.headings.get(index)?;
+        self.next += 1;
+
+        // A later heading is a direct child while no heading in between is
+        // shallower than it; the section ends at the first one that isn't deeper
+        let mut children = Vec::new();
+        let mut shallowest = usize::MAX;
+        for (idx, h) in self.doc.headings.iter().enumerate().skip(index + 1) {
+            if h.level <= heading.level {
+                break;
+            }
+            if h.level <= shallowest {
+                shallowest = h.level;
+                children.push(idx);
+            }
+        }
+
+        let (bounds, _) = super::builder::section_bounds(self.doc, heading);
+        Some(SectionView {
+            index,
+            heading,
+            body: self.doc.content[bounds].trim(),
+            children,
+            doc: self.doc,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.doc.headings.len().saturating_sub(self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Sections<'_> {}
+
 /// A heading in a markdown document.
 ///
 /// Represents a single heading with its level (1-6), text content, and byte position.
@@ -27,6 +155,10 @@ pub struct Heading {
     /// Byte offset where the heading starts in the source document
     #[serde(skip_serializing)]
     pub offset: usize,
+    /// Inline structure of the heading (links, code, emphasis), omitted from
+    /// JSON when the heading is plain text
+    #[serde(skip_serializing_if = "is_plain_text")]
+    pub inline: Vec<InlineElement>,
 }
 
 /// A node in the heading tree.
@@ -35,12 +167,58 @@ pub struct Heading {
 #[derive(Debug, Clone)]
 pub struct HeadingNode {
     pub heading: Heading,
+    /// Hierarchical section number, e.g. `1.2.1`
+    pub number: String,
     pub children: Vec<HeadingNode>,
 }
 
 impl Document {
     pub fn new(content: String, headings: Vec<Heading>) -> Self {
-        Self { content, headings }
+        Self {
+            content,
+            headings,
+            line_starts: OnceLock::new(),
+            sections: OnceLock::new(),
+            preamble: OnceLock::new(),
+        }
+    }
+
+    /// The markdown source
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Headings in document order
+    pub fn headings(&self) -> &[Heading] {
+        &self.headings
+    }
+
+    /// Parse markdown content into a document.
+    ///
+    /// Equivalent to [`parse_markdown`](super::parse_markdown).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::Document;
+    ///
+    /// let doc = Document::parse("# Hello\n## World");
+    /// assert_eq!(doc.headings().len(), 2);
+    /// assert_eq!(doc.headings()[1].text, "World");
+    /// ```
+    pub fn parse(content: &str) -> Self {
+        super::parse_markdown(content)
+    }
+
+    /// Read and parse a markdown file.
+    ///
+    /// Equivalent to [`parse_file`](super::parse_file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn parse_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        super::parse_file(path.as_ref())
     }
 
     /// Build a hierarchical tree from flat heading list
@@ -71,12 +249,81 @@ impl Document {
         }
 
         // Convert arena to tree structure
-        roots
+        let mut tree: Vec<HeadingNode> = roots
             .into_iter()
             .map(|root_id| build_heading_node(root_id, &arena))
+            .collect();
+        assign_numbers(&mut tree, "");
+        tree
+    }
+
+    /// Hierarchical section numbers (`1`, `1.1`, `1.2.1`, ...) for each heading.
+    ///
+    /// Numbers follow the tree from [`build_tree`](Self::build_tree), so a
+    /// heading that skips levels (an H3 directly under an H1) is numbered as
+    /// the next child of its parent (`1.1`). Returned in document order.
+    pub fn heading_numbers(&self) -> Vec<String> {
+        fn collect(nodes: &[HeadingNode], numbers: &mut Vec<String>) {
+            for node in nodes {
+                numbers.push(node.number.clone());
+                collect(&node.children, numbers);
+            }
+        }
+
+        let mut numbers = Vec::with_capacity(self.headings.len());
+        collect(&self.build_tree(), &mut numbers);
+        numbers
+    }
+
+    /// Ancestors of the heading at `idx`, outermost first.
+    ///
+    /// Matches the parents in [`build_tree`](Self::build_tree): each ancestor
+    /// is the nearest earlier heading with a lower level than the one below
+    /// it. Empty for top-level headings and out-of-range indexes.
+    pub fn ancestors(&self, idx: usize) -> Vec<&Heading> {
+        let Some(mut level) = self.headings.get(idx).map(|h| h.level) else {
+            return Vec::new();
+        };
+        let mut ancestors = Vec::new();
+        for heading in self.headings[..idx].iter().rev() {
+            if heading.level < level {
+                level = heading.level;
+                ancestors.push(heading);
+            }
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Index of each heading's parent, the last of its
+    /// [`ancestors`](Self::ancestors), or `None` for top-level headings.
+    pub fn heading_parents(&self) -> Vec<Option<usize>> {
+        let mut stack: Vec<usize> = Vec::new();
+        self.headings
+            .iter()
+            .enumerate()
+            .map(|(i, heading)| {
+                while stack
+                    .last()
+                    .is_some_and(|&top| self.headings[top].level >= heading.level)
+                {
+                    stack.pop();
+                }
+                let parent = stack.last().copied();
+                stack.push(i);
+                parent
+            })
             .collect()
     }
 
+    /// Number of sections whose content has been parsed so far
+    #[cfg(test)]
+    pub(crate) fn parsed_section_count(&self) -> usize {
+        self.sections.get().map_or(0, |sections| {
+            sections.iter().filter(|s| s.get().is_some()).count()
+        })
+    }
+
     /// Get headings at a specific level
     pub fn headings_at_level(&self, level: usize) -> Vec<&Heading> {
         self.headings.iter().filter(|h| h.level == level).collect()
@@ -99,6 +346,312 @@ impl Document {
             .collect()
     }
 
+    /// Get all headings whose text matches a regular expression.
+    ///
+    /// Unlike [`filter_headings`](Self::filter_headings), matching is case-sensitive
+    /// unless the pattern asks otherwise with `(?i)`. Fails if the pattern is invalid.
+    pub fn filter_headings_regex(&self, pattern: &str) -> Result<Vec<&Heading>, regex::Error> {
+        let re = regex::Regex::new(pattern)?;
+        Ok(self
+            .headings
+            .iter()
+            .filter(|h| re.is_match(&h.text))
+            .collect())
+    }
+
+    /// Convert a byte offset into a 1-based line number.
+    ///
+    /// Uses a line index built on first call, so repeated lookups on large
+    /// documents don't rescan the content.
+    pub fn line_at(&self, offset: usize) -> usize {
+        let line_starts = self.line_starts.get_or_init(|| {
+            std::iter::once(0)
+                .chain(self.content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect()
+        });
+        line_starts.partition_point(|&start| start <= offset)
+    }
+
+    /// Convert a byte offset into a 1-based `(line, column)` pair.
+    ///
+    /// The column counts characters rather than bytes, so positions after
+    /// multi-byte text line up with what an editor displays.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.content.len());
+        let before = &self.content[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+        (self.line_at(offset), column)
+    }
+
+    /// Parsed content of the section under heading `idx`, excluding subsections.
+    ///
+    /// The section runs from the line after the heading up to the next heading
+    /// of any level, exactly as in [`build_json_output`](super::build_json_output).
+    /// Content is parsed on first access and cached, so callers that only look
+    /// at a few sections of a large document don't pay for the rest.
+    pub fn section_content(&self, idx: usize) -> Option<&Content> {
+        self.parsed_section(idx).map(|section| &section.content)
+    }
+
+    /// Parsed blocks of a section and its subsections, in document order: the
+    /// content [`extract_section`](Self::extract_section) returns for
+    /// `heading_text`, or the whole document without it.
+    ///
+    /// Built from the cached [`section_content`](Self::section_content) of
+    /// each heading involved, so only those sections are ever parsed.
+    pub fn section_blocks(&self, heading_text: Option<&str>) -> Vec<&Block> {
+        let search = heading_text.map(str::to_lowercase);
+        let found = search.and_then(|search| {
+            self.headings
+                .iter()
+                .position(|h| h.text.to_lowercase() == search)
+        });
+        let Some(idx) = found else {
+            return self.blocks().map(|(block, _)| block).collect();
+        };
+
+        let end = self.section_end(idx);
+        (idx..self.headings.len())
+            .take_while(|&i| i == idx || self.headings[i].offset < end)
+            .filter_map(|i| self.section_content(i))
+            .flat_map(|content| &content.blocks)
+            .collect()
+    }
+
+    /// Every parsed block of the document with the byte range it was parsed
+    /// from: the content before the first heading, then each section's.
+    pub(crate) fn blocks(&self) -> impl Iterator<Item = (&Block, Range<usize>)> {
+        let preamble = self.preamble.get_or_init(|| {
+            let end = self
+                .headings
+                .first()
+                .map_or(self.content.len(), |h| h.offset);
+            ParsedSection::parse(self, 0..end, 1)
+        });
+        std::iter::once(preamble)
+            .chain((0..self.headings.len()).filter_map(|idx| self.parsed_section(idx)))
+            .flat_map(|section| {
+                section
+                    .content
+                    .blocks
+                    .iter()
+                    .zip(section.spans.iter().cloned())
+            })
+    }
+
+    fn parsed_section(&self, idx: usize) -> Option<&ParsedSection> {
+        let heading = self.headings.get(idx)?;
+        let sections = self
+            .sections
+            .get_or_init(|| vec![OnceLock::new(); self.headings.len()]);
+
+        Some(sections.get(idx)?.get_or_init(|| {
+            let (bounds, line) = super::builder::section_bounds(self, heading);
+            ParsedSection::parse(self, bounds, line)
+        }))
+    }
+
+    /// Iterate over every heading with its section body and subsections.
+    ///
+    /// An entry point for tools that walk a document section by section
+    /// without the query engine or the full JSON output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::Document;
+    ///
+    /// let doc = Document::parse("# Guide\n\nIntro.\n\n## Install\n\nRun it.\n");
+    /// for section in doc.sections() {
+    ///     println!("{} {}", "#".repeat(section.heading.level), section.heading.text);
+    /// }
+    ///
+    /// let guide = doc.sections().next().unwrap();
+    /// assert_eq!(guide.body, "Intro.");
+    /// assert_eq!(guide.children, [1]);
+    /// ```
+    pub fn sections(&self) -> Sections<'_> {
+        Sections { doc: self, next: 0 }
+    }
+
+    /// The start of the section under heading `idx` as plain text.
+    ///
+    /// Markdown is stripped from the parsed [`section_content`](Self::section_content)
+    /// and whitespace collapsed to single spaces. Previews longer than
+    /// `max_chars` characters are cut there and end with `…`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::Document;
+    ///
+    /// let doc = Document::parse("# Intro\n\nSome **bold** [text](/t).\n## Next\n");
+    /// assert_eq!(doc.section_preview(0, 80).unwrap(), "Some bold text.");
+    /// assert_eq!(doc.section_preview(0, 8).unwrap(), "Some bol…");
+    /// ```
+    pub fn section_preview(&self, idx: usize, max_chars: usize) -> Option<String> {
+        let content = self.section_content(idx)?;
+        let text = super::output::blocks_plain_text(&content.blocks)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(match text.char_indices().nth(max_chars) {
+            Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+            None => text,
+        })
+    }
+
+    /// The front matter block at the start of the document, if any.
+    pub fn front_matter(&self) -> Option<super::frontmatter::FrontMatter<'_>> {
+        super::frontmatter::split_front_matter(&self.content)
+    }
+
+    /// Checked and total task-list items in the section under heading `idx`,
+    /// excluding its subsections.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::Document;
+    ///
+    /// let doc = Document::parse("# Todo\n\n- [x] a\n- [ ] b\n  - [x] c\n- d\n");
+    /// assert_eq!(doc.section_tasks(0), Some((2, 3)));
+    /// ```
+    pub fn section_tasks(&self, idx: usize) -> Option<(usize, usize)> {
+        let content = self.section_content(idx)?;
+        Some(super::output::blocks_task_counts(&content.blocks))
+    }
+
+    /// Search the body of every section, returning headings whose body matches.
+    ///
+    /// A section's body runs from the line after its heading up to the next
+    /// heading of any level, so a match is attributed to the innermost section
+    /// containing it. Each match carries the 1-based line numbers and text of the
+    /// matching lines.
+    pub fn grep_sections<F>(&self, is_match: F) -> Vec<SectionMatch<'_>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.grep_sections_with_context(is_match, 0)
+    }
+
+    /// Like [`grep_sections`](Self::grep_sections), also collecting up to
+    /// `context` lines before and after each match.
+    ///
+    /// Context stays inside the matching section's body. Overlapping or
+    /// adjacent windows merge into one run in [`SectionMatch::context`]; with a
+    /// `context` of 0 no runs are collected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::Document;
+    ///
+    /// let doc = Document::parse("# A\none\ntwo\nthree\nfour\n");
+    /// let matches = doc.grep_sections_with_context(|line| line == "one", 1);
+    /// assert_eq!(matches[0].context, vec![vec![(2, "one"), (3, "two")]]);
+    /// ```
+    pub fn grep_sections_with_context<F>(
+        &self,
+        is_match: F,
+        context: usize,
+    ) -> Vec<SectionMatch<'_>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut matches = Vec::new();
+
+        for (idx, heading) in self.headings.iter().enumerate() {
+            let start = self.heading_end(heading);
+            let end = self
+                .headings
+                .get(idx + 1)
+                .map(|h| h.offset)
+                .unwrap_or(self.content.len());
+
+            let first_line = self.line_col(start).0;
+            let body: Vec<_> = (first_line..)
+                .zip(self.content[start..end.max(start)].lines())
+                .collect();
+            let hits: Vec<usize> = (0..body.len()).filter(|&i| is_match(body[i].1)).collect();
+            if hits.is_empty() {
+                continue;
+            }
+
+            let context = if context == 0 {
+                Vec::new()
+            } else {
+                context_windows(&hits, context, body.len())
+                    .into_iter()
+                    .map(|window| body[window].to_vec())
+                    .collect()
+            };
+            let lines = hits.into_iter().map(|i| body[i]).collect();
+            matches.push(SectionMatch {
+                heading,
+                lines,
+                context,
+            });
+        }
+
+        matches
+    }
+
+    /// Runs of lines around the given 1-based line numbers, with up to
+    /// `context` lines on each side.
+    ///
+    /// Overlapping or adjacent windows merge into one run of (line number,
+    /// text) pairs. `lines` should be sorted; out-of-range lines are ignored.
+    pub fn line_context(&self, lines: &[usize], context: usize) -> Vec<Vec<(usize, &str)>> {
+        let all: Vec<&str> = self.content.lines().collect();
+        let hits: Vec<usize> = lines
+            .iter()
+            .filter(|&&line| (1..=all.len()).contains(&line))
+            .map(|line| line - 1)
+            .collect();
+        context_windows(&hits, context, all.len())
+            .into_iter()
+            .map(|window| window.clone().map(|i| (i + 1, all[i])).collect())
+            .collect()
+    }
+
+    /// Find the first section whose body matches, returning its heading and full text.
+    ///
+    /// The match is attributed to the innermost section (see
+    /// [`grep_sections`](Self::grep_sections)); the returned text is that whole
+    /// section, heading included, up to the next heading of the same or higher level.
+    pub fn find_section_matching<F>(&self, is_match: F) -> Option<(&Heading, &str)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let heading = self.grep_sections(is_match).first()?.heading;
+        let heading_idx = self
+            .headings
+            .iter()
+            .position(|h| h.offset == heading.offset)?;
+
+        let end = self.section_end(heading_idx);
+        Some((heading, self.content[heading.offset..end].trim_end()))
+    }
+
+    /// Find a section by heading text (case-insensitive) and return its
+    /// heading with the section's markdown as written, heading line included.
+    ///
+    /// The section is cut at heading offsets, so setext headings (`Title`
+    /// underlined with `===` or `---`) work the same as ATX ones.
+    pub fn find_section(&self, heading_text: &str) -> Option<(&Heading, &str)> {
+        let search = heading_text.to_lowercase();
+        let heading_idx = self
+            .headings
+            .iter()
+            .position(|h| h.text.to_lowercase() == search)?;
+
+        let heading = &self.headings[heading_idx];
+        let end = self.section_end(heading_idx);
+        Some((heading, self.content[heading.offset..end].trim_end()))
+    }
+
     /// Extract the content of a section by heading text.
     ///
     /// Uses stored byte offsets for fast, accurate extraction without string searching.
@@ -111,28 +664,69 @@ impl Document {
 
         let heading = &self.headings[heading_idx];
 
-        // Start from the heading's stored byte offset
-        let start = heading.offset;
-
-        // Find content start (skip the heading line itself)
-        let after_heading = &self.content[start..];
-        let content_start = after_heading
-            .find('\n')
-            .map(|i| start + i + 1)
-            .unwrap_or(start);
+        // Find content start (skip the heading line, and a setext underline)
+        let content_start = self.heading_end(heading);
 
         // Find end: next heading at same or higher level
-        let end = self
-            .headings
+        let end = self.section_end(heading_idx).max(content_start);
+
+        // Extract section content
+        Some(self.content[content_start..end].trim().to_string())
+    }
+
+    /// Byte offset just past a heading's source: its line for an ATX heading,
+    /// or through the `===`/`---` underline for a setext heading.
+    pub(crate) fn heading_end(&self, heading: &Heading) -> usize {
+        heading.offset + heading_source_len(&self.content[heading.offset..])
+    }
+
+    /// Byte offset where the section of `headings[heading_idx]` ends: the
+    /// next heading at the same or a higher level, or the end of the document.
+    fn section_end(&self, heading_idx: usize) -> usize {
+        let level = self.headings[heading_idx].level;
+        self.headings
             .iter()
             .skip(heading_idx + 1)
-            .find(|h| h.level <= heading.level)
+            .find(|h| h.level <= level)
             .map(|h| h.offset)
-            .unwrap_or(self.content.len());
+            .unwrap_or(self.content.len())
+    }
+}
 
-        // Extract section content
-        Some(self.content[content_start..end].trim().to_string())
+/// Length of the heading at the start of `source` (see [`Document::heading_end`]).
+fn heading_source_len(source: &str) -> usize {
+    let first_line = source.find('\n').map_or(source.len(), |i| i + 1);
+    if source.trim_start().starts_with('#') {
+        return first_line;
     }
+
+    let mut len = 0;
+    for line in source.split_inclusive('\n') {
+        let marker = line.trim();
+        if marker.is_empty() {
+            break;
+        }
+        len += line.len();
+        let is_underline = marker.bytes().all(|b| b == b'=') || marker.bytes().all(|b| b == b'-');
+        if is_underline && len > line.len() {
+            return len;
+        }
+    }
+    first_line
+}
+
+/// Index ranges reaching `context` lines either side of each hit (sorted
+/// indices into `len` lines), merging windows that overlap or touch.
+fn context_windows(hits: &[usize], context: usize, len: usize) -> Vec<std::ops::Range<usize>> {
+    let mut windows: Vec<std::ops::Range<usize>> = Vec::new();
+    for &hit in hits {
+        let window = hit.saturating_sub(context)..(hit + context + 1).min(len);
+        match windows.last_mut() {
+            Some(last) if window.start <= last.end => last.end = last.end.max(window.end),
+            _ => windows.push(window),
+        }
+    }
+    windows
 }
 
 fn build_heading_node(node_id: NodeId, arena: &Arena<Heading>) -> HeadingNode {
@@ -142,28 +736,96 @@ fn build_heading_node(node_id: NodeId, arena: &Arena<Heading>) -> HeadingNode {
         .map(|child_id| build_heading_node(child_id, arena))
         .collect();
 
-    HeadingNode { heading, children }
+    HeadingNode {
+        heading,
+        number: String::new(),
+        children,
+    }
+}
+
+fn assign_numbers(nodes: &mut [HeadingNode], prefix: &str) {
+    for (i, node) in nodes.iter_mut().enumerate() {
+        node.number = if prefix.is_empty() {
+            (i + 1).to_string()
+        } else {
+            format!("{}.{}", prefix, i + 1)
+        };
+        assign_numbers(&mut node.children, &node.number);
+    }
 }
 
 impl HeadingNode {
     /// Render as tree with box-drawing characters
     pub fn render_box_tree(&self, prefix: &str, is_last: bool) -> String {
+        self.render_tree(prefix, is_last, &BOX_GLYPHS, false)
+    }
+
+    /// Render the tree like [`render_box_tree`](Self::render_box_tree), using only ASCII characters
+    pub fn render_ascii_tree(&self, prefix: &str, is_last: bool) -> String {
+        self.render_tree(prefix, is_last, &ASCII_GLYPHS, false)
+    }
+
+    /// Render like [`render_box_tree`](Self::render_box_tree), with each
+    /// heading's [`number`](Self::number) before its text
+    pub fn render_numbered_box_tree(&self, prefix: &str, is_last: bool) -> String {
+        self.render_tree(prefix, is_last, &BOX_GLYPHS, true)
+    }
+
+    /// Render like [`render_ascii_tree`](Self::render_ascii_tree), with each
+    /// heading's [`number`](Self::number) before its text
+    pub fn render_numbered_ascii_tree(&self, prefix: &str, is_last: bool) -> String {
+        self.render_tree(prefix, is_last, &ASCII_GLYPHS, true)
+    }
+
+    fn render_tree(
+        &self,
+        prefix: &str,
+        is_last: bool,
+        glyphs: &TreeGlyphs,
+        numbered: bool,
+    ) -> String {
         let mut result = String::new();
 
-        let connector = if is_last { "└─ " } else { "├─ " };
+        let connector = if is_last { glyphs.last } else { glyphs.branch };
         let marker = "#".repeat(self.heading.level);
-        result.push_str(&format!(
-            "{}{}{} {}\n",
-            prefix, connector, marker, self.heading.text
-        ));
+        if numbered {
+            result.push_str(&format!(
+                "{}{}{} {} {}\n",
+                prefix, connector, marker, self.number, self.heading.text
+            ));
+        } else {
+            result.push_str(&format!(
+                "{}{}{} {}\n",
+                prefix, connector, marker, self.heading.text
+            ));
+        }
 
-        let child_prefix = format!("{}{}   ", prefix, if is_last { " " } else { "│" });
+        let child_prefix = format!("{}{}   ", prefix, if is_last { " " } else { glyphs.pipe });
 
         for (i, child) in self.children.iter().enumerate() {
             let is_last_child = i == self.children.len() - 1;
-            result.push_str(&child.render_box_tree(&child_prefix, is_last_child));
+            result.push_str(&child.render_tree(&child_prefix, is_last_child, glyphs, numbered));
         }
 
         result
     }
 }
+
+/// Connectors drawn by [`HeadingNode`]'s tree renderers
+struct TreeGlyphs {
+    branch: &'static str,
+    last: &'static str,
+    pipe: &'static str,
+}
+
+const BOX_GLYPHS: TreeGlyphs = TreeGlyphs {
+    branch: "├─ ",
+    last: "└─ ",
+    pipe: "│",
+};
+
+const ASCII_GLYPHS: TreeGlyphs = TreeGlyphs {
+    branch: "|-- ",
+    last: "`-- ",
+    pipe: "|",
+};
@@ -0,0 +1,88 @@
+//! Front matter detection.
+//!
+//! Front matter is a metadata block at the very start of a document, used by
+//! static site generators: YAML between `---` lines, or TOML between `+++`
+//! lines. This module only finds the block; the query engine parses it.
+
+/// Syntax of a front matter block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// YAML between `---` lines (the closing line may also be `...`)
+    Yaml,
+    /// TOML between `+++` lines
+    Toml,
+}
+
+/// A front matter block found at the start of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub format: FrontMatterFormat,
+    /// Text between the delimiter lines
+    pub raw: &'a str,
+    /// Byte offset just past the closing delimiter line
+    pub end: usize,
+}
+
+/// Find the front matter block at the start of `content`, if any.
+///
+/// The opening delimiter must be the first line, and the block ends at the
+/// next line holding only the matching delimiter. Without a closing line
+/// there is no front matter.
+///
+/// # Examples
+///
+/// ```
+/// use treemd::parser::frontmatter::{FrontMatterFormat, split_front_matter};
+///
+/// let fm = split_front_matter("---\ntitle: Hi\n---\n# Hi\n").unwrap();
+/// assert_eq!(fm.format, FrontMatterFormat::Yaml);
+/// assert_eq!(fm.raw, "title: Hi\n");
+/// assert_eq!(fm.end, 18);
+///
+/// assert!(split_front_matter("# No front matter\n---\n").is_none());
+/// ```
+pub fn split_front_matter(content: &str) -> Option<FrontMatter<'_>> {
+    let (format, closers): (_, &[&str]) = match content.lines().next()?.trim_end() {
+        "---" => (FrontMatterFormat::Yaml, &["---", "..."]),
+        "+++" => (FrontMatterFormat::Toml, &["+++"]),
+        _ => return None,
+    };
+
+    let body_start = content.find('\n')? + 1;
+    let mut line_start = body_start;
+    for line in content[body_start..].split_inclusive('\n') {
+        if closers.contains(&line.trim_end()) {
+            return Some(FrontMatter {
+                format,
+                raw: &content[body_start..line_start],
+                end: line_start + line.len(),
+            });
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let toml = split_front_matter("+++\ntitle = \"Hi\"\n+++\n\nBody").unwrap();
+        assert_eq!(toml.format, FrontMatterFormat::Toml);
+        assert_eq!(toml.raw, "title = \"Hi\"\n");
+        assert_eq!(toml.end, 21);
+
+        // YAML may close with `...`, and an empty block is still front matter
+        let yaml = split_front_matter("---\na: 1\n...\n").unwrap();
+        assert_eq!(yaml.raw, "a: 1\n");
+        assert_eq!(split_front_matter("---\n---\n").unwrap().raw, "");
+
+        // Unclosed, indented or not at the start
+        assert!(split_front_matter("---\ntitle: Hi\n").is_none());
+        assert!(split_front_matter(" ---\na: 1\n---\n").is_none());
+        assert!(split_front_matter("\n---\na: 1\n---\n").is_none());
+        assert!(split_front_matter("").is_none());
+    }
+}
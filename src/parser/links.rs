@@ -105,7 +105,7 @@ pub fn extract_links(content: &str) -> Vec<Link> {
 }
 
 /// Parse a link URL into a LinkTarget.
-fn parse_link_target(url: &str) -> LinkTarget {
+pub(crate) fn parse_link_target(url: &str) -> LinkTarget {
     if let Some(anchor) = url.strip_prefix('#') {
         // Anchor link within current document
         LinkTarget::Anchor(anchor.to_string())
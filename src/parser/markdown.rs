@@ -0,0 +1,664 @@
+//! Markdown serialization of parsed content
+//!
+//! The inverse of [`parse_content`](super::content::parse_content): turns
+//! [`Block`]s and [`InlineElement`]s back into markdown source.
+
+use super::HEADING_OPTIONS;
+use super::document::{Document, Heading};
+use super::output::{
+    Alignment, Block, DocumentOutput, InlineElement, ListItem, Section, is_plain_text,
+};
+use pulldown_cmark::{Event, Parser, Tag};
+
+/// Render parsed blocks back to markdown, separated by blank lines.
+///
+/// Blocks don't include headings; [`DocumentOutput::to_markdown`] adds them
+/// back from the section structure.
+///
+/// # Example
+///
+/// ```
+/// use treemd::parser::{content::parse_content, render_markdown};
+///
+/// let blocks = parse_content("Some **bold** text\n\n- [x] done\n", 0);
+/// assert_eq!(render_markdown(&blocks), "Some **bold** text\n\n- [x] done");
+/// ```
+pub fn render_markdown(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(render_block)
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render inline elements back to markdown.
+pub fn render_inline(elements: &[InlineElement]) -> String {
+    let mut out = String::new();
+    for element in elements {
+        match element {
+            InlineElement::Text { value } => {
+                // A newline inside a paragraph is a hard break
+                out.push_str(&escape_text(value).replace('\n', "\\\n"))
+            }
+            InlineElement::Strong { value } => out.push_str(&format!("**{}**", escape_text(value))),
+            InlineElement::Emphasis { value } => out.push_str(&format!("*{}*", escape_text(value))),
+            InlineElement::Strikethrough { value } => {
+                out.push_str(&format!("~~{}~~", escape_text(value)))
+            }
+            InlineElement::Code { value } => out.push_str(&code_span(value)),
+            InlineElement::Link { text, url, title } => out.push_str(&format!(
+                "[{}]({})",
+                escape_text(text),
+                destination(url, title.as_deref())
+            )),
+            InlineElement::Image { alt, src, title } => out.push_str(&format!(
+                "![{}]({})",
+                escape_text(alt),
+                destination(src, title.as_deref())
+            )),
+            InlineElement::Html { raw } => out.push_str(raw),
+            InlineElement::FootnoteReference { label } => out.push_str(&format!("[^{}]", label)),
+        }
+    }
+    out
+}
+
+impl DocumentOutput {
+    /// Rebuild the markdown source of the document from its sections.
+    ///
+    /// Section content is rendered from the parsed blocks, so edits made to
+    /// the JSON show up in the result. Sections without content (structure-only
+    /// output) produce just their heading.
+    pub fn to_markdown(&self) -> String {
+        self.to_document().content().to_string()
+    }
+
+    /// Rebuild a [`Document`] from the sections, without parsing markdown.
+    ///
+    /// Headings come straight from the section titles and levels, with offsets
+    /// pointing into the rebuilt markdown.
+    pub fn to_document(&self) -> Document {
+        let mut content = String::new();
+        let mut headings = Vec::new();
+        for section in &self.document.sections {
+            write_section(section, &mut content, &mut headings);
+        }
+        Document::new(content, headings)
+    }
+}
+
+/// Shift a heading level by `by`, clamped to 1-6.
+///
+/// Returns the new level and whether it had to be clamped.
+///
+/// # Example
+///
+/// ```
+/// use treemd::parser::markdown::shift_level;
+///
+/// assert_eq!(shift_level(2, 1), (3, false));
+/// assert_eq!(shift_level(6, 1), (6, true));
+/// assert_eq!(shift_level(1, -1), (1, true));
+/// ```
+pub fn shift_level(level: usize, by: isize) -> (usize, bool) {
+    let shifted = level as isize + by;
+    let clamped = shifted.clamp(1, 6);
+    (clamped as usize, clamped != shifted)
+}
+
+/// Rewrite the markdown of a document with every heading level shifted by `by`.
+///
+/// Only heading markers change, so everything else is kept as written: ATX
+/// headings get more or fewer `#`s, and setext headings swap their underline,
+/// or become ATX headings below level 2. Returns the markdown and how many
+/// headings had to be clamped to stay within 1-6.
+pub fn shift_headings(doc: &Document, by: isize) -> (String, usize) {
+    let source = doc.content();
+    let mut markdown = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut clamped = 0;
+
+    for (event, range) in Parser::new_ext(source, HEADING_OPTIONS).into_offset_iter() {
+        let Event::Start(Tag::Heading { level, .. }) = event else {
+            continue;
+        };
+        let level = level as usize;
+        let (shifted, was_clamped) = shift_level(level, by);
+        clamped += usize::from(was_clamped);
+
+        markdown.push_str(&source[copied..range.start]);
+        let heading = &source[range.clone()];
+        if shifted == level {
+            markdown.push_str(heading);
+        } else if heading.starts_with('#') {
+            markdown.push_str(&"#".repeat(shifted));
+            markdown.push_str(&heading[level..]);
+        } else {
+            markdown.push_str(&shift_setext(heading, shifted));
+        }
+        copied = range.end;
+    }
+
+    markdown.push_str(&source[copied..]);
+    (markdown, clamped)
+}
+
+/// Rewrite a setext heading (text lines, then a `===` or `---` underline) at
+/// `level`.
+fn shift_setext(heading: &str, level: usize) -> String {
+    let newline = if heading.ends_with('\n') { "\n" } else { "" };
+    let body = heading.strip_suffix('\n').unwrap_or(heading);
+    let (text, underline) = body.rsplit_once('\n').unwrap_or(("", body));
+
+    let marker_start = underline.find(['=', '-']).unwrap_or(underline.len());
+    let (prefix, marker) = underline.split_at(marker_start);
+    if level <= 2 {
+        let (from, to) = if level == 1 { ('-', "=") } else { ('=', "-") };
+        return format!(
+            "{}\n{}{}{}",
+            text,
+            prefix,
+            marker.replace(from, to),
+            newline
+        );
+    }
+
+    // Continuation lines repeat the underline's container markers
+    let text: Vec<&str> = text
+        .lines()
+        .map(|line| line.strip_prefix(prefix).unwrap_or(line).trim())
+        .collect();
+    format!("{} {}{}", "#".repeat(level), text.join(" "), newline)
+}
+
+impl DocumentOutput {
+    /// Shift every section's heading level by `by`, clamped to 1-6.
+    ///
+    /// Returns how many headings had to be clamped.
+    pub fn shift_headings(&mut self, by: isize) -> usize {
+        fn shift(sections: &mut [Section], by: isize) -> usize {
+            sections
+                .iter_mut()
+                .map(|section| {
+                    let (level, clamped) = shift_level(section.level, by);
+                    section.level = level;
+                    usize::from(clamped) + shift(&mut section.children, by)
+                })
+                .sum()
+        }
+        shift(&mut self.document.sections, by)
+    }
+}
+
+fn write_section(section: &Section, content: &mut String, headings: &mut Vec<Heading>) {
+    if !content.is_empty() {
+        content.push('\n');
+    }
+
+    headings.push(Heading {
+        level: section.level,
+        text: section.title.clone(),
+        offset: content.len(),
+        inline: section.inline.clone(),
+    });
+    // Plain-text titles are written as-is
+    let title = if is_plain_text(&section.inline) {
+        section.title.clone()
+    } else {
+        render_inline(&section.inline)
+    };
+    content.push_str(&format!(
+        "{} {}\n",
+        "#".repeat(section.level.clamp(1, 6)),
+        title
+    ));
+
+    if let Some(section_content) = &section.content {
+        let body = render_markdown(&section_content.blocks);
+        if !body.is_empty() {
+            content.push('\n');
+            content.push_str(&body);
+            content.push('\n');
+        }
+    }
+
+    for child in &section.children {
+        write_section(child, content, headings);
+    }
+}
+
+fn render_block(block: &Block) -> String {
+    match block {
+        Block::Paragraph { content, inline } => {
+            let text = if inline.is_empty() {
+                escape_text(content)
+            } else {
+                render_inline(inline)
+            };
+            escape_block_start(&text)
+        }
+        Block::Code {
+            language, content, ..
+        } => {
+            let fence = fence_for(content);
+            format!(
+                "{}{}\n{}\n{}",
+                fence,
+                language.as_deref().unwrap_or(""),
+                content,
+                fence
+            )
+        }
+        Block::List { ordered, items } => render_list(items, *ordered),
+        Block::Blockquote { content, blocks } => {
+            // The content is the quoted markdown itself
+            let inner = if blocks.is_empty() {
+                content.trim().to_string()
+            } else {
+                render_markdown(blocks)
+            };
+            inner
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Block::Table {
+            headers,
+            alignments,
+            rows,
+        } => render_table(headers, alignments, rows),
+        Block::Image { alt, src, title } => format!(
+            "![{}]({})",
+            escape_text(alt),
+            destination(src, title.as_deref())
+        ),
+        Block::HorizontalRule => "---".to_string(),
+        Block::Html { raw } => raw.clone(),
+        Block::Footnote { label, blocks, .. } => {
+            // Continuation lines are indented to stay inside the definition
+            let body = render_markdown(blocks)
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("    {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("[^{}]: {}", label, body.trim_start())
+        }
+        Block::Details {
+            summary,
+            blocks,
+            open,
+            ..
+        } => {
+            let tag = if *open { "<details open>" } else { "<details>" };
+            let body = render_markdown(blocks);
+            if body.is_empty() {
+                format!("{}\n<summary>{}</summary>\n</details>", tag, summary)
+            } else {
+                format!(
+                    "{}\n<summary>{}</summary>\n\n{}\n\n</details>",
+                    tag, summary, body
+                )
+            }
+        }
+    }
+}
+
+fn render_list(items: &[ListItem], ordered: bool) -> String {
+    let mut lines = Vec::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let mut marker = if ordered {
+            format!("{}. ", item.number.unwrap_or(i as u64 + 1))
+        } else {
+            "- ".to_string()
+        };
+        let indent = " ".repeat(marker.len());
+        match item.checked {
+            Some(true) => marker.push_str("[x] "),
+            Some(false) => marker.push_str("[ ] "),
+            None => {}
+        }
+
+        let text = if item.inline.is_empty() {
+            escape_text(&item.content)
+        } else {
+            // Newlines separate the paragraphs of a loose item
+            item.inline
+                .split(|e| matches!(e, InlineElement::Text { value } if value == "\n"))
+                .map(render_inline)
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+
+        for (j, line) in text.lines().enumerate() {
+            if j == 0 {
+                lines.push(format!("{}{}", marker, line));
+            } else if line.is_empty() {
+                lines.push(String::new());
+            } else {
+                lines.push(format!("{}{}", indent, line));
+            }
+        }
+        if text.is_empty() {
+            lines.push(marker.trim_end().to_string());
+        }
+
+        if !item.children.is_empty() {
+            let nested_ordered = item.children.iter().any(|child| child.number.is_some());
+            for line in render_list(&item.children, nested_ordered).lines() {
+                lines.push(format!("{}{}", indent, line));
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn render_table(headers: &[String], alignments: &[Alignment], rows: &[Vec<String>]) -> String {
+    let row = |cells: &[String]| {
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let separator: Vec<&str> = (0..headers.len())
+        .map(|i| match alignments.get(i) {
+            Some(Alignment::Left) => ":---",
+            Some(Alignment::Center) => ":---:",
+            Some(Alignment::Right) => "---:",
+            Some(Alignment::None) | None => "---",
+        })
+        .collect();
+
+    let mut lines = vec![row(headers), format!("| {} |", separator.join(" | "))];
+    lines.extend(rows.iter().map(|cells| row(cells)));
+    lines.join("\n")
+}
+
+/// A code fence longer than any run of backticks in `content`
+fn fence_for(content: &str) -> String {
+    "`".repeat((longest_run(content, '`') + 1).max(3))
+}
+
+/// A code span that can contain backticks
+fn code_span(value: &str) -> String {
+    let ticks = "`".repeat(longest_run(value, '`') + 1);
+    if value.starts_with('`') || value.ends_with('`') {
+        format!("{} {} {}", ticks, value, ticks)
+    } else {
+        format!("{}{}{}", ticks, value, ticks)
+    }
+}
+
+fn longest_run(text: &str, ch: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in text.chars() {
+        if c == ch {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Link destination with an optional title, wrapping it in `<>` if it has spaces
+fn destination(url: &str, title: Option<&str>) -> String {
+    let url = if url.contains(' ') || url.contains(')') {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    };
+    match title {
+        Some(title) if !title.is_empty() => format!("{} \"{}\"", url, title.replace('"', "\\\"")),
+        _ => url,
+    }
+}
+
+/// Escape the start of each line that would otherwise read as a heading,
+/// blockquote, list item or rule.
+fn escape_block_start(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+            let is_marker = matches!(trimmed.chars().next(), Some('#' | '>' | '-' | '+' | '='))
+                || (digits > 0 && matches!(trimmed[digits..].chars().next(), Some('.' | ')')));
+            if !is_marker {
+                return line.to_string();
+            }
+            let indent = line.len() - trimmed.len();
+            let at = indent + digits;
+            format!("{}\\{}", &line[..at], &line[at..])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape characters that would otherwise start inline markup.
+///
+/// Underscores inside words (`snake_case`) can't start emphasis, so they are
+/// left alone.
+fn escape_text(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let escape = match c {
+            '\\' | '*' | '`' | '[' | ']' | '<' | '~' => true,
+            '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1);
+                !(before.is_some_and(char::is_alphanumeric)
+                    && after.is_some_and(|c| c.is_alphanumeric()))
+            }
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::content::parse_content;
+
+    /// Parse, render and re-parse `md`, checking the blocks come back the same
+    /// (ignoring source line numbers). Returns the rendered markdown.
+    fn round_trip(md: &str) -> String {
+        fn blocks_json(blocks: &[Block]) -> serde_json::Value {
+            let mut value = serde_json::to_value(blocks).unwrap();
+            fn strip(value: &mut serde_json::Value) {
+                match value {
+                    serde_json::Value::Object(map) => {
+                        map.remove("startLine");
+                        map.remove("endLine");
+                        map.values_mut().for_each(strip);
+                    }
+                    serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+                    _ => {}
+                }
+            }
+            strip(&mut value);
+            value
+        }
+
+        let blocks = parse_content(md, 0);
+        let rendered = render_markdown(&blocks);
+        let reparsed = parse_content(&rendered, 0);
+        assert_eq!(
+            blocks_json(&reparsed),
+            blocks_json(&blocks),
+            "rendered:\n{}",
+            rendered
+        );
+
+        // Rendering is stable once normalized
+        assert_eq!(render_markdown(&reparsed), rendered);
+        rendered
+    }
+
+    #[test]
+    fn test_paragraph_inline_formatting() {
+        let rendered = round_trip(
+            "Some **bold**, *em*, `code`, ~~gone~~, [link](https://example.com) \
+             and ![icon](icon.png \"Icon\").",
+        );
+        assert_eq!(
+            rendered,
+            "Some **bold**, *em*, `code`, ~~gone~~, [link](https://example.com) \
+             and ![icon](icon.png \"Icon\")."
+        );
+    }
+
+    #[test]
+    fn test_paragraph_escapes_markup_characters() {
+        let rendered = round_trip("2 \\* 3, \\[not a link\\] and snake_case");
+        assert_eq!(rendered, "2 \\* 3, \\[not a link\\] and snake_case");
+        round_trip("Code with a tick: `` a`b ``");
+    }
+
+    #[test]
+    fn test_paragraph_escapes_block_markers() {
+        let rendered = round_trip("\\# not a heading\n\n1\\. not a list\n\n\\- nor this");
+        assert_eq!(
+            rendered,
+            "\\# not a heading\n\n1\\. not a list\n\n\\- nor this"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let rendered = round_trip("- one\n- two\n  - nested\n");
+        assert_eq!(rendered, "- one\n- two\n  - nested");
+
+        let rendered = round_trip("3. three\n4. four\n   1. sub\n");
+        assert_eq!(rendered, "3. three\n4. four\n   1. sub");
+
+        let rendered = round_trip("- [x] done\n- [ ] todo\n");
+        assert_eq!(rendered, "- [x] done\n- [ ] todo");
+    }
+
+    #[test]
+    fn test_code_block() {
+        let rendered = round_trip("```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n");
+        assert_eq!(
+            rendered,
+            "```rust\nfn main() {\n    println!(\"hi\");\n}\n```"
+        );
+
+        // Fences grow past backticks in the code
+        let rendered = round_trip("````md\n```\ninner\n```\n````\n");
+        assert!(rendered.starts_with("````md\n"));
+    }
+
+    #[test]
+    fn test_table_alignment() {
+        let rendered = round_trip("| L | C | R | N |\n|:--|:-:|--:|---|\n| 1 | 2 | 3 | 4 |\n");
+        assert_eq!(
+            rendered,
+            "| L | C | R | N |\n| :--- | :---: | ---: | --- |\n| 1 | 2 | 3 | 4 |"
+        );
+    }
+
+    #[test]
+    fn test_blockquote_and_rule() {
+        let rendered = round_trip("> Quoted *text*\n\n---\n\nAfter\n");
+        assert_eq!(rendered, "> Quoted *text*\n\n---\n\nAfter");
+
+        let rendered = round_trip("> **Note:** see `cfg`\n>\n> - [x] done\n>\n> > nested\n");
+        assert_eq!(
+            rendered,
+            "> **Note:** see `cfg`\n>\n> - [x] done\n>\n> > nested"
+        );
+    }
+
+    #[test]
+    fn test_block_image() {
+        let image = Block::Image {
+            alt: "Logo".to_string(),
+            src: "assets/my logo.png".to_string(),
+            title: Some("The \"logo\"".to_string()),
+        };
+        assert_eq!(
+            render_markdown(&[image, Block::HorizontalRule]),
+            "![Logo](<assets/my logo.png> \"The \\\"logo\\\"\")\n\n---"
+        );
+    }
+
+    #[test]
+    fn test_details() {
+        let rendered =
+            round_trip("<details open>\n<summary>More</summary>\n\nHidden **text**\n</details>\n");
+        assert_eq!(
+            rendered,
+            "<details open>\n<summary>More</summary>\n\nHidden **text**\n\n</details>"
+        );
+    }
+
+    #[test]
+    fn test_shift_headings_up_and_down() {
+        let doc = crate::parser::parse_markdown(
+            "Intro text.\n\n# Title\n\nBody.\n\n## Part\n\n###### Deep\n",
+        );
+
+        let (markdown, clamped) = shift_headings(&doc, 1);
+        assert_eq!(clamped, 1);
+        assert_eq!(
+            markdown,
+            "Intro text.\n\n## Title\n\nBody.\n\n### Part\n\n###### Deep\n"
+        );
+
+        let (markdown, clamped) = shift_headings(&doc, -2);
+        assert_eq!(clamped, 2);
+        assert_eq!(
+            markdown,
+            "Intro text.\n\n# Title\n\nBody.\n\n# Part\n\n#### Deep\n"
+        );
+
+        let (markdown, clamped) = shift_headings(&doc, 0);
+        assert_eq!(clamped, 0);
+        assert!(markdown.contains("## Part\n"));
+    }
+
+    #[test]
+    fn test_shift_headings_keeps_source() {
+        let md = "Title\n=====\n\n* item  \n\n> Sub\n> title\n> ---\n\n  ## Closed ##\n\n\
+                  ```\n# not a heading\n```\n";
+        let doc = crate::parser::parse_markdown(md);
+
+        let (markdown, _) = shift_headings(&doc, 1);
+        assert_eq!(
+            markdown,
+            "Title\n-----\n\n* item  \n\n> ### Sub title\n\n  ### Closed ##\n\n\
+             ```\n# not a heading\n```\n"
+        );
+
+        let (markdown, _) = shift_headings(&crate::parser::parse_markdown(&markdown), -1);
+        assert!(markdown.starts_with("Title\n=====\n"));
+        assert!(markdown.contains("> ## Sub title\n"));
+
+        let (markdown, clamped) = shift_headings(&doc, 0);
+        assert_eq!((markdown.as_str(), clamped), (md, 0));
+    }
+}
@@ -6,18 +6,25 @@
 pub mod builder;
 pub mod content;
 mod document;
+pub mod frontmatter;
 pub mod links;
+pub mod markdown;
 pub mod output;
 pub mod utils;
 
-pub use builder::build_json_output;
-pub use document::{Document, Heading, HeadingNode};
+pub use builder::{CodeWords, JsonOptions, build_json_output, build_json_output_with};
+pub use document::{Document, Heading, HeadingNode, SectionMatch, SectionView, Sections};
 pub use links::{Link, LinkTarget, extract_links};
+pub use markdown::{render_inline, render_markdown};
 pub use output::{Block, DocumentOutput, InlineElement, Section};
 
-use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
 use std::path::Path;
 
+/// Parser options for finding headings; front matter is skipped
+pub(crate) const HEADING_OPTIONS: Options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+    .union(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+
 /// Parse a markdown file and extract its structure.
 ///
 /// # Arguments
@@ -48,39 +55,115 @@ pub fn parse_file(path: &Path) -> std::io::Result<Document> {
 /// # Returns
 ///
 /// A `Document` containing the content and extracted headings with byte offsets.
+///
+/// A leading byte order mark is dropped and CRLF line endings become LF
+/// before parsing (see [`utils::normalize_line_endings`]). The document keeps
+/// the normalized text, and all offsets and line numbers refer to it.
+///
+/// Front matter (see [`frontmatter`]) is kept in the text but never read as
+/// headings.
 pub fn parse_markdown(content: &str) -> Document {
-    let parser = Parser::new(content).into_offset_iter();
+    let content = utils::normalize_line_endings(content);
+    let parser = Parser::new_ext(&content, HEADING_OPTIONS).into_offset_iter();
     let mut headings = Vec::new();
-    let mut current_heading: Option<(usize, String, usize)> = None;
-    let mut in_heading = false;
+    let mut current_heading: Option<HeadingBuilder> = None;
 
     for (event, range) in parser {
+        if let Event::Start(Tag::Heading { level, .. }) = event {
+            // Store the byte offset where this heading starts
+            current_heading = Some(HeadingBuilder::new(level as usize, range.start));
+            continue;
+        }
+        let Some(heading) = current_heading.as_mut() else {
+            continue;
+        };
+
         match event {
-            Event::Start(Tag::Heading { level, .. }) => {
-                in_heading = true;
-                // Store the byte offset where this heading starts
-                current_heading = Some((level as usize, String::new(), range.start));
-            }
             Event::End(TagEnd::Heading(_)) => {
-                if let Some((level, text, offset)) = current_heading.take() {
-                    headings.push(Heading {
-                        level,
-                        text: text.trim().to_string(),
-                        offset,
-                    });
+                if let Some(heading) = current_heading.take() {
+                    headings.push(heading.finish());
                 }
-                in_heading = false;
             }
-            Event::Text(text) if in_heading => {
-                if let Some((_, ref mut heading_text, _)) = current_heading {
-                    heading_text.push_str(&text);
+            Event::Text(text) => heading.push_text(&text, false),
+            Event::Code(code) => heading.push_text(&code, true),
+            Event::Start(Tag::Strong) => heading.strong = true,
+            Event::End(TagEnd::Strong) => heading.strong = false,
+            Event::Start(Tag::Emphasis) => heading.emphasis = true,
+            Event::End(TagEnd::Emphasis) => heading.emphasis = false,
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => {
+                heading.link = Some((dest_url.to_string(), title.to_string(), String::new()));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((url, title, text)) = heading.link.take() {
+                    heading.inline.push(InlineElement::Link {
+                        text,
+                        url,
+                        title: (!title.is_empty()).then_some(title),
+                    });
                 }
             }
             _ => {}
         }
     }
 
-    Document::new(content.to_string(), headings)
+    Document::new(content.into_owned(), headings)
+}
+
+/// Accumulates the plain text and inline elements of a heading being parsed.
+struct HeadingBuilder {
+    level: usize,
+    offset: usize,
+    text: String,
+    inline: Vec<InlineElement>,
+    strong: bool,
+    emphasis: bool,
+    /// URL, title and text of the link being read
+    link: Option<(String, String, String)>,
+}
+
+impl HeadingBuilder {
+    fn new(level: usize, offset: usize) -> Self {
+        Self {
+            level,
+            offset,
+            text: String::new(),
+            inline: Vec::new(),
+            strong: false,
+            emphasis: false,
+            link: None,
+        }
+    }
+
+    fn push_text(&mut self, value: &str, code: bool) {
+        self.text.push_str(value);
+        if let Some((_, _, link_text)) = &mut self.link {
+            link_text.push_str(value);
+            return;
+        }
+
+        let value = value.to_string();
+        let element = if code {
+            InlineElement::Code { value }
+        } else if self.strong {
+            InlineElement::Strong { value }
+        } else if self.emphasis {
+            InlineElement::Emphasis { value }
+        } else {
+            InlineElement::Text { value }
+        };
+        self.inline.push(element);
+    }
+
+    fn finish(self) -> Heading {
+        Heading {
+            level: self.level,
+            text: self.text.trim().to_string(),
+            offset: self.offset,
+            inline: self.inline,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -102,11 +185,11 @@ Details
 End"#;
 
         let doc = parse_markdown(md);
-        assert_eq!(doc.headings.len(), 4);
-        assert_eq!(doc.headings[0].level, 1);
-        assert_eq!(doc.headings[0].text, "Title");
-        assert_eq!(doc.headings[1].level, 2);
-        assert_eq!(doc.headings[1].text, "Section 1");
+        assert_eq!(doc.headings().len(), 4);
+        assert_eq!(doc.headings()[0].level, 1);
+        assert_eq!(doc.headings()[0].text, "Title");
+        assert_eq!(doc.headings()[1].level, 2);
+        assert_eq!(doc.headings()[1].text, "Section 1");
     }
 
     #[test]
@@ -120,11 +203,11 @@ End"#;
 #### **1. Item** with number"#;
 
         let doc = parse_markdown(md);
-        assert_eq!(doc.headings.len(), 4);
+        assert_eq!(doc.headings().len(), 4);
 
         // Verify bold formatting is stripped from heading text
-        assert_eq!(doc.headings[1].text, "Bold Section");
-        assert_eq!(doc.headings[3].text, "1. Item with number");
+        assert_eq!(doc.headings()[1].text, "Bold Section");
+        assert_eq!(doc.headings()[3].text, "1. Item with number");
     }
 
     #[test]
@@ -136,14 +219,14 @@ Content here
 More content"#;
 
         let doc = parse_markdown(md);
-        assert_eq!(doc.headings.len(), 2);
+        assert_eq!(doc.headings().len(), 2);
 
         // Verify offsets are stored
-        assert_eq!(doc.headings[0].offset, 0); // "# First" starts at byte 0
-        assert!(doc.headings[1].offset > doc.headings[0].offset);
+        assert_eq!(doc.headings()[0].offset, 0); // "# First" starts at byte 0
+        assert!(doc.headings()[1].offset > doc.headings()[0].offset);
 
         // Verify we can use offsets to extract
-        let first_heading_text = &md[doc.headings[0].offset..doc.headings[0].offset + 7];
+        let first_heading_text = &md[doc.headings()[0].offset..doc.headings()[0].offset + 7];
         assert_eq!(first_heading_text, "# First");
     }
 
@@ -206,4 +289,402 @@ End of doc"#;
         assert!(content.contains("More lines"));
         assert!(content.contains("End of doc"));
     }
+
+    #[test]
+    fn test_find_section_matching_body() {
+        let md = "# CLI\n\n## Output\n\nUse --json for JSON.\n\n### Colors\n\nSet --no-color.\n\n## Input\n\nRead stdin.\n";
+        let doc = parse_markdown(md);
+
+        let (heading, text) = doc
+            .find_section_matching(|line| line.contains("--json"))
+            .unwrap();
+        assert_eq!(heading.text, "Output");
+        // The whole section, including subsections, up to the next `##`
+        assert_eq!(
+            text,
+            "## Output\n\nUse --json for JSON.\n\n### Colors\n\nSet --no-color."
+        );
+
+        let (heading, text) = doc
+            .find_section_matching(|line| line.contains("stdin"))
+            .unwrap();
+        assert_eq!(heading.text, "Input");
+        assert_eq!(text, "## Input\n\nRead stdin.");
+
+        assert!(
+            doc.find_section_matching(|line| line.contains("yaml"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_filter_headings_regex() {
+        let doc = parse_markdown("# Changelog\n\n## v1.2.0\n\n## Unreleased v2\n\n## V1.0.0\n");
+        let texts = |pattern: &str| -> Vec<String> {
+            doc.filter_headings_regex(pattern)
+                .unwrap()
+                .iter()
+                .map(|h| h.text.clone())
+                .collect()
+        };
+
+        // Anchors apply to the whole heading text
+        assert_eq!(texts(r"^v\d+\."), vec!["v1.2.0"]);
+        assert_eq!(texts(r"v\d+$"), vec!["Unreleased v2"]);
+        assert_eq!(texts(r"(?i)^v\d"), vec!["v1.2.0", "V1.0.0"]);
+        assert!(texts("^Release").is_empty());
+
+        assert!(doc.filter_headings_regex("v[0-9").is_err());
+    }
+
+    #[test]
+    fn test_word_count_code_exclusion() {
+        let md = "# Intro\n\nRead the `install` guide first.\n\n```sh\ncargo install treemd --locked\n```\n";
+        let doc = parse_markdown(md);
+        let words = |code_words| {
+            let options = JsonOptions {
+                code_words,
+                ..JsonOptions::default()
+            };
+            let output = build_json_output_with(&doc, None, options);
+            (
+                output.document.metadata.word_count,
+                output.document.sections[0].word_count,
+            )
+        };
+
+        // Old behavior: every token in the raw markdown, fences included
+        assert_eq!(words(CodeWords::Include).0, 13);
+        // Default: heading + paragraph, code block skipped
+        assert_eq!(words(CodeWords::ExcludeBlocks), (6, 5));
+        assert_eq!(
+            build_json_output(&doc, None).document.metadata.word_count,
+            6
+        );
+        // Inline code skipped too
+        assert_eq!(words(CodeWords::ExcludeAll), (5, 4));
+    }
+
+    #[test]
+    fn test_heading_numbers() {
+        let doc = parse_markdown("# A\n### A skip\n## A two\n### A two one\n# B\n## B one\n");
+
+        assert_eq!(
+            doc.heading_numbers(),
+            ["1", "1.1", "1.2", "1.2.1", "2", "2.1"]
+        );
+
+        let tree = doc.build_tree();
+        assert_eq!(tree[1].number, "2");
+        assert_eq!(tree[0].children[1].children[0].number, "1.2.1");
+    }
+
+    #[test]
+    fn test_sections_bodies_and_children() {
+        let md = "Preamble\n# Guide\n\nIntro.\n\n## Install\n\n```sh\n# not a heading\n```\n\n\
+                  #### Deep\n### Linux\n## Usage\n# Appendix\n\nEnd.";
+        let doc = parse_markdown(md);
+        let sections: Vec<_> = doc.sections().collect();
+
+        let titles: Vec<_> = sections.iter().map(|s| s.heading.text.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["Guide", "Install", "Deep", "Linux", "Usage", "Appendix"]
+        );
+
+        // Bodies stop at the next heading of any level, but not at `#` in code
+        assert_eq!(sections[0].body, "Intro.");
+        assert_eq!(sections[1].body, "```sh\n# not a heading\n```");
+        assert_eq!(sections[2].body, "");
+        assert_eq!(sections[5].body, "End.");
+        assert_eq!(sections[1].content().blocks.len(), 1);
+
+        // Skipped levels still nest under the nearest shallower heading
+        assert_eq!(sections[0].children, [1, 4]);
+        assert_eq!(sections[1].children, [2, 3]);
+        assert!(sections[5].children.is_empty());
+        assert_eq!(doc.sections().len(), 6);
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let doc = parse_markdown(
+            "# Usage\n## Installation\n### Linux\n### macOS\n#### Homebrew\n## Config\n",
+        );
+        let texts =
+            |idx| -> Vec<&str> { doc.ancestors(idx).iter().map(|h| h.text.as_str()).collect() };
+        assert_eq!(texts(3), ["Usage", "Installation"]);
+        assert_eq!(texts(4), ["Usage", "Installation", "macOS"]);
+        assert_eq!(texts(5), ["Usage"]);
+        assert!(texts(0).is_empty());
+        assert!(texts(9).is_empty());
+
+        // A skipped level still nests under the nearest higher heading
+        let doc = parse_markdown("## A\n#### B\n### C\n");
+        assert_eq!(doc.ancestors(2)[0].text, "A");
+    }
+
+    #[test]
+    fn test_heading_numbers_start_below_h1() {
+        // Documents without an H1 number their top-level headings from 1
+        let doc = parse_markdown("## Install\n## Usage\n#### Flags\n");
+        assert_eq!(doc.heading_numbers(), ["1", "2", "2.1"]);
+    }
+
+    #[test]
+    fn test_lazy_section_content_matches_json_output() {
+        let md = "Preamble\n\n# Guide\n\nIntro with `code`.\n\n## Install\n\n```sh\n# not a heading\ncargo install treemd\n```\n\n  ### Indented\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n# Usage\n\n- one\n- [x] two\n";
+        let eager = build_json_output(&parse_markdown(md), None);
+
+        fn flatten(sections: &[Section], out: &mut Vec<Section>) {
+            for section in sections {
+                out.push(section.clone());
+                flatten(&section.children, out);
+            }
+        }
+        let mut sections = Vec::new();
+        flatten(&eager.document.sections, &mut sections);
+
+        // Fresh document, so every section is parsed on access
+        let doc = parse_markdown(md);
+        assert_eq!(sections.len(), doc.headings().len());
+        for (idx, section) in sections.iter().enumerate().rev() {
+            let lazy = doc.section_content(idx).unwrap();
+            let eager = section.content.as_ref().unwrap();
+            assert_eq!(lazy.raw, eager.raw);
+            assert_eq!(
+                serde_json::to_value(&lazy.blocks).unwrap(),
+                serde_json::to_value(&eager.blocks).unwrap()
+            );
+        }
+
+        // Cached: the same parsed content is handed back
+        assert!(std::ptr::eq(
+            doc.section_content(1).unwrap(),
+            doc.section_content(1).unwrap()
+        ));
+        assert!(doc.section_content(doc.headings().len()).is_none());
+    }
+
+    #[test]
+    fn test_section_blocks_match_extract_section() {
+        let md = "Preamble\n\n# Guide\n\nIntro.\n\n## Install\n\n- one\n- [x] two\n\n# Usage\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        let doc = parse_markdown(md);
+        let blocks = |blocks: Vec<&Block>| serde_json::to_value(blocks).unwrap();
+
+        for heading in ["Guide", "Install", "Usage"] {
+            let section = doc.extract_section(heading).unwrap();
+            assert_eq!(
+                blocks(doc.section_blocks(Some(heading))),
+                serde_json::to_value(content::parse_content(&section, 0)).unwrap(),
+                "{heading}"
+            );
+        }
+        // The whole document without a heading, preamble included
+        assert_eq!(
+            blocks(doc.section_blocks(None)),
+            serde_json::to_value(content::parse_content(md, 0)).unwrap()
+        );
+        assert_eq!(doc.section_blocks(Some("Missing")).len(), 4);
+    }
+
+    #[test]
+    fn test_line_at() {
+        let doc = parse_markdown("# A\n\ntext\n## B\n");
+        assert_eq!(doc.line_at(0), 1);
+        assert_eq!(doc.line_at(3), 1);
+        assert_eq!(doc.line_at(4), 2);
+        assert_eq!(doc.line_at(doc.headings()[1].offset), 4);
+        assert_eq!(doc.line_at(doc.content().len()), 5);
+    }
+
+    #[test]
+    fn test_json_output_without_content() {
+        let md = "# Guide\n\nIntro text.\n\n## Install\n\n```sh\ncargo install treemd\n```\n";
+        let doc = parse_markdown(md);
+        let options = JsonOptions {
+            content: false,
+            ..JsonOptions::default()
+        };
+        let output = build_json_output_with(&doc, None, options);
+
+        // Nothing was parsed
+        assert_eq!(doc.parsed_section_count(), 0);
+
+        let json = serde_json::to_value(&output).unwrap();
+        let guide = &json["document"]["sections"][0];
+        assert_eq!(guide["title"], "Guide");
+        assert!(guide.get("content").is_none());
+        assert!(guide["children"][0].get("content").is_none());
+        // Word counts come from raw text, code included
+        assert_eq!(guide["wordCount"], 2);
+        assert_eq!(guide["children"][0]["wordCount"], 5);
+
+        build_json_output(&doc, None);
+        assert_eq!(doc.parsed_section_count(), 2);
+    }
+
+    /// Section titles, levels and blocks, ignoring source positions
+    fn structure(output: &DocumentOutput) -> serde_json::Value {
+        fn strip(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for key in ["position", "startLine", "endLine", "raw"] {
+                        map.remove(key);
+                    }
+                    // Raw text copies of the blocks (not a section's `content` object)
+                    if map.get("content").is_some_and(|v| v.is_string()) {
+                        map.remove("content");
+                    }
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+
+        let mut value = serde_json::to_value(&output.document.sections).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    #[test]
+    fn test_json_round_trip_to_markdown() {
+        let md = "# Guide\n\nIntro with **bold**, *em*, `code`, ~~old~~ and a [link](https://example.com).\n\n\
+                  ## Setup\n\n1. First\n2. Second\n   - [x] done\n   - [ ] todo\n\n\
+                  ```rust\nfn main() {}\n```\n\n\
+                  | Name | Size |\n|:-----|-----:|\n| a | 1 |\n\n\
+                  > Quoted *text*\n\n---\n\n![logo](logo.png)\n\n\
+                  ### Notes\n\n<details open>\n<summary>More</summary>\n\nHidden\n\n</details>\n\n\
+                  # Appendix\n\nsnake_case and under_scores_\n";
+        let output = build_json_output(&parse_markdown(md), None);
+
+        // Through JSON text, as `--from-json` reads it
+        let json = serde_json::to_string(&output).unwrap();
+        let decoded: DocumentOutput = serde_json::from_str(&json).unwrap();
+        let rebuilt = decoded.to_document();
+
+        let titles: Vec<_> = rebuilt
+            .headings()
+            .iter()
+            .map(|h| (h.level, h.text.as_str()))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![(1, "Guide"), (2, "Setup"), (3, "Notes"), (1, "Appendix")]
+        );
+        for heading in rebuilt.headings() {
+            assert!(rebuilt.content()[heading.offset..].starts_with('#'));
+        }
+
+        let reparsed = build_json_output(&parse_markdown(rebuilt.content()), None);
+        assert_eq!(structure(&reparsed), structure(&output));
+        assert_eq!(decoded.to_markdown(), rebuilt.content());
+    }
+
+    #[test]
+    fn test_heading_inline_links_and_code() {
+        let doc = parse_markdown("# Intro\n\n## See [docs](/docs)\n\n## The `--json` flag\n");
+
+        let intro = &doc.headings()[0];
+        assert!(output::is_plain_text(&intro.inline));
+
+        let see = &doc.headings()[1];
+        assert_eq!(see.text, "See docs");
+        assert_eq!(
+            see.inline,
+            [
+                InlineElement::Text {
+                    value: "See ".to_string()
+                },
+                InlineElement::Link {
+                    text: "docs".to_string(),
+                    url: "/docs".to_string(),
+                    title: None,
+                },
+            ]
+        );
+
+        // Inline code is part of the text, not dropped
+        let flag = &doc.headings()[2];
+        assert_eq!(flag.text, "The --json flag");
+        assert!(flag.inline.contains(&InlineElement::Code {
+            value: "--json".to_string()
+        }));
+
+        // JSON keeps the link, and only for headings with structure
+        let json = serde_json::to_value(build_json_output(&doc, None)).unwrap();
+        let sections = &json["document"]["sections"][0];
+        assert!(sections.get("inline").is_none());
+        assert_eq!(sections["children"][0]["inline"][1]["url"], "/docs");
+
+        // ...and rebuilding markdown from it writes the link back
+        let output: DocumentOutput = serde_json::from_value(json).unwrap();
+        assert!(output.to_markdown().contains("## See [docs](/docs)\n"));
+    }
+
+    #[test]
+    fn test_section_preview() {
+        let md = "# Guide\n\nRead **the** [manual](/m) and `run` it:\n\n- one\n- two\n\n## Next\n\nShort.\n\n### Empty\n";
+        let doc = parse_markdown(md);
+
+        assert_eq!(
+            doc.section_preview(0, 80).unwrap(),
+            "Read the manual and run it: one two"
+        );
+        // Cut at exactly 13 characters, trailing space dropped
+        assert_eq!(doc.section_preview(0, 13).unwrap(), "Read the manu…");
+        assert_eq!(doc.section_preview(0, 9).unwrap(), "Read the…");
+        // The next heading's marker never leaks into the preview
+        assert_eq!(doc.section_preview(1, 80).unwrap(), "Short.");
+        assert_eq!(doc.section_preview(2, 80).unwrap(), "");
+        assert!(doc.section_preview(3, 80).is_none());
+    }
+
+    #[test]
+    fn test_bom_and_crlf_are_normalized() {
+        let md = "\u{feff}# Title\r\n\r\nIntro\r\n\r\n## Usage\r\n\r\nRun it.\r\n";
+        let doc = parse_markdown(md);
+
+        assert_eq!(doc.headings().len(), 2);
+        assert_eq!(doc.headings()[0].text, "Title");
+        assert_eq!(doc.headings()[0].offset, 0);
+        assert_eq!(doc.headings()[1].text, "Usage");
+        assert!(!doc.content().contains('\r'));
+        assert_eq!(&doc.content()[doc.headings()[1].offset..][..8], "## Usage");
+        assert_eq!(doc.line_col(doc.headings()[1].offset).0, 5);
+        assert_eq!(doc.extract_section("Usage").unwrap().trim(), "Run it.");
+    }
+
+    #[test]
+    fn test_setext_sections_use_heading_offsets() {
+        let md = "Title\n=====\n\nIntro.\n\nSetup\n-----\n\nRun it.\n\n## Usage\n\nUse it.\n";
+        let doc = parse_markdown(md);
+
+        let levels: Vec<_> = doc
+            .headings()
+            .iter()
+            .map(|h| (h.level, h.text.as_str()))
+            .collect();
+        assert_eq!(levels, vec![(1, "Title"), (2, "Setup"), (2, "Usage")]);
+        // A setext heading's offset is its text line, not a `#`
+        assert_eq!(&doc.content()[doc.headings()[1].offset..][..6], "Setup\n");
+
+        // The underline isn't part of the content
+        assert_eq!(doc.extract_section("Setup").unwrap(), "Run it.");
+        assert!(doc.extract_section("Title").unwrap().starts_with("Intro."));
+
+        // Sections as written run to the next heading of the same or higher level
+        let (heading, text) = doc.find_section("setup").unwrap();
+        assert_eq!(heading.level, 2);
+        assert_eq!(text, "Setup\n-----\n\nRun it.");
+        assert!(doc.find_section("Title").unwrap().1.ends_with("Use it."));
+
+        // A thematic break after a blank line is not an underline
+        let doc = parse_markdown("> # Quoted\n\nText.\n\n---\n\nMore.\n");
+        assert_eq!(
+            doc.extract_section("Quoted").unwrap(),
+            "Text.\n\n---\n\nMore."
+        );
+    }
 }
@@ -1,20 +1,27 @@
 //! JSON output types for nested, markdown-intelligent structure
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Root document structure with metadata and nested sections
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DocumentOutput {
     pub document: DocumentRoot,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// JSON Schema (draft 2020-12) describing [`DocumentOutput`], as printed by
+/// `treemd --schema`.
+pub fn json_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(DocumentOutput)).expect("schema serializes to JSON")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DocumentRoot {
     pub metadata: DocumentMetadata,
     pub sections: Vec<Section>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DocumentMetadata {
     pub source: Option<String>,
     #[serde(rename = "headingCount")]
@@ -26,7 +33,7 @@ pub struct DocumentMetadata {
 }
 
 /// A section with nested children based on heading hierarchy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Section {
     /// Unique identifier (slugified heading)
     pub id: String,
@@ -34,17 +41,24 @@ pub struct Section {
     pub level: usize,
     /// Heading text
     pub title: String,
+    /// Inline structure of the heading, absent when it is plain text
+    #[serde(default, skip_serializing_if = "is_plain_text")]
+    pub inline: Vec<InlineElement>,
     /// URL-friendly slug
     pub slug: String,
     /// Position in document
     pub position: Position,
-    /// Parsed content
-    pub content: Content,
+    /// Words in this section's own content (excluding child sections)
+    #[serde(rename = "wordCount", default)]
+    pub word_count: usize,
+    /// Parsed content (absent in structure-only output)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub content: Option<Content>,
     /// Child sections (nested headings)
     pub children: Vec<Section>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Position {
     /// Line number in source file (1-indexed)
     pub line: usize,
@@ -52,7 +66,7 @@ pub struct Position {
     pub offset: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Content {
     /// Raw markdown content
     pub raw: String,
@@ -61,7 +75,7 @@ pub struct Content {
 }
 
 /// Content block types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Block {
     Paragraph {
@@ -81,6 +95,7 @@ pub enum Block {
         items: Vec<ListItem>,
     },
     Blockquote {
+        /// Quoted markdown, without the `>` markers
         content: String,
         blocks: Vec<Block>,
     },
@@ -96,25 +111,45 @@ pub enum Block {
     },
     #[serde(rename = "horizontal_rule")]
     HorizontalRule,
+    /// Raw HTML block, kept as written
+    Html {
+        raw: String,
+    },
     Details {
         summary: String,
         content: String,
         blocks: Vec<Block>,
+        /// Whether the block has the `open` attribute (expanded by default)
+        #[serde(default)]
+        open: bool,
+    },
+    /// Footnote definition (`[^label]: ...`); references point to it by label
+    Footnote {
+        label: String,
+        /// Definition rendered back to markdown
+        content: String,
+        blocks: Vec<Block>,
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ListItem {
     /// For task lists: true/false/null
     pub checked: Option<bool>,
+    /// Item number for ordered lists (honors the list's start number)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
     /// Raw content
     pub content: String,
     /// Parsed inline elements
     pub inline: Vec<InlineElement>,
+    /// Items of a nested list, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ListItem>,
 }
 
 /// Inline formatting elements
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum InlineElement {
     Text {
@@ -142,9 +177,126 @@ pub enum InlineElement {
     Strikethrough {
         value: String,
     },
+    /// Raw inline HTML tag such as `<br>` or `<kbd>`, kept as written
+    Html {
+        raw: String,
+    },
+    /// Footnote reference (`[^label]`) to the definition with the same label
+    #[serde(rename = "footnote_reference")]
+    FootnoteReference {
+        label: String,
+    },
+}
+
+/// Whether inline elements are nothing but plain text (or nothing at all).
+pub fn is_plain_text(inline: &[InlineElement]) -> bool {
+    inline
+        .iter()
+        .all(|element| matches!(element, InlineElement::Text { .. }))
+}
+
+/// Text of inline elements with the markdown stripped.
+///
+/// Links contribute their text and images their alt text; HTML tags are dropped.
+pub fn inline_plain_text(inline: &[InlineElement]) -> String {
+    inline
+        .iter()
+        .map(|element| match element {
+            InlineElement::Text { value }
+            | InlineElement::Strong { value }
+            | InlineElement::Emphasis { value }
+            | InlineElement::Code { value }
+            | InlineElement::Strikethrough { value } => value.as_str(),
+            InlineElement::Link { text, .. } => text,
+            InlineElement::Image { alt, .. } => alt,
+            InlineElement::Html { .. } | InlineElement::FootnoteReference { .. } => "",
+        })
+        .collect()
+}
+
+impl Block {
+    /// Text of the block with the markdown stripped, as one line per
+    /// paragraph, list item, table row or line of code.
+    pub fn plain_text(&self) -> String {
+        match self {
+            Block::Paragraph { content, inline } if inline.is_empty() => content.clone(),
+            Block::Paragraph { inline, .. } => inline_plain_text(inline),
+            Block::Code { content, .. } => content.clone(),
+            Block::List { items, .. } => list_plain_text(items),
+            Block::Blockquote { blocks, .. } => blocks_plain_text(blocks),
+            Block::Table { headers, rows, .. } => std::iter::once(headers)
+                .chain(rows)
+                .map(|row| row.join(" "))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Image { alt, .. } => alt.clone(),
+            Block::HorizontalRule | Block::Html { .. } => String::new(),
+            Block::Details {
+                summary, blocks, ..
+            } => format!("{}\n{}", summary, blocks_plain_text(blocks)),
+            Block::Footnote { blocks, .. } => blocks_plain_text(blocks),
+        }
+    }
+}
+
+/// Plain text of several blocks, one block per line.
+pub fn blocks_plain_text(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(Block::plain_text)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Checked and total task-list items in `blocks`, counting nested lists and
+/// lists inside blockquotes and details. Items without a checkbox don't count.
+pub fn blocks_task_counts(blocks: &[Block]) -> (usize, usize) {
+    fn count_items(items: &[ListItem], counts: &mut (usize, usize)) {
+        for item in items {
+            if let Some(checked) = item.checked {
+                counts.0 += usize::from(checked);
+                counts.1 += 1;
+            }
+            count_items(&item.children, counts);
+        }
+    }
+
+    let mut counts = (0, 0);
+    for block in blocks {
+        match block {
+            Block::List { items, .. } => count_items(items, &mut counts),
+            Block::Blockquote { blocks, .. } | Block::Details { blocks, .. } => {
+                let (done, total) = blocks_task_counts(blocks);
+                counts.0 += done;
+                counts.1 += total;
+            }
+            _ => {}
+        }
+    }
+    counts
+}
+
+fn list_plain_text(items: &[ListItem]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let text = if item.inline.is_empty() {
+                item.content.clone()
+            } else {
+                inline_plain_text(&item.inline)
+            };
+            if item.children.is_empty() {
+                text
+            } else {
+                format!("{}\n{}", text, list_plain_text(&item.children))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Alignment {
     Left,
@@ -21,6 +21,26 @@ pub fn strip_markdown_inline(text: &str) -> String {
         .replace("~~", "")
 }
 
+/// Strip a leading UTF-8 byte order mark and turn CRLF line endings into LF.
+///
+/// Returns the input unchanged (borrowed) when there is nothing to normalize.
+///
+/// # Examples
+///
+/// ```
+/// # use treemd::parser::utils::normalize_line_endings;
+/// assert_eq!(normalize_line_endings("\u{feff}# Title\r\nText\r\n"), "# Title\nText\n");
+/// assert_eq!(normalize_line_endings("a\rb"), "a\rb");
+/// ```
+pub fn normalize_line_endings(content: &str) -> std::borrow::Cow<'_, str> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    if content.contains("\r\n") {
+        std::borrow::Cow::Owned(content.replace("\r\n", "\n"))
+    } else {
+        std::borrow::Cow::Borrowed(content)
+    }
+}
+
 /// Extract the heading level from a line of markdown text.
 ///
 /// Returns `Some(level)` if the line is a valid heading (1-6 #'s followed by whitespace),
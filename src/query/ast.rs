@@ -156,11 +156,24 @@ pub enum ElementKind {
     Blockquote,
     /// Paragraph: `.para`
     Paragraph,
+    /// Collapsible details block: `.details`
+    Details,
+    /// Footnote definition: `.footnote`
+    Footnote,
     /// Front matter: `.frontmatter`
     FrontMatter,
 }
 
 impl ElementKind {
+    /// All names accepted by [`ElementKind::from_str`], used for suggestions.
+    pub const NAMES: &'static [&'static str] = &[
+        "h", "heading", "headings", "header", "headers", "h1", "h2", "h3", "h4", "h5", "h6",
+        "code", "codeblock", "codeblocks", "pre", "link", "links", "a", "anchor", "img", "image",
+        "images", "table", "tables", "list", "lists", "ul", "ol", "blockquote", "blockquotes",
+        "quote", "quotes", "bq", "para", "paragraph", "paragraphs", "p", "details", "disclosure",
+        "spoiler", "footnote", "footnotes", "fn", "frontmatter", "fm", "meta", "yaml",
+    ];
+
     /// Parse an element kind from a string.
     /// Supports multiple aliases for discoverability and convenience.
     pub fn from_str(s: &str) -> Option<Self> {
@@ -195,6 +208,12 @@ impl ElementKind {
             // Paragraphs
             "para" | "paragraph" | "paragraphs" | "p" => Some(ElementKind::Paragraph),
 
+            // Details blocks
+            "details" | "disclosure" | "spoiler" => Some(ElementKind::Details),
+
+            // Footnote definitions
+            "footnote" | "footnotes" | "fn" => Some(ElementKind::Footnote),
+
             // Front matter
             "frontmatter" | "fm" | "meta" | "yaml" => Some(ElementKind::FrontMatter),
 
@@ -220,6 +239,8 @@ impl ElementKind {
             ElementKind::List => "list",
             ElementKind::Blockquote => "blockquote",
             ElementKind::Paragraph => "para",
+            ElementKind::Details => "details",
+            ElementKind::Footnote => "footnote",
             ElementKind::FrontMatter => "frontmatter",
         }
     }
@@ -417,6 +438,13 @@ mod tests {
         assert_eq!(ElementKind::from_str("unknown"), None);
     }
 
+    #[test]
+    fn test_element_kind_names_all_parse() {
+        for name in ElementKind::NAMES {
+            assert!(ElementKind::from_str(name).is_some(), "{} should parse", name);
+        }
+    }
+
     #[test]
     fn test_binary_op_precedence() {
         assert!(BinaryOp::Mul.precedence() > BinaryOp::Add.precedence());
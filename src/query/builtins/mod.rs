@@ -5,10 +5,15 @@
 mod collection;
 mod string;
 
-use super::error::QueryError;
-use super::eval::EvalContext;
+use crate::parser::markdown::{shift_headings, shift_level};
+use crate::parser::parse_markdown;
+use super::ast::Span;
+use super::error::{QueryError, QueryErrorKind};
+use super::eval::{EvalContext, resource_limit};
+use super::output::{integral_numbers, value_to_json};
 use super::registry::{Function, Registry};
-use super::value::Value;
+use super::value::{ListItemValue, ListValue, Value};
+use unicode_width::UnicodeWidthStr;
 
 /// Register all built-in functions.
 pub fn register_all(registry: &mut Registry) {
@@ -21,7 +26,9 @@ pub fn register_all(registry: &mut Registry) {
     registry.register_function("sort", Function::new(fn_sort, 0..=0));
     registry.register_function("unique", Function::new(fn_unique, 0..=0));
     registry.register_function("flatten", Function::new(fn_flatten, 0..=0));
+    registry.register_function("paths", Function::new(fn_paths, 0..=0));
     registry.register_function("keys", Function::new(fn_keys, 0..=0));
+    registry.register_function("keys_unsorted", Function::new(fn_keys_unsorted, 0..=0));
     registry.register_function("values", Function::new(fn_values, 0..=0));
     registry.register_function("empty", Function::new(fn_empty, 0..=0));
 
@@ -30,21 +37,43 @@ pub fn register_all(registry: &mut Registry) {
     registry.register_function("upper", Function::new(fn_upper, 0..=0));
     registry.register_function("lower", Function::new(fn_lower, 0..=0));
     registry.register_function("trim", Function::new(fn_trim, 0..=0));
-    registry.register_function("split", Function::new(fn_split, 1..=1));
+    registry.register_function("indent", Function::new(fn_indent, 1..=1));
+    registry.register_function("dedent", Function::new(fn_dedent, 0..=0));
+    registry.register_function("split", Function::new(fn_split, 1..=2));
     registry.register_function("join", Function::new(fn_join, 1..=1));
     registry.register_function("replace", Function::new(fn_replace, 2..=2));
+    registry.register_function("sub", Function::new(fn_sub, 2..=3));
+    registry.register_function("gsub", Function::new(fn_gsub, 2..=3));
+    registry.register_function("capture", Function::new(fn_capture, 1..=2));
+    registry.register_function("splits", Function::new(fn_splits, 1..=2));
+    registry.register_function("scan", Function::new(fn_scan, 1..=2));
     registry.register_function("lines", Function::new(fn_lines, 0..=0));
     registry.register_function("words", Function::new(fn_words, 0..=0));
     registry.register_function("chars", Function::new(fn_chars, 0..=0));
+    registry.register_function("explode", Function::new(fn_explode, 0..=0));
+    registry.register_function("implode", Function::new(fn_implode, 0..=0));
+    registry.register_function("ascii", Function::new(fn_ascii, 0..=0));
     registry.register_function("slugify", Function::new(fn_slugify, 0..=0));
+    registry.register_function("index", Function::new(fn_index, 1..=1));
+    registry.register_function("rindex", Function::new(fn_rindex, 1..=1));
+    registry.register_function("indices", Function::new(fn_indices, 1..=1));
+
+    // Encoding functions
+    registry.register_function("tojson", Function::new(fn_tojson, 0..=0));
+    registry.register_function("fromjson", Function::new(fn_fromjson, 0..=0));
+    registry.register_function("base64", Function::new(fn_base64, 0..=0));
+    registry.register_function("base64d", Function::new(fn_base64d, 0..=0));
+    registry.register_function("html_escape", Function::new(fn_html_escape, 0..=0));
+    registry.register_function("uri_escape", Function::new(fn_uri_escape, 0..=0));
 
     // Boolean/filter functions
     registry.register_function("select", Function::new(fn_select, 1..=1).with_takes_input(true));
     registry.register_function("contains", Function::new(fn_contains, 1..=1));
     registry.register_function("startswith", Function::new(fn_startswith, 1..=1));
     registry.register_function("endswith", Function::new(fn_endswith, 1..=1));
-    registry.register_function("matches", Function::new(fn_matches, 1..=1));
+    registry.register_function("matches", Function::new(fn_matches, 1..=2));
     registry.register_function("has", Function::new(fn_has, 1..=1));
+    registry.register_function("in", Function::new(fn_in, 1..=1));
     registry.register_function("type", Function::new(fn_type, 0..=0));
 
     // Content functions
@@ -52,27 +81,45 @@ pub fn register_all(registry: &mut Registry) {
     registry.register_function("md", Function::new(fn_md, 0..=0));
     registry.register_function("url", Function::new(fn_url, 0..=0));
     registry.register_function("lang", Function::new(fn_lang, 0..=0));
+    registry.register_function("shift_level", Function::new(fn_shift_level, 1..=1));
 
     // Aggregation functions
     registry.register_function("stats", Function::new(fn_stats, 0..=0));
     registry.register_function("levels", Function::new(fn_levels, 0..=0));
     registry.register_function("langs", Function::new(fn_langs, 0..=0));
     registry.register_function("types", Function::new(fn_types, 0..=0));
+    registry.register_function("task_progress", Function::new(fn_task_progress, 0..=0));
+    registry.register_function("histogram", Function::new(fn_histogram, 0..=1));
 
     // Utility functions
     registry.register_function("limit", Function::new(fn_limit, 1..=1));
     registry.register_function("skip", Function::new(fn_skip, 1..=1));
-    registry.register_function("nth", Function::new(fn_nth, 1..=1));
-    registry.register_function("any", Function::new(fn_any, 1..=1).with_takes_input(true));
-    registry.register_function("all", Function::new(fn_all, 1..=1).with_takes_input(true));
+    registry.register_function("nth", Function::new(fn_nth, 1..=2));
+    registry.register_function("any", Function::new(fn_any, 0..=1).with_takes_input(true));
+    registry.register_function("all", Function::new(fn_all, 0..=1).with_takes_input(true));
     registry.register_function("min", Function::new(fn_min, 0..=0));
     registry.register_function("max", Function::new(fn_max, 0..=0));
     registry.register_function("add", Function::new(fn_add, 0..=0));
     registry.register_function("not", Function::new(fn_not, 0..=0));
     registry.register_function("null", Function::new(fn_null, 0..=0));
     registry.register_function("debug", Function::new(fn_debug, 0..=0));
-    registry.register_function("group_by", Function::new(fn_group_by, 1..=1));
+    registry.register_function("group_into", Function::new(fn_group_into, 1..=1));
     registry.register_function("sort_by", Function::new(fn_sort_by, 1..=1));
+    registry.register_function("recurse", Function::new(fn_engine_evaluated, 0..=1));
+    registry.register_function("walk", Function::new(fn_engine_evaluated, 1..=1));
+    registry.register_function(
+        "range",
+        Function::new(fn_engine_evaluated, 1..=3).with_takes_input(false),
+    );
+    registry.register_function("group_by", Function::new(fn_group_by, 1..=1));
+    registry.register_function("map_values", Function::new(fn_map_values, 1..=1));
+
+    // Environment functions (disabled unless the engine allows env access)
+    registry.register_function("env", Function::new(fn_env, 0..=1));
+    registry.register_function("now", Function::new(fn_now, 0..=0));
+
+    // Date functions
+    registry.register_function("strftime", Function::new(fn_strftime, 1..=1));
 
     // Aliases - comprehensive for discoverability
     // Length/count
@@ -86,10 +133,13 @@ pub fn register_all(registry: &mut Registry) {
     // String contains (JavaScript style)
     registry.register_alias("includes", "contains");
 
+    // Regex test (jq style)
+    registry.register_alias("test", "matches");
+
     // Underscore variants (Rust/Python style)
     registry.register_alias("starts_with", "startswith");
     registry.register_alias("ends_with", "endswith");
-    registry.register_alias("group", "group_by");
+    registry.register_alias("group", "group_into");
 
     // First/last alternatives (FP style)
     registry.register_alias("head", "first");
@@ -105,6 +155,16 @@ pub fn register_all(registry: &mut Registry) {
     registry.register_alias("language", "lang");
     registry.register_alias("src", "url");
     registry.register_alias("href", "url");
+
+    // jq-style variable for the whole environment
+    registry.register_alias("$ENV", "env");
+
+    // jq-style format strings
+    registry.register_alias("@json", "tojson");
+    registry.register_alias("@base64", "base64");
+    registry.register_alias("@base64d", "base64d");
+    registry.register_alias("@html", "html_escape");
+    registry.register_alias("@uri", "uri_escape");
 }
 
 // ============================================================================
@@ -116,7 +176,7 @@ fn fn_count(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError
     let count = match input {
         Value::Array(a) => a.len(),
         Value::String(s) => s.len(),
-        Value::Object(o) => o.len(),
+        Value::Object(o) | Value::FrontMatter(o) => o.len(),
         _ => 1,
     };
     Ok(vec![Value::Number(count as f64)])
@@ -158,7 +218,7 @@ fn fn_sort(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
     match input {
         Value::Array(a) => {
             let mut sorted = a.clone();
-            sorted.sort_by(|a, b| a.to_text().cmp(&b.to_text()));
+            sorted.sort_by(|a, b| a.sort_cmp(b));
             Ok(vec![Value::Array(sorted)])
         }
         _ => Ok(vec![input.clone()]),
@@ -169,12 +229,20 @@ fn fn_unique(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryErro
     let input = args.first().unwrap_or(&Value::Null);
     match input {
         Value::Array(a) => {
-            let mut seen = std::collections::HashSet::new();
-            let unique: Vec<Value> = a
-                .iter()
-                .filter(|v| seen.insert(v.to_text()))
-                .cloned()
-                .collect();
+            // Values are compared structurally, so `1` and `"1"` both stay.
+            // Candidates are looked up by their JSON text (serde_json sorts
+            // object keys) and then compared, as element JSON omits fields.
+            let mut unique: Vec<Value> = Vec::new();
+            let mut by_json: std::collections::HashMap<String, Vec<usize>> =
+                std::collections::HashMap::new();
+            for item in a {
+                let json = integral_numbers(value_to_json(item)).to_string();
+                let candidates = by_json.entry(json).or_default();
+                if !candidates.iter().any(|&i| unique[i] == *item) {
+                    candidates.push(unique.len());
+                    unique.push(item.clone());
+                }
+            }
             Ok(vec![Value::Array(unique)])
         }
         _ => Ok(vec![input.clone()]),
@@ -199,10 +267,67 @@ fn fn_flatten(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryErr
     }
 }
 
-fn fn_keys(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+/// Paths (arrays of keys and indices) to every value below the input, in
+/// depth-first order, like jq.
+///
+/// Walks with an explicit stack so deeply nested values can't overflow it.
+fn fn_paths(args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let limits = ctx.limits;
+
+    let mut paths = Vec::new();
+    let mut stack: Vec<(Vec<Value>, &Value)> = vec![(Vec::new(), input)];
+    while let Some((path, value)) = stack.pop() {
+        if path.len() > limits.max_depth {
+            return Err(resource_limit("levels of nesting", limits.max_depth as u128));
+        }
+        let children: Vec<(Value, &Value)> = match value {
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| (Value::Number(i as f64), item))
+                .collect(),
+            Value::Object(map) | Value::FrontMatter(map) => {
+                map.iter().map(|(k, v)| (Value::String(k.clone()), v)).collect()
+            }
+            _ => Vec::new(),
+        };
+        for (key, child) in children.into_iter().rev() {
+            let mut child_path = path.clone();
+            child_path.push(key);
+            stack.push((child_path, child));
+        }
+        if !path.is_empty() {
+            paths.push(Value::Array(path));
+            if paths.len() > limits.max_output {
+                return Err(resource_limit("output values", limits.max_output as u128));
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Object keys sorted alphabetically (like jq); array indices for arrays.
+///
+/// Use `keys_unsorted` to keep insertion order.
+fn fn_keys(args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    match input {
+        Value::Object(o) | Value::FrontMatter(o) => {
+            let mut keys: Vec<&String> = o.keys().collect();
+            keys.sort();
+            let keys: Vec<Value> = keys.into_iter().map(|k| Value::String(k.clone())).collect();
+            Ok(vec![Value::Array(keys)])
+        }
+        _ => fn_keys_unsorted(args, ctx),
+    }
+}
+
+/// Object keys in insertion order (e.g. front matter order); array indices for arrays.
+fn fn_keys_unsorted(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     match input {
-        Value::Object(o) => {
+        Value::Object(o) | Value::FrontMatter(o) => {
             let keys: Vec<Value> = o.keys().map(|k| Value::String(k.clone())).collect();
             Ok(vec![Value::Array(keys)])
         }
@@ -217,7 +342,7 @@ fn fn_keys(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
 fn fn_values(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     match input {
-        Value::Object(o) => {
+        Value::Object(o) | Value::FrontMatter(o) => {
             let values: Vec<Value> = o.values().cloned().collect();
             Ok(vec![Value::Array(values)])
         }
@@ -232,7 +357,7 @@ fn fn_empty(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError
         Value::Null => true,
         Value::String(s) => s.is_empty(),
         Value::Array(a) => a.is_empty(),
-        Value::Object(o) => o.is_empty(),
+        Value::Object(o) | Value::FrontMatter(o) => o.is_empty(),
         _ => false,
     };
     Ok(vec![Value::Bool(is_empty)])
@@ -262,22 +387,120 @@ fn fn_trim(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
     Ok(vec![Value::String(input.to_text().trim().to_string())])
 }
 
+/// Prefix every non-blank line with `n` spaces. Null, arrays and objects
+/// pass through unchanged.
+fn fn_indent(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let n = match args.get(1) {
+        Some(Value::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => *n as usize,
+        other => {
+            let found = other.map(|v| v.to_text()).unwrap_or_default();
+            return Err(type_error("non-negative whole number", found));
+        }
+    };
+    if matches!(input, Value::Null | Value::Array(_) | Value::Object(_)) {
+        return Ok(vec![input.clone()]);
+    }
+    Ok(vec![Value::String(indent_text(&input.to_text(), n))])
+}
+
+/// Strip the longest leading whitespace shared by every non-blank line.
+/// Null, arrays and objects pass through unchanged.
+fn fn_dedent(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    if matches!(input, Value::Null | Value::Array(_) | Value::Object(_)) {
+        return Ok(vec![input.clone()]);
+    }
+    Ok(vec![Value::String(dedent_text(&input.to_text()))])
+}
+
+fn indent_text(text: &str, n: usize) -> String {
+    let prefix = " ".repeat(n);
+    text.split_inclusive('\n')
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect()
+}
+
+/// Whitespace is compared character by character, so a tab and four spaces
+/// don't share a prefix: lines indented with a mix are only stripped of what
+/// they have in common. Blank lines don't count towards the prefix and lose
+/// whatever of it they have.
+fn dedent_text(text: &str) -> String {
+    let indent_of = |line: &str| -> usize {
+        line.len() - line.trim_start_matches(|c: char| c.is_whitespace() && c != '\n').len()
+    };
+    let prefix = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..indent_of(line)])
+        .reduce(|common, indent| {
+            let len = common
+                .char_indices()
+                .zip(indent.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(common.len().min(indent.len()), |((i, _), _)| i);
+            &common[..len]
+        })
+        .unwrap_or("");
+
+    text.split_inclusive('\n')
+        // Only blank lines can lack the prefix
+        .map(|line| line.strip_prefix(prefix).unwrap_or_else(|| &line[indent_of(line)..]))
+        .collect()
+}
+
+/// Split text on a literal separator, or on a regex when flags are given.
+///
+/// `split(sep)` splits on the literal `sep`; `split(re; flags)` treats `re` as a
+/// regular expression (`"g"` for a plain regex, see [`compile_regex`] for the
+/// others). Null, arrays and objects pass through unchanged.
 fn fn_split(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
+    if matches!(input, Value::Null | Value::Array(_) | Value::Object(_)) {
+        return Ok(vec![input.clone()]);
+    }
+
+    let text = input.to_text();
     let sep = args.get(1).map(|v| v.to_text()).unwrap_or_default();
-    let parts: Vec<Value> = input
-        .to_text()
-        .split(&sep)
-        .map(|s| Value::String(s.to_string()))
-        .collect();
+
+    let parts: Vec<Value> = match args.get(2).map(|v| v.to_text()) {
+        Some(flags) => {
+            compile_regex(&sep, &flags, true)?
+                .split(&text)
+                .map(|s| Value::String(s.to_string()))
+                .collect()
+        }
+        None => text.split(&sep).map(|s| Value::String(s.to_string())).collect(),
+    };
     Ok(vec![Value::Array(parts)])
 }
 
+/// Join array elements with a separator, flattening one level of nested
+/// arrays. Objects (and arrays nested deeper) can't be joined.
 fn fn_join(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let sep = args.get(1).map(|v| v.to_text()).unwrap_or_default();
     let result = match input {
-        Value::Array(a) => a.iter().map(|v| v.to_text()).collect::<Vec<_>>().join(&sep),
+        Value::Array(a) => {
+            let leaves = a.iter().flat_map(|v| match v {
+                Value::Array(inner) => inner.iter().collect(),
+                _ => vec![v],
+            });
+            let mut texts = Vec::new();
+            for leaf in leaves {
+                if matches!(leaf, Value::Array(_) | Value::Object(_)) {
+                    return Err(type_error("scalar to join", leaf.kind().to_string()));
+                }
+                texts.push(leaf.to_text());
+            }
+            texts.join(&sep)
+        }
         _ => input.to_text(),
     };
     Ok(vec![Value::String(result)])
@@ -290,6 +513,200 @@ fn fn_replace(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryErr
     Ok(vec![Value::String(input.to_text().replace(&from, &to))])
 }
 
+/// Replace the first regex match, or every match with the `"g"` flag.
+fn fn_sub(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let global = args.get(3).is_some_and(|flags| flags.to_text().contains('g'));
+    regex_replace(args, if global { 0 } else { 1 })
+}
+
+fn fn_gsub(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    regex_replace(args, 0)
+}
+
+/// Replace up to `limit` regex matches (0 = all) in the input string.
+///
+/// Shared by `sub` and `gsub`. Args are `[input, pattern, replacement, flags?]`.
+fn regex_replace(args: &[Value], limit: usize) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().map(|v| v.to_text()).unwrap_or_default();
+    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let replacement = args.get(2).map(|v| v.to_text()).unwrap_or_default();
+    let flags = args.get(3).map(|v| v.to_text()).unwrap_or_default();
+
+    let re = compile_regex(&pattern, &flags, true)?;
+    let replacement = expand_backreferences(&replacement);
+    let result = re.replacen(&input, limit, replacement.as_str());
+    Ok(vec![Value::String(result.into_owned())])
+}
+
+/// Named groups of the first regex match as an object (unmatched groups are
+/// null). Produces no output when nothing matches, like jq.
+fn fn_capture(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().map(|v| v.to_text()).unwrap_or_default();
+    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let flags = args.get(2).map(|v| v.to_text()).unwrap_or_default();
+
+    let re = compile_regex(&pattern, &flags, false)?;
+    let Some(caps) = re.captures(&input) else {
+        return Ok(vec![]);
+    };
+    let object = re
+        .capture_names()
+        .flatten()
+        .map(|name| {
+            let value = caps
+                .name(name)
+                .map_or(Value::Null, |m| Value::String(m.as_str().to_string()));
+            (name.to_string(), value)
+        })
+        .collect();
+    Ok(vec![Value::Object(object)])
+}
+
+/// Split on a regex, producing each piece as a separate result.
+fn fn_splits(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().map(|v| v.to_text()).unwrap_or_default();
+    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let flags = args.get(2).map(|v| v.to_text()).unwrap_or_default();
+
+    let re = compile_regex(&pattern, &flags, true)?;
+    Ok(re.split(&input).map(|s| Value::String(s.to_string())).collect())
+}
+
+/// Every non-overlapping regex match, each as a separate result like jq.
+/// With capture groups, each result is the array of the groups instead
+/// (null for a group that didn't take part).
+fn fn_scan(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().map(|v| v.to_text()).unwrap_or_default();
+    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let flags = args.get(2).map(|v| v.to_text()).unwrap_or_default();
+
+    let re = compile_regex(&pattern, &flags, true)?;
+    let text = |m: Option<regex::Match>| {
+        m.map_or(Value::Null, |m| Value::String(m.as_str().to_string()))
+    };
+    Ok(re
+        .captures_iter(&input)
+        .map(|caps| {
+            if caps.len() == 1 {
+                text(caps.get(0))
+            } else {
+                Value::Array(caps.iter().skip(1).map(text).collect())
+            }
+        })
+        .collect())
+}
+
+/// Compile a regex with jq-style flags, reporting failures as `InvalidRegex`.
+///
+/// `i` ignores case, `m` lets `^`/`$` match at line breaks, `s` lets `.` match
+/// newlines and `x` allows whitespace and `#` comments in the pattern.
+///
+/// `g` only means something to callers that can match more than once, which
+/// pass `global`: `sub` replaces every match with it, while `gsub`, `scan`,
+/// `split` and `splits` always match globally and accept it for jq
+/// compatibility. Elsewhere (`test`, `capture`) it is rejected.
+fn compile_regex(pattern: &str, flags: &str, global: bool) -> Result<regex::Regex, QueryError> {
+    let invalid = |error: String| {
+        QueryError::new(
+            QueryErrorKind::InvalidRegex {
+                pattern: pattern.to_string(),
+                error,
+            },
+            Span::default(),
+            String::new(),
+        )
+    };
+
+    let mut builder = regex::RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            'g' if global => &mut builder,
+            'g' => {
+                return Err(invalid(
+                    "flag 'g' only applies to sub, gsub, scan, split and splits".to_string(),
+                ));
+            }
+            other => return Err(invalid(format!("unknown flag '{}'", other))),
+        };
+    }
+    builder.build().map_err(|e| invalid(e.to_string()))
+}
+
+/// Convert `\1`-style backreferences to the regex crate's `${1}` syntax.
+///
+/// `$1` and `${name}` are already understood and pass through unchanged.
+fn expand_backreferences(replacement: &str) -> String {
+    let mut result = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek().is_some_and(|d| d.is_ascii_digit()) {
+            let mut group = String::new();
+            while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                group.push(*d);
+                chars.next();
+            }
+            result.push_str(&format!("${{{}}}", group));
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+fn fn_index(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let found = positions(args).first().copied();
+    Ok(vec![found.map(|i| Value::Number(i as f64)).unwrap_or(Value::Null)])
+}
+
+fn fn_rindex(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let found = positions(args).last().copied();
+    Ok(vec![found.map(|i| Value::Number(i as f64)).unwrap_or(Value::Null)])
+}
+
+fn fn_indices(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let found = positions(args)
+        .into_iter()
+        .map(|i| Value::Number(i as f64))
+        .collect();
+    Ok(vec![Value::Array(found)])
+}
+
+/// Positions of the needle (`args[1]`) in the input (`args[0]`).
+///
+/// Arrays yield element indices (compared by text); anything else is treated
+/// as a string and yields char indices of every match, including overlapping ones.
+fn positions(args: &[Value]) -> Vec<usize> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let needle = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+
+    match input {
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.to_text() == needle)
+            .map(|(i, _)| i)
+            .collect(),
+        _ => {
+            if needle.is_empty() {
+                return Vec::new();
+            }
+            let haystack = input.to_text();
+            haystack
+                .char_indices()
+                .enumerate()
+                .filter(|(_, (byte, _))| haystack[*byte..].starts_with(&needle))
+                .map(|(char_idx, _)| char_idx)
+                .collect()
+        }
+    }
+}
+
 fn fn_lines(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let count = input.to_text().lines().count();
@@ -308,6 +725,56 @@ fn fn_chars(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError
     Ok(vec![Value::Number(count as f64)])
 }
 
+/// String to array of Unicode codepoints.
+fn fn_explode(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let codepoints = input
+        .to_text()
+        .chars()
+        .map(|c| Value::Number(c as u32 as f64))
+        .collect();
+    Ok(vec![Value::Array(codepoints)])
+}
+
+/// Array of Unicode codepoints back to a string.
+fn fn_implode(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    match args.first().unwrap_or(&Value::Null) {
+        Value::Array(items) => {
+            let text = items.iter().map(to_codepoint).collect::<Result<String, _>>()?;
+            Ok(vec![Value::String(text)])
+        }
+        other => Err(type_error("array of codepoints", other.kind().to_string())),
+    }
+}
+
+/// ASCII codepoint (0-127) to a one-character string.
+fn fn_ascii(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    match to_codepoint(input)? {
+        c if c.is_ascii() => Ok(vec![Value::String(c.to_string())]),
+        c => Err(type_error("ASCII codepoint", (c as u32).to_string())),
+    }
+}
+
+/// Convert a number to the `char` with that codepoint.
+fn to_codepoint(value: &Value) -> Result<char, QueryError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64 => {
+            char::from_u32(*n as u32).ok_or_else(|| type_error("valid codepoint", n.to_string()))
+        }
+        Value::Number(n) => Err(type_error("valid codepoint", n.to_string())),
+        other => Err(type_error("codepoint", other.kind().to_string())),
+    }
+}
+
+fn type_error(expected: &'static str, found: String) -> QueryError {
+    QueryError::new(
+        QueryErrorKind::TypeError { expected, found },
+        Span::default(),
+        String::new(),
+    )
+}
+
 fn fn_slugify(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let text = input.to_text();
@@ -332,6 +799,90 @@ fn fn_slugify(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryErr
     Ok(vec![Value::String(slug)])
 }
 
+// ============================================================================
+// Encoding functions
+// ============================================================================
+
+/// Serialize the input as compact JSON text.
+fn fn_tojson(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let json = integral_numbers(value_to_json(input));
+    Ok(vec![Value::String(json.to_string())])
+}
+
+/// Parse JSON text into a value.
+fn fn_fromjson(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let value = serde_json::from_str(&input.to_text())
+        .map_err(|e| invalid_input("JSON", e.to_string()))?;
+    Ok(vec![value])
+}
+
+fn fn_base64(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    use base64::Engine;
+    let input = args.first().unwrap_or(&Value::Null);
+    let encoded = base64::engine::general_purpose::STANDARD.encode(input.to_text());
+    Ok(vec![Value::String(encoded)])
+}
+
+/// Decode base64 text, with or without padding. Invalid UTF-8 is replaced.
+fn fn_base64d(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    use base64::Engine;
+    use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+
+    const LENIENT: GeneralPurpose = GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    );
+
+    let input = args.first().unwrap_or(&Value::Null);
+    let bytes = LENIENT
+        .decode(input.to_text().trim())
+        .map_err(|e| invalid_input("base64", e.to_string()))?;
+    Ok(vec![Value::String(String::from_utf8_lossy(&bytes).into_owned())])
+}
+
+/// Escape `<>&"'` so the text can be embedded in HTML content or attributes.
+fn fn_html_escape(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null).to_text();
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    Ok(vec![Value::String(escaped)])
+}
+
+/// Percent-encode every byte outside the RFC 3986 unreserved set
+/// (`A-Z a-z 0-9 - . _ ~`), so the text is safe in any part of a URL.
+fn fn_uri_escape(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    use std::fmt::Write;
+    let input = args.first().unwrap_or(&Value::Null).to_text();
+    let mut escaped = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            escaped.push(byte as char);
+        } else {
+            let _ = write!(escaped, "%{:02X}", byte);
+        }
+    }
+    Ok(vec![Value::String(escaped)])
+}
+
+fn invalid_input(format: &'static str, error: String) -> QueryError {
+    QueryError::new(
+        QueryErrorKind::InvalidInput { format, error },
+        Span::default(),
+        String::new(),
+    )
+}
+
 // ============================================================================
 // Boolean/filter functions
 // ============================================================================
@@ -348,48 +899,93 @@ fn fn_select(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryErro
     }
 }
 
-fn fn_contains(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
-    let input = args.first().unwrap_or(&Value::Null);
-    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
-    let result = input.to_text().to_lowercase().contains(&pattern.to_lowercase());
+/// Case-insensitive string test of the input against the argument.
+///
+/// An array argument is a list of candidates: the test passes if any matches.
+fn test_text(args: &[Value], test: impl Fn(&str, &str) -> bool) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null).to_text().to_lowercase();
+    let result = match args.get(1) {
+        Some(Value::Array(candidates)) => candidates
+            .iter()
+            .any(|c| test(&input, &c.to_text().to_lowercase())),
+        other => {
+            let pattern = other.map(|v| v.to_text()).unwrap_or_default();
+            test(&input, &pattern.to_lowercase())
+        }
+    };
     Ok(vec![Value::Bool(result)])
 }
 
+/// Substring test for text; subset test for containers (like jq).
+///
+/// An array input contains an array argument if every argument element is
+/// contained in some input element; an object input contains an object
+/// argument if every key is present with a containing value.
+fn fn_contains(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    match (args.first(), args.get(1)) {
+        (Some(input @ Value::Array(_)), Some(other @ Value::Array(_)))
+        | (Some(input @ Value::Object(_)), Some(other @ Value::Object(_))) => {
+            Ok(vec![Value::Bool(value_contains(input, other))])
+        }
+        _ => test_text(args, |text, pattern| text.contains(pattern)),
+    }
+}
+
+fn value_contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::Array(a), Value::Array(b)) => b
+            .iter()
+            .all(|needle| a.iter().any(|item| value_contains(item, needle))),
+        (Value::Object(a), Value::Object(b)) => b
+            .iter()
+            .all(|(key, needle)| a.get(key).is_some_and(|item| value_contains(item, needle))),
+        _ => haystack
+            .to_text()
+            .to_lowercase()
+            .contains(&needle.to_text().to_lowercase()),
+    }
+}
+
 fn fn_startswith(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
-    let input = args.first().unwrap_or(&Value::Null);
-    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
-    let result = input.to_text().to_lowercase().starts_with(&pattern.to_lowercase());
-    Ok(vec![Value::Bool(result)])
+    test_text(args, |text, pattern| text.starts_with(pattern))
 }
 
 fn fn_endswith(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
-    let input = args.first().unwrap_or(&Value::Null);
-    let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
-    let result = input.to_text().to_lowercase().ends_with(&pattern.to_lowercase());
-    Ok(vec![Value::Bool(result)])
+    test_text(args, |text, pattern| text.ends_with(pattern))
 }
 
+/// Whether the input matches a regex, with optional flags (alias: `test`).
 fn fn_matches(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let pattern = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let flags = args.get(2).map(|v| v.to_text()).unwrap_or_default();
 
-    let result = regex::Regex::new(&pattern)
-        .map(|re| re.is_match(&input.to_text()))
-        .unwrap_or(false);
-
-    Ok(vec![Value::Bool(result)])
+    let re = compile_regex(&pattern, &flags, false)?;
+    Ok(vec![Value::Bool(re.is_match(&input.to_text()))])
 }
 
+/// Whether an object has a key, or an array has a valid index.
 fn fn_has(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
-    let key = args.get(1).map(|v| v.to_text()).unwrap_or_default();
+    let key = args.get(1).unwrap_or(&Value::Null);
+    Ok(vec![Value::Bool(has_key(input, key))])
+}
 
-    let result = match input {
-        Value::Object(o) => o.contains_key(&key),
-        _ => false,
-    };
+/// The reverse of `has`: whether the input is a key/index of the argument.
+fn fn_in(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let key = args.first().unwrap_or(&Value::Null);
+    let container = args.get(1).unwrap_or(&Value::Null);
+    Ok(vec![Value::Bool(has_key(container, key))])
+}
 
-    Ok(vec![Value::Bool(result)])
+fn has_key(container: &Value, key: &Value) -> bool {
+    match (container, key) {
+        (Value::Array(a), Value::Number(n)) => {
+            n.fract() == 0.0 && *n >= 0.0 && (*n as usize) < a.len()
+        }
+        (Value::Object(o) | Value::FrontMatter(o), _) => o.contains_key(&key.to_text()),
+        _ => false,
+    }
 }
 
 fn fn_type(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
@@ -434,6 +1030,26 @@ fn fn_lang(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
     }
 }
 
+/// Shift a heading's level by n (clamped to 1-6), along with every heading
+/// in its markdown.
+fn fn_shift_level(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let by = match args.get(1) {
+        Some(Value::Number(n)) if n.fract() == 0.0 => *n as isize,
+        Some(other) => return Err(type_error("integer", other.to_text())),
+        None => 0,
+    };
+    match input {
+        Value::Heading(h) => {
+            let mut heading = h.clone();
+            heading.level = shift_level(h.level as usize, by).0 as u8;
+            heading.raw_md = shift_headings(&parse_markdown(&h.raw_md), by).0;
+            Ok(vec![Value::Heading(heading)])
+        }
+        other => Err(type_error("heading", other.kind().to_string())),
+    }
+}
+
 // ============================================================================
 // Aggregation functions
 // ============================================================================
@@ -496,32 +1112,156 @@ fn fn_types(args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
     Ok(vec![Value::Object(obj)])
 }
 
+/// Checked and total task-list items of a list, or of an array of lists,
+/// as `{done, total, percent}`, counting nested items. Items without a
+/// checkbox don't count.
+fn fn_task_progress(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let lists: Vec<&ListValue> = match input {
+        Value::List(list) => vec![list],
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::List(list) => Ok(list),
+                other => Err(type_error("list", other.kind().to_string())),
+            })
+            .collect::<Result<_, _>>()?,
+        other => return Err(type_error("list", other.kind().to_string())),
+    };
+
+    fn count_items(items: &[ListItemValue], counts: &mut (usize, usize)) {
+        for item in items {
+            if let Some(checked) = item.checked {
+                counts.0 += usize::from(checked);
+                counts.1 += 1;
+            }
+            count_items(&item.children, counts);
+        }
+    }
+
+    let mut counts = (0, 0);
+    for list in lists {
+        count_items(&list.items, &mut counts);
+    }
+    let (done, total) = counts;
+    let percent = if total == 0 {
+        0.0
+    } else {
+        (done as f64 * 100.0 / total as f64).round()
+    };
+
+    let mut obj = indexmap::IndexMap::new();
+    obj.insert("done".to_string(), Value::Number(done as f64));
+    obj.insert("total".to_string(), Value::Number(total as f64));
+    obj.insert("percent".to_string(), Value::Number(percent));
+    Ok(vec![Value::Object(obj)])
+}
+
+/// Columns a `histogram` fits without a width argument or a terminal
+const HISTOGRAM_WIDTH: usize = 80;
+
+/// Render an object of counts (like `levels` or `langs` output) as ASCII
+/// bars, largest first: `rust  ████████ 12`. Lines fit `width` columns,
+/// by default the terminal's (see [`EvalContext::terminal_width`]), or
+/// [`HISTOGRAM_WIDTH`] when output doesn't go to one.
+fn fn_histogram(args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let obj = match input {
+        Value::Object(o) | Value::FrontMatter(o) => o,
+        other => return Err(type_error("object of counts", other.kind().to_string())),
+    };
+    let width = match args.get(1) {
+        Some(Value::Number(n)) if *n >= 1.0 => *n as usize,
+        Some(other) => return Err(type_error("positive width", other.to_text())),
+        None => ctx.terminal_width.unwrap_or(HISTOGRAM_WIDTH),
+    };
+
+    let mut rows = obj
+        .iter()
+        .map(|(label, value)| match value {
+            Value::Number(n) if *n >= 0.0 => Ok((label.as_str(), *n, value.to_text())),
+            other => Err(type_error("non-negative count", other.to_text())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let label_width = rows.iter().map(|(label, ..)| label.width()).max().unwrap_or(0);
+    let count_width = rows.iter().map(|(.., count)| count.len()).max().unwrap_or(0);
+    let max_bar = width.saturating_sub(label_width + count_width + 3).max(1);
+    let counts: Vec<f64> = rows.iter().map(|(_, n, _)| *n).collect();
+
+    let lines: Vec<String> = rows
+        .iter()
+        .zip(bar_lengths(&counts, max_bar))
+        .map(|((label, _, count), bar)| {
+            let pad = label_width - label.width();
+            format!("{}{}  {} {}", label, " ".repeat(pad), "█".repeat(bar), count)
+        })
+        .collect();
+    Ok(vec![Value::String(lines.join("\n"))])
+}
+
+/// Bar lengths for `counts`, scaled so the largest gets `max_bar` cells.
+/// Any non-zero count gets at least one cell so it stays visible.
+fn bar_lengths(counts: &[f64], max_bar: usize) -> Vec<usize> {
+    let max = counts.iter().copied().fold(0.0, f64::max);
+    counts
+        .iter()
+        .map(|&n| {
+            if n <= 0.0 || max <= 0.0 {
+                0
+            } else {
+                ((n / max * max_bar as f64).round() as usize).clamp(1, max_bar)
+            }
+        })
+        .collect()
+}
+
 // ============================================================================
 // Utility functions
 // ============================================================================
 
+/// First `n` elements or characters; a negative `n` keeps the last `-n`.
 fn fn_limit(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
-    let input = args.first().unwrap_or(&Value::Null);
-    let n = args.get(1)
-        .and_then(|v| if let Value::Number(n) = v { Some(*n as usize) } else { None })
-        .unwrap_or(0);
-
-    match input {
-        Value::Array(a) => Ok(vec![Value::Array(a.iter().take(n).cloned().collect())]),
-        Value::String(s) => Ok(vec![Value::String(s.chars().take(n).collect())]),
-        _ => Ok(vec![input.clone()]),
-    }
+    slice_by_count(args, |len, n| {
+        if n < 0 {
+            len.saturating_sub(n.unsigned_abs() as usize)..len
+        } else {
+            0..len.min(n as usize)
+        }
+    })
 }
 
+/// Drop the first `n` elements or characters; a negative `n` drops the last `-n`.
 fn fn_skip(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    slice_by_count(args, |len, n| {
+        if n < 0 {
+            0..len.saturating_sub(n.unsigned_abs() as usize)
+        } else {
+            len.min(n as usize)..len
+        }
+    })
+}
+
+/// Shared by `limit` and `skip`: keep the range that `range(len, n)` picks out
+/// of an array or string. Counts past the length clamp; other values pass
+/// through.
+fn slice_by_count(
+    args: &[Value],
+    range: impl Fn(usize, i64) -> std::ops::Range<usize>,
+) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let n = args.get(1)
-        .and_then(|v| if let Value::Number(n) = v { Some(*n as usize) } else { None })
+        .and_then(|v| if let Value::Number(n) = v { Some(*n as i64) } else { None })
         .unwrap_or(0);
 
     match input {
-        Value::Array(a) => Ok(vec![Value::Array(a.iter().skip(n).cloned().collect())]),
-        Value::String(s) => Ok(vec![Value::String(s.chars().skip(n).collect())]),
+        Value::Array(a) => Ok(vec![Value::Array(a[range(a.len(), n)].to_vec())]),
+        Value::String(s) => {
+            let kept = range(s.chars().count(), n);
+            let text = s.chars().skip(kept.start).take(kept.len()).collect();
+            Ok(vec![Value::String(text)])
+        }
         _ => Ok(vec![input.clone()]),
     }
 }
@@ -553,28 +1293,28 @@ fn fn_nth(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
     }
 }
 
+/// `any`/`all` without a predicate, or on a single value. Predicates over
+/// arrays and objects are evaluated per element by the engine.
 fn fn_any(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
-    let condition = args.get(1).unwrap_or(&Value::Bool(false));
 
-    let result = match input {
-        Value::Array(a) => a.iter().any(|v| {
-            // If condition is a function result applied to each element
-            // For now, check if any element is truthy
-            v.is_truthy()
-        }),
-        _ => condition.is_truthy(),
+    let result = match (input, args.get(1)) {
+        (Value::Array(a), None) => a.iter().any(Value::is_truthy),
+        (Value::Object(o) | Value::FrontMatter(o), None) => o.values().any(Value::is_truthy),
+        (_, Some(condition)) => condition.is_truthy(),
+        (_, None) => input.is_truthy(),
     };
     Ok(vec![Value::Bool(result)])
 }
 
 fn fn_all(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
-    let _condition = args.get(1).unwrap_or(&Value::Bool(true));
 
-    let result = match input {
-        Value::Array(a) => a.iter().all(|v| v.is_truthy()),
-        _ => input.is_truthy(),
+    let result = match (input, args.get(1)) {
+        (Value::Array(a), None) => a.iter().all(Value::is_truthy),
+        (Value::Object(o) | Value::FrontMatter(o), None) => o.values().all(Value::is_truthy),
+        (_, Some(condition)) => condition.is_truthy(),
+        (_, None) => input.is_truthy(),
     };
     Ok(vec![Value::Bool(result)])
 }
@@ -586,11 +1326,8 @@ fn fn_min(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
             let min = a.iter()
                 .filter_map(|v| if let Value::Number(n) = v { Some(*n) } else { None })
                 .fold(f64::INFINITY, f64::min);
-            if min.is_infinite() {
-                Ok(vec![Value::Null])
-            } else {
-                Ok(vec![Value::Number(min)])
-            }
+            // No numbers leaves the fold at infinity, which becomes null
+            Ok(vec![Value::number(min)])
         }
         Value::Number(n) => Ok(vec![Value::Number(*n)]),
         _ => Ok(vec![Value::Null]),
@@ -604,36 +1341,58 @@ fn fn_max(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError>
             let max = a.iter()
                 .filter_map(|v| if let Value::Number(n) = v { Some(*n) } else { None })
                 .fold(f64::NEG_INFINITY, f64::max);
-            if max.is_infinite() {
-                Ok(vec![Value::Null])
-            } else {
-                Ok(vec![Value::Number(max)])
-            }
+            Ok(vec![Value::number(max)])
         }
         Value::Number(n) => Ok(vec![Value::Number(*n)]),
         _ => Ok(vec![Value::Null]),
     }
 }
 
+/// Sum numbers, or concatenate strings or arrays; nulls are skipped.
+///
+/// The non-null items must all be numbers, all strings or all arrays.
+/// Elements and objects are a type error: project them to numbers first,
+/// e.g. `[.h | .level] | add`.
 fn fn_add(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
-    match input {
-        Value::Array(a) => {
-            // Check if all are numbers
-            let all_numbers = a.iter().all(|v| matches!(v, Value::Number(_)));
-            if all_numbers {
-                let sum: f64 = a.iter()
-                    .filter_map(|v| if let Value::Number(n) = v { Some(*n) } else { None })
-                    .sum();
-                Ok(vec![Value::Number(sum)])
-            } else {
-                // Concatenate as strings
-                let concat: String = a.iter().map(|v| v.to_text()).collect();
-                Ok(vec![Value::String(concat)])
+    let Value::Array(a) = input else {
+        return Ok(vec![input.clone()]);
+    };
+
+    let items: Vec<&Value> = a.iter().filter(|v| !matches!(v, Value::Null)).collect();
+    let result = match items.first() {
+        None => Value::Number(0.0),
+        Some(Value::Number(_)) if items.iter().all(|v| matches!(v, Value::Number(_))) => {
+            Value::number(items.iter().map(|v| v.as_number().unwrap_or(0.0)).sum())
+        }
+        Some(Value::String(_)) if items.iter().all(|v| matches!(v, Value::String(_))) => {
+            Value::String(items.iter().map(|v| v.to_text()).collect())
+        }
+        Some(Value::Array(_)) if items.iter().all(|v| matches!(v, Value::Array(_))) => {
+            Value::Array(
+                items
+                    .iter()
+                    .flat_map(|v| match v {
+                        Value::Array(inner) => inner.clone(),
+                        _ => Vec::new(),
+                    })
+                    .collect(),
+            )
+        }
+        Some(_) => {
+            let mut kinds: Vec<String> = Vec::new();
+            for kind in items.iter().map(|v| v.kind().to_string()) {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
             }
+            return Err(type_error(
+                "numbers, strings or arrays to add",
+                kinds.join(" and "),
+            ));
         }
-        _ => Ok(vec![input.clone()]),
-    }
+    };
+    Ok(vec![result])
 }
 
 fn fn_not(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
@@ -652,7 +1411,12 @@ fn fn_debug(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError
     Ok(vec![input.clone()])
 }
 
-fn fn_group_by(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+/// Group into an object keyed by group name, in first-seen order.
+///
+/// Unlike `group_by` (jq-compatible, an array of groups sorted by key, see
+/// [`fn_group_by`]), headings are keyed `h1`, `h2`, ... and code
+/// blocks by language.
+fn fn_group_into(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
     let key_name = args.get(1).map(|v| v.to_text()).unwrap_or_else(|| "key".to_string());
 
@@ -661,7 +1425,7 @@ fn fn_group_by(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryEr
             let mut groups: indexmap::IndexMap<String, Vec<Value>> = indexmap::IndexMap::new();
             for item in a {
                 let key = match item {
-                    Value::Object(o) => o.get(&key_name).map(|v| v.to_text()).unwrap_or_default(),
+                    Value::Object(o) | Value::FrontMatter(o) => o.get(&key_name).map(|v| v.to_text()).unwrap_or_default(),
                     Value::Heading(h) => format!("h{}", h.level),
                     Value::Code(c) => c.language.clone().unwrap_or_else(|| "none".to_string()),
                     _ => item.to_text(),
@@ -679,30 +1443,189 @@ fn fn_group_by(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryEr
     }
 }
 
+/// Sort an array by a key per element, stably.
+///
+/// The engine evaluates the key expression on each element and passes the
+/// outputs as an array of arrays (see `Engine::per_item_outputs`); an element
+/// without output sorts by null. Keys compare by type first (see
+/// [`Value::sort_cmp`]), so 1 and "1" stay apart and 10 sorts after 2.
 fn fn_sort_by(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
     let input = args.first().unwrap_or(&Value::Null);
-    let key_name = args.get(1).map(|v| v.to_text()).unwrap_or_else(|| "key".to_string());
+    let (Value::Array(items), Some(Value::Array(keys))) = (input, args.get(1)) else {
+        return Ok(vec![input.clone()]);
+    };
 
-    match input {
-        Value::Array(a) => {
-            let mut sorted = a.clone();
-            sorted.sort_by(|a, b| {
-                let a_key = match a {
-                    Value::Object(o) => o.get(&key_name).map(|v| v.to_text()).unwrap_or_default(),
-                    Value::Heading(h) if key_name == "level" => h.level.to_string(),
-                    Value::Heading(h) if key_name == "text" => h.text.clone(),
-                    _ => a.to_text(),
-                };
-                let b_key = match b {
-                    Value::Object(o) => o.get(&key_name).map(|v| v.to_text()).unwrap_or_default(),
-                    Value::Heading(h) if key_name == "level" => h.level.to_string(),
-                    Value::Heading(h) if key_name == "text" => h.text.clone(),
-                    _ => b.to_text(),
-                };
-                a_key.cmp(&b_key)
-            });
-            Ok(vec![Value::Array(sorted)])
+    let mut keyed: Vec<(&Value, &Value)> =
+        items.iter().zip(keys.iter().map(first_output)).collect();
+    keyed.sort_by(|(_, a), (_, b)| a.sort_cmp(b));
+    Ok(vec![Value::Array(keyed.into_iter().map(|(item, _)| item.clone()).collect())])
+}
+
+/// Group an array by a key per element into an array of groups, sorted by
+/// key as in [`fn_sort_by`], each keeping its elements in input order.
+fn fn_group_by(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let (Value::Array(items), Some(Value::Array(keys))) = (input, args.get(1)) else {
+        return Ok(vec![input.clone()]);
+    };
+
+    let mut keyed: Vec<(&Value, &Value)> =
+        items.iter().zip(keys.iter().map(first_output)).collect();
+    keyed.sort_by(|(_, a), (_, b)| a.sort_cmp(b));
+
+    let mut groups: Vec<(&Value, Vec<Value>)> = Vec::new();
+    for (item, key) in keyed {
+        match groups.last_mut() {
+            Some((last, group)) if last.sort_cmp(key).is_eq() => group.push(item.clone()),
+            _ => groups.push((key, vec![item.clone()])),
         }
-        _ => Ok(vec![input.clone()]),
+    }
+    let groups = groups.into_iter().map(|(_, group)| Value::Array(group)).collect();
+    Ok(vec![Value::Array(groups)])
+}
+
+/// Replace each value of an object or array with its first output of an
+/// expression, keeping keys. A value without output is dropped.
+///
+/// As with [`fn_sort_by`], the engine supplies the outputs per value.
+fn fn_map_values(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let input = args.first().unwrap_or(&Value::Null);
+    let Some(Value::Array(outputs)) = args.get(1) else {
+        return Ok(vec![input.clone()]);
+    };
+    let first = |outputs: &Value| match outputs {
+        Value::Array(outputs) => outputs.first().cloned(),
+        other => Some(other.clone()),
+    };
+
+    let result = match input {
+        Value::Object(entries) => Value::Object(
+            entries
+                .keys()
+                .zip(outputs)
+                .filter_map(|(key, outputs)| Some((key.clone(), first(outputs)?)))
+                .collect(),
+        ),
+        Value::Array(_) => Value::Array(outputs.iter().filter_map(first).collect()),
+        other => other.clone(),
+    };
+    Ok(vec![result])
+}
+
+/// `recurse`, `range` and `walk` need their arguments unevaluated, so the
+/// engine evaluates them itself while they are registered as these built-ins.
+/// They are registered anyway so they can be listed, suggested and replaced.
+fn fn_engine_evaluated(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let found = args.first().map_or_else(|| "nothing".to_string(), |v| v.kind().to_string());
+    Err(type_error("call evaluated by the query engine", found))
+}
+
+/// The first of an element's outputs from `Engine::per_item_outputs`, or null
+fn first_output(outputs: &Value) -> &Value {
+    match outputs {
+        Value::Array(outputs) => outputs.first().unwrap_or(&Value::Null),
+        other => other,
+    }
+}
+
+// ============================================================================
+// Environment functions
+// ============================================================================
+
+fn env_disabled(what: &str) -> QueryError {
+    QueryError::new(QueryErrorKind::EnvDisabled, Span::default(), String::new())
+        .with_help(format!("use --allow-env to let queries read {}", what))
+}
+
+fn fn_env(args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    if !ctx.allow_env {
+        return Err(env_disabled("environment variables"));
+    }
+
+    match args.get(1) {
+        Some(name) => Ok(vec![
+            std::env::var(name.to_text())
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+        ]),
+        None => {
+            let mut vars: Vec<(String, String)> = std::env::vars().collect();
+            vars.sort();
+            let obj = vars
+                .into_iter()
+                .map(|(k, v)| (k, Value::String(v)))
+                .collect();
+            Ok(vec![Value::Object(obj)])
+        }
+    }
+}
+
+/// Current Unix time in seconds, with a fractional part. Reading the clock
+/// makes a query non-deterministic, so it needs env access like `env`.
+fn fn_now(_args: &[Value], ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    if !ctx.allow_env {
+        return Err(env_disabled("the clock"));
+    }
+    let elapsed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(vec![Value::Number(elapsed.as_secs_f64())])
+}
+
+// ============================================================================
+// Date functions
+// ============================================================================
+
+/// Format a Unix time (seconds, UTC) with C `strftime` specifiers.
+fn fn_strftime(args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+    let secs = match args.first().unwrap_or(&Value::Null) {
+        Value::Number(n) => n.floor(),
+        other => return Err(type_error("number of seconds", other.kind().to_string())),
+    };
+    let format = args.get(1).map(Value::to_text).unwrap_or_default();
+
+    let time = time::OffsetDateTime::from_unix_timestamp(secs as i64)
+        .map_err(|e| invalid_input("timestamp", e.to_string()))?;
+    let items = time::format_description::parse_strftime_borrowed(&format)
+        .map_err(|e| invalid_input("strftime format", e.to_string()))?;
+    let text = time
+        .format(&items)
+        .map_err(|e| invalid_input("strftime format", e.to_string()))?;
+    Ok(vec![Value::String(text)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_lengths_scale_to_max() {
+        assert_eq!(bar_lengths(&[12.0, 6.0, 3.0], 8), vec![8, 4, 2]);
+        assert_eq!(bar_lengths(&[100.0, 1.0, 0.0], 20), vec![20, 1, 0]);
+        assert_eq!(bar_lengths(&[5.0, 5.0], 1), vec![1, 1]);
+        assert_eq!(bar_lengths(&[0.0, 0.0], 10), vec![0, 0]);
+        assert_eq!(bar_lengths(&[], 10), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_dedent_mixed_indentation() {
+        // Tabs and spaces only share what they have in common
+        assert_eq!(dedent_text("    a\n      b\n    c\n"), "a\n  b\nc\n");
+        assert_eq!(dedent_text("\t  a\n\t    b"), "a\n  b");
+        assert_eq!(dedent_text("\ta\n    b\n"), "\ta\n    b\n");
+        assert_eq!(dedent_text("  \ta\n    b\n"), "\ta\n  b\n");
+        // Blank lines don't count, and lose their whitespace
+        assert_eq!(dedent_text("    a\n\n  \n    b"), "a\n\n\nb");
+        assert_eq!(dedent_text("  a\r\n  b\r\n"), "a\r\nb\r\n");
+        assert_eq!(dedent_text(""), "");
+    }
+
+    #[test]
+    fn test_indent_dedent_round_trip() {
+        let code = "fn main() {\n    println!(\"hi\");\n\n}\n";
+        let indented = indent_text(code, 4);
+        assert_eq!(indented, "    fn main() {\n        println!(\"hi\");\n\n    }\n");
+        assert_eq!(dedent_text(&indented), code);
+        assert_eq!(indent_text(code, 0), code);
     }
 }
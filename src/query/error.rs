@@ -94,6 +94,24 @@ impl QueryError {
 
         output
     }
+
+    /// The error as JSON, for tools that consume query failures.
+    ///
+    /// The span is in bytes of the query source; `help` and `note` are null
+    /// when absent.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": self.kind.name(),
+            "message": self.kind.to_string(),
+            "span": {
+                "start": self.span.start,
+                "end": self.span.end,
+            },
+            "suggestions": self.suggestions,
+            "help": self.help,
+            "note": self.note,
+        })
+    }
 }
 
 impl fmt::Display for QueryError {
@@ -160,9 +178,51 @@ pub enum QueryErrorKind {
         error: String,
     },
     DivisionByZero,
+    InvalidInput {
+        format: &'static str,
+        error: String,
+    },
+    EnvDisabled,
+    ResourceLimit {
+        resource: &'static str,
+        limit: u128,
+    },
 }
 
 impl QueryErrorKind {
+    /// Stable identifier of the error kind, used in JSON error output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueryErrorKind::UnexpectedChar(_) => "unexpected_char",
+            QueryErrorKind::UnterminatedString => "unterminated_string",
+            QueryErrorKind::UnterminatedRegex => "unterminated_regex",
+            QueryErrorKind::InvalidEscape(_) => "invalid_escape",
+            QueryErrorKind::UnexpectedToken { .. } => "unexpected_token",
+            QueryErrorKind::UnexpectedEof { .. } => "unexpected_eof",
+            QueryErrorKind::InvalidHeadingLevel(_) => "invalid_heading_level",
+            QueryErrorKind::InvalidElementType(_) => "invalid_element_type",
+            QueryErrorKind::InvalidFilter(_) => "invalid_filter",
+            QueryErrorKind::MissingColon => "missing_colon",
+            QueryErrorKind::MissingClosingBracket => "missing_closing_bracket",
+            QueryErrorKind::MissingClosingParen => "missing_closing_paren",
+            QueryErrorKind::MissingClosingBrace => "missing_closing_brace",
+            QueryErrorKind::MissingThen => "missing_then",
+            QueryErrorKind::MissingEnd => "missing_end",
+            QueryErrorKind::TypeError { .. } => "type_error",
+            QueryErrorKind::PropertyNotFound { .. } => "property_not_found",
+            QueryErrorKind::UnknownFunction(_) => "unknown_function",
+            QueryErrorKind::UnknownElement(_) => "unknown_element",
+            QueryErrorKind::InvalidArity { .. } => "invalid_arity",
+            QueryErrorKind::NoMatch { .. } => "no_match",
+            QueryErrorKind::IndexOutOfBounds { .. } => "index_out_of_bounds",
+            QueryErrorKind::InvalidRegex { .. } => "invalid_regex",
+            QueryErrorKind::DivisionByZero => "division_by_zero",
+            QueryErrorKind::InvalidInput { .. } => "invalid_input",
+            QueryErrorKind::EnvDisabled => "env_disabled",
+            QueryErrorKind::ResourceLimit { .. } => "resource_limit",
+        }
+    }
+
     /// Get a short message for inline display.
     pub fn short_message(&self) -> &'static str {
         match self {
@@ -190,6 +250,9 @@ impl QueryErrorKind {
             QueryErrorKind::IndexOutOfBounds { .. } => "index out of bounds",
             QueryErrorKind::InvalidRegex { .. } => "invalid regex",
             QueryErrorKind::DivisionByZero => "division by zero",
+            QueryErrorKind::InvalidInput { .. } => "invalid input",
+            QueryErrorKind::EnvDisabled => "environment access disabled",
+            QueryErrorKind::ResourceLimit { .. } => "resource limit exceeded",
         }
     }
 }
@@ -311,6 +374,15 @@ impl fmt::Display for QueryErrorKind {
             QueryErrorKind::DivisionByZero => {
                 write!(f, "Division by zero")
             }
+            QueryErrorKind::InvalidInput { format, error } => {
+                write!(f, "Invalid {}: {}", format, error)
+            }
+            QueryErrorKind::EnvDisabled => {
+                write!(f, "Environment access is disabled")
+            }
+            QueryErrorKind::ResourceLimit { resource, limit } => {
+                write!(f, "Query exceeded the limit of {} {}", limit, resource)
+            }
         }
     }
 }
@@ -336,4 +408,26 @@ mod tests {
         assert!(formatted.contains("h1"));
         assert!(formatted.contains("heading levels"));
     }
+
+    #[test]
+    fn test_error_json() {
+        let error = QueryError::new(
+            QueryErrorKind::UnknownFunction("lenght".to_string()),
+            Span::new(6, 12),
+            ".h | lenght".to_string(),
+        )
+        .with_suggestions(vec!["length".to_string()]);
+
+        assert_eq!(
+            error.to_json(),
+            serde_json::json!({
+                "kind": "unknown_function",
+                "message": error.kind.to_string(),
+                "span": {"start": 6, "end": 12},
+                "suggestions": ["length"],
+                "help": null,
+                "note": null,
+            })
+        );
+    }
 }
@@ -3,13 +3,129 @@
 //! Executes parsed queries against markdown documents.
 
 use indexmap::IndexMap;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use super::ast::*;
 use super::error::{QueryError, QueryErrorKind};
-use super::registry::Registry;
+use super::registry::{levenshtein, ExtractorFn, Function, Registry};
 use super::value::*;
-use crate::parser::Document;
+use crate::parser::frontmatter::{FrontMatterFormat, split_front_matter};
+use crate::parser::links::parse_link_target;
+use crate::parser::{Document, InlineElement, Link};
+
+/// Resource limits applied while evaluating a query.
+///
+/// The defaults are generous enough for any real document, but keep runaway
+/// queries (e.g. an unbounded `recurse`) from hanging or exhausting the stack.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Maximum number of expression evaluations
+    pub max_steps: usize,
+    /// Maximum number of values a single pipeline may produce
+    pub max_output: usize,
+    /// Maximum nesting depth of expression evaluation, and of values walked
+    /// by `recurse`, `walk` and `paths`
+    pub max_depth: usize,
+    /// Maximum wall-clock time for the whole query
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: 1_000_000,
+            max_output: 100_000,
+            max_depth: 256,
+            timeout: None,
+        }
+    }
+}
+
+/// Builder for an [`Engine`] with custom functions and options.
+///
+/// Created with [`Engine::builder`].
+pub struct EngineBuilder {
+    registry: Registry,
+    strict: bool,
+    allow_env: bool,
+    limits: Limits,
+    terminal_width: Option<usize>,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        Self {
+            registry: Registry::with_builtins(),
+            strict: false,
+            allow_env: false,
+            limits: Limits::default(),
+            terminal_width: None,
+        }
+    }
+}
+
+impl EngineBuilder {
+    /// Replace the registry, e.g. with [`Registry::new`] to drop the built-ins.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Register a function, replacing any with the same name, built-ins
+    /// included.
+    pub fn function(mut self, name: impl Into<String>, func: Function) -> Self {
+        self.registry.register_function(name, func);
+        self
+    }
+
+    /// Register an alias for an existing function.
+    pub fn alias(mut self, alias: impl Into<String>, target: impl Into<String>) -> Self {
+        self.registry.register_alias(alias, target);
+        self
+    }
+
+    /// Register an element extractor on the registry.
+    pub fn extractor(mut self, name: impl Into<String>, extractor: ExtractorFn) -> Self {
+        self.registry.register_extractor(name, extractor);
+        self
+    }
+
+    /// Enable or disable strict mode (see [`Engine::strict`]).
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Allow or deny access to environment variables (see [`Engine::allow_env`]).
+    pub fn allow_env(mut self, allow: bool) -> Self {
+        self.allow_env = allow;
+        self
+    }
+
+    /// Set the resource limits used when executing queries.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the width of the terminal output goes to (see [`Engine::terminal_width`]).
+    pub fn terminal_width(mut self, width: Option<usize>) -> Self {
+        self.terminal_width = width;
+        self
+    }
+
+    /// Build the engine for a document.
+    pub fn build(self, doc: &Document) -> Engine<'_> {
+        Engine::with_registry(doc, self.registry)
+            .strict(self.strict)
+            .allow_env(self.allow_env)
+            .with_limits(self.limits)
+            .terminal_width(self.terminal_width)
+    }
+}
 
 /// Evaluation context passed to functions.
 pub struct EvalContext {
@@ -17,6 +133,8 @@ pub struct EvalContext {
     pub current: Value,
     /// All headings in the document
     pub headings: Vec<HeadingValue>,
+    /// Index of each heading's parent in `headings` (the heading tree)
+    pub heading_parents: Vec<Option<usize>>,
     /// All code blocks
     pub code_blocks: Vec<CodeValue>,
     /// All links
@@ -27,34 +145,67 @@ pub struct EvalContext {
     pub tables: Vec<TableValue>,
     /// All lists
     pub lists: Vec<ListValue>,
+    /// All top-level paragraphs
+    pub paragraphs: Vec<ParagraphValue>,
+    /// All details blocks
+    pub details: Vec<DetailsValue>,
+    /// All footnote definitions
+    pub footnotes: Vec<FootnoteValue>,
     /// Document metadata
     pub document: DocumentValue,
     /// Raw document content
     pub raw_content: String,
+    /// Error on unknown properties instead of returning null
+    pub strict: bool,
+    /// Allow `env` / `$ENV` to read environment variables
+    pub allow_env: bool,
+    /// Resource limits for this evaluation
+    pub limits: Limits,
+    /// Columns of the terminal output goes to, if any, for functions that
+    /// lay out text such as `histogram`
+    pub terminal_width: Option<usize>,
 }
 
 impl EvalContext {
     /// Create a new context from a document.
     pub fn from_document(doc: &Document) -> Self {
         let headings = extract_headings(doc);
-        let (code_blocks, links, images, tables, lists) = extract_blocks(doc);
+        let heading_parents = doc.heading_parents();
+        let ExtractedBlocks {
+            code_blocks,
+            links,
+            images,
+            tables,
+            lists,
+            paragraphs,
+            details,
+            footnotes,
+        } = extract_blocks(doc);
 
         let document = DocumentValue {
-            content: doc.content.clone(),
-            heading_count: doc.headings.len(),
-            word_count: doc.content.split_whitespace().count(),
+            content: doc.content().to_string(),
+            heading_count: doc.headings().len(),
+            word_count: doc.content().split_whitespace().count(),
         };
 
         Self {
             current: Value::Document(document.clone()),
             headings,
+            heading_parents,
             code_blocks,
             links,
             images,
             tables,
             lists,
+            paragraphs,
+            details,
+            footnotes,
             document,
-            raw_content: doc.content.clone(),
+            raw_content: doc.content().to_string(),
+            strict: false,
+            allow_env: false,
+            limits: Limits::default(),
+            terminal_width: None,
         }
     }
 }
@@ -65,47 +216,216 @@ pub struct Engine<'a> {
     doc: &'a Document,
     registry: Arc<Registry>,
     context: EvalContext,
+    /// Expressions evaluated so far in the current query
+    steps: usize,
+    /// Current expression nesting depth
+    depth: usize,
+    /// When the current query started executing
+    started: Option<Instant>,
+    /// Input for `.` instead of the document (see [`Engine::execute_with_input`])
+    input: Option<Value>,
+}
+
+/// The built-in registry, built once and shared by every [`Engine::new`].
+///
+/// [`Registry::with_builtins`] clones it, so every registry shares the
+/// built-in implementations and [`Engine::is_builtin`] can tell them apart
+/// from custom registrations.
+pub(super) fn builtins() -> &'static Arc<Registry> {
+    static BUILTINS: OnceLock<Arc<Registry>> = OnceLock::new();
+    BUILTINS.get_or_init(|| {
+        let mut registry = Registry::new();
+        super::builtins::register_all(&mut registry);
+        Arc::new(registry)
+    })
 }
 
+/// Built-ins whose argument is evaluated on each element of the input (see
+/// [`Engine::per_item_outputs`])
+const PER_ITEM_FUNCTIONS: &[&str] = &["sort_by", "group_by", "map_values"];
+
+/// Receives a stream of values one at a time; [`ControlFlow::Break`] stops it.
+type Emit<'e, 'a> = dyn FnMut(&mut Engine<'a>, Value) -> Result<ControlFlow<()>, QueryError> + 'e;
+
 impl<'a> Engine<'a> {
-    /// Create a new engine with default registry.
+    /// Create a new engine with all built-in functions ([`Registry::with_builtins`]).
+    ///
+    /// The built-in registry is built once and shared by every engine, so
+    /// creating an engine per document is cheap. Registering a function
+    /// gives the engine its own copy.
     pub fn new(doc: &'a Document) -> Self {
-        Self::with_registry(doc, Registry::with_builtins())
+        Self::with_shared_registry(doc, Arc::clone(builtins()))
+    }
+
+    /// Start configuring an engine with custom functions and options.
+    ///
+    /// The builder starts from [`Registry::with_builtins`], so custom functions
+    /// are added alongside the built-ins (and replace any with the same name).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use treemd::query::{self, Engine, Function, Value};
+    ///
+    /// let doc = treemd::Document::parse("# Guide\n## Install\n## Usage");
+    /// let shout = Function::new(
+    ///     |args, _ctx| Ok(vec![Value::String(format!("{}!", args[0].to_text()))]),
+    ///     0..=0,
+    /// );
+    ///
+    /// let mut engine = Engine::builder()
+    ///     .function("shout", shout)
+    ///     .strict(true)
+    ///     .build(&doc);
+    /// let results = engine.execute(&query::parse(".h2 | text | shout")?)?;
+    ///
+    /// assert_eq!(results[0].to_text(), "Install!");
+    /// assert_eq!(results[1].to_text(), "Usage!");
+    /// # Ok::<(), treemd::query::QueryError>(())
+    /// ```
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
     }
 
     /// Create a new engine with a custom registry.
+    ///
+    /// The registry is used as-is; start from [`Registry::with_builtins`] to
+    /// keep the built-in functions.
     pub fn with_registry(doc: &'a Document, registry: Registry) -> Self {
+        Self::with_shared_registry(doc, Arc::new(registry))
+    }
+
+    fn with_shared_registry(doc: &'a Document, registry: Arc<Registry>) -> Self {
         let context = EvalContext::from_document(doc);
         Self {
             doc,
-            registry: Arc::new(registry),
+            registry,
             context,
+            steps: 0,
+            depth: 0,
+            started: None,
+            input: None,
         }
     }
 
+    /// Enable or disable strict mode.
+    ///
+    /// In strict mode, accessing a property that doesn't exist on a value
+    /// raises `PropertyNotFound` instead of yielding null.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.context.strict = strict;
+        self
+    }
+
+    /// Allow or deny access to environment variables via `env` and `$ENV`.
+    ///
+    /// Disabled by default so untrusted queries can't read the environment.
+    pub fn allow_env(mut self, allow: bool) -> Self {
+        self.context.allow_env = allow;
+        self
+    }
+
+    /// Set the resource limits used when executing queries.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.context.limits = limits;
+        self
+    }
+
+    /// Set the width of the terminal output goes to.
+    ///
+    /// Functions that lay out text, like `histogram`, fit it by default.
+    /// Unset, they use a fixed width so output doesn't depend on where it goes.
+    pub fn terminal_width(mut self, width: Option<usize>) -> Self {
+        self.context.terminal_width = width;
+        self
+    }
+
+    /// Register a function on this engine, replacing any with the same name.
+    ///
+    /// Usually done up front with [`Engine::builder`]; this is for adding
+    /// functions to an engine that already exists.
+    pub fn register_function(&mut self, name: impl Into<String>, func: Function) {
+        Arc::make_mut(&mut self.registry).register_function(name, func);
+    }
+
     /// Execute a query and return results.
     pub fn execute(&mut self, query: &Query) -> Result<Vec<Value>, QueryError> {
+        self.steps = 0;
+        self.depth = 0;
+        self.started = Some(Instant::now());
+
         let mut all_results = Vec::new();
 
         for piped_expr in &query.expressions {
             let results = self.eval_piped(piped_expr)?;
             all_results.extend(results);
+            self.check_output(all_results.len())?;
         }
 
         Ok(all_results)
     }
 
+    /// Execute a query with `input` as `.` instead of the document.
+    ///
+    /// Element selectors still read the document. Used for `--raw-input`,
+    /// where each line of input is a string.
+    pub fn execute_with_input(
+        &mut self,
+        query: &Query,
+        input: Value,
+    ) -> Result<Vec<Value>, QueryError> {
+        self.input = Some(input);
+        let results = self.execute(query);
+        self.input = None;
+        results
+    }
+
+    /// Count one evaluation step, enforcing the step and time limits.
+    fn tick(&mut self) -> Result<(), QueryError> {
+        let limits = self.context.limits;
+        self.steps += 1;
+        if self.steps > limits.max_steps {
+            return Err(resource_limit("evaluation steps", limits.max_steps as u128));
+        }
+        // Checking the clock on every step is wasteful; every 256 is plenty
+        if let (Some(timeout), Some(started)) = (limits.timeout, self.started) {
+            if self.steps % 256 == 0 && started.elapsed() > timeout {
+                return Err(resource_limit("milliseconds", timeout.as_millis()));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_output(&self, count: usize) -> Result<(), QueryError> {
+        let max_output = self.context.limits.max_output;
+        if count > max_output {
+            return Err(resource_limit("output values", max_output as u128));
+        }
+        Ok(())
+    }
+
     fn eval_piped(&mut self, piped: &PipedExpr) -> Result<Vec<Value>, QueryError> {
         // Start with the document as input
-        let mut current = vec![Value::Document(self.context.document.clone())];
+        let start = match &self.input {
+            Some(input) => input.clone(),
+            None => Value::Document(self.context.document.clone()),
+        };
+        let mut current = vec![start];
 
-        for stage in &piped.stages {
+        let mut stages = piped.stages.as_slice();
+        while !stages.is_empty() {
+            let fused = self.fused_select(stages);
             let mut next = Vec::new();
             for input in current {
                 self.context.current = input;
-                next.extend(self.eval_expr(stage)?);
+                next.extend(match fused {
+                    Some((source, predicate)) => self.eval_fused_select(source, predicate)?,
+                    None => self.eval_expr(&stages[0])?,
+                });
+                self.check_output(next.len())?;
             }
             current = next;
+            stages = &stages[if fused.is_some() { 2 } else { 1 }..];
 
             // Short-circuit if no results
             if current.is_empty() {
@@ -116,7 +436,110 @@ impl<'a> Engine<'a> {
         Ok(current)
     }
 
+    /// Whether `name` calls the built-in function of that name rather than a
+    /// custom registration, so the evaluator may handle it specially.
+    fn is_builtin(&self, name: &str) -> bool {
+        match (self.registry.get_function(name), builtins().get_function(name)) {
+            (Some(func), Some(builtin)) => Arc::ptr_eq(&func.func, &builtin.func),
+            _ => false,
+        }
+    }
+
+    /// The outputs of `expr` on each element of an array (or value of an
+    /// object) in the current value, as an array of arrays, for built-ins
+    /// such as `sort_by` that take an expression per element.
+    ///
+    /// A string literal names a property when `literal_is_property` is set,
+    /// so `sort_by("level")` works like `sort_by(.level)`.
+    fn per_item_outputs(
+        &mut self,
+        expr: &Expr,
+        literal_is_property: bool,
+    ) -> Result<Value, QueryError> {
+        let input = self.context.current.clone();
+        let items: Vec<Value> = match &input {
+            Value::Array(items) => items.clone(),
+            Value::Object(map) => map.values().cloned().collect(),
+            _ => Vec::new(),
+        };
+
+        let mut outputs = Vec::with_capacity(items.len());
+        for item in items {
+            self.tick()?;
+            let output = match expr {
+                Expr::Literal { value: Literal::String(property), .. } if literal_is_property => {
+                    vec![item.get_property(property).unwrap_or(Value::Null)]
+                }
+                _ => {
+                    self.context.current = item;
+                    self.eval_expr(expr)?
+                }
+            };
+            outputs.push(Value::Array(output));
+        }
+        self.context.current = input;
+        Ok(Value::Array(outputs))
+    }
+
+    /// `source | select(predicate)` at the start of `stages`, if `source`
+    /// streams its elements and `select` is the built-in one (a custom
+    /// `select` could do anything with its input).
+    fn fused_select<'e>(&self, stages: &'e [Expr]) -> Option<(&'e Expr, &'e Expr)> {
+        let [source, Expr::Function { name, args, .. }, ..] = stages else {
+            return None;
+        };
+        let [predicate] = args.as_slice() else {
+            return None;
+        };
+        let select = &self.registry.get_function(name)?.func;
+        let builtin = &builtins().get_function("select")?.func;
+        (is_element_stream(source) && Arc::ptr_eq(select, builtin)).then_some((source, predicate))
+    }
+
+    /// `source | select(predicate)` without collecting `source` first: each
+    /// element is tested as it is produced and only those that pass are kept,
+    /// so filtering a large selector doesn't copy every element it visits.
+    ///
+    /// Agrees with the unfused pipeline, where `select` gets its condition's
+    /// outputs as one value (an array unless there is exactly one).
+    fn eval_fused_select(
+        &mut self,
+        source: &Expr,
+        predicate: &Expr,
+    ) -> Result<Vec<Value>, QueryError> {
+        let mut kept = Vec::new();
+        let _ = self.for_each_output(source, &mut |engine, element| {
+            engine.tick()?;
+            engine.context.current = element.clone();
+            let mut outputs = engine.eval_expr(predicate)?;
+            let condition = match outputs.len() {
+                1 => outputs.pop().unwrap_or(Value::Null),
+                _ => Value::Array(outputs),
+            };
+            if condition.is_truthy() {
+                kept.push(element);
+                engine.check_output(kept.len())?;
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(kept)
+    }
+
     fn eval_expr(&mut self, expr: &Expr) -> Result<Vec<Value>, QueryError> {
+        self.tick()?;
+
+        let max_depth = self.context.limits.max_depth;
+        if self.depth >= max_depth {
+            return Err(resource_limit("levels of nesting", max_depth as u128));
+        }
+
+        self.depth += 1;
+        let result = self.eval_expr_inner(expr);
+        self.depth -= 1;
+        result
+    }
+
+    fn eval_expr_inner(&mut self, expr: &Expr) -> Result<Vec<Value>, QueryError> {
         match expr {
             Expr::Identity => Ok(vec![self.context.current.clone()]),
 
@@ -171,64 +594,37 @@ impl<'a> Engine<'a> {
         kind: &ElementKind,
         filters: &[Filter],
         index: Option<&IndexOp>,
-        _span: Span,
+        span: Span,
     ) -> Result<Vec<Value>, QueryError> {
         // Get all elements of the requested kind
         let mut elements: Vec<Value> = match kind {
-            ElementKind::Heading(level) => {
-                self.context.headings
-                    .iter()
-                    .filter(|h| level.is_none() || Some(h.level) == *level)
-                    .cloned()
-                    .map(Value::Heading)
-                    .collect()
-            }
-            ElementKind::Code => {
-                self.context.code_blocks
-                    .iter()
-                    .cloned()
-                    .map(Value::Code)
-                    .collect()
-            }
-            ElementKind::Link => {
-                self.context.links
-                    .iter()
-                    .cloned()
-                    .map(Value::Link)
-                    .collect()
-            }
-            ElementKind::Image => {
-                self.context.images
-                    .iter()
-                    .cloned()
-                    .map(Value::Image)
-                    .collect()
-            }
-            ElementKind::Table => {
-                self.context.tables
-                    .iter()
-                    .cloned()
-                    .map(Value::Table)
-                    .collect()
-            }
-            ElementKind::List => {
-                self.context.lists
-                    .iter()
-                    .cloned()
-                    .map(Value::List)
-                    .collect()
-            }
-            ElementKind::Blockquote => {
-                // TODO: extract blockquotes
-                Vec::new()
-            }
-            ElementKind::Paragraph => {
-                // TODO: extract paragraphs
-                Vec::new()
-            }
             ElementKind::FrontMatter => {
-                // TODO: parse front matter
-                Vec::new()
+                match split_front_matter(&self.context.raw_content) {
+                    Some(front_matter) => {
+                        vec![Value::from_front_matter(&front_matter).map_err(|error| {
+                            QueryError::new(
+                                QueryErrorKind::InvalidInput {
+                                    format: match front_matter.format {
+                                        FrontMatterFormat::Yaml => "YAML front matter",
+                                        FrontMatterFormat::Toml => "TOML front matter",
+                                    },
+                                    error,
+                                },
+                                span,
+                                String::new(),
+                            )
+                        })?]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            _ => {
+                let mut elements = Vec::new();
+                let _ = self.stream_kind(kind, &mut |_, value| {
+                    elements.push(value);
+                    Ok(ControlFlow::Continue(()))
+                })?;
+                elements
             }
         };
 
@@ -245,6 +641,55 @@ impl<'a> Engine<'a> {
         Ok(elements)
     }
 
+    /// Emit every element of `kind` in document order. Front matter is
+    /// parsed on demand by [`Self::eval_element`] instead, so it emits nothing.
+    fn stream_kind(
+        &mut self,
+        kind: &ElementKind,
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        let ctx = &self.context;
+        let count = match kind {
+            ElementKind::Heading(_) => ctx.headings.len(),
+            ElementKind::Code => ctx.code_blocks.len(),
+            ElementKind::Link => ctx.links.len(),
+            ElementKind::Image => ctx.images.len(),
+            ElementKind::Table => ctx.tables.len(),
+            ElementKind::List => ctx.lists.len(),
+            ElementKind::Details => ctx.details.len(),
+            ElementKind::Footnote => ctx.footnotes.len(),
+            ElementKind::Paragraph => ctx.paragraphs.len(),
+            // TODO: extract blockquotes
+            ElementKind::Blockquote | ElementKind::FrontMatter => 0,
+        };
+
+        for i in 0..count {
+            let ctx = &self.context;
+            let value = match kind {
+                ElementKind::Heading(level) => {
+                    let heading = &ctx.headings[i];
+                    if level.is_some_and(|level| heading.level != level) {
+                        continue;
+                    }
+                    Value::Heading(heading.clone())
+                }
+                ElementKind::Code => Value::Code(ctx.code_blocks[i].clone()),
+                ElementKind::Link => Value::Link(ctx.links[i].clone()),
+                ElementKind::Image => Value::Image(ctx.images[i].clone()),
+                ElementKind::Table => Value::Table(ctx.tables[i].clone()),
+                ElementKind::List => Value::List(ctx.lists[i].clone()),
+                ElementKind::Details => Value::Details(ctx.details[i].clone()),
+                ElementKind::Footnote => Value::Footnote(ctx.footnotes[i].clone()),
+                ElementKind::Paragraph => Value::Paragraph(ctx.paragraphs[i].clone()),
+                ElementKind::Blockquote | ElementKind::FrontMatter => break,
+            };
+            if emit(self, value)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
     fn apply_filter(&self, elements: Vec<Value>, filter: &Filter) -> Result<Vec<Value>, QueryError> {
         match filter {
             Filter::Text { pattern, exact, .. } => {
@@ -297,17 +742,73 @@ impl<'a> Engine<'a> {
     fn eval_property(&mut self, name: &str, span: Span) -> Result<Vec<Value>, QueryError> {
         let current = &self.context.current;
 
+        if let Value::Heading(heading) = current {
+            if let Some(value) = self.heading_navigation(heading.index, name) {
+                return Ok(vec![value]);
+            }
+        }
+
         if let Some(value) = current.get_property(name) {
             Ok(vec![value])
-        } else {
-            Err(QueryError::new(
+        } else if let Some(error) = unknown_element_error(name, current, span) {
+            Err(error)
+        } else if self.context.strict {
+            let known = current.property_names();
+            let mut suggestions: Vec<String> = known
+                .iter()
+                .filter(|s| levenshtein(s, name) <= 2)
+                .cloned()
+                .collect();
+            suggestions.sort_by_key(|s| levenshtein(s, name));
+            suggestions.truncate(3);
+
+            let mut error = QueryError::new(
                 QueryErrorKind::PropertyNotFound {
                     property: name.to_string(),
                     on_type: current.kind().to_string(),
                 },
                 span,
                 String::new(),
-            ))
+            )
+            .with_suggestions(suggestions);
+            if !known.is_empty() {
+                error = error.with_help(format!("available properties: {}", known.join(", ")));
+            }
+            Err(error)
+        } else {
+            Ok(vec![Value::Null])
+        }
+    }
+
+    /// Tree-aware heading properties: `parent`, `children`, `siblings`, `depth`.
+    ///
+    /// These need the whole heading tree, so they're resolved here rather than
+    /// in [`HeadingValue::get_property`].
+    fn heading_navigation(&self, index: usize, name: &str) -> Option<Value> {
+        let headings = &self.context.headings;
+        let parents = &self.context.heading_parents;
+        let parent = *parents.get(index)?;
+        let heading_at = |i: usize| Value::Heading(headings[i].clone());
+
+        match name {
+            "parent" => Some(parent.map_or(Value::Null, heading_at)),
+            "children" => Some(Value::Array(
+                (0..headings.len())
+                    .filter(|&i| parents[i] == Some(index))
+                    .map(heading_at)
+                    .collect(),
+            )),
+            "siblings" => Some(Value::Array(
+                (0..headings.len())
+                    .filter(|&i| i != index && parents[i] == parent)
+                    .map(heading_at)
+                    .collect(),
+            )),
+            "depth" => {
+                let depth = std::iter::successors(Some(index), |&i| parents[i]).count();
+                Some(Value::Number(depth as f64))
+            }
+            _ => None,
         }
     }
 
@@ -317,21 +818,141 @@ impl<'a> Engine<'a> {
         args: &[Expr],
         span: Span,
     ) -> Result<Vec<Value>, QueryError> {
-        // Handle special built-in functions
+        // Handle special built-in functions. Anything a user can call is only
+        // handled here while it's the built-in, so registering a function of
+        // the same name replaces it.
         match name {
             "_pipe" => {
-                // Internal pipe handling
-                let mut current = vec![self.context.current.clone()];
-                for arg in args {
+                // Internal pipe handling. The input is restored afterwards, so
+                // a pipe on one side of an operator doesn't leak its stages'
+                // inputs into the other side.
+                let input = self.context.current.clone();
+                let mut current = vec![input.clone()];
+                let mut stages = args;
+                while !stages.is_empty() {
+                    let fused = self.fused_select(stages);
                     let mut next = Vec::new();
                     for input in current {
                         self.context.current = input;
-                        next.extend(self.eval_expr(arg)?);
+                        next.extend(match fused {
+                            Some((source, predicate)) => {
+                                self.eval_fused_select(source, predicate)?
+                            }
+                            None => self.eval_expr(&stages[0])?,
+                        });
                     }
                     current = next;
+                    stages = &stages[if fused.is_some() { 2 } else { 1 }..];
                 }
+                self.context.current = input;
                 return Ok(current);
             }
+            "_comma" => {
+                // Outputs of each alternative in turn, all from the same input
+                let input = self.context.current.clone();
+                let mut results = Vec::new();
+                for arg in args {
+                    self.context.current = input.clone();
+                    results.extend(self.eval_expr(arg)?);
+                    self.check_output(results.len())?;
+                }
+                return Ok(results);
+            }
+            "recurse" if self.is_builtin(name) => {
+                let mut results = Vec::new();
+                let _ = self.stream_recurse(args, &mut |engine, value| {
+                    results.push(value);
+                    engine.check_output(results.len())?;
+                    Ok(ControlFlow::Continue(()))
+                })?;
+                return Ok(results);
+            }
+            "range" if self.is_builtin(name) => {
+                let mut results = Vec::new();
+                let _ = self.stream_range(args, span, &mut |engine, value| {
+                    results.push(value);
+                    engine.check_output(results.len())?;
+                    Ok(ControlFlow::Continue(()))
+                })?;
+                return Ok(results);
+            }
+            "nth" if args.len() == 2 && self.is_builtin(name) => {
+                // jq's `nth(n; f)`: the nth output of `f`, which stops as soon
+                // as it is reached. One argument indexes the input instead.
+                let input = self.context.current.clone();
+                let n = match self.eval_expr(&args[0])?.into_iter().next() {
+                    Some(Value::Number(n)) if n >= 0.0 => n as usize,
+                    Some(Value::Number(n)) => {
+                        return Err(QueryError::new(
+                            QueryErrorKind::TypeError {
+                                expected: "non-negative index",
+                                found: n.to_string(),
+                            },
+                            span,
+                            String::new(),
+                        ));
+                    }
+                    other => return Err(number_expected(other, span)),
+                };
+                self.context.current = input;
+
+                let mut seen = 0;
+                let mut found = None;
+                let _ = self.for_each_output(&args[1], &mut |_, value| {
+                    if seen == n {
+                        found = Some(value);
+                        return Ok(ControlFlow::Break(()));
+                    }
+                    seen += 1;
+                    Ok(ControlFlow::Continue(()))
+                })?;
+                return Ok(found.into_iter().collect());
+            }
+            "any" | "all" if args.len() == 1 && self.is_builtin(name) => {
+                // jq-compatible: test the predicate on each array element or
+                // object value, stopping at the first one that decides it
+                let items: Option<Vec<Value>> = match &self.context.current {
+                    Value::Array(items) => Some(items.clone()),
+                    Value::Object(map) | Value::FrontMatter(map) => {
+                        Some(map.values().cloned().collect())
+                    }
+                    // Anything else is tested as a whole by the builtin
+                    _ => None,
+                };
+                if let Some(items) = items {
+                    let want = name == "any";
+                    for item in items {
+                        self.tick()?;
+                        self.context.current = item;
+                        let passed = self
+                            .eval_expr(&args[0])?
+                            .into_iter()
+                            .next()
+                            .is_some_and(|v| v.is_truthy());
+                        if passed == want {
+                            return Ok(vec![Value::Bool(want)]);
+                        }
+                    }
+                    return Ok(vec![Value::Bool(!want)]);
+                }
+            }
+            "walk" if self.is_builtin(name) => {
+                // jq-compatible: apply the expression bottom-up to every value.
+                // The first output replaces the value; no output drops it.
+                let Some(f) = args.first() else {
+                    return Err(QueryError::new(
+                        QueryErrorKind::InvalidArity {
+                            function: name.to_string(),
+                            expected: "1..=1".to_string(),
+                            found: 0,
+                        },
+                        span,
+                        String::new(),
+                    ));
+                };
+                let input = self.context.current.clone();
+                return Ok(self.walk(input, f)?.into_iter().collect());
+            }
             "_index" => {
                 // Internal index handling
                 if args.len() >= 2 {
@@ -355,7 +976,14 @@ impl<'a> Engine<'a> {
                 eval_args.push(self.context.current.clone());
             }
 
+            // Built-ins that take an expression per element get its outputs
+            // on each element instead of on the input
+            let per_item = PER_ITEM_FUNCTIONS.contains(&name) && self.is_builtin(name);
             for arg in args {
+                if per_item {
+                    eval_args.push(self.per_item_outputs(arg, name != "map_values")?);
+                    continue;
+                }
                 let arg_values = self.eval_expr(arg)?;
                 if arg_values.len() == 1 {
                     eval_args.push(arg_values.into_iter().next().unwrap());
@@ -395,93 +1023,226 @@ impl<'a> Engine<'a> {
         }
     }
 
-    fn eval_hierarchy(
+    /// Feed the outputs of `expr` to `emit` one at a time, stopping as soon as
+    /// it returns [`ControlFlow::Break`].
+    ///
+    /// Pipes, `range`, `recurse` and element selectors without filters or an
+    /// index stream their outputs, so a consumer that only needs the first few
+    /// (like `nth(n; f)`) cuts them short. Anything else is evaluated in full
+    /// first.
+    fn for_each_output(
         &mut self,
-        parent: &Expr,
-        child: &Expr,
-        direct: bool,
-        _span: Span,
-    ) -> Result<Vec<Value>, QueryError> {
-        // Evaluate parent expression
-        let parent_values = self.eval_expr(parent)?;
+        expr: &Expr,
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        self.tick()?;
+        match expr {
+            Expr::Group { expr, .. } => self.for_each_output(expr, emit),
+            Expr::Function { name, args, .. } if name == "_pipe" => {
+                let input = self.context.current.clone();
+                self.stream_pipe(args, input, emit)
+            }
+            Expr::Function { name, args, .. } if name == "_comma" => {
+                let input = self.context.current.clone();
+                for arg in args {
+                    self.context.current = input.clone();
+                    if self.for_each_output(arg, emit)?.is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+                Ok(ControlFlow::Continue(()))
+            }
+            Expr::Function { name, args, span } if name == "range" && self.is_builtin(name) => {
+                self.stream_range(args, *span, emit)
+            }
+            Expr::Function { name, args, .. } if name == "recurse" && self.is_builtin(name) => {
+                self.stream_recurse(args, emit)
+            }
+            Expr::Element { kind, .. } if is_element_stream(expr) => self.stream_kind(kind, emit),
+            Expr::Hierarchy { parent, child, direct, .. } if is_element_stream(expr) => {
+                self.stream_hierarchy(parent, child, *direct, emit)
+            }
+            _ => {
+                for value in self.eval_expr(expr)? {
+                    if emit(self, value)?.is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+    }
 
-        let mut results = Vec::new();
+    /// Run `input` through the remaining pipe `stages`, one value at a time
+    fn stream_pipe(
+        &mut self,
+        stages: &[Expr],
+        input: Value,
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        let Some((stage, rest)) = stages.split_first() else {
+            return emit(self, input);
+        };
+        self.context.current = input;
+        self.for_each_output(stage, &mut |engine, value| {
+            engine.stream_pipe(rest, value, &mut *emit)
+        })
+    }
 
-        for parent_val in parent_values {
-            // For headings, find children
-            if let Value::Heading(ref parent_heading) = parent_val {
-                // Get child element kind
-                let child_kind = match child {
-                    Expr::Element { kind, .. } => Some(kind.clone()),
-                    _ => None,
-                };
+    /// `range(upto)`, `range(from; upto)` or `range(from; upto; by)`, like jq
+    fn stream_range(
+        &mut self,
+        args: &[Expr],
+        span: Span,
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        if args.is_empty() || args.len() > 3 {
+            return Err(QueryError::new(
+                QueryErrorKind::InvalidArity {
+                    function: "range".to_string(),
+                    expected: "1..=3".to_string(),
+                    found: args.len(),
+                },
+                span,
+                String::new(),
+            ));
+        }
 
-                if let Some(kind) = child_kind {
-                    // Find headings that are children of this parent
-                    let parent_idx = parent_heading.index;
-                    let parent_level = parent_heading.level;
-
-                    match kind {
-                        ElementKind::Heading(level_filter) => {
-                            // Find child headings
-                            for (idx, h) in self.context.headings.iter().enumerate() {
-                                if idx <= parent_idx {
-                                    continue;
-                                }
-
-                                // Stop if we hit a heading at same or higher level
-                                if h.level <= parent_level {
-                                    break;
-                                }
-
-                                // Check level filter
-                                if let Some(target_level) = level_filter {
-                                    if h.level != target_level {
-                                        if direct && h.level > target_level {
-                                            // Skip deeper headings in direct mode
-                                            continue;
-                                        }
-                                        if h.level != target_level {
-                                            continue;
-                                        }
-                                    }
-                                }
-
-                                // In direct mode, only include immediate children
-                                if direct {
-                                    // Find if there's an intermediate heading
-                                    let has_intermediate = self.context.headings[parent_idx + 1..idx]
-                                        .iter()
-                                        .any(|intermediate| {
-                                            intermediate.level > parent_level
-                                                && intermediate.level < h.level
-                                        });
-                                    if has_intermediate {
-                                        continue;
-                                    }
-                                }
-
-                                results.push(Value::Heading(h.clone()));
-                            }
-                        }
-                        ElementKind::Code => {
-                            // Find code blocks under this heading
-                            // For now, return all code blocks (simplified)
-                            // TODO: Implement proper scoping
-                            results.extend(
-                                self.context.code_blocks
-                                    .iter()
-                                    .cloned()
-                                    .map(Value::Code),
-                            );
-                        }
-                        _ => {
-                            // Other element types under headings
-                        }
+        let input = self.context.current.clone();
+        let mut bounds = Vec::with_capacity(args.len());
+        for arg in args {
+            self.context.current = input.clone();
+            match self.eval_expr(arg)?.into_iter().next() {
+                Some(Value::Number(n)) => bounds.push(n),
+                other => return Err(number_expected(other, span)),
+            }
+        }
+        let (from, upto, by) = match bounds[..] {
+            [upto] => (0.0, upto, 1.0),
+            [from, upto] => (from, upto, 1.0),
+            [from, upto, by, ..] => (from, upto, by),
+            [] => unreachable!("arity checked above"),
+        };
+
+        let mut i = from;
+        while (by > 0.0 && i < upto) || (by < 0.0 && i > upto) {
+            self.tick()?;
+            if emit(self, Value::Number(i))?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+            i += by;
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Depth-first: emit each value, then the values `f` produces from it (or
+    /// its array elements / object values when called without `f`)
+    fn stream_recurse(
+        &mut self,
+        args: &[Expr],
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        // Iterative, so deeply nested values can't overflow the stack.
+        // `recurse(f)` may descend forever without nesting anything, so
+        // only the step limit bounds it. The input is restored afterwards,
+        // as `f` runs against each value in turn.
+        let max_depth = self.context.limits.max_depth;
+        let input = self.context.current.clone();
+        let mut stack = vec![(input.clone(), 0)];
+        while let Some((value, depth)) = stack.pop() {
+            self.tick()?;
+            if args.is_empty() && depth > max_depth {
+                return Err(resource_limit("levels of nesting", max_depth as u128));
+            }
+            if emit(self, value.clone())?.is_break() {
+                self.context.current = input;
+                return Ok(ControlFlow::Break(()));
+            }
+            let children = match args.first() {
+                Some(f) => {
+                    self.context.current = value;
+                    self.eval_expr(f)?
+                }
+                None => match value {
+                    Value::Array(a) => a,
+                    Value::Object(o) => o.into_values().collect(),
+                    _ => Vec::new(),
+                },
+            };
+            stack.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+        }
+        self.context.current = input;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Post-order `walk(f)` with an explicit stack of partly rebuilt
+    /// containers, so deeply nested values can't overflow the stack.
+    fn walk(&mut self, root: Value, f: &Expr) -> Result<Option<Value>, QueryError> {
+        let max_depth = self.context.limits.max_depth;
+        let mut stack: Vec<WalkFrame> = Vec::new();
+        let mut value = root;
+
+        loop {
+            // Descend to the first leaf (or empty container)
+            let mut result = loop {
+                let frame = match value {
+                    Value::Array(items) => WalkFrame::Array {
+                        todo: items.into_iter(),
+                        done: Vec::new(),
+                    },
+                    Value::Object(map) => WalkFrame::Object {
+                        todo: map.into_iter(),
+                        key: String::new(),
+                        done: IndexMap::new(),
+                    },
+                    leaf => break self.walk_apply(f, leaf)?,
+                };
+                if stack.len() >= max_depth {
+                    return Err(resource_limit("levels of nesting", max_depth as u128));
+                }
+                stack.push(frame);
+                match stack.last_mut().and_then(WalkFrame::next_child) {
+                    Some(child) => value = child,
+                    None => {
+                        let container = stack.pop().map(WalkFrame::finish).unwrap_or(Value::Null);
+                        break self.walk_apply(f, container)?;
                     }
                 }
+            };
+
+            // Hand the result up, finishing every container that has no children left
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(result);
+                };
+                frame.accept(result);
+                if let Some(child) = frame.next_child() {
+                    value = child;
+                    break;
+                }
+                let container = stack.pop().map(WalkFrame::finish).unwrap_or(Value::Null);
+                result = self.walk_apply(f, container)?;
             }
         }
+    }
+
+    fn walk_apply(&mut self, f: &Expr, value: Value) -> Result<Option<Value>, QueryError> {
+        self.context.current = value;
+        Ok(self.eval_expr(f)?.into_iter().next())
+    }
+
+    fn eval_hierarchy(
+        &mut self,
+        parent: &Expr,
+        child: &Expr,
+        direct: bool,
+        _span: Span,
+    ) -> Result<Vec<Value>, QueryError> {
+        let mut results = Vec::new();
+        let _ = self.stream_hierarchy(parent, child, direct, &mut |_, value| {
+            results.push(value);
+            Ok(ControlFlow::Continue(()))
+        })?;
 
         // Apply child filters if any
         if let Expr::Element { filters, index, .. } = child {
@@ -496,18 +1257,102 @@ impl<'a> Engine<'a> {
         Ok(results)
     }
 
-    fn eval_binary(
+    /// Emit the `child` elements under each heading `parent` produces, before
+    /// the child's filters and index are applied
+    fn stream_hierarchy(
         &mut self,
-        op: BinaryOp,
-        left: &Expr,
-        right: &Expr,
-        _span: Span,
-    ) -> Result<Vec<Value>, QueryError> {
-        let left_vals = self.eval_expr(left)?;
-        let right_vals = self.eval_expr(right)?;
+        parent: &Expr,
+        child: &Expr,
+        direct: bool,
+        emit: &mut Emit<'_, 'a>,
+    ) -> Result<ControlFlow<()>, QueryError> {
+        // Get child element kind
+        let Expr::Element { kind, .. } = child else {
+            return Ok(ControlFlow::Continue(()));
+        };
 
-        let left_val = left_vals.into_iter().next().unwrap_or(Value::Null);
-        let right_val = right_vals.into_iter().next().unwrap_or(Value::Null);
+        // Evaluate parent expression
+        let parent_values = self.eval_expr(parent)?;
+
+        for parent_val in parent_values {
+            // For headings, find children
+            let Value::Heading(parent_heading) = parent_val else {
+                continue;
+            };
+            let parent_idx = parent_heading.index;
+            let parent_level = parent_heading.level;
+
+            match kind {
+                ElementKind::Heading(level_filter) => {
+                    // Find child headings
+                    for idx in parent_idx + 1..self.context.headings.len() {
+                        let headings = &self.context.headings;
+                        let h = &headings[idx];
+
+                        // Stop if we hit a heading at same or higher level
+                        if h.level <= parent_level {
+                            break;
+                        }
+
+                        // Check level filter
+                        if level_filter.is_some_and(|target_level| h.level != target_level) {
+                            continue;
+                        }
+
+                        // In direct mode, only include immediate children
+                        if direct {
+                            // Find if there's an intermediate heading
+                            let has_intermediate =
+                                headings[parent_idx + 1..idx].iter().any(|intermediate| {
+                                    intermediate.level > parent_level
+                                        && intermediate.level < h.level
+                                });
+                            if has_intermediate {
+                                continue;
+                            }
+                        }
+
+                        let value = Value::Heading(h.clone());
+                        if emit(self, value)?.is_break() {
+                            return Ok(ControlFlow::Break(()));
+                        }
+                    }
+                }
+                ElementKind::Code => {
+                    // Find code blocks under this heading
+                    // For now, return all code blocks (simplified)
+                    // TODO: Implement proper scoping
+                    for i in 0..self.context.code_blocks.len() {
+                        let value = Value::Code(self.context.code_blocks[i].clone());
+                        if emit(self, value)?.is_break() {
+                            return Ok(ControlFlow::Break(()));
+                        }
+                    }
+                }
+                _ => {
+                    // Other element types under headings
+                }
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn eval_binary(
+        &mut self,
+        op: BinaryOp,
+        left: &Expr,
+        right: &Expr,
+        _span: Span,
+    ) -> Result<Vec<Value>, QueryError> {
+        if op == BinaryOp::Alt {
+            return self.eval_alt(left, right);
+        }
+
+        let left_vals = self.eval_expr(left)?;
+        let right_vals = self.eval_expr(right)?;
+
+        let left_val = left_vals.into_iter().next().unwrap_or(Value::Null);
+        let right_val = right_vals.into_iter().next().unwrap_or(Value::Null);
 
         let result = match op {
             BinaryOp::Eq => Value::Bool(values_equal(&left_val, &right_val)),
@@ -524,18 +1369,41 @@ impl<'a> Engine<'a> {
             BinaryOp::Div => div_values(&left_val, &right_val)?,
             BinaryOp::Mod => mod_values(&left_val, &right_val)?,
             BinaryOp::Concat => concat_values(&left_val, &right_val),
-            BinaryOp::Alt => {
-                if left_val.is_truthy() {
-                    left_val
-                } else {
-                    right_val
-                }
-            }
+            BinaryOp::Alt => unreachable!("handled by eval_alt"),
         };
 
         Ok(vec![result])
     }
 
+    /// Evaluate `left // right` with jq semantics.
+    ///
+    /// Yields every value of `left` that isn't `null` or `false`; if there are
+    /// none (including when `left` produces nothing at all), yields `right`.
+    /// A failed lookup on the left (a missing property in strict mode, or a
+    /// selector with no match) also falls back rather than erroring.
+    fn eval_alt(&mut self, left: &Expr, right: &Expr) -> Result<Vec<Value>, QueryError> {
+        let input = self.context.current.clone();
+        let left_vals = match self.eval_expr(left) {
+            Ok(values) => values,
+            Err(e) if matches!(
+                e.kind,
+                QueryErrorKind::PropertyNotFound { .. } | QueryErrorKind::NoMatch { .. }
+            ) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let kept: Vec<Value> = left_vals
+            .into_iter()
+            .filter(|v| !matches!(v, Value::Null | Value::Bool(false)))
+            .collect();
+        if !kept.is_empty() {
+            return Ok(kept);
+        }
+
+        self.context.current = input;
+        self.eval_expr(right)
+    }
+
     fn eval_unary(&mut self, op: UnaryOp, expr: &Expr, _span: Span) -> Result<Vec<Value>, QueryError> {
         let vals = self.eval_expr(expr)?;
         let val = vals.into_iter().next().unwrap_or(Value::Null);
@@ -602,29 +1470,45 @@ impl<'a> Engine<'a> {
 // Helper functions
 
 fn extract_headings(doc: &Document) -> Vec<HeadingValue> {
-    doc.headings
+    let numbers = doc.heading_numbers();
+
+    doc.headings()
         .iter()
+        .zip(numbers)
         .enumerate()
-        .map(|(idx, h)| {
+        .map(|(idx, (h, number))| {
             // Calculate line number
-            let line = doc.content[..h.offset].lines().count() + 1;
+            let line = doc.line_at(h.offset);
 
             // Extract content (simplified - until next heading)
-            let content_start = doc.content[h.offset..]
+            let content_start = doc.content()[h.offset..]
                 .find('\n')
                 .map(|i| h.offset + i + 1)
                 .unwrap_or(h.offset);
 
             let content_end = doc
-                .headings
+                .headings()
                 .iter()
                 .skip(idx + 1)
                 .find(|next_h| next_h.level <= h.level)
                 .map(|next_h| next_h.offset)
-                .unwrap_or(doc.content.len());
+                .unwrap_or(doc.content().len());
+
+            let content = doc.content()[content_start..content_end].trim().to_string();
+            let raw_md = doc.content()[h.offset..content_end].to_string();
 
-            let content = doc.content[content_start..content_end].trim().to_string();
-            let raw_md = doc.content[h.offset..content_end].to_string();
+            // Links in the heading itself, positioned at the heading
+            let title_links = h
+                .inline
+                .iter()
+                .filter_map(|element| match element {
+                    InlineElement::Link { text, url, .. } => {
+                        let target = parse_link_target(url);
+                        Some(link_value(Link::new(text.clone(), target, h.offset), doc))
+                    }
+                    _ => None,
+                })
+                .collect();
 
             HeadingValue {
                 level: h.level as u8,
@@ -634,84 +1518,334 @@ fn extract_headings(doc: &Document) -> Vec<HeadingValue> {
                 content,
                 raw_md,
                 index: idx,
+                number,
+                title_links,
             }
         })
         .collect()
 }
 
-fn extract_blocks(doc: &Document) -> (Vec<CodeValue>, Vec<LinkValue>, Vec<ImageValue>, Vec<TableValue>, Vec<ListValue>) {
-    use crate::parser::content::parse_content;
+/// Build an error for a property access that looks like a mistyped element selector.
+///
+/// `.h7` reports an invalid heading level; names close to a known element alias
+/// (and closer to it than to any property of the current element) report an
+/// unknown element with suggestions.
+fn unknown_element_error(name: &str, current: &Value, span: Span) -> Option<QueryError> {
+    if let Some(digits) = name.strip_prefix('h') {
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            let level = digits.parse::<u8>().unwrap_or(u8::MAX);
+            let nearest = if level == 0 { "h1" } else { "h6" };
+            return Some(
+                QueryError::new(QueryErrorKind::InvalidHeadingLevel(level), span, String::new())
+                    .with_suggestions(vec![nearest.to_string(), "h".to_string()]),
+            );
+        }
+    }
+
+    // Missing object keys are ordinary nulls, and very short names match too much
+    if matches!(current, Value::Object(_) | Value::FrontMatter(_)) || name.chars().count() < 3 {
+        return None;
+    }
+
+    let max_distance = if name.chars().count() <= 4 { 1 } else { 2 };
+    let element_distance = ElementKind::NAMES
+        .iter()
+        .map(|alias| levenshtein(alias, name))
+        .min()?;
+    if element_distance > max_distance {
+        return None;
+    }
+
+    let property_distance = current
+        .property_names()
+        .iter()
+        .map(|prop| levenshtein(prop, name))
+        .min()
+        .unwrap_or(usize::MAX);
+    if property_distance <= element_distance {
+        return None;
+    }
+
+    let mut suggestions: Vec<&str> = ElementKind::NAMES
+        .iter()
+        .copied()
+        .filter(|alias| levenshtein(alias, name) == element_distance)
+        .collect();
+    suggestions.truncate(3);
+
+    Some(
+        QueryError::new(QueryErrorKind::UnknownElement(name.to_string()), span, String::new())
+            .with_suggestions(suggestions.into_iter().map(String::from).collect()),
+    )
+}
+
+/// Elements extracted from the document body.
+struct ExtractedBlocks {
+    code_blocks: Vec<CodeValue>,
+    links: Vec<LinkValue>,
+    images: Vec<ImageValue>,
+    tables: Vec<TableValue>,
+    lists: Vec<ListValue>,
+    paragraphs: Vec<ParagraphValue>,
+    details: Vec<DetailsValue>,
+    footnotes: Vec<FootnoteValue>,
+}
+
+/// A container being rebuilt by [`Engine::walk`]
+enum WalkFrame {
+    Array {
+        todo: std::vec::IntoIter<Value>,
+        done: Vec<Value>,
+    },
+    Object {
+        todo: indexmap::map::IntoIter<String, Value>,
+        /// Key of the child currently being walked
+        key: String,
+        done: IndexMap<String, Value>,
+    },
+}
+
+impl WalkFrame {
+    fn next_child(&mut self) -> Option<Value> {
+        match self {
+            WalkFrame::Array { todo, .. } => todo.next(),
+            WalkFrame::Object { todo, key, .. } => todo.next().map(|(k, v)| {
+                *key = k;
+                v
+            }),
+        }
+    }
+
+    /// Store the walked child; `None` (no output from `f`) drops it.
+    fn accept(&mut self, walked: Option<Value>) {
+        let Some(walked) = walked else { return };
+        match self {
+            WalkFrame::Array { done, .. } => done.push(walked),
+            WalkFrame::Object { key, done, .. } => {
+                done.insert(std::mem::take(key), walked);
+            }
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            WalkFrame::Array { done, .. } => Value::Array(done),
+            WalkFrame::Object { done, .. } => Value::Object(done),
+        }
+    }
+}
+
+fn number_expected(found: Option<Value>, span: Span) -> QueryError {
+    QueryError::new(
+        QueryErrorKind::TypeError {
+            expected: "number",
+            found: found.map_or_else(|| "no value".to_string(), |v| v.kind().to_string()),
+        },
+        span,
+        String::new(),
+    )
+}
+
+pub(super) fn resource_limit(resource: &'static str, limit: u128) -> QueryError {
+    QueryError::new(
+        QueryErrorKind::ResourceLimit { resource, limit },
+        Span::default(),
+        String::new(),
+    )
+    .with_help("simplify the query or raise the limit with Engine::with_limits")
+}
+
+/// Count references to each footnote label and collect all images using
+/// pulldown-cmark's offset iterator.
+///
+/// References inside `<details>` blocks are skipped, like the footnotes
+/// defined there.
+fn scan_elements(doc: &Document) -> (HashMap<String, usize>, Vec<ImageValue>) {
+    use crate::parser::content::details_ranges;
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+
+    let hidden = details_ranges(doc.content());
+    let is_hidden = |offset: usize| hidden.iter().any(|r| r.contains(&offset));
+
+    let mut references = HashMap::new();
+    let mut images = Vec::new();
+    let mut current_image: Option<ImageValue> = None;
+
+    for (event, range) in Parser::new_ext(doc.content(), options).into_offset_iter() {
+        match event {
+            Event::FootnoteReference(label) if !is_hidden(range.start) => {
+                *references.entry(label.to_string()).or_default() += 1;
+            }
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                current_image = Some(ImageValue {
+                    alt: String::new(),
+                    src: dest_url.to_string(),
+                    title: if title.is_empty() { None } else { Some(title.to_string()) },
+                    line: doc.line_at(range.start),
+                    offset: range.start,
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(image) = current_image.as_mut() {
+                    image.alt.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Image) => {
+                images.extend(current_image.take());
+            }
+            _ => {}
+        }
+    }
+
+    (references, images)
+}
+
+fn extract_blocks(doc: &Document) -> ExtractedBlocks {
     use crate::parser::links::extract_links;
     use crate::parser::output::Block;
 
-    let blocks = parse_content(&doc.content, 1);
-    let links = extract_links(&doc.content);
+    // Parsed a section at a time and cached on the document
+    let blocks = doc.blocks().map(|(block, span)| (block.clone(), span));
+    let links = extract_links(doc.content());
+    let (references, images) = scan_elements(doc);
 
     let mut code_blocks = Vec::new();
-    let mut images = Vec::new();
     let mut tables = Vec::new();
     let mut lists = Vec::new();
+    let mut paragraphs = Vec::new();
+    let mut details = Vec::new();
+    let mut footnotes = Vec::new();
 
-    for block in blocks {
+    for (block, span) in blocks {
+        let (line, offset) = (doc.line_at(span.start), span.start);
         match block {
-            Block::Code { language, content, start_line, end_line } => {
+            Block::Code { language, content, .. } => {
+                // Lines of the block's fences
                 code_blocks.push(CodeValue {
                     language,
                     content,
-                    start_line,
-                    end_line,
+                    start_line: line,
+                    end_line: doc.line_at(span.end.saturating_sub(1).max(span.start)),
+                    offset,
                 });
             }
-            Block::Image { alt, src, title } => {
-                images.push(ImageValue { alt, src, title });
+            Block::Blockquote { content, .. } => {
+                quoted_code(doc, &content, offset, &mut code_blocks)
             }
             Block::Table { headers, rows, alignments } => {
                 tables.push(TableValue {
                     headers,
                     rows,
                     alignments: alignments.iter().map(|a| format!("{:?}", a).to_lowercase()).collect(),
+                    line,
+                    offset,
                 });
             }
             Block::List { ordered, items } => {
                 lists.push(ListValue {
                     ordered,
-                    items: items.into_iter().map(|i| ListItemValue {
-                        content: i.content,
-                        checked: i.checked,
-                    }).collect(),
+                    items: items.into_iter().map(list_item_value).collect(),
+                    line,
+                    offset,
                 });
             }
+            Block::Paragraph { content, .. } => {
+                paragraphs.push(ParagraphValue { content, line, offset });
+            }
+            Block::Details { summary, content, open, .. } => {
+                details.push(DetailsValue { summary, content, open });
+            }
+            Block::Footnote { label, content, .. } => {
+                let references = references.get(&label).copied().unwrap_or(0);
+                footnotes.push(FootnoteValue { label, content, references, line, offset });
+            }
             _ => {}
         }
     }
 
-    let link_values: Vec<LinkValue> = links
-        .into_iter()
-        .map(|l| {
-            use crate::parser::links::LinkTarget;
-            let (url, link_type) = match l.target {
-                LinkTarget::Anchor(s) => (format!("#{}", s), LinkType::Anchor),
-                LinkTarget::External(s) => (s, LinkType::External),
-                LinkTarget::RelativeFile { path, anchor } => {
-                    let mut url = path.to_string_lossy().to_string();
-                    if let Some(a) = anchor {
-                        url.push('#');
-                        url.push_str(&a);
-                    }
-                    (url, LinkType::Relative)
-                }
-                LinkTarget::WikiLink { target, .. } => (target, LinkType::WikiLink),
-            };
-            LinkValue {
-                text: l.text,
-                url,
-                link_type,
-                offset: l.offset,
-            }
-        })
-        .collect();
+    let link_values: Vec<LinkValue> = links.into_iter().map(|l| link_value(l, doc)).collect();
+
+    ExtractedBlocks {
+        code_blocks,
+        links: link_values,
+        images,
+        tables,
+        lists,
+        paragraphs,
+        details,
+        footnotes,
+    }
+}
+
+/// Code blocks in a blockquote's content, at any quote depth. The content has
+/// the quote's lines one for one, so each block is placed at the start of its
+/// line in the quote, which begins at byte `start` of the document.
+fn quoted_code(doc: &Document, content: &str, start: usize, code_blocks: &mut Vec<CodeValue>) {
+    use crate::parser::content::parse_content_spans;
+    use crate::parser::output::Block;
+
+    // Byte offset in the document of the line holding `content[offset]`
+    let line_offset = |offset: usize| match content[..offset].matches('\n').count() {
+        0 => start,
+        n => doc.content()[start..]
+            .match_indices('\n')
+            .nth(n - 1)
+            .map_or(start, |(i, _)| start + i + 1),
+    };
+    for (block, span) in parse_content_spans(content, doc.line_at(start)) {
+        let offset = line_offset(span.start);
+        match block {
+            Block::Code { language, content, .. } => code_blocks.push(CodeValue {
+                language,
+                content,
+                start_line: doc.line_at(offset),
+                end_line: doc.line_at(line_offset(span.end.saturating_sub(1).max(span.start))),
+                offset,
+            }),
+            Block::Blockquote { content, .. } => quoted_code(doc, &content, offset, code_blocks),
+            _ => {}
+        }
+    }
+}
+
+fn list_item_value(item: crate::parser::output::ListItem) -> ListItemValue {
+    ListItemValue {
+        content: item.content,
+        checked: item.checked,
+        children: item.children.into_iter().map(list_item_value).collect(),
+    }
+}
 
-    (code_blocks, link_values, images, tables, lists)
+fn link_value(link: crate::parser::Link, doc: &Document) -> LinkValue {
+    use crate::parser::links::LinkTarget;
+    let (url, link_type) = match link.target {
+        LinkTarget::Anchor(s) => (format!("#{}", s), LinkType::Anchor),
+        LinkTarget::External(s) => (s, LinkType::External),
+        LinkTarget::RelativeFile { path, anchor } => {
+            let mut url = path.to_string_lossy().to_string();
+            if let Some(a) = anchor {
+                url.push('#');
+                url.push_str(&a);
+            }
+            (url, LinkType::Relative)
+        }
+        LinkTarget::WikiLink { target, .. } => (target, LinkType::WikiLink),
+    };
+    LinkValue {
+        text: link.text,
+        url,
+        link_type,
+        line: doc.line_at(link.offset),
+        offset: link.offset,
+    }
 }
 
 fn literal_to_value(lit: &Literal) -> Value {
@@ -723,6 +1857,19 @@ fn literal_to_value(lit: &Literal) -> Value {
     }
 }
 
+/// Whether `expr` is an element selector (or hierarchy of them) that can
+/// produce its elements one at a time. Filters and indexes need the full list.
+fn is_element_stream(expr: &Expr) -> bool {
+    let plain = |expr: &Expr| {
+        matches!(expr, Expr::Element { filters, index: None, .. } if filters.is_empty())
+    };
+    match expr {
+        Expr::Element { kind, .. } => plain(expr) && *kind != ElementKind::FrontMatter,
+        Expr::Hierarchy { child, .. } => plain(child),
+        _ => false,
+    }
+}
+
 fn apply_index(mut values: Vec<Value>, index: &IndexOp) -> Result<Vec<Value>, QueryError> {
     match index {
         IndexOp::Single(idx) => {
@@ -753,16 +1900,31 @@ fn apply_index(mut values: Vec<Value>, index: &IndexOp) -> Result<Vec<Value>, Qu
     }
 }
 
+/// `==` compares structurally, except that an element compared with a
+/// string compares its text, so `.h1 == "Title"` matches the heading.
 fn values_equal(a: &Value, b: &Value) -> bool {
     match (a, b) {
-        (Value::Null, Value::Null) => true,
-        (Value::Bool(a), Value::Bool(b)) => a == b,
-        (Value::Number(a), Value::Number(b)) => (a - b).abs() < f64::EPSILON,
-        (Value::String(a), Value::String(b)) => a == b,
-        _ => a.to_text() == b.to_text(),
+        (Value::String(s), element) | (element, Value::String(s)) if is_element(element) => {
+            element.to_text() == *s
+        }
+        _ => a == b,
     }
 }
 
+/// Whether `value` is a document element rather than plain data
+fn is_element(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Null
+            | Value::Bool(_)
+            | Value::Number(_)
+            | Value::String(_)
+            | Value::Array(_)
+            | Value::Object(_)
+            | Value::FrontMatter(_)
+    )
+}
+
 fn compare_values(a: &Value, b: &Value) -> i32 {
     match (a, b) {
         (Value::Number(a), Value::Number(b)) => {
@@ -775,7 +1937,7 @@ fn compare_values(a: &Value, b: &Value) -> i32 {
 
 fn add_values(a: &Value, b: &Value) -> Value {
     match (a, b) {
-        (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+        (Value::Number(a), Value::Number(b)) => Value::number(a + b),
         (Value::String(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
         (Value::Array(a), Value::Array(b)) => {
             let mut result = a.clone();
@@ -788,14 +1950,14 @@ fn add_values(a: &Value, b: &Value) -> Value {
 
 fn sub_values(a: &Value, b: &Value) -> Value {
     match (a, b) {
-        (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+        (Value::Number(a), Value::Number(b)) => Value::number(a - b),
         _ => Value::Null,
     }
 }
 
 fn mul_values(a: &Value, b: &Value) -> Value {
     match (a, b) {
-        (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+        (Value::Number(a), Value::Number(b)) => Value::number(a * b),
         (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => {
             Value::String(s.repeat(*n as usize))
         }
@@ -813,7 +1975,7 @@ fn div_values(a: &Value, b: &Value) -> Result<Value, QueryError> {
                     String::new(),
                 ))
             } else {
-                Ok(Value::Number(a / b))
+                Ok(Value::number(a / b))
             }
         }
         _ => Ok(Value::Null),
@@ -830,7 +1992,7 @@ fn mod_values(a: &Value, b: &Value) -> Result<Value, QueryError> {
                     String::new(),
                 ))
             } else {
-                Ok(Value::Number(a % b))
+                Ok(Value::number(a % b))
             }
         }
         _ => Ok(Value::Null),
@@ -896,4 +2058,1323 @@ mod tests {
             assert_eq!(h.text, "World");
         }
     }
+
+    #[test]
+    fn test_details_selection() {
+        let md = "# Doc\n\n<details open>\n<summary>Install</summary>\n\nRun it\n</details>\n\n<details>\n<summary>FAQ</summary>\n\nAnswers\n</details>\n";
+        let results = eval(md, ".details");
+        assert_eq!(results.len(), 2);
+        if let Value::Details(d) = &results[0] {
+            assert_eq!(d.summary, "Install");
+            assert_eq!(d.content, "Run it");
+            assert!(d.open);
+        } else {
+            panic!("Expected Details");
+        }
+
+        let summaries = eval(md, ".spoiler | .summary");
+        let summaries: Vec<_> = summaries.iter().map(|v| v.to_text()).collect();
+        assert_eq!(summaries, vec!["Install", "FAQ"]);
+
+        let closed = eval(md, ".details[FAQ] | .open");
+        assert!(matches!(closed[0], Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_footnote_selection() {
+        let md = "# Doc\n\nFirst claim[^a], second[^note] and again[^a].\n\n[^a]: Source A.\n[^note]: A longer\n    note.\n";
+        let results = eval(md, ".footnote");
+        assert_eq!(results.len(), 2);
+        let Value::Footnote(f) = &results[0] else {
+            panic!("Expected Footnote, got {:?}", results[0]);
+        };
+        assert_eq!(f.label, "a");
+        assert_eq!(f.content, "Source A.");
+        assert_eq!(f.references, 2);
+        assert_eq!(f.line, 5);
+
+        let labels: Vec<_> = eval(md, ".fn | .label").iter().map(|v| v.to_text()).collect();
+        assert_eq!(labels, vec!["a", "note"]);
+
+        let note = eval(md, ".footnote[longer]");
+        assert_eq!(note.len(), 1);
+        assert_eq!(note[0].get_property("content").unwrap().to_text(), "A longer note.");
+        assert!(matches!(note[0].get_property("references"), Some(Value::Number(n)) if n == 1.0));
+
+        // The definitions aren't paragraphs of their own
+        assert_eq!(eval(md, "[.para] | count").remove(0), Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_unknown_property_is_null_by_default() {
+        let results = eval("# Title", ".h1 | .levle");
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Value::Null));
+    }
+
+    #[test]
+    fn test_unknown_property_errors_in_strict_mode() {
+        let doc = parse_markdown("# Title");
+        let query = parse(".h1 | .levle").unwrap();
+        let err = Engine::new(&doc).strict(true).execute(&query).unwrap_err();
+
+        assert!(matches!(err.kind, QueryErrorKind::PropertyNotFound { .. }));
+        assert_eq!(err.suggestions.first().map(String::as_str), Some("level"));
+
+        let query = parse(".h1 | .level").unwrap();
+        let results = Engine::new(&doc).strict(true).execute(&query).unwrap();
+        assert_eq!(results[0].to_text(), "1");
+    }
+
+    #[test]
+    fn test_invalid_heading_level_suggestions() {
+        let doc = parse_markdown("# Title");
+        let query = parse(".h7").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+
+        assert!(matches!(err.kind, QueryErrorKind::InvalidHeadingLevel(7)));
+        assert_eq!(err.suggestions, vec!["h6", "h"]);
+    }
+
+    #[test]
+    fn test_unknown_element_suggestions() {
+        let doc = parse_markdown("# Title\n\n![alt](img.png)");
+        let query = parse(".imgg").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+
+        assert!(matches!(err.kind, QueryErrorKind::UnknownElement(ref name) if name == "imgg"));
+        assert!(err.suggestions.contains(&"img".to_string()));
+    }
+
+    #[test]
+    fn test_property_typo_is_not_an_element() {
+        // "lvel" is closer to the heading's "level" property than to any element
+        let results = eval("# Title", ".h1 | .lvel");
+        assert!(matches!(results[0], Value::Null));
+    }
+
+    #[test]
+    fn test_element_positions() {
+        let md = "# Title\n\nSee [docs](https://example.com).\n\n![logo](logo.png)\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n- one\n- two\n";
+
+        let links = eval(md, ".link | .line");
+        assert_eq!(links[0].to_text(), "3");
+
+        let images = eval(md, ".img");
+        assert_eq!(images.len(), 1);
+        if let Value::Image(i) = &images[0] {
+            assert_eq!(i.alt, "logo");
+            assert_eq!(i.line, 5);
+            assert_eq!(&md[i.offset..i.offset + 2], "![");
+        } else {
+            panic!("Expected Image");
+        }
+
+        assert_eq!(eval(md, ".table | .line")[0].to_text(), "7");
+        assert_eq!(eval(md, ".list | .line")[0].to_text(), "11");
+        let paragraphs = eval(md, ".para | .line");
+        assert_eq!(paragraphs[0].to_text(), "3");
+
+        // An image-only paragraph is not a paragraph, and must not shift the ones after it
+        let md = "# Title\n\n![logo](logo.png)\n\nHello world\n\n```sh\nls\n```\n";
+        assert_eq!(eval(md, ".para | .line")[0].to_text(), "5");
+        assert_eq!(eval(md, ".code | .start_line")[0].to_text(), "7");
+
+        // Nor do elements inside <details>
+        let md = "<details>\n<summary>More</summary>\n\nHidden\n\n- a\n</details>\n\n- b\n\nShown\n";
+        assert_eq!(eval(md, ".list | .line")[0].to_text(), "9");
+        assert_eq!(eval(md, ".para | .line")[0].to_text(), "11");
+    }
+
+    #[test]
+    fn test_env_disabled_by_default() {
+        let doc = parse_markdown("# Title");
+        let query = parse("env(\"HOME\")").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::EnvDisabled));
+
+        let query = parse("$ENV").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::EnvDisabled));
+    }
+
+    #[test]
+    fn test_now_and_strftime() {
+        let doc = parse_markdown("# Title");
+        let err = Engine::new(&doc).execute(&parse("now").unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::EnvDisabled));
+
+        let now = Engine::new(&doc).allow_env(true).execute(&parse("now").unwrap()).unwrap();
+        assert!(matches!(now[0], Value::Number(n) if n > 1_700_000_000.0));
+
+        let format = |query: &str| eval("# Title", query)[0].to_text();
+        assert_eq!(
+            format(r#"1700000000 | strftime("%Y-%m-%d %H:%M:%S")"#),
+            "2023-11-14 22:13:20"
+        );
+        assert_eq!(
+            format(r#"1700000000.9 | strftime("%a %d %b %Y, %I:%M %p (day %j) %%")"#),
+            "Tue 14 Nov 2023, 10:13 PM (day 318) %"
+        );
+        assert_eq!(format(r#"0 | strftime("%FT%TZ")"#), "1970-01-01T00:00:00Z");
+        assert_eq!(format(r#"-86400 | strftime("%D")"#), "12/31/69");
+
+        let err = Engine::new(&doc)
+            .execute(&parse(r#""today" | strftime("%F")"#).unwrap())
+            .unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::TypeError { .. }));
+        let err = Engine::new(&doc)
+            .execute(&parse(r#"0 | strftime("%Q")"#).unwrap())
+            .unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::InvalidInput { .. }));
+    }
+
+    #[test]
+    fn test_env_lookup() {
+        // Look up a variable that's already set: setting one would race with
+        // tests reading the environment on other threads
+        let (name, value) = std::env::vars()
+            .find(|(name, _)| {
+                name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+            .expect("an environment variable to look up");
+        let doc = parse_markdown("# Title");
+
+        let query = parse(&format!("env(\"{name}\")")).unwrap();
+        let results = Engine::new(&doc).allow_env(true).execute(&query).unwrap();
+        assert_eq!(results[0].to_text(), value);
+
+        let query = parse(&format!("$ENV | .{name}")).unwrap();
+        let results = Engine::new(&doc).allow_env(true).execute(&query).unwrap();
+        assert_eq!(results[0].to_text(), value);
+
+        let query = parse("env(\"TREEMD_TEST_UNSET_VARIABLE\")").unwrap();
+        let results = Engine::new(&doc).allow_env(true).execute(&query).unwrap();
+        assert!(matches!(results[0], Value::Null));
+    }
+
+    #[test]
+    fn test_recurse() {
+        let results = eval("# Title", "[1, [2, 3]] | recurse");
+        let texts: Vec<String> = results.iter().map(|v| v.kind().to_string()).collect();
+        assert_eq!(texts, vec!["array", "number", "array", "number", "number"]);
+
+        let results = eval("# Title", "1 | recurse(select(. < 3) | . + 1)");
+        let texts: Vec<String> = results.iter().map(Value::to_text).collect();
+        assert_eq!(texts, vec!["1", "2", "3"]);
+
+        // `f` doesn't leave its last input behind for the rest of the expression
+        let query = r#"{"x": {"x": 1}} | [recurse(.x | select(. != null))] + [.] | tojson"#;
+        assert_eq!(
+            eval("# Title", query)[0].to_text(),
+            r#"[{"x":{"x":1}},{"x":1},1,{"x":{"x":1}}]"#
+        );
+    }
+
+    #[test]
+    fn test_unbounded_recurse_hits_step_limit() {
+        let doc = parse_markdown("# Title");
+        let query = parse("0 | recurse(. + 1)").unwrap();
+        let limits = Limits {
+            max_steps: 10_000,
+            ..Limits::default()
+        };
+        let err = Engine::new(&doc).with_limits(limits).execute(&query).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            QueryErrorKind::ResourceLimit { resource: "evaluation steps", limit: 10_000 }
+        ));
+    }
+
+    #[test]
+    fn test_output_and_depth_limits() {
+        let doc = parse_markdown("# Title");
+        let limits = Limits {
+            max_output: 50,
+            ..Limits::default()
+        };
+        let query = parse("0 | recurse(. + 1)").unwrap();
+        let err = Engine::new(&doc).with_limits(limits).execute(&query).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            QueryErrorKind::ResourceLimit { resource: "output values", .. }
+        ));
+
+        let nested = format!("{}1{}", "(".repeat(40), ")".repeat(40));
+        let query = parse(&nested).unwrap();
+        let limits = Limits {
+            max_depth: 16,
+            ..Limits::default()
+        };
+        let err = Engine::new(&doc).with_limits(limits).execute(&query).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            QueryErrorKind::ResourceLimit { resource: "levels of nesting", .. }
+        ));
+    }
+
+    #[test]
+    fn test_sub_replaces_first_match() {
+        let results = eval("# Title", r#""a-1 b-2" | sub("([a-z])-(\\d)"; "\\2\\1")"#);
+        assert_eq!(results[0].to_text(), "1a b-2");
+
+        let results = eval("# Title", r#""a-1 b-2" | sub("-(\\d)", "=$1")"#);
+        assert_eq!(results[0].to_text(), "a=1 b-2");
+
+        let results = eval("# Title", r#""a-1 b-2" | gsub("-"; "+")"#);
+        assert_eq!(results[0].to_text(), "a+1 b+2");
+    }
+
+    #[test]
+    fn test_sub_leaves_non_matching_strings() {
+        let results = eval("# Title", r#""no digits" | sub("\\d+"; "N")"#);
+        assert_eq!(results[0].to_text(), "no digits");
+
+        let doc = parse_markdown("# Title");
+        let query = parse(r#""x" | sub("("; "y")"#).unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::InvalidRegex { .. }));
+    }
+
+    #[test]
+    fn test_limit_and_skip_negative_counts() {
+        let value = |query: &str| eval("# Title", query).remove(0);
+
+        assert_eq!(value("[1, 2, 3, 4] | limit(-2)"), value("[3, 4]"));
+        assert_eq!(value("[1, 2, 3, 4] | skip(-1)"), value("[1, 2, 3]"));
+        assert_eq!(value(r#""héllo" | limit(-2)"#), value(r#""lo""#));
+        assert_eq!(value(r#""héllo" | skip(-1)"#), value(r#""héll""#));
+
+        // Counts past the length clamp in both directions
+        assert_eq!(value("[1, 2] | limit(5)"), value("[1, 2]"));
+        assert_eq!(value("[1, 2] | limit(-5)"), value("[1, 2]"));
+        assert_eq!(value("[1, 2] | skip(5)"), value("[]"));
+        assert_eq!(value("[1, 2] | skip(-5)"), value("[]"));
+        assert_eq!(value(r#""ab" | skip(3)"#), value(r#""""#));
+        assert_eq!(value(r#""ab" | limit(-3)"#), value(r#""ab""#));
+    }
+
+    #[test]
+    fn test_non_finite_numbers_become_null() {
+        use crate::query::{OutputFormat, format_output};
+
+        let json = |q: &str| format_output(&eval("# Title", q), OutputFormat::Json);
+        assert_eq!(json("[] | min"), "null");
+        assert_eq!(json("[.h2 | .level] | max"), "null");
+        assert_eq!(json("[1e308, 1e308] | add"), "null");
+        assert_eq!(json("1e308 * 10"), "null");
+        assert_eq!(json(r#"(1e308 * 10) // "n/a""#), r#""n/a""#);
+
+        // 0/0 is guarded by an error instead of producing NaN
+        let doc = parse_markdown("# Title");
+        let mut engine = Engine::new(&doc);
+        let err = engine.execute(&parse("0 / 0").unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::DivisionByZero));
+        let err = engine.execute(&parse("1 % 0").unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::DivisionByZero));
+    }
+
+    #[test]
+    fn test_add_requires_one_kind_of_value() {
+        let value = |query: &str| eval("# Title", query).remove(0);
+        assert_eq!(value("[1, null, 2] | add"), Value::Number(3.0));
+        assert_eq!(value(r#"["a", "b"] | add"#), Value::String("ab".to_string()));
+        assert_eq!(value("[[1], [2, 3]] | add"), value("[1, 2, 3]"));
+        assert_eq!(value("[] | add"), Value::Number(0.0));
+
+        let doc = parse_markdown("# A\n## B\n");
+        let mut engine = Engine::new(&doc);
+        for (query, found) in [
+            (r#"[1, "a"] | add"#, "number and string"),
+            ("[.h] | add", "heading"),
+        ] {
+            let err = engine.execute(&parse(query).unwrap()).unwrap_err();
+            match err.kind {
+                QueryErrorKind::TypeError { found: f, .. } => assert_eq!(f, found),
+                other => panic!("expected a type error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_regex_flags() {
+        let is_match = |query: &str| eval("# Title", query)[0] == Value::Bool(true);
+
+        assert!(!is_match(r#""Hello" | test("hello")"#));
+        assert!(is_match(r#""Hello" | test("hello"; "i")"#));
+        assert!(!is_match(r#""a\nb" | matches("^b$")"#));
+        assert!(is_match(r#""a\nb" | matches("^b$"; "m")"#));
+        assert!(!is_match(r#""a\nb" | test("a.b")"#));
+        assert!(is_match(r#""a\nb" | test("a.b"; "s")"#));
+        assert!(is_match(r#""abc" | test("a b  c # spaced"; "x")"#));
+
+        let results = eval("# Title", r#""a-1 A-2" | sub("a"; "x"; "gi")"#);
+        assert_eq!(results[0].to_text(), "x-1 x-2");
+        let results = eval("# Title", r#""aXbxc" | [splits("x"; "i")]"#);
+        assert_eq!(results[0], eval("# Title", r#"["a", "b", "c"]"#)[0]);
+
+        let query = r#""v1.2" | capture("V(?P<major>\\d)\\.(?P<minor>\\d)"; "i")"#;
+        let results = eval("# Title", query);
+        assert_eq!(results[0].to_text(), r#"{"major":"1","minor":"2"}"#);
+        assert!(eval("# Title", r#""none" | capture("(?P<n>\\d)")"#).is_empty());
+
+        let doc = parse_markdown("# Title");
+        for query in [
+            r#""x" | test("x"; "q")"#,
+            r#""x" | matches("(")"#,
+            // `g` means nothing to a single match
+            r#""x" | test("x"; "g")"#,
+            r#""x" | capture("(?P<x>x)"; "gi")"#,
+        ] {
+            let err = Engine::new(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(matches!(err.kind, QueryErrorKind::InvalidRegex { .. }), "{}", query);
+        }
+        // ...but is accepted where matching is global anyway
+        assert_eq!(eval("# Title", r#""a-b" | [splits("-"; "g")] | length"#)[0].to_text(), "2");
+        assert_eq!(eval("# Title", r#""aa" | gsub("a"; "b"; "g")"#)[0].to_text(), "bb");
+    }
+
+    #[test]
+    fn test_scan() {
+        let md = "# Changelog\n\nUpgrade from v1.2 to v1.10, skipping v2.0-rc. See #tags.\n";
+        let texts = |query: &str| -> Vec<String> {
+            eval(md, query).iter().map(|v| v.to_text()).collect()
+        };
+
+        // Each match is a separate result, like jq
+        assert_eq!(texts(r#".p | text | scan("v\\d+\\.\\d+")"#), ["v1.2", "v1.10", "v2.0"]);
+        assert_eq!(
+            texts(r#".p | text | [scan("V\\d+\\.\\d+"; "i")] | length"#),
+            ["3"]
+        );
+
+        // With groups, each result is the array of groups
+        assert_eq!(
+            eval(md, r#".p | text | [scan("v(\\d+)\\.(\\d+)(-rc)?")]"#),
+            eval(md, r#"[["1", "2", null], ["1", "10", null], ["2", "0", "-rc"]]"#)
+        );
+        assert!(eval(md, r#".p | text | scan("v9")"#).is_empty());
+
+        let doc = parse_markdown(md);
+        let err = Engine::new(&doc).execute(&parse(r#""x" | scan("(")"#).unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::InvalidRegex { .. }));
+    }
+
+    #[test]
+    fn test_index_and_rindex() {
+        assert_eq!(eval("# T", r#""a,b, cd, efg" | index(", ")"#)[0].to_text(), "3");
+        assert_eq!(eval("# T", r#""a,b, cd, efg" | rindex(", ")"#)[0].to_text(), "7");
+        assert_eq!(eval("# T", r#""héllo wörld" | index("o")"#)[0].to_text(), "4");
+        assert!(matches!(eval("# T", r#""abc" | index("z")"#)[0], Value::Null));
+        assert_eq!(eval("# T", r#"["a", "b", "a"] | rindex("a")"#)[0].to_text(), "2");
+    }
+
+    #[test]
+    fn test_indices_overlapping_and_non_overlapping() {
+        let positions = |query: &str| -> Vec<String> {
+            match &eval("# T", query)[0] {
+                Value::Array(a) => a.iter().map(Value::to_text).collect(),
+                other => panic!("Expected array, got {:?}", other),
+            }
+        };
+
+        assert_eq!(positions(r#""aaaa" | indices("aa")"#), vec!["0", "1", "2"]);
+        assert_eq!(positions(r#""a-b-c" | indices("-")"#), vec!["1", "3"]);
+        assert!(positions(r#""abc" | indices("z")"#).is_empty());
+    }
+
+    #[test]
+    fn test_startswith_any_of_array() {
+        let md = "# Changelog\n## feat: add search\n## fix: crash on empty file\n## docs: update readme\n";
+
+        let results = eval(md, r#".h2 | select(startswith(["feat:", "fix:"])) | text"#);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].to_text(), "feat: add search");
+        assert_eq!(results[1].to_text(), "fix: crash on empty file");
+
+        let results = eval(md, r#".h2 | select(endswith(["readme", "file"])) | text"#);
+        assert_eq!(results.len(), 2);
+
+        let results = eval(md, r#".h2 | select(contains(["search", "nothing"])) | text"#);
+        assert_eq!(results.len(), 1);
+
+        // A plain string argument behaves as before
+        let results = eval(md, r#".h2 | select(startswith("FEAT")) | text"#);
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_keys_sorted_and_unsorted() {
+        let keys = |query: &str| match &eval("# T", query)[0] {
+            Value::Array(items) => items.iter().map(|v| v.to_text()).collect::<Vec<_>>(),
+            other => panic!("expected array, got {:?}", other),
+        };
+
+        assert_eq!(keys("{zeta: 1, alpha: 2, mid: 3} | keys"), ["alpha", "mid", "zeta"]);
+        assert_eq!(
+            keys("{zeta: 1, alpha: 2, mid: 3} | keys_unsorted"),
+            ["zeta", "alpha", "mid"]
+        );
+        assert_eq!(keys("[\"a\", \"b\"] | keys"), ["0", "1"]);
+    }
+
+    #[test]
+    fn test_explode_implode_round_trip() {
+        let results = eval("# T", r#""Hé 世" | explode"#);
+        match &results[0] {
+            Value::Array(items) => {
+                let codepoints: Vec<String> = items.iter().map(|v| v.to_text()).collect();
+                assert_eq!(codepoints, ["72", "233", "32", "19990"]);
+            }
+            other => panic!("expected array, got {:?}", other),
+        }
+
+        let results = eval("# Café ☕", ".h1 | text | explode | implode");
+        assert_eq!(results[0].to_text(), "Café ☕");
+
+        assert_eq!(eval("# T", "65 | ascii")[0].to_text(), "A");
+    }
+
+    #[test]
+    fn test_implode_invalid_codepoint_errors() {
+        let doc = parse_markdown("# T");
+        let mut engine = Engine::new(&doc);
+
+        let queries = [
+            "[1114112] | implode",
+            "[55296] | implode",
+            "[65.5] | implode",
+            "[\"a\"] | implode",
+            "200 | ascii",
+        ];
+        for query in queries {
+            let err = engine.execute(&parse(query).unwrap()).unwrap_err();
+            assert!(
+                matches!(err.kind, QueryErrorKind::TypeError { .. }),
+                "{}: {:?}",
+                query,
+                err.kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_group_by_array_of_groups_and_group_into_object() {
+        let md = "# A\n## B\n### C\n## D\n# E\n";
+        let texts = |value: &Value| match value {
+            Value::Array(items) => items.iter().map(|v| v.to_text()).collect::<Vec<_>>(),
+            other => panic!("expected array, got {:?}", other),
+        };
+
+        // jq-compatible: array of arrays, sorted by key
+        let results = eval(md, "[.h] | group_by(.level)");
+        let Value::Array(groups) = &results[0] else {
+            panic!("expected array of groups, got {:?}", results[0]);
+        };
+        assert_eq!(groups.len(), 3);
+        assert_eq!(texts(&groups[0]), ["A", "E"]);
+        assert_eq!(texts(&groups[1]), ["B", "D"]);
+        assert_eq!(texts(&groups[2]), ["C"]);
+
+        // A string names a property, as before
+        let results = eval(md, r#"[.h] | group_by("level") | length"#);
+        assert_eq!(results[0].to_text(), "3");
+
+        // Object-producing form keyed by group name
+        let results = eval(md, r#"[.h] | group_into("level")"#);
+        let Value::Object(groups) = &results[0] else {
+            panic!("expected object, got {:?}", results[0]);
+        };
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["h1", "h2", "h3"]);
+        assert_eq!(texts(&groups["h2"]), ["B", "D"]);
+    }
+
+    #[test]
+    fn test_split_literal_and_regex() {
+        let texts = |query: &str| match &eval("# T", query)[0] {
+            Value::Array(items) => items.iter().map(|v| v.to_text()).collect::<Vec<_>>(),
+            other => panic!("expected array, got {:?}", other),
+        };
+
+        // Literal separator: every single space splits
+        assert_eq!(texts(r#""a  b c" | split(" ")"#), ["a", "", "b", "c"]);
+        // Regex separator with the "g" flag
+        assert_eq!(texts(r#""a  b\tc" | split("\\s+"; "g")"#), ["a", "b", "c"]);
+        // A literal separator is not interpreted as a regex
+        assert_eq!(texts(r#""a.b" | split(".")"#), ["a", "b"]);
+        assert_eq!(texts(r#""aXbxc" | split("x"; "i")"#), ["a", "b", "c"]);
+
+        // Non-string inputs pass through
+        assert_eq!(texts(r#"["a b"] | split(" ")"#), ["a b"]);
+    }
+
+    #[test]
+    fn test_contains_containers() {
+        let check = |query: &str| eval("# T", query)[0].to_text();
+
+        // Array subset
+        assert_eq!(check("[1, 2, 3] | contains([2, 3])"), "true");
+        assert_eq!(check("[1, 2, 3] | contains([2, 4])"), "false");
+        assert_eq!(check(r#"["foobar", "baz"] | contains(["bar"])"#), "true");
+
+        // Recursive object containment
+        assert_eq!(check(r#"{a: 1, b: {c: "deep", d: 2}} | contains({b: {c: "deep"}})"#), "true");
+        assert_eq!(check("{a: 1, b: 2} | contains({a: 1, c: 3})"), "false");
+
+        // Strings still match substrings, case-insensitively
+        assert_eq!(check(r#""Hello World" | contains("world")"#), "true");
+        assert_eq!(check(r#""Hello World" | contains("moon")"#), "false");
+    }
+
+    #[test]
+    fn test_has_and_in_for_arrays_and_objects() {
+        let check = |query: &str| eval("# T", query)[0].to_text();
+
+        assert_eq!(check("[10, 20] | has(1)"), "true");
+        assert_eq!(check("[10, 20] | has(5)"), "false");
+        assert_eq!(check("[10, 20] | has(0.5)"), "false");
+        assert_eq!(check(r#"{a: 1} | has("a")"#), "true");
+        assert_eq!(check(r#"{a: 1} | has("b")"#), "false");
+
+        assert_eq!(check("1 | in([10, 20])"), "true");
+        assert_eq!(check("2 | in([10, 20])"), "false");
+        assert_eq!(check(r#""a" | in({a: 1})"#), "true");
+    }
+
+    #[test]
+    fn test_heading_number_property() {
+        let results = eval("# A\n### Skipped\n# B\n## B1\n", ".h | .number");
+        let numbers: Vec<String> = results.iter().map(|v| v.to_text()).collect();
+        assert_eq!(numbers, ["1", "1.1", "2", "2.1"]);
+    }
+
+    #[test]
+    fn test_heading_tree_navigation() {
+        let md = "# Guide\n## Install\n### Linux\n### macOS\n## Usage\n### Flags\n";
+        let texts = |query: &str| -> Vec<String> {
+            eval(md, query).iter().map(|v| v.to_text()).collect()
+        };
+
+        assert_eq!(texts(".h3 | .parent | .text"), ["Install", "Install", "Usage"]);
+        assert_eq!(texts(".h1 | .parent"), [""]);
+        assert_eq!(texts(".h1 | .children | length"), ["2"]);
+        assert_eq!(texts(".h1 | .children | last | .text"), ["Usage"]);
+        assert_eq!(texts(r#".h3 | select(.text == "Linux") | .siblings | first | .text"#), ["macOS"]);
+        assert_eq!(texts(".h3 | .siblings | length"), ["1", "1", "0"]);
+        assert_eq!(texts(".h | .depth"), ["1", "2", "3", "3", "2", "3"]);
+    }
+
+    #[test]
+    fn test_alt_falls_back_on_empty() {
+        let md = "# Guide\n## Install\n## Usage\n";
+        let texts = |query: &str| -> Vec<String> {
+            eval(md, query).iter().map(|v| v.to_text()).collect()
+        };
+
+        // Selector with no match produces nothing, so the right side is used
+        assert_eq!(texts(".h2[Missing] // .h1 | .text"), ["Guide"]);
+        assert_eq!(texts(".h3 // .h1 | .text"), ["Guide"]);
+        // Every non-null left value is kept
+        assert_eq!(texts(".h2 // .h1 | .text"), ["Install", "Usage"]);
+        assert_eq!(texts(".h2[Install] // .h1 | .text"), ["Install"]);
+    }
+
+    #[test]
+    fn test_alt_falls_back_on_null_and_false() {
+        let md = "# Guide\n";
+        let texts = |query: &str| -> Vec<String> {
+            eval(md, query).iter().map(|v| v.to_text()).collect()
+        };
+
+        assert_eq!(texts("null // \"default\""), ["default"]);
+        assert_eq!(texts("false // \"default\""), ["default"]);
+        assert_eq!(texts("null // false // 3"), ["3"]);
+        // Only null and false fall back; other falsy-looking values are kept
+        assert_eq!(texts("0 // 1"), ["0"]);
+        assert_eq!(texts("\"\" // \"x\""), [""]);
+        assert_eq!(texts(".h1 | .nope // .text"), ["Guide"]);
+    }
+
+    #[test]
+    fn test_alt_catches_missing_property_in_strict_mode() {
+        let doc = parse_markdown("# Guide\n");
+        let query = parse(".h1 | .nope // \"fallback\"").unwrap();
+        let results = Engine::new(&doc).strict(true).execute(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].to_text(), "fallback");
+    }
+
+    #[test]
+    fn test_engine_builder() {
+        let doc = parse_markdown("# Guide\n## Install\n");
+        let double = Function::new(
+            |args, _ctx| match &args[0] {
+                Value::Number(n) => Ok(vec![Value::Number(n * 2.0)]),
+                _ => Ok(vec![Value::Null]),
+            },
+            0..=0,
+        );
+
+        let mut engine = Engine::builder()
+            .function("double", double)
+            .alias("twice", "double")
+            .strict(true)
+            .build(&doc);
+        let results = engine.execute(&parse(".h2 | .level | twice").unwrap()).unwrap();
+        assert_eq!(results[0].to_text(), "4");
+        // Built-ins are still there, and options were applied
+        assert!(engine.execute(&parse(".h1 | text | upper").unwrap()).is_ok());
+        assert!(engine.execute(&parse(".h1 | .nope").unwrap()).is_err());
+
+        // Functions can also be added to an existing engine
+        engine.register_function(
+            "one",
+            Function::new(|_args, _ctx| Ok(vec![Value::Number(1.0)]), 0..=0),
+        );
+        let results = engine.execute(&parse("one").unwrap()).unwrap();
+        assert_eq!(results[0].to_text(), "1");
+    }
+
+    #[test]
+    fn test_code_block_lines() {
+        let md = "# Doc\n\n```rust\nfn main() {}\n```\n\n- item\n\n  ```sh\n  ls\n  ```\n\n```\n```\n\n    indented\n";
+        let lines: Vec<String> = eval(md, ".code | [.start_line, .end_line]")
+            .iter()
+            .map(|v| v.to_text().replace('\n', "-"))
+            .collect();
+
+        // Empty blocks are skipped; nested ones keep their own lines
+        assert_eq!(lines, ["3-5", "9-11", "16-16"]);
+
+        // Quoted code counts too, at its lines in the document
+        let md = "# Doc\n\n> Run:\n>\n> ```sh\n> ls\n> ```\n>\n> > ```\n> > pwd\n> > ```\n";
+        let lines: Vec<String> = eval(md, ".code | [.start_line, .end_line]")
+            .iter()
+            .map(|v| v.to_text().replace('\n', "-"))
+            .collect();
+        assert_eq!(lines, ["5-7", "9-11"]);
+    }
+
+    #[test]
+    fn test_map_values() {
+        let md = "# Guide\n## Install\n## Usage\n### Flags\n";
+        let plain = |query: &str| -> String {
+            crate::query::format_output(&eval(md, query), crate::query::OutputFormat::Plain)
+        };
+
+        assert_eq!(plain(". | levels | map_values(. * 10)"), "h1: 10\nh2: 20\nh3: 10");
+        assert_eq!(
+            plain(r#"[.h] | group_into("level") | map_values(length)"#),
+            "h1: 1\nh2: 2\nh3: 1"
+        );
+        // No output drops the key
+        assert_eq!(
+            plain(". | levels | map_values(select(. > 1))"),
+            "h2: 2"
+        );
+        // Arrays keep their order; elements without output are dropped
+        assert_eq!(plain("[1, 2, 3] | map_values(. + 1)"), "2\n3\n4");
+        assert_eq!(plain("[1, 2, 3] | map_values(select(. != 2))"), "1\n3");
+    }
+
+    #[test]
+    fn test_range() {
+        let texts = |query: &str| -> Vec<String> {
+            eval("# Title", query).iter().map(Value::to_text).collect()
+        };
+        assert_eq!(texts("range(3)"), vec!["0", "1", "2"]);
+        assert_eq!(texts("range(2; 5)"), vec!["2", "3", "4"]);
+        assert_eq!(texts("range(10; 0; -4)"), vec!["10", "6", "2"]);
+        assert!(texts("range(0)").is_empty());
+        assert_eq!(texts("[range(5)] | length"), vec!["5"]);
+    }
+
+    #[test]
+    fn test_nth_generator() {
+        let texts = |query: &str| -> Vec<String> {
+            eval("# Title", query).iter().map(Value::to_text).collect()
+        };
+        assert_eq!(texts("nth(2; range(100))"), vec!["2"]);
+        assert_eq!(texts("nth(0; range(5) | . * 10)"), vec!["0"]);
+        assert_eq!(texts("nth(3; range(1; 10; 2))"), vec!["7"]);
+        assert_eq!(texts("nth(4; 0 | recurse(. + 1))"), vec!["4"]);
+        assert!(texts("nth(5; range(3))").is_empty());
+
+        // The single-argument form still indexes the input
+        let md = "# A\n## B\n## C";
+        let last = eval(md, "[.h] | nth(-1) | text");
+        assert_eq!(last[0].to_text(), "C");
+
+        // Stops the generator once reached instead of materializing it
+        let doc = parse_markdown("# Title");
+        let limits = Limits {
+            max_output: 10,
+            ..Limits::default()
+        };
+        let query = parse("nth(2; range(1000000000))").unwrap();
+        let results = Engine::new(&doc).with_limits(limits).execute(&query).unwrap();
+        assert_eq!(results[0].to_text(), "2");
+        let query = parse("[range(100)] | length").unwrap();
+        assert!(Engine::new(&doc).with_limits(limits).execute(&query).is_err());
+
+        let query = parse("nth(-1; range(3))").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            QueryErrorKind::TypeError { expected: "non-negative index", .. }
+        ));
+    }
+
+    #[test]
+    fn test_sort_and_group_by_are_type_aware() {
+        let md = "# A\n## B\n### C\n## D\n# E\n";
+        let texts = |value: &Value| match value {
+            Value::Array(items) => items.iter().map(|v| v.to_text()).collect::<Vec<_>>(),
+            other => panic!("expected array, got {:?}", other),
+        };
+
+        // null < false < true < numbers (numerically) < strings
+        let results = eval(md, r#"[3, 10, 2, "b", "a", true, false, null, "10"] | sort"#);
+        assert_eq!(
+            texts(&results[0]),
+            ["", "false", "true", "2", "3", "10", "10", "a", "b"]
+        );
+        let Value::Array(sorted) = &results[0] else { unreachable!() };
+        assert!(matches!(sorted[0], Value::Null));
+        assert!(matches!(sorted[5], Value::Number(_)));
+        assert!(matches!(sorted[6], Value::String(_)));
+
+        // Keys compare numerically, not as text
+        let results = eval(md, "[10, 2, 1] | sort_by(.)");
+        assert_eq!(texts(&results[0]), ["1", "2", "10"]);
+        let results = eval(md, r#"[.h] | sort_by("level")"#);
+        assert_eq!(texts(&results[0]), ["A", "E", "B", "D", "C"]);
+        // Stable for equal keys
+        let results = eval(md, "[3, 1, 2] | sort_by(. > 1)");
+        assert_eq!(texts(&results[0]), ["1", "3", "2"]);
+
+        // Grouping by a boolean predicate: false group first
+        let results = eval(md, "[.h] | group_by(.level > 1)");
+        let Value::Array(groups) = &results[0] else {
+            panic!("expected array of groups, got {:?}", results[0]);
+        };
+        assert_eq!(groups.len(), 2);
+        assert_eq!(texts(&groups[0]), ["A", "E"]);
+        assert_eq!(texts(&groups[1]), ["B", "C", "D"]);
+
+        // Keys of different types never share a group
+        let results = eval(md, r#"[1, "1", 1, true, "true"] | group_by(.) | length"#);
+        assert_eq!(results[0].to_text(), "4");
+    }
+
+    #[test]
+    fn test_blocks_come_from_the_section_cache() {
+        let md = "Intro.\n\n# A\n\n    indented code\n\n# B\n\n```sh\nls\n```\n";
+        let doc = parse_markdown(md);
+        let mut engine = Engine::new(&doc);
+        assert_eq!(doc.parsed_section_count(), 2);
+
+        let results = engine.execute(&parse(".code | .content").unwrap()).unwrap();
+        assert_eq!(results, [Value::String("indented code".into()), Value::String("ls".into())]);
+        let results = engine.execute(&parse("[.code | .start_line]").unwrap()).unwrap();
+        assert_eq!(results, vec![Value::Array(vec![Value::Number(5.0), Value::Number(9.0)])]);
+        let results = engine.execute(&parse(".p | text").unwrap()).unwrap();
+        assert_eq!(results, vec![Value::String("Intro.".into())]);
+    }
+
+    #[test]
+    fn test_special_builtins_can_be_replaced() {
+        let doc = parse_markdown("# A\n");
+        let name_of = |name: &'static str| {
+            Function::new(move |_args, _ctx| Ok(vec![Value::String(name.into())]), 0..=3)
+        };
+        let mut engine = Engine::builder()
+            .function("recurse", name_of("recurse"))
+            .function("range", name_of("range"))
+            .function("walk", name_of("walk"))
+            .function("nth", name_of("nth"))
+            .function("any", name_of("any"))
+            .build(&doc);
+
+        for (query, name) in [
+            ("[1, [2]] | recurse", "recurse"),
+            ("range(3)", "range"),
+            ("[1] | walk(.)", "walk"),
+            ("nth(0; 1, 2)", "nth"),
+            ("[1] | any(. > 0)", "any"),
+        ] {
+            let results = engine.execute(&parse(query).unwrap()).unwrap();
+            assert_eq!(results, vec![Value::String(name.into())], "{query}");
+        }
+
+        // Including where their outputs are streamed
+        let mut engine = Engine::builder().function("range", name_of("range")).build(&doc);
+        let results = engine.execute(&parse("nth(0; range(3))").unwrap()).unwrap();
+        assert_eq!(results, vec![Value::String("range".into())]);
+
+        // The built-ins are still listed and suggested
+        let err = Engine::new(&doc).execute(&parse("wakl(.)").unwrap()).unwrap_err();
+        assert!(err.suggestions.contains(&"walk".to_string()));
+    }
+
+    #[test]
+    fn test_per_item_builtins_are_registered() {
+        let doc = parse_markdown("# A\n## B\n");
+
+        // Misspellings suggest them like any other function
+        let err = Engine::new(&doc).execute(&parse("sort_bi(.)").unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::UnknownFunction(_)));
+        assert!(err.suggestions.contains(&"sort_by".to_string()));
+        let err = Engine::new(&doc).execute(&parse("group_bi(.)").unwrap()).unwrap_err();
+        assert!(err.suggestions.contains(&"group_by".to_string()));
+        let err = Engine::new(&doc).execute(&parse("map_valus(.)").unwrap()).unwrap_err();
+        assert!(err.suggestions.contains(&"map_values".to_string()));
+
+        // A custom registration replaces them, and its argument is evaluated
+        // on the input like any other function's
+        let arg = Function::new(|args, _ctx| Ok(vec![args[1].clone()]), 1..=1);
+        let mut engine = Engine::builder().function("sort_by", arg).build(&doc);
+        let results = engine.execute(&parse("[2, 1] | sort_by(length)").unwrap()).unwrap();
+        assert_eq!(results, vec![Value::Number(2.0)]);
+    }
+
+    #[test]
+    fn test_heading_links() {
+        let md = "# Guide\n## See [docs](/docs) and [site](https://example.com)\n";
+
+        let results = eval(md, ".h2 | .title_links | length");
+        assert_eq!(results[0].to_text(), "2");
+        let results = eval(md, ".h2 | .title_links | first | .url");
+        assert_eq!(results[0].to_text(), "/docs");
+        let results = eval(md, ".h2 | .title_links | last | .type");
+        assert_eq!(results[0].to_text(), "external");
+        let results = eval(md, ".h2 | .text");
+        assert_eq!(results[0].to_text(), "See docs and site");
+
+        let results = eval(md, ".h1 | .title_links | length");
+        assert_eq!(results[0].to_text(), "0");
+    }
+
+    #[test]
+    fn test_html_and_uri_escape() {
+        let text = |query: &str| eval("# Title", query)[0].to_text();
+
+        assert_eq!(
+            text(r#""<a href=\"x\">Tom & Jerry's</a>" | @html"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(text(r#""plain" | html_escape"#), "plain");
+
+        assert_eq!(text(r#""docs/my file.md" | @uri"#), "docs%2Fmy%20file.md");
+        assert_eq!(text(r#""a-b_c.d~e" | uri_escape"#), "a-b_c.d~e");
+        assert_eq!(text(r#""ö?&=" | @uri"#), "%C3%B6%3F%26%3D");
+    }
+
+    #[test]
+    fn test_base64_and_json_round_trip() {
+        let text = |query: &str| eval("# Title", query)[0].to_text();
+
+        assert_eq!(text(r#""hello, wörld" | base64"#), "aGVsbG8sIHfDtnJsZA==");
+        assert_eq!(text(r#""hello, wörld" | @base64 | @base64d"#), "hello, wörld");
+        // Padding is optional when decoding
+        assert_eq!(text(r#""aGk" | base64d"#), "hi");
+
+        assert_eq!(
+            text(r#"{a: 1, b: [true, null, "x"]} | tojson"#),
+            r#"{"a":1,"b":[true,null,"x"]}"#
+        );
+        assert_eq!(
+            text(r#"{a: 1.5, b: [true, null, "x"]} | @json | fromjson | .b | last"#),
+            "x"
+        );
+        assert_eq!(text(r#"{n: 1.5} | @json | fromjson | .n"#), "1.5");
+        assert_eq!(text(".h1 | tojson | fromjson | .text"), "Title");
+
+        let doc = parse_markdown("# Title");
+        for query in [r#""{not json" | fromjson"#, r#""not base64!" | base64d"#] {
+            let err = Engine::new(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(matches!(err.kind, QueryErrorKind::InvalidInput { .. }), "{}", query);
+        }
+        let err = Engine::new(&doc)
+            .execute(&parse(r#""{not json" | fromjson"#).unwrap())
+            .unwrap_err();
+        assert!(err.kind.to_string().starts_with("Invalid JSON: "));
+    }
+
+    #[test]
+    fn test_walk_and_paths() {
+        let text = |query: &str| eval("# Title", query)[0].to_text();
+
+        assert_eq!(
+            text(r#"[1, [2, 3], {n: 4}] | walk(if type == "number" then . * 10 else . end) | tojson"#),
+            r#"[10,[20,30],{"n":40}]"#
+        );
+        // No output drops the value
+        assert_eq!(text("[1, [2, 3]] | walk(select(. != 2)) | tojson"), "[1,[3]]");
+
+        assert_eq!(
+            text(r#"{n: [1, {b: 2}]} | [paths] | tojson"#),
+            r#"[["n"],["n",0],["n",1],["n",1,"b"]]"#
+        );
+        assert_eq!(text("[1, 2] | [recurse] | length"), "3");
+    }
+
+    #[test]
+    fn test_deeply_nested_values_hit_the_depth_limit() {
+        // 10,000 levels: [[[...[]...]]]
+        fn deep(_args: &[Value], _ctx: &EvalContext) -> Result<Vec<Value>, QueryError> {
+            let mut value = Value::Array(Vec::new());
+            for _ in 0..10_000 {
+                value = Value::Array(vec![value]);
+            }
+            Ok(vec![value])
+        }
+
+        let doc = parse_markdown("# Title");
+        let engine = || Engine::builder().function("deep", Function::new(deep, 0..=0));
+
+        let queries = ["deep | walk(.) | type", "deep | [paths] | length", "deep | [recurse] | length"];
+        for query in queries {
+            let err = engine().build(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(
+                matches!(err.kind, QueryErrorKind::ResourceLimit { .. }),
+                "{}: {:?}",
+                query,
+                err.kind
+            );
+        }
+
+        // With the limit raised, walking completes without overflowing the stack
+        let limits = Limits {
+            max_depth: 20_000,
+            ..Limits::default()
+        };
+        let query = parse("deep | walk(length)").unwrap();
+        let results = engine().limits(limits).build(&doc).execute(&query).unwrap();
+        assert_eq!(results[0].to_text(), "1");
+    }
+
+    #[test]
+    fn test_shift_level() {
+        let md = "# Guide\n## Setup\n\nSteps.\n\n###### Detail\n";
+        let text = |query: &str| eval(md, query)[0].to_text();
+
+        assert_eq!(text(".h2 | shift_level(1) | .level"), "3");
+        assert_eq!(text(".h2 | shift_level(-5) | .level"), "1");
+        assert_eq!(
+            text(".h2 | shift_level(1) | md"),
+            "### Setup\n\nSteps.\n\n###### Detail\n"
+        );
+        assert_eq!(text(".h6 | shift_level(2) | .level"), "6");
+
+        let doc = parse_markdown(md);
+        let query = parse("\"x\" | shift_level(1)").unwrap();
+        let err = Engine::new(&doc).execute(&query).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::TypeError { expected: "heading", .. }));
+    }
+
+    #[test]
+    fn test_join_flattens_nested_arrays() {
+        let text = |query: &str| eval("# Title", query)[0].to_text();
+
+        assert_eq!(text(r#"["a", 1, null, true] | join("-")"#), "a-1--true");
+        assert_eq!(text(r#"[["a", "b"], "c", [["d"]] | first] | join(", ")"#), "a, b, c, d");
+        assert_eq!(text(r#"[[1, 2], [3]] | join(" ")"#), "1 2 3");
+
+        let doc = parse_markdown("# Title");
+        for query in [r#"[{n: 1}] | join(",")"#, r#"[[[1]]] | join(",")"#] {
+            let err = Engine::new(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(
+                matches!(err.kind, QueryErrorKind::TypeError { expected: "scalar to join", .. }),
+                "{}: {:?}",
+                query,
+                err.kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let doc = parse_markdown("# Guide\n## Install\n## Usage\n");
+        let mut engine = Engine::new(&doc);
+        let render = |engine: &mut Engine, template| crate::query::render_template(engine, template);
+
+        assert_eq!(
+            render(&mut engine, "{{.h1 | text}} has {{ [.h2] | count }} sections.\n").unwrap(),
+            "Guide has 2 sections.\n"
+        );
+        // First result only; no results leave nothing behind
+        assert_eq!(render(&mut engine, "[{{ .h2 }}|{{ .h5 }}]").unwrap(), "[Install|]");
+        // Unclosed braces are plain text
+        assert_eq!(render(&mut engine, "a {{ b").unwrap(), "a {{ b");
+
+        let err = render(&mut engine, "ok {{ .h2 | nope }}").unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::UnknownFunction(_)));
+        assert_eq!(err.note.as_deref(), Some("in template placeholder {{ .h2 | nope }}"));
+    }
+
+    #[test]
+    fn test_comma_merges_element_kinds() {
+        let md = "# Doc\n[a](/a) ![i](i.png) [b](https://b.example)\n\n## More\n![j](j.png)\n";
+        let count = |query: &str| eval(md, query)[0].to_text();
+
+        assert_eq!(count("[.link] | count"), "2");
+        assert_eq!(count("[.img] | count"), "2");
+        assert_eq!(count("[.link, .img] | count"), "4");
+        assert_eq!(count("[(.link, .img)] | count"), "4");
+        assert_eq!(count("(.link, .img) | [.] | count"), "1");
+
+        // Top-level commas concatenate in order
+        let kinds: Vec<String> =
+            eval(md, ".link, .img").iter().map(|v| v.kind().to_string()).collect();
+        assert_eq!(kinds, vec!["link", "link", "image", "image"]);
+
+        // A group pipes every kind; kinds with no matches add nothing
+        let texts: Vec<String> =
+            eval(md, "(.table, .img, .link) | text").iter().map(Value::to_text).collect();
+        assert_eq!(texts, vec!["i", "j", "a", "b"]);
+        assert_eq!(count("[(.table, .img)] | first | text"), "i");
+    }
+
+    #[test]
+    fn test_equality_respects_type() {
+        let len = |query: &str| match eval("# Title", query).as_slice() {
+            [Value::Array(items)] => items.len(),
+            other => panic!("expected array, got {:?}", other),
+        };
+        assert_eq!(len(r#"[1, "1"] | unique"#), 2);
+        assert_eq!(len("[{a: 1}, {a: 1}] | unique"), 1);
+        assert_eq!(len("[{a: 1, b: 2}, {b: 2, a: 1}, [1, 2], [1, 2]] | unique"), 2);
+
+        assert_eq!(eval("# Title", r#"1 == "1""#), vec![Value::Bool(false)]);
+        assert_eq!(eval("# Title", "{a: [1, 2]} == {a: [1, 2]}"), vec![Value::Bool(true)]);
+        assert_eq!(eval("# Title", "[1, 2] != [2, 1]"), vec![Value::Bool(true)]);
+        assert_eq!(eval("# Title", ".h1 | .text == \"Title\""), vec![Value::Bool(true)]);
+        assert_eq!(eval("# Title", ".h1 == \"Title\""), vec![Value::Bool(true)]);
+        assert_eq!(eval("# Title", "\"Other\" != .h1"), vec![Value::Bool(true)]);
+        assert_eq!(eval("# Title", "[.h1] == [\"Title\"]"), vec![Value::Bool(false)]);
+
+        let md: String = (0..4000).map(|i| format!("# {}\n\n", i % 2000)).collect();
+        match eval(&md, "[.h | text] | unique").as_slice() {
+            [Value::Array(items)] => assert_eq!(items.len(), 2000),
+            other => panic!("expected array, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_any_all_with_predicates() {
+        let check = |query: &str| match eval("# Title", query).as_slice() {
+            [Value::Bool(b)] => *b,
+            other => panic!("expected bool, got {:?}", other),
+        };
+        assert!(check("{a: 1, b: 0} | any(. > 0)"));
+        assert!(!check("{a: 1, b: 0} | all(. > 0)"));
+        assert!(check("{a: 1, b: 2} | all(. > 0)"));
+        assert!(!check("{a: 1, b: 0} | any(. > 5)"));
+
+        assert!(check("[1, 5, 3] | any(. > 4)"));
+        assert!(!check("[1, 5, 3] | all(. > 4)"));
+        assert!(!check("[] | any(. > 0)"));
+        assert!(check("[] | all(. > 0)"));
+
+        // Without a predicate the values themselves are tested
+        assert!(check("{a: 0, b: 2} | any"));
+        assert!(!check("{a: 0, b: 2} | all"));
+    }
+
+    #[test]
+    fn test_execute_with_input() {
+        let doc = parse_markdown("# Title");
+        let mut engine = Engine::new(&doc);
+        let query = parse("upper").unwrap();
+
+        let results = engine.execute_with_input(&query, Value::String("abc".into())).unwrap();
+        assert_eq!(results, vec![Value::String("ABC".into())]);
+
+        // Without an input, `.` is the document again
+        let results = engine.execute(&parse(".h1 | text").unwrap()).unwrap();
+        assert_eq!(results, vec![Value::String("Title".into())]);
+    }
+
+    #[test]
+    fn test_task_progress() {
+        let md = "# Todo\n\n- [x] a\n- [ ] b\n- [x] c\n- plain\n\n## More\n\n- [ ] d\n";
+        let progress = |query: &str| match eval(md, query).as_slice() {
+            [Value::Object(obj)] => ["done", "total", "percent"].map(|k| obj[k].to_text()),
+            other => panic!("expected object, got {:?}", other),
+        };
+
+        assert_eq!(progress("[.list] | first | task_progress"), ["2", "3", "67"]);
+        assert_eq!(progress("[.list] | task_progress"), ["2", "4", "50"]);
+        assert_eq!(progress("[] | task_progress"), ["0", "0", "0"]);
+
+        // Nested tasks count too, as with --tasks
+        let nested = eval("- [x] a\n- [ ] b\n  - [x] c\n  - [ ] d\n", ".list | task_progress");
+        let [Value::Object(obj)] = nested.as_slice() else {
+            panic!("expected object, got {:?}", nested);
+        };
+        assert_eq!(["done", "total"].map(|k| obj[k].to_text()), ["2", "4"]);
+
+        let doc = parse_markdown(md);
+        let err = Engine::new(&doc).execute(&parse(".h1 | task_progress").unwrap()).unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::TypeError { .. }));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let md = "```rust\na\n```\n\n```sh\nb\n```\n\n```rust\nc\n```\n";
+        assert_eq!(
+            eval(md, ". | langs | histogram(16)"),
+            vec![Value::String("rust  ████████ 2\nsh    ████ 1".into())]
+        );
+        assert_eq!(eval(md, "{} | histogram(16)"), vec![Value::String(String::new())]);
+        // 80 columns by default: 4 + 2 + 72 + 1 + 1
+        let chart = eval(md, ". | langs | histogram")[0].to_text();
+        assert_eq!(chart.lines().next().unwrap().chars().count(), 80);
+
+        // The terminal's width when there is one; an argument still wins
+        let doc = parse_markdown(md);
+        let mut engine = Engine::new(&doc).terminal_width(Some(16));
+        let chart = engine.execute(&parse(". | langs | histogram").unwrap()).unwrap();
+        assert_eq!(chart, vec![Value::String("rust  ████████ 2\nsh    ████ 1".into())]);
+        let chart = engine.execute(&parse(". | langs | histogram(80)").unwrap()).unwrap();
+        assert_eq!(chart[0].to_text().lines().next().unwrap().chars().count(), 80);
+
+        for query in [".code | histogram", "{a: \"x\"} | histogram", ". | langs | histogram(0)"] {
+            let err = Engine::new(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(matches!(err.kind, QueryErrorKind::TypeError { .. }), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_select_compound_conditions() {
+        let md = "# Guide\n\n## Usage\n\n## Install\n\n### Use cases\n\n## User notes\n";
+        let texts = |query: &str| -> Vec<String> {
+            eval(md, query).iter().map(|v| v.to_text()).collect()
+        };
+
+        // A property comparison and a piped string predicate, in either order
+        for query in [
+            ".h | select(.level == 2 and .text | startswith(\"User\")) | text",
+            ".h | select(.text | startswith(\"User\") and .level == 2) | text",
+            ".h | select((.text | startswith(\"User\")) and .level == 2) | text",
+        ] {
+            assert_eq!(texts(query), vec!["User notes"], "{}", query);
+        }
+        assert_eq!(
+            texts(".h | select(.level == 3 or .text | endswith(\"all\")) | text"),
+            vec!["Install", "Use cases"]
+        );
+        assert_eq!(
+            texts(".h | select(.level > 1 and .text | contains(\"Us\") and .level < 3) | text"),
+            vec!["Usage", "User notes"]
+        );
+        // Predicates on the element itself still work alongside them
+        assert_eq!(
+            texts(".h2 | select(contains(\"Us\") and .text | endswith(\"age\")) | text"),
+            vec!["Usage"]
+        );
+    }
+
+    #[test]
+    fn test_indent_and_dedent() {
+        let md = "# Setup\n\n```sh\n    cargo build\n      --release\n```\n";
+        assert_eq!(
+            eval(md, ".code | content | dedent"),
+            vec![Value::String("cargo build\n  --release".into())]
+        );
+        assert_eq!(
+            eval(md, ".code | content | dedent | indent(2)"),
+            vec![Value::String("  cargo build\n    --release".into())]
+        );
+        assert_eq!(eval(md, "null | dedent"), vec![Value::Null]);
+
+        let doc = parse_markdown(md);
+        for query in [".code | content | indent(-1)", ".code | content | indent(\"2\")"] {
+            let err = Engine::new(&doc).execute(&parse(query).unwrap()).unwrap_err();
+            assert!(matches!(err.kind, QueryErrorKind::TypeError { .. }), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_fused_select_matches_unfused() {
+        let md = "# Guide\n\n## Install\n\n```sh\nmake\n```\n\n### From source\n\n\
+                  ## Usage\n\n```rust\nfn main() {}\n```\n\nSee [docs](https://x.dev).\n";
+        let doc = parse_markdown(md);
+        let predicates = [
+            ".level == 2",
+            "contains(\"s\")",
+            ".lang == \"rust\"",
+            ".text | startswith(\"I\") and .level > 1",
+            "(.level, .text)",
+            "empty",
+        ];
+        let sources = [".h", ".h2", ".code", ".link", ".h >> .code", ".h1 > .h2", ".h2 >> .h"];
+
+        for source in sources {
+            for predicate in predicates {
+                // Parenthesized, `select` is no longer a pipe stage of its own,
+                // so it runs unfused on the collected elements
+                let fused = parse(&format!("{} | select({})", source, predicate)).unwrap();
+                let unfused = parse(&format!("{} | (select({}))", source, predicate)).unwrap();
+
+                let mut engine = Engine::new(&doc);
+                assert!(engine.fused_select(&fused.expressions[0].stages).is_some());
+                assert!(engine.fused_select(&unfused.expressions[0].stages).is_none());
+                assert_eq!(
+                    engine.execute(&fused).unwrap(),
+                    engine.execute(&unfused).unwrap(),
+                    "{} | select({})",
+                    source,
+                    predicate
+                );
+
+                // Inside a nested pipe too
+                let nested = format!("[{} | select({})] | length", source, predicate);
+                let nested_unfused = format!("[{} | (select({}))] | length", source, predicate);
+                assert_eq!(
+                    engine.execute(&parse(&nested).unwrap()).unwrap(),
+                    engine.execute(&parse(&nested_unfused).unwrap()).unwrap()
+                );
+            }
+        }
+
+        // Filters and indexes need every element first, so they aren't fused
+        let engine = Engine::new(&doc);
+        for query in [".h[Install] | select(.level > 1)", ".h[0] | select(true)"] {
+            let query = parse(query).unwrap();
+            assert!(engine.fused_select(&query.expressions[0].stages).is_none());
+        }
+
+        // Neither is a custom `select`, which may not filter at all
+        let keep_all = Function::new(|args, _ctx| Ok(vec![args[0].clone()]), 1..=1);
+        let mut engine = Engine::builder().function("select", keep_all).build(&doc);
+        let query = parse(".h | select(false)").unwrap();
+        assert!(engine.fused_select(&query.expressions[0].stages).is_none());
+        assert_eq!(engine.execute(&query).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_frontmatter_selector() {
+        let md = "---\ntitle: Post\ntags:\n  - a\n  - b\nextra:\n  draft: true\n---\n# Post\n";
+        assert_eq!(eval(md, ".frontmatter | .title"), vec![Value::String("Post".into())]);
+        assert_eq!(eval(md, ".fm | .tags | count"), vec![Value::Number(2.0)]);
+        assert_eq!(eval(md, ".fm | .extra | .draft"), vec![Value::Bool(true)]);
+        assert_eq!(eval(md, ".fm | keys_unsorted | join(\",\")")[0].to_text(), "title,tags,extra");
+        // The closing `---` doesn't turn the block into a heading
+        assert_eq!(eval(md, "[.h] | count"), vec![Value::Number(1.0)]);
+
+        let toml = "+++\ntitle = \"Post\"\nweight = 2\n+++\n# Post\n";
+        assert_eq!(eval(toml, ".fm | .weight"), vec![Value::Number(2.0)]);
+
+        assert!(eval("# No front matter", ".frontmatter").is_empty());
+
+        let doc = parse_markdown("---\ntitle: [unclosed\n---\n# Post\n");
+        let err = Engine::new(&doc).execute(&parse(".frontmatter").unwrap()).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            QueryErrorKind::InvalidInput { format: "YAML front matter", .. }
+        ));
+    }
 }
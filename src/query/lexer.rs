@@ -12,6 +12,7 @@ pub enum TokenKind {
     Dot,        // .
     Pipe,       // |
     Comma,      // ,
+    Semicolon,  // ;
     Colon,      // :
     LBracket,   // [
     RBracket,   // ]
@@ -67,6 +68,7 @@ impl TokenKind {
             TokenKind::Dot => "'.'",
             TokenKind::Pipe => "'|'",
             TokenKind::Comma => "','",
+            TokenKind::Semicolon => "';'",
             TokenKind::Colon => "':'",
             TokenKind::LBracket => "'['",
             TokenKind::RBracket => "']'",
@@ -300,6 +302,7 @@ impl<'a> Lexer<'a> {
             '.' => Token::new(TokenKind::Dot, Span::new(start, self.pos)),
             '|' => Token::new(TokenKind::Pipe, Span::new(start, self.pos)),
             ',' => Token::new(TokenKind::Comma, Span::new(start, self.pos)),
+            ';' => Token::new(TokenKind::Semicolon, Span::new(start, self.pos)),
             ':' => Token::new(TokenKind::Colon, Span::new(start, self.pos)),
             '[' => Token::new(TokenKind::LBracket, Span::new(start, self.pos)),
             ']' => Token::new(TokenKind::RBracket, Span::new(start, self.pos)),
@@ -389,6 +392,16 @@ impl<'a> Lexer<'a> {
 
             c if c.is_alphabetic() || c == '_' => self.read_identifier(start, c),
 
+            // Variables like `$ENV` are lexed as identifiers and resolved by name
+            '$' if self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') => {
+                self.read_identifier(start, '$')
+            }
+
+            // jq-style formats like `@base64`, registered as function aliases
+            '@' if self.peek().is_some_and(|c| c.is_alphabetic()) => {
+                self.read_identifier(start, '@')
+            }
+
             c => {
                 return Err(QueryError::new(
                     QueryErrorKind::UnexpectedChar(c),
@@ -557,6 +570,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_identifiers() {
+        assert_eq!(
+            tokenize_kinds(". | @base64"),
+            vec![
+                TokenKind::Dot,
+                TokenKind::Pipe,
+                TokenKind::Ident("@base64".into()),
+                TokenKind::Eof
+            ]
+        );
+    }
+
     #[test]
     fn test_keywords() {
         assert_eq!(
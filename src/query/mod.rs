@@ -13,13 +13,15 @@
 //!
 //! ## Example
 //!
-//! ```ignore
-//! use treemd::query::{Engine, Query};
-//! use treemd::parser::Document;
+//! ```
+//! use treemd::Document;
+//! use treemd::query::{self, Engine};
 //!
 //! let doc = Document::parse("# Hello\n## World");
-//! let engine = Engine::new(&doc);
-//! let results = engine.execute(".h2 | text")?;
+//! let query = query::parse(".h2 | text")?;
+//! let results = Engine::new(&doc).execute(&query)?;
+//! assert_eq!(results[0].to_text(), "World");
+//! # Ok::<(), treemd::query::QueryError>(())
 //! ```
 
 mod ast;
@@ -37,8 +39,9 @@ pub mod extractors;
 pub use ast::{Expr, Query};
 pub use ast::Span;
 pub use error::{QueryError, QueryErrorKind};
-pub use eval::{Engine, EvalContext};
+pub use eval::{Engine, EngineBuilder, EvalContext, Limits};
 pub use registry::{ExtractorFn, Function, FunctionRegistry, Registry};
+pub(crate) use registry::levenshtein;
 pub use value::{Value, ValueKind};
 
 use crate::parser::Document;
@@ -49,8 +52,11 @@ use crate::parser::Document;
 ///
 /// # Example
 ///
-/// ```ignore
+/// ```
+/// let doc = treemd::Document::parse("# Guide\n## Install\n## Usage");
 /// let results = treemd::query::execute(&doc, ".h2 | text")?;
+/// assert_eq!(results.len(), 2);
+/// # Ok::<(), treemd::query::QueryError>(())
 /// ```
 pub fn execute(doc: &Document, query_str: &str) -> Result<Vec<Value>, QueryError> {
     let query = parse(query_str)?;
@@ -66,6 +72,33 @@ pub fn parse(query_str: &str) -> Result<Query, QueryError> {
     parser::parse(&tokens, query_str)
 }
 
+/// Compile a query once to run it against many documents.
+///
+/// A [`Query`] doesn't borrow the source string or any document, so it can
+/// be kept and passed to [`execute_compiled`] or [`Engine::execute`] for each
+/// document, skipping the tokenize and parse steps of [`execute`].
+///
+/// # Example
+///
+/// ```
+/// use treemd::{Document, query};
+///
+/// let query = query::compile("[.h2] | count")?;
+/// for md in ["# A\n## B", "# C\n## D\n## E"] {
+///     let results = query::execute_compiled(&Document::parse(md), &query)?;
+///     println!("{}", results[0].to_text());
+/// }
+/// # Ok::<(), treemd::query::QueryError>(())
+/// ```
+pub fn compile(query_str: &str) -> Result<Query, QueryError> {
+    parse(query_str)
+}
+
+/// Execute a query compiled with [`compile`] against a document.
+pub fn execute_compiled(doc: &Document, query: &Query) -> Result<Vec<Value>, QueryError> {
+    Engine::new(doc).execute(query)
+}
+
 /// Create a new query engine with default configuration.
 pub fn engine(doc: &Document) -> Engine<'_> {
     Engine::new(doc)
@@ -73,25 +106,85 @@ pub fn engine(doc: &Document) -> Engine<'_> {
 
 /// Create a new query engine with a custom registry.
 ///
-/// This allows registering custom functions and extractors.
+/// The registry is used as-is, so start from [`Registry::with_builtins`] to
+/// keep the built-in functions. [`Engine::builder`] is usually more convenient.
 ///
 /// # Example
 ///
-/// ```ignore
-/// let mut registry = Registry::default();
-/// registry.register_function("custom_fn", my_function);
+/// ```
+/// use treemd::query::{Function, Registry, Value};
+///
+/// let doc = treemd::Document::parse("# Guide");
+/// let mut registry = Registry::with_builtins();
+/// registry.register_function(
+///     "answer",
+///     Function::new(|_args, _ctx| Ok(vec![Value::Number(42.0)]), 0..=0),
+/// );
 ///
-/// let engine = treemd::query::engine_with_registry(&doc, registry);
+/// let mut engine = treemd::query::engine_with_registry(&doc, registry);
+/// let results = engine.execute(&treemd::query::parse("answer")?)?;
+/// assert_eq!(results[0].to_text(), "42");
+/// # Ok::<(), treemd::query::QueryError>(())
 /// ```
 pub fn engine_with_registry(doc: &Document, registry: Registry) -> Engine<'_> {
     Engine::with_registry(doc, registry)
 }
 
+/// Fill `{{ query }}` placeholders in a template with query results.
+///
+/// Each placeholder is replaced by the text of the query's first result (or
+/// nothing if it has none); everything else is copied verbatim, including a
+/// `{{` that is never closed. Errors carry a note naming the placeholder.
+///
+/// # Example
+///
+/// ```
+/// let doc = treemd::Document::parse("# Guide\n## Install\n## Usage");
+/// let mut engine = treemd::query::engine(&doc);
+/// let text = treemd::query::render_template(
+///     &mut engine,
+///     "{{ .h1 | text }} has {{ [.h2] | count }} sections",
+/// )?;
+/// assert_eq!(text, "Guide has 2 sections");
+/// # Ok::<(), treemd::query::QueryError>(())
+/// ```
+pub fn render_template(engine: &mut Engine<'_>, template: &str) -> Result<String, QueryError> {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let query_str = rest[start + 2..start + 2 + len].trim();
+        rest = &rest[start + 2 + len + 2..];
+
+        let results = parse(query_str).and_then(|query| engine.execute(&query));
+        let results = results.map_err(|e| {
+            let note = format!("in template placeholder {{{{ {} }}}}", query_str);
+            if e.note.is_some() { e } else { e.with_note(note) }
+        })?;
+        if let Some(first) = results.first() {
+            output.push_str(&first.to_text());
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
 /// Format query results for output.
 pub fn format_output(values: &[Value], format: OutputFormat) -> String {
     output::format(values, format)
 }
 
+/// Convert a query result to JSON, writing whole numbers without a fraction
+/// as `tojson` does.
+pub fn to_json(value: &Value) -> serde_json::Value {
+    output::integral_numbers(output::value_to_json(value))
+}
+
 mod output;
 
 /// Output format for query results.
@@ -110,6 +203,8 @@ pub enum OutputFormat {
     Markdown,
     /// Tree structure with box-drawing
     Tree,
+    /// Plain text values separated by NUL bytes, for `xargs -0`
+    RawZero,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -123,6 +218,7 @@ impl std::str::FromStr for OutputFormat {
             "jsonl" | "jsonlines" | "ndjson" => Ok(Self::JsonLines),
             "md" | "markdown" => Ok(Self::Markdown),
             "tree" => Ok(Self::Tree),
+            "raw0" => Ok(Self::RawZero),
             _ => Err(format!("Unknown output format: {}", s)),
         }
     }
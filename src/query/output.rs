@@ -12,9 +12,22 @@ pub fn format(values: &[Value], format: OutputFormat) -> String {
         OutputFormat::JsonLines => format_json_lines(values),
         OutputFormat::Markdown => format_markdown(values),
         OutputFormat::Tree => format_tree(values),
+        OutputFormat::RawZero => format_raw_zero(values),
     }
 }
 
+/// Join each value's text with NUL bytes, like `find -print0`.
+///
+/// Values may contain spaces or newlines, so this is the safe way to feed
+/// results to `xargs -0`. There is no trailing separator.
+fn format_raw_zero(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_text())
+        .collect::<Vec<_>>()
+        .join("\0")
+}
+
 fn format_plain(values: &[Value]) -> String {
     values
         .iter()
@@ -99,6 +112,24 @@ fn format_plain_value(value: &Value) -> String {
                 .join("\n")
         }
         Value::Paragraph(p) => p.content.clone(),
+        Value::Details(d) => {
+            format!(
+                "<details{}>\n<summary>{}</summary>\n\n{}\n</details>",
+                if d.open { " open" } else { "" },
+                d.summary,
+                d.content
+            )
+        }
+        Value::Footnote(f) => {
+            let mut lines = f.content.lines();
+            let first = format!("[^{}]: {}", f.label, lines.next().unwrap_or(""));
+            std::iter::once(first)
+                .chain(lines.map(|line| {
+                    if line.is_empty() { String::new() } else { format!("    {}", line) }
+                }))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
         Value::Document(d) => {
             format!(
                 "Document: {} headings, {} words",
@@ -139,10 +170,28 @@ fn format_json_lines(values: &[Value]) -> String {
         .join("\n")
 }
 
-fn value_to_json(value: &Value) -> serde_json::Value {
+/// Write whole numbers without a fraction, as jq does (`1`, not `1.0`).
+pub(crate) fn integral_numbers(json: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match json {
+        Json::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Json::from(f as i64),
+            _ => Json::Number(n),
+        },
+        Json::Array(items) => Json::Array(items.into_iter().map(integral_numbers).collect()),
+        Json::Object(map) => {
+            Json::Object(map.into_iter().map(|(k, v)| (k, integral_numbers(v))).collect())
+        }
+        other => other,
+    }
+}
+
+pub(crate) fn value_to_json(value: &Value) -> serde_json::Value {
     match value {
         Value::Null => serde_json::Value::Null,
         Value::Bool(b) => serde_json::Value::Bool(*b),
+        // JSON has no NaN or infinity
+        Value::Number(n) if !n.is_finite() => serde_json::Value::Null,
         Value::Number(n) => serde_json::json!(n),
         Value::String(s) => serde_json::Value::String(s.clone()),
         Value::Array(a) => {
@@ -161,6 +210,7 @@ fn value_to_json(value: &Value) -> serde_json::Value {
                 "level": h.level,
                 "text": h.text,
                 "line": h.line,
+                "number": h.number,
             })
         }
         Value::Code(c) => {
@@ -178,6 +228,8 @@ fn value_to_json(value: &Value) -> serde_json::Value {
                 "text": l.text,
                 "url": l.url,
                 "link_type": l.link_type.as_str(),
+                "line": l.line,
+                "offset": l.offset,
             })
         }
         Value::Image(i) => {
@@ -186,6 +238,8 @@ fn value_to_json(value: &Value) -> serde_json::Value {
                 "alt": i.alt,
                 "src": i.src,
                 "title": i.title,
+                "line": i.line,
+                "offset": i.offset,
             })
         }
         Value::Table(t) => {
@@ -193,6 +247,8 @@ fn value_to_json(value: &Value) -> serde_json::Value {
                 "type": "table",
                 "headers": t.headers,
                 "rows": t.rows,
+                "line": t.line,
+                "offset": t.offset,
             })
         }
         Value::List(l) => {
@@ -205,6 +261,8 @@ fn value_to_json(value: &Value) -> serde_json::Value {
                         "checked": i.checked,
                     })
                 }).collect::<Vec<_>>(),
+                "line": l.line,
+                "offset": l.offset,
             })
         }
         Value::Blockquote(b) => {
@@ -217,6 +275,26 @@ fn value_to_json(value: &Value) -> serde_json::Value {
             serde_json::json!({
                 "type": "paragraph",
                 "content": p.content,
+                "line": p.line,
+                "offset": p.offset,
+            })
+        }
+        Value::Details(d) => {
+            serde_json::json!({
+                "type": "details",
+                "summary": d.summary,
+                "content": d.content,
+                "open": d.open,
+            })
+        }
+        Value::Footnote(f) => {
+            serde_json::json!({
+                "type": "footnote",
+                "label": f.label,
+                "content": f.content,
+                "references": f.references,
+                "line": f.line,
+                "offset": f.offset,
             })
         }
         Value::Document(d) => {
@@ -322,6 +400,8 @@ mod tests {
             content: String::new(),
             raw_md: "## Test".to_string(),
             index: 0,
+            number: String::new(),
+            title_links: Vec::new(),
         });
 
         let output = format(&[heading], OutputFormat::Plain);
@@ -335,4 +415,27 @@ mod tests {
         assert!(output.contains("42"));
         assert!(output.contains("hello"));
     }
+
+    #[test]
+    fn test_format_json_non_finite_numbers_as_null() {
+        let values = vec![Value::Array(vec![
+            Value::Number(f64::NAN),
+            Value::Number(f64::INFINITY),
+            Value::Number(1.5),
+        ])];
+        let output = format(&values, OutputFormat::Json);
+        assert_eq!(output, "[null,null,1.5]");
+        assert!(serde_json::from_str::<serde_json::Value>(&output).is_ok());
+    }
+
+    #[test]
+    fn test_format_raw_zero() {
+        let values = vec![
+            Value::String("two words".to_string()),
+            Value::String("line one\nline two".to_string()),
+            Value::Number(3.0),
+        ];
+        let output = format(&values, OutputFormat::RawZero);
+        assert_eq!(output.as_bytes(), b"two words\0line one\nline two\x003");
+    }
 }
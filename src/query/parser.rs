@@ -117,7 +117,15 @@ fn parse_piped_expr(p: &mut Parser) -> Result<PipedExpr, QueryError> {
 }
 
 fn parse_hierarchy_expr(p: &mut Parser) -> Result<Expr, QueryError> {
-    let mut expr = parse_or_expr(p)?;
+    parse_hierarchy_of(p, parse_or_expr)
+}
+
+/// Hierarchy operators (`>` and `>>`) between operands parsed by `operand`
+fn parse_hierarchy_of(
+    p: &mut Parser,
+    operand: fn(&mut Parser) -> Result<Expr, QueryError>,
+) -> Result<Expr, QueryError> {
+    let mut expr = operand(p)?;
 
     // Handle hierarchy operators (> and >>)
     loop {
@@ -130,7 +138,7 @@ fn parse_hierarchy_expr(p: &mut Parser) -> Result<Expr, QueryError> {
         };
 
         let start_span = expr.span();
-        let child = parse_or_expr(p)?;
+        let child = operand(p)?;
         let end_span = child.span();
 
         expr = Expr::Hierarchy {
@@ -428,6 +436,30 @@ fn parse_postfix_expr(p: &mut Parser) -> Result<Expr, QueryError> {
     Ok(expr)
 }
 
+/// Tokens that can follow a bare `.` (identity), e.g. `. + 1` or `if . then`.
+fn is_operator_or_keyword(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Eq
+            | TokenKind::Ne
+            | TokenKind::Lt
+            | TokenKind::Le
+            | TokenKind::Ge
+            | TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Star
+            | TokenKind::Slash
+            | TokenKind::Percent
+            | TokenKind::SlashSlash
+            | TokenKind::And
+            | TokenKind::Or
+            | TokenKind::Then
+            | TokenKind::Elif
+            | TokenKind::Else
+            | TokenKind::End
+    )
+}
+
 fn parse_primary_expr(p: &mut Parser) -> Result<Expr, QueryError> {
     let span = p.current_span();
 
@@ -443,6 +475,8 @@ fn parse_primary_expr(p: &mut Parser) -> Result<Expr, QueryError> {
             || p.check(&TokenKind::GtGt)
             || p.check(&TokenKind::RParen)
             || p.check(&TokenKind::RBracket)
+            || p.check(&TokenKind::RBrace)
+            || is_operator_or_keyword(p.current_kind())
         {
             // Just a dot - identity
             return Ok(Expr::Identity);
@@ -500,13 +534,27 @@ fn parse_primary_expr(p: &mut Parser) -> Result<Expr, QueryError> {
         ));
     }
 
-    // Parenthesized expression
+    // Parenthesized expression; commas inside merge the outputs, so
+    // `(.link, .img) | text` pipes both kinds of element
     if p.matches(&[TokenKind::LParen]) {
-        let expr = parse_piped_expr(p)?;
+        let mut alternatives = vec![Expr::from(parse_piped_expr(p)?)];
+        while p.matches(&[TokenKind::Comma]) {
+            alternatives.push(Expr::from(parse_piped_expr(p)?));
+        }
         let end_span = p.current_span();
         p.expect(&TokenKind::RParen)?;
+
+        let expr = if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Expr::Function {
+                name: "_comma".to_string(),
+                args: alternatives,
+                span: span.merge(end_span),
+            }
+        };
         return Ok(Expr::Group {
-            expr: Box::new(Expr::from(expr)),
+            expr: Box::new(expr),
             span: span.merge(end_span),
         });
     }
@@ -571,7 +619,7 @@ fn parse_primary_expr(p: &mut Parser) -> Result<Expr, QueryError> {
 
         // Check for function call
         if p.matches(&[TokenKind::LParen]) {
-            let args = parse_function_args(p)?;
+            let args = parse_function_args(p, &name)?;
             let end_span = p.current_span();
             p.expect(&TokenKind::RParen)?;
             return Ok(Expr::Function {
@@ -764,20 +812,80 @@ fn parse_index_or_filter(p: &mut Parser) -> Result<(IndexOp, Span), QueryError>
     }
 }
 
-fn parse_function_args(p: &mut Parser) -> Result<Vec<Expr>, QueryError> {
+/// Functions whose argument is parsed as a condition, see [`parse_condition`]
+const CONDITION_FUNCTIONS: &[&str] = &["select", "where", "filter"];
+
+fn parse_function_args(p: &mut Parser, name: &str) -> Result<Vec<Expr>, QueryError> {
+    let parse_arg = if CONDITION_FUNCTIONS.contains(&name) {
+        parse_condition
+    } else {
+        |p: &mut Parser| parse_piped_expr(p).map(Expr::from)
+    };
     let mut args = Vec::new();
 
     if !p.check(&TokenKind::RParen) {
-        args.push(parse_piped_expr(p).map(Expr::from)?);
+        args.push(parse_arg(p)?);
 
-        while p.matches(&[TokenKind::Comma]) {
-            args.push(parse_piped_expr(p).map(Expr::from)?);
+        // Arguments may be separated jq-style with ';' or with ','
+        while p.matches(&[TokenKind::Comma, TokenKind::Semicolon]) {
+            args.push(parse_arg(p)?);
         }
     }
 
     Ok(args)
 }
 
+/// The argument of `select` (or `where`/`filter`): pipelines joined by
+/// `and`/`or`.
+///
+/// Unlike everywhere else, `and` and `or` bind looser than `|` here, so in
+/// `select(.level == 2 and .text | startswith("Use"))` each side is its own
+/// pipeline run against the input. Parenthesize for jq's grouping, as in
+/// `select(.text | (ascii_downcase == "a" or . == "B"))`.
+fn parse_condition(p: &mut Parser) -> Result<Expr, QueryError> {
+    let mut left = parse_condition_and(p)?;
+
+    while p.matches(&[TokenKind::Or]) {
+        let right = parse_condition_and(p)?;
+        let span = left.span().merge(right.span());
+        left = Expr::Binary {
+            op: BinaryOp::Or,
+            left: Box::new(left),
+            right: Box::new(right),
+            span,
+        };
+    }
+
+    Ok(left)
+}
+
+fn parse_condition_and(p: &mut Parser) -> Result<Expr, QueryError> {
+    let mut left = parse_condition_pipe(p)?;
+
+    while p.matches(&[TokenKind::And]) {
+        let right = parse_condition_pipe(p)?;
+        let span = left.span().merge(right.span());
+        left = Expr::Binary {
+            op: BinaryOp::And,
+            left: Box::new(left),
+            right: Box::new(right),
+            span,
+        };
+    }
+
+    Ok(left)
+}
+
+fn parse_condition_pipe(p: &mut Parser) -> Result<Expr, QueryError> {
+    let mut stages = vec![parse_hierarchy_of(p, parse_equality_expr)?];
+
+    while p.matches(&[TokenKind::Pipe]) {
+        stages.push(parse_hierarchy_of(p, parse_equality_expr)?);
+    }
+
+    Ok(PipedExpr::new(stages).into())
+}
+
 fn parse_object_literal(p: &mut Parser, start_span: Span) -> Result<Expr, QueryError> {
     let mut pairs = Vec::new();
 
@@ -897,10 +1005,11 @@ impl From<PipedExpr> for Expr {
         } else {
             // For multi-stage pipes, we wrap in a special form
             // The evaluator will handle this
+            let span = piped.stages[0].span().merge(piped.stages[piped.stages.len() - 1].span());
             Expr::Function {
                 name: "_pipe".to_string(),
                 args: piped.stages,
-                span: Span::default(),
+                span,
             }
         }
     }
@@ -993,4 +1102,48 @@ mod tests {
             panic!("Expected Binary");
         }
     }
+
+    #[test]
+    fn test_function_arg_pipes_bind_tighter_than_and() {
+        let query = parse_str("select(.level == 2 and .text | startswith(\"Use\"))").unwrap();
+        let Expr::Function { args, .. } = &query.expressions[0].stages[0] else {
+            panic!("Expected Function");
+        };
+        let Expr::Binary { op, left, right, .. } = &args[0] else {
+            panic!("Expected Binary");
+        };
+        assert_eq!(*op, BinaryOp::And);
+        assert!(matches!(&**left, Expr::Binary { op: BinaryOp::Eq, .. }));
+        assert!(matches!(&**right, Expr::Function { name, .. } if name == "_pipe"));
+
+        // Other functions keep jq's precedence, with `|` loosest
+        let query = parse_str("map(.text | . == \"a\" or . == \"b\")").unwrap();
+        let Expr::Function { args, .. } = &query.expressions[0].stages[0] else {
+            panic!("Expected Function");
+        };
+        assert!(matches!(&args[0], Expr::Function { name, .. } if name == "_pipe"));
+    }
+
+    #[test]
+    fn test_comma_in_group() {
+        let query = parse_str("(.link, .img | text) | count").unwrap();
+        assert_eq!(query.expressions.len(), 1);
+        if let Expr::Group { expr, .. } = &query.expressions[0].stages[0] {
+            let Expr::Function { name, args, .. } = &**expr else {
+                panic!("Expected Function");
+            };
+            assert_eq!(name, "_comma");
+            assert_eq!(args.len(), 2);
+        } else {
+            panic!("Expected Group");
+        }
+
+        // A single expression stays a plain group
+        let query = parse_str("(.h2)").unwrap();
+        if let Expr::Group { expr, .. } = &query.expressions[0].stages[0] {
+            assert!(matches!(&**expr, Expr::Element { .. }));
+        } else {
+            panic!("Expected Group");
+        }
+    }
 }
@@ -109,7 +109,7 @@ impl std::fmt::Debug for Function {
 }
 
 /// Registry for functions, extractors, and other extensibility points.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Registry {
     functions: HashMap<String, Function>,
     extractors: HashMap<String, ExtractorFn>,
@@ -124,9 +124,7 @@ impl Registry {
 
     /// Create a registry with all built-in functions registered.
     pub fn with_builtins() -> Self {
-        let mut registry = Self::new();
-        super::builtins::register_all(&mut registry);
-        registry
+        Registry::clone(super::eval::builtins())
     }
 
     /// Register a function.
@@ -217,7 +215,7 @@ pub trait FunctionRegistry {
 }
 
 /// Simple Levenshtein distance for suggestions.
-fn levenshtein(a: &str, b: &str) -> usize {
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
     let a_chars: Vec<char> = a.chars().collect();
     let b_chars: Vec<char> = b.chars().collect();
     let a_len = a_chars.len();
@@ -3,6 +3,7 @@
 //! The value system is designed to be extensible while maintaining
 //! type safety and efficient operations.
 
+use crate::parser::frontmatter::{FrontMatter, FrontMatterFormat};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -11,7 +12,7 @@ use std::fmt;
 ///
 /// Values are the currency of the query language - every expression
 /// produces and consumes values.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Value {
     /// Null/empty value
@@ -56,6 +57,12 @@ pub enum Value {
     /// Paragraph element
     Paragraph(ParagraphValue),
 
+    /// Details (collapsible) element
+    Details(DetailsValue),
+
+    /// Footnote definition
+    Footnote(FootnoteValue),
+
     /// Full document reference
     Document(DocumentValue),
 
@@ -63,6 +70,180 @@ pub enum Value {
     FrontMatter(IndexMap<String, Value>),
 }
 
+/// Cloning copies nested arrays and objects with an explicit stack, so
+/// deeply nested values can't overflow the call stack.
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        let mut stack: Vec<CloneFrame<'_>> = Vec::new();
+        let mut next = self;
+
+        loop {
+            // Descend to the first leaf (or empty container) and copy it
+            let mut copied = loop {
+                let frame = match next {
+                    Value::Array(items) => {
+                        CloneFrame::Array(items.iter(), Vec::with_capacity(items.len()))
+                    }
+                    Value::Object(map) | Value::FrontMatter(map) => CloneFrame::Map {
+                        front_matter: matches!(next, Value::FrontMatter(_)),
+                        todo: map.iter(),
+                        key: "",
+                        done: IndexMap::with_capacity(map.len()),
+                    },
+                    leaf => break leaf.clone_leaf(),
+                };
+                stack.push(frame);
+                match stack.last_mut().and_then(CloneFrame::next_child) {
+                    Some(child) => next = child,
+                    None => break stack.pop().map(CloneFrame::finish).unwrap_or(Value::Null),
+                }
+            };
+
+            // Hand the copy up, finishing every container that has no children left
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return copied;
+                };
+                frame.accept(copied);
+                if let Some(child) = frame.next_child() {
+                    next = child;
+                    break;
+                }
+                copied = stack.pop().map(CloneFrame::finish).unwrap_or(Value::Null);
+            }
+        }
+    }
+}
+
+/// A container being copied by [`Value::clone`]
+enum CloneFrame<'v> {
+    Array(std::slice::Iter<'v, Value>, Vec<Value>),
+    Map {
+        front_matter: bool,
+        todo: indexmap::map::Iter<'v, String, Value>,
+        /// Key of the child currently being copied
+        key: &'v str,
+        done: IndexMap<String, Value>,
+    },
+}
+
+impl<'v> CloneFrame<'v> {
+    fn next_child(&mut self) -> Option<&'v Value> {
+        match self {
+            CloneFrame::Array(todo, _) => todo.next(),
+            CloneFrame::Map { todo, key, .. } => todo.next().map(|(k, v)| {
+                *key = k.as_str();
+                v
+            }),
+        }
+    }
+
+    fn accept(&mut self, copied: Value) {
+        match self {
+            CloneFrame::Array(_, done) => done.push(copied),
+            CloneFrame::Map { key, done, .. } => {
+                done.insert(key.to_string(), copied);
+            }
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            CloneFrame::Array(_, done) => Value::Array(done),
+            CloneFrame::Map { front_matter: true, done, .. } => Value::FrontMatter(done),
+            CloneFrame::Map { done, .. } => Value::Object(done),
+        }
+    }
+}
+
+/// Structural equality: values of different types are never equal, numbers
+/// compare numerically, and arrays and objects compare element by element
+/// (objects regardless of key order). Front matter compares like an object.
+///
+/// Nested values are walked with an explicit stack, like [`Value::clone`].
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+
+        while let Some(pair) = pending.pop() {
+            match pair {
+                (Value::Array(a), Value::Array(b)) => {
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    pending.extend(a.iter().zip(b));
+                }
+                (
+                    Value::Object(a) | Value::FrontMatter(a),
+                    Value::Object(b) | Value::FrontMatter(b),
+                ) => {
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    for (key, value) in a {
+                        let Some(other) = b.get(key) else {
+                            return false;
+                        };
+                        pending.push((value, other));
+                    }
+                }
+                (a, b) if !a.leaf_eq(b) => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl Value {
+    /// Clone a value that isn't an array, object or front matter.
+    fn clone_leaf(&self) -> Value {
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(b) => Value::Bool(*b),
+            Value::Number(n) => Value::Number(*n),
+            Value::String(s) => Value::String(s.clone()),
+            Value::Heading(h) => Value::Heading(h.clone()),
+            Value::Code(c) => Value::Code(c.clone()),
+            Value::Link(l) => Value::Link(l.clone()),
+            Value::Image(i) => Value::Image(i.clone()),
+            Value::Table(t) => Value::Table(t.clone()),
+            Value::List(l) => Value::List(l.clone()),
+            Value::Blockquote(b) => Value::Blockquote(b.clone()),
+            Value::Paragraph(p) => Value::Paragraph(p.clone()),
+            Value::Details(d) => Value::Details(d.clone()),
+            Value::Footnote(f) => Value::Footnote(f.clone()),
+            Value::Document(d) => Value::Document(d.clone()),
+            Value::Array(_) | Value::Object(_) | Value::FrontMatter(_) => {
+                unreachable!("containers are copied by Value::clone")
+            }
+        }
+    }
+
+    /// Compare two values where at least one isn't an array, object or front
+    /// matter.
+    fn leaf_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Heading(a), Value::Heading(b)) => a == b,
+            (Value::Code(a), Value::Code(b)) => a == b,
+            (Value::Link(a), Value::Link(b)) => a == b,
+            (Value::Image(a), Value::Image(b)) => a == b,
+            (Value::Table(a), Value::Table(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Blockquote(a), Value::Blockquote(b)) => a == b,
+            (Value::Paragraph(a), Value::Paragraph(b)) => a == b,
+            (Value::Details(a), Value::Details(b)) => a == b,
+            (Value::Footnote(a), Value::Footnote(b)) => a == b,
+            (Value::Document(a), Value::Document(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl Value {
     /// Get the kind/type of this value as a string.
     pub fn kind(&self) -> ValueKind {
@@ -81,6 +262,8 @@ impl Value {
             Value::List(_) => ValueKind::List,
             Value::Blockquote(_) => ValueKind::Blockquote,
             Value::Paragraph(_) => ValueKind::Paragraph,
+            Value::Details(_) => ValueKind::Details,
+            Value::Footnote(_) => ValueKind::Footnote,
             Value::Document(_) => ValueKind::Document,
             Value::FrontMatter(_) => ValueKind::FrontMatter,
         }
@@ -139,6 +322,29 @@ impl Value {
         }
     }
 
+    /// Total order used for sorting and grouping.
+    ///
+    /// Values of different types order by type: null, booleans (false before
+    /// true), numbers (numerically), strings, then everything else by its text.
+    pub fn sort_cmp(&self, other: &Value) -> std::cmp::Ordering {
+        let rank = |v: &Value| match v {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            _ => 4,
+        };
+
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            _ => rank(self)
+                .cmp(&rank(other))
+                .then_with(|| self.to_text().cmp(&other.to_text())),
+        }
+    }
+
     /// Get a property from this value by name.
     ///
     /// This is the core property access mechanism used by `.property` syntax.
@@ -151,12 +357,53 @@ impl Value {
             Value::Image(i) => i.get_property(name),
             Value::Table(t) => t.get_property(name),
             Value::List(l) => l.get_property(name),
+            Value::Paragraph(p) => p.get_property(name),
+            Value::Details(d) => d.get_property(name),
+            Value::Footnote(f) => f.get_property(name),
             Value::Document(d) => d.get_property(name),
             Value::FrontMatter(fm) => fm.get(name).cloned(),
             _ => None,
         }
     }
 
+    /// Get the names of the properties available on this value.
+    ///
+    /// Used to suggest alternatives when a property lookup fails.
+    pub fn property_names(&self) -> Vec<String> {
+        let names = match self {
+            Value::Object(obj) | Value::FrontMatter(obj) => return obj.keys().cloned().collect(),
+            Value::Heading(_) => HeadingValue::PROPERTIES,
+            Value::Code(_) => CodeValue::PROPERTIES,
+            Value::Link(_) => LinkValue::PROPERTIES,
+            Value::Image(_) => ImageValue::PROPERTIES,
+            Value::Table(_) => TableValue::PROPERTIES,
+            Value::List(_) => ListValue::PROPERTIES,
+            Value::Paragraph(_) => ParagraphValue::PROPERTIES,
+            Value::Details(_) => DetailsValue::PROPERTIES,
+            Value::Footnote(_) => FootnoteValue::PROPERTIES,
+            Value::Document(_) => DocumentValue::PROPERTIES,
+            _ => &[],
+        };
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Get the byte offset of this value in the source document.
+    ///
+    /// Returns `None` for values that don't map to a single source location.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Value::Heading(h) => Some(h.offset),
+            Value::Code(c) => Some(c.offset),
+            Value::Link(l) => Some(l.offset),
+            Value::Image(i) => Some(i.offset),
+            Value::Table(t) => Some(t.offset),
+            Value::List(l) => Some(l.offset),
+            Value::Paragraph(p) => Some(p.offset),
+            Value::Footnote(f) => Some(f.offset),
+            _ => None,
+        }
+    }
+
     /// Get the "text" representation of this value.
     ///
     /// Used by the `text` function and for plain output.
@@ -182,6 +429,8 @@ impl Value {
             Value::List(l) => l.items.iter().map(|i| i.content.clone()).collect::<Vec<_>>().join("\n"),
             Value::Blockquote(b) => b.content.clone(),
             Value::Paragraph(p) => p.content.clone(),
+            Value::Details(d) => d.summary.clone(),
+            Value::Footnote(f) => f.content.clone(),
             Value::Document(d) => d.content.clone(),
             Value::FrontMatter(fm) => serde_json::to_string(fm).unwrap_or_default(),
         }
@@ -279,6 +528,8 @@ pub enum ValueKind {
     List,
     Blockquote,
     Paragraph,
+    Details,
+    Footnote,
     Document,
     FrontMatter,
 }
@@ -300,6 +551,8 @@ impl fmt::Display for ValueKind {
             ValueKind::List => "list",
             ValueKind::Blockquote => "blockquote",
             ValueKind::Paragraph => "paragraph",
+            ValueKind::Details => "details",
+            ValueKind::Footnote => "footnote",
             ValueKind::Document => "document",
             ValueKind::FrontMatter => "frontmatter",
         };
@@ -312,7 +565,7 @@ impl fmt::Display for ValueKind {
 // ============================================================================
 
 /// Heading element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HeadingValue {
     pub level: u8,
     pub text: String,
@@ -327,9 +580,21 @@ pub struct HeadingValue {
     /// Index in the flat headings list (for navigation)
     #[serde(skip)]
     pub index: usize,
+    /// Hierarchical section number, e.g. `1.2.1`
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub number: String,
+    /// Links in the heading text itself (`.link` selects the section's links)
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub title_links: Vec<LinkValue>,
 }
 
 impl HeadingValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &[
+        "level", "text", "offset", "line", "content", "md", "slug", "number", "title_links",
+        "parent", "children", "siblings", "depth",
+    ];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "level" => Some(Value::Number(self.level as f64)),
@@ -339,22 +604,31 @@ impl HeadingValue {
             "content" => Some(Value::String(self.content.clone())),
             "md" | "markdown" => Some(Value::String(self.raw_md.clone())),
             "slug" => Some(Value::String(slugify(&self.text))),
+            "number" => Some(Value::String(self.number.clone())),
+            "title_links" => {
+                Some(Value::Array(self.title_links.iter().cloned().map(Value::Link).collect()))
+            }
             _ => None,
         }
     }
 }
 
 /// Code block element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CodeValue {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
     pub content: String,
     pub start_line: usize,
     pub end_line: usize,
+    pub offset: usize,
 }
 
 impl CodeValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] =
+        &["lang", "content", "start_line", "end_line", "lines", "offset"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "lang" | "language" => self.language.clone().map(Value::String).or(Some(Value::Null)),
@@ -362,27 +636,33 @@ impl CodeValue {
             "start_line" => Some(Value::Number(self.start_line as f64)),
             "end_line" => Some(Value::Number(self.end_line as f64)),
             "lines" => Some(Value::Number(self.content.lines().count() as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
             _ => None,
         }
     }
 }
 
 /// Link element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LinkValue {
     pub text: String,
     pub url: String,
     #[serde(rename = "type")]
     pub link_type: LinkType,
+    pub line: usize,
     pub offset: usize,
 }
 
 impl LinkValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["text", "url", "type", "line", "offset"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "text" => Some(Value::String(self.text.clone())),
             "url" => Some(Value::String(self.url.clone())),
             "type" => Some(Value::String(self.link_type.as_str().to_string())),
+            "line" => Some(Value::Number(self.line as f64)),
             "offset" => Some(Value::Number(self.offset as f64)),
             _ => None,
         }
@@ -411,34 +691,46 @@ impl LinkType {
 }
 
 /// Image element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ImageValue {
     pub alt: String,
     pub src: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    pub line: usize,
+    pub offset: usize,
 }
 
 impl ImageValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["alt", "src", "title", "line", "offset"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "alt" | "text" => Some(Value::String(self.alt.clone())),
             "src" | "url" => Some(Value::String(self.src.clone())),
             "title" => self.title.clone().map(Value::String).or(Some(Value::Null)),
+            "line" => Some(Value::Number(self.line as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
             _ => None,
         }
     }
 }
 
 /// Table element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableValue {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<String>>,
     pub alignments: Vec<String>,
+    pub line: usize,
+    pub offset: usize,
 }
 
 impl TableValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["headers", "rows", "cols", "alignments", "line", "offset"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "headers" => Some(Value::Array(
@@ -456,19 +748,26 @@ impl TableValue {
             "alignments" => Some(Value::Array(
                 self.alignments.iter().map(|a| Value::String(a.clone())).collect(),
             )),
+            "line" => Some(Value::Number(self.line as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
             _ => None,
         }
     }
 }
 
 /// List element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListValue {
     pub ordered: bool,
     pub items: Vec<ListItemValue>,
+    pub line: usize,
+    pub offset: usize,
 }
 
 impl ListValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["ordered", "items", "length", "line", "offset"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "ordered" => Some(Value::Bool(self.ordered)),
@@ -476,33 +775,106 @@ impl ListValue {
                 self.items.iter().map(|i| Value::String(i.content.clone())).collect(),
             )),
             "length" | "count" => Some(Value::Number(self.items.len() as f64)),
+            "line" => Some(Value::Number(self.line as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
             _ => None,
         }
     }
 }
 
 /// List item value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListItemValue {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checked: Option<bool>,
+    /// Items of a nested list, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ListItemValue>,
 }
 
 /// Blockquote element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BlockquoteValue {
     pub content: String,
 }
 
 /// Paragraph element value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParagraphValue {
     pub content: String,
+    pub line: usize,
+    pub offset: usize,
+}
+
+impl ParagraphValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["content", "line", "offset"];
+
+    pub fn get_property(&self, name: &str) -> Option<Value> {
+        match name {
+            "content" | "text" => Some(Value::String(self.content.clone())),
+            "line" => Some(Value::Number(self.line as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
+            _ => None,
+        }
+    }
+}
+
+/// Details (collapsible `<details>` block) element value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetailsValue {
+    pub summary: String,
+    pub content: String,
+    pub open: bool,
+}
+
+impl DetailsValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["summary", "content", "open"];
+
+    pub fn get_property(&self, name: &str) -> Option<Value> {
+        match name {
+            "summary" | "text" => Some(Value::String(self.summary.clone())),
+            "content" => Some(Value::String(self.content.clone())),
+            "open" => Some(Value::Bool(self.open)),
+            _ => None,
+        }
+    }
+}
+
+/// Footnote definition element value.
+///
+/// References (`[^label]`) point to the definition with the same label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FootnoteValue {
+    pub label: String,
+    pub content: String,
+    /// Number of references to this footnote in the document
+    pub references: usize,
+    pub line: usize,
+    pub offset: usize,
+}
+
+impl FootnoteValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] =
+        &["label", "content", "references", "line", "offset"];
+
+    pub fn get_property(&self, name: &str) -> Option<Value> {
+        match name {
+            "label" | "id" => Some(Value::String(self.label.clone())),
+            "content" | "text" => Some(Value::String(self.content.clone())),
+            "references" | "refs" => Some(Value::Number(self.references as f64)),
+            "line" => Some(Value::Number(self.line as f64)),
+            "offset" => Some(Value::Number(self.offset as f64)),
+            _ => None,
+        }
+    }
 }
 
 /// Document value (root).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DocumentValue {
     pub content: String,
     pub heading_count: usize,
@@ -510,6 +882,9 @@ pub struct DocumentValue {
 }
 
 impl DocumentValue {
+    /// Canonical property names, used for suggestions.
+    pub const PROPERTIES: &'static [&'static str] = &["content", "heading_count", "word_count"];
+
     pub fn get_property(&self, name: &str) -> Option<Value> {
         match name {
             "content" | "text" => Some(Value::String(self.content.clone())),
@@ -524,6 +899,73 @@ impl DocumentValue {
 // Helper Functions
 // ============================================================================
 
+impl Value {
+    /// A number, or null if `n` is NaN or infinite.
+    ///
+    /// JSON has no representation for non-finite numbers, so arithmetic and
+    /// aggregations build their results through this.
+    pub fn number(n: f64) -> Value {
+        if n.is_finite() { Value::Number(n) } else { Value::Null }
+    }
+
+    /// Parse a front matter block into a [`Value::FrontMatter`].
+    ///
+    /// YAML keys keep their order; TOML keys are sorted. An empty block gives
+    /// an empty map. Fails with the parser's message if the block is invalid
+    /// or isn't a mapping.
+    pub fn from_front_matter(front_matter: &FrontMatter<'_>) -> Result<Value, String> {
+        let value = match front_matter.format {
+            FrontMatterFormat::Yaml => {
+                let docs = yaml_rust2::YamlLoader::load_from_str(front_matter.raw)
+                    .map_err(|e| e.to_string())?;
+                docs.first().map_or(Value::Object(IndexMap::new()), yaml_to_value)
+            }
+            FrontMatterFormat::Toml => {
+                let table: toml::Table = toml::from_str(front_matter.raw)
+                    .map_err(|e| e.message().to_string())?;
+                toml_to_value(toml::Value::Table(table))
+            }
+        };
+        match value {
+            Value::Object(map) => Ok(Value::FrontMatter(map)),
+            Value::Null => Ok(Value::FrontMatter(IndexMap::new())),
+            other => Err(format!("expected a mapping of keys to values, found {}", other.kind())),
+        }
+    }
+}
+
+fn yaml_to_value(yaml: &yaml_rust2::Yaml) -> Value {
+    use yaml_rust2::Yaml;
+
+    match yaml {
+        Yaml::String(s) => Value::String(s.clone()),
+        Yaml::Integer(n) => Value::Number(*n as f64),
+        Yaml::Real(_) => yaml.as_f64().map_or(Value::Null, Value::number),
+        Yaml::Boolean(b) => Value::Bool(*b),
+        Yaml::Array(items) => Value::Array(items.iter().map(yaml_to_value).collect()),
+        Yaml::Hash(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (yaml_to_value(key).to_text(), yaml_to_value(value)))
+                .collect(),
+        ),
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => Value::Null,
+    }
+}
+
+fn toml_to_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(n) => Value::Number(n as f64),
+        toml::Value::Float(n) => Value::number(n),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_value).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table.into_iter().map(|(key, value)| (key, toml_to_value(value))).collect(),
+        ),
+    }
+}
+
 /// Generate URL-friendly slug from text.
 fn slugify(text: &str) -> String {
     text.to_lowercase()
@@ -1,12 +1,17 @@
 use crate::config::Config;
 use crate::parser::{Document, HeadingNode, Link, extract_links};
+use crate::tui::file_picker::{self, FilePicker};
 use crate::tui::interactive::InteractiveState;
+use crate::tui::query_bar::QueryBar;
+use crate::tui::replace::ReplacePrompt;
 use crate::tui::syntax::SyntaxHighlighter;
 use crate::tui::terminal_compat::ColorMode;
-use crate::tui::theme::{Theme, ThemeName};
+use crate::tui::theme::{HeadingStyle, OutlineStyle, Theme, ThemeName};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::{ListState, ScrollbarState};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Focus {
@@ -23,6 +28,9 @@ pub enum AppMode {
     ThemePicker,
     Help,
     CellEdit,
+    Query,
+    Replace,
+    FilePicker,
 }
 
 pub struct App {
@@ -36,17 +44,30 @@ pub struct App {
     pub content_scroll: u16,
     pub content_scroll_state: ScrollbarState,
     pub content_height: u16,
+    pub content_width: u16, // Inner width of the content pane (0 until first render)
     pub show_help: bool,
     pub help_scroll: u16,
     pub show_search: bool,
     pub search_query: String,
+    pub highlight_query: String, // Confirmed search, highlighted in the content pane
     pub highlighter: SyntaxHighlighter,
     pub show_outline: bool,
     pub outline_width: u16,                // Percentage: 20, 30, or 40
     pub bookmark_position: Option<String>, // Bookmarked heading text (was: outline position)
+    pub marks: HashMap<char, usize>,       // Named marks: letter -> index in document.headings
+    pub pending_mark: Option<MarkAction>,  // `m` or `` ` `` pressed, waiting for the mark letter
     collapsed_headings: HashSet<String>,   // Track which headings are collapsed by text
     pub current_theme: ThemeName,
     pub theme: Theme,
+    pub tab_width: usize,   // Spaces per tab when rendering content
+    pub no_color: bool,     // Strip all colors when rendering
+    pub mouse: bool,        // Mouse capture enabled (click/scroll)
+    pub heading_style: HeadingStyle, // Banner for the focused H1/H2 in the content pane
+    pub outline_style: OutlineStyle, // Layout of the outline pane
+    pub padding: u16,                // Blank columns inside the content pane borders
+    pub max_width: Option<u16>,      // Widest the content column gets (centered)
+    pub outline_area: Rect, // Outline pane as last rendered (for mouse hit-testing)
+    pub content_area: Rect, // Content pane as last rendered
     pub show_theme_picker: bool,
     pub theme_picker_selected: usize,
     previous_selection: Option<String>, // Track previous selection to detect changes
@@ -58,11 +79,21 @@ pub struct App {
     pub selected_link_idx: Option<usize>, // Currently selected link index
     pub file_history: Vec<FileState>, // Back navigation stack
     pub file_future: Vec<FileState>, // Forward navigation stack (for undo back)
+    pub recent_files: Vec<PathBuf>,  // Opened files, most recent (the current one) first
     pub status_message: Option<String>, // Temporary status message to display
 
     // Interactive element navigation
     pub interactive_state: InteractiveState,
 
+    // Live query bar (`:`)
+    pub query_bar: QueryBar,
+
+    // Find/replace preview (`R`)
+    pub replace_prompt: ReplacePrompt,
+
+    // Sibling file picker (`o`)
+    pub file_picker: FilePicker,
+
     // Cell editing state
     pub cell_edit_value: String, // Current value being edited
     pub cell_edit_row: usize,    // Row being edited
@@ -77,6 +108,15 @@ pub struct App {
     color_mode: ColorMode,
 }
 
+/// The two-key mark commands, waiting for the letter that names the mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkAction {
+    /// `m{a-z}`: mark the selected heading
+    Set,
+    /// `` `{a-z} ``: jump to a marked heading
+    Jump,
+}
+
 /// Saved state for file navigation history
 #[derive(Debug, Clone)]
 pub struct FileState {
@@ -88,6 +128,46 @@ pub struct FileState {
     pub outline_state_selected: Option<usize>,
 }
 
+/// Initial expansion state of the outline tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlineExpansion {
+    /// Every heading expanded
+    #[default]
+    ExpandAll,
+    /// Only top-level headings visible
+    CollapseAll,
+    /// Headings expanded down to this tree depth (0 collapses everything)
+    Level(usize),
+}
+
+impl OutlineExpansion {
+    /// Texts of the headings to collapse so that `tree` matches this policy.
+    fn collapsed(self, tree: &[HeadingNode]) -> HashSet<String> {
+        fn collect(
+            nodes: &[HeadingNode],
+            depth: usize,
+            max_depth: usize,
+            collapsed: &mut HashSet<String>,
+        ) {
+            for node in nodes {
+                if !node.children.is_empty() && depth > max_depth {
+                    collapsed.insert(node.heading.text.clone());
+                }
+                collect(&node.children, depth + 1, max_depth, collapsed);
+            }
+        }
+
+        let max_depth = match self {
+            OutlineExpansion::ExpandAll => return HashSet::new(),
+            OutlineExpansion::CollapseAll => 0,
+            OutlineExpansion::Level(depth) => depth,
+        };
+        let mut collapsed = HashSet::new();
+        collect(tree, 1, max_depth, &mut collapsed);
+        collapsed
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OutlineItem {
     pub level: usize,
@@ -103,9 +183,10 @@ impl App {
         file_path: PathBuf,
         config: Config,
         color_mode: ColorMode,
+        expansion: OutlineExpansion,
     ) -> Self {
         let tree = document.build_tree();
-        let collapsed_headings = HashSet::new();
+        let collapsed_headings = expansion.collapsed(&tree);
         let outline_items = Self::flatten_tree(&tree, &collapsed_headings);
 
         let mut outline_state = ListState::default();
@@ -113,7 +194,7 @@ impl App {
             outline_state.select(Some(0));
         }
 
-        let content_lines = document.content.lines().count();
+        let content_lines = document.content().lines().count();
 
         // Load theme from config, apply color mode, then apply custom colors
         let current_theme = config.theme_name();
@@ -135,23 +216,37 @@ impl App {
             content_scroll: 0,
             content_scroll_state: ScrollbarState::new(content_lines),
             content_height: content_lines as u16,
+            content_width: 0,
             show_help: false,
             help_scroll: 0,
             show_search: false,
             search_query: String::new(),
+            highlight_query: String::new(),
             highlighter: SyntaxHighlighter::new(),
             show_outline: true,
             outline_width,
             bookmark_position: None,
+            marks: HashMap::new(),
+            pending_mark: None,
             collapsed_headings,
             current_theme,
             theme,
             show_theme_picker: false,
+            tab_width: config.defaults.tab_width(),
+            no_color: config.defaults.no_color(),
+            mouse: config.defaults.mouse(),
+            heading_style: config.defaults.heading_style().parse().unwrap_or_default(),
+            outline_style: config.defaults.outline_style().parse().unwrap_or_default(),
+            padding: config.defaults.padding(),
+            max_width: config.defaults.max_width(),
+            outline_area: Rect::default(),
+            content_area: Rect::default(),
             theme_picker_selected: 0,
             previous_selection: None,
 
             // Link following state
             mode: AppMode::Normal,
+            recent_files: vec![file_path.clone()],
             current_file_path: file_path,
             links_in_view: Vec::new(),
             selected_link_idx: None,
@@ -162,6 +257,15 @@ impl App {
             // Interactive element navigation
             interactive_state: InteractiveState::new(),
 
+            // Live query bar (`:`)
+            query_bar: QueryBar::new(),
+
+            // Find/replace preview (`R`)
+            replace_prompt: ReplacePrompt::new(),
+
+            // Sibling file picker (`o`)
+            file_picker: FilePicker::default(),
+
             // Cell editing state
             cell_edit_value: String::new(),
             cell_edit_row: 0,
@@ -242,20 +346,43 @@ impl App {
                 // Use extract_section_content to get the actual displayed content
                 self.document
                     .extract_section(heading_text)
-                    .unwrap_or_else(|| self.document.content.clone())
+                    .unwrap_or_else(|| self.document.content().to_string())
             } else {
-                self.document.content.clone()
+                self.document.content().to_string()
             }
         } else {
-            self.document.content.clone()
+            self.document.content().to_string()
         };
 
-        let content_lines = content_text.lines().count();
+        let content_text = self.expand_tabs(content_text);
+
+        // Count wrapped rows (in display columns) once the pane width is known
+        let content_lines = if self.content_width > 0 {
+            crate::tui::wrap::wrapped_height(&content_text, self.content_width as usize)
+        } else {
+            content_text.lines().count()
+        } + self.heading_banner_height();
         self.content_height = content_lines as u16;
         self.content_scroll_state =
             ScrollbarState::new(content_lines).position(self.content_scroll as usize);
     }
 
+    /// Rows taken by the heading banner above the selected section's content
+    pub fn heading_banner_height(&self) -> usize {
+        self.selected_heading_text()
+            .and_then(|text| self.document.find_heading(text))
+            .map_or(0, |heading| self.heading_style.banner_height(heading.level))
+    }
+
+    /// Replace tabs with `tab_width` spaces for display
+    pub fn expand_tabs(&self, text: String) -> String {
+        if text.contains('\t') {
+            text.replace('\t', &" ".repeat(self.tab_width))
+        } else {
+            text
+        }
+    }
+
     pub fn next(&mut self) {
         if self.focus == Focus::Outline {
             let i = match self.outline_state.selected() {
@@ -280,6 +407,35 @@ impl App {
         }
     }
 
+    /// Handle a mouse event: click to focus a pane or select an outline entry,
+    /// wheel to scroll the focused pane.
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let position = Position::new(event.column, event.row);
+                if self.show_outline && self.outline_area.contains(position) {
+                    self.focus = Focus::Outline;
+                    if let Some(i) = outline_index_at(
+                        self.outline_area,
+                        self.outline_state.offset(),
+                        event.column,
+                        event.row,
+                    )
+                    .filter(|&i| i < self.outline_items.len())
+                    {
+                        self.outline_state.select(Some(i));
+                        self.outline_scroll_state = self.outline_scroll_state.position(i);
+                    }
+                } else if self.content_area.contains(position) {
+                    self.focus = Focus::Content;
+                }
+            }
+            MouseEventKind::ScrollDown => self.next(),
+            MouseEventKind::ScrollUp => self.previous(),
+            _ => {}
+        }
+    }
+
     pub fn previous(&mut self) {
         if self.focus == Focus::Outline {
             let i = match self.outline_state.selected() {
@@ -360,9 +516,128 @@ impl App {
         self.show_search = !self.show_search;
         if !self.show_search {
             self.search_query.clear();
+            self.highlight_query.clear();
+        }
+    }
+
+    /// Close the search bar, keeping the filtered outline and the query's
+    /// highlighting in the content pane
+    pub fn confirm_search(&mut self) {
+        let query = std::mem::take(&mut self.search_query);
+        self.toggle_search();
+        self.highlight_query = query;
+    }
+
+    /// Text to highlight in the content pane: the query being typed, or the
+    /// last confirmed one
+    pub fn content_highlight(&self) -> Option<&str> {
+        if self.show_search && !self.search_query.is_empty() {
+            Some(&self.search_query)
+        } else if !self.highlight_query.is_empty() {
+            Some(&self.highlight_query)
+        } else {
+            None
         }
     }
 
+    /// Open the query bar, optionally starting from `query`
+    pub fn open_query_bar(&mut self, query: Option<&str>) {
+        if let Some(query) = query {
+            self.query_bar.input = query.to_string();
+            self.query_bar.output = None;
+        }
+        self.query_bar.open();
+        self.mode = AppMode::Query;
+    }
+
+    pub fn close_query_bar(&mut self) {
+        self.query_bar.close();
+        self.mode = AppMode::Normal;
+    }
+
+    /// Run the query bar's query against the open document
+    pub fn run_query(&mut self) {
+        self.query_bar.run(&self.document);
+    }
+
+    /// Run the query bar's query and leave its results up for reading
+    pub fn submit_query(&mut self) {
+        self.query_bar.submit(&self.document);
+    }
+
+    /// Open the find/replace preview, keeping the last pattern and replacement
+    pub fn open_replace(&mut self) {
+        self.replace_prompt.update(self.document.content());
+        self.mode = AppMode::Replace;
+    }
+
+    pub fn close_replace(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn replace_input(&mut self, c: char) {
+        self.replace_prompt.input(c, self.document.content());
+    }
+
+    pub fn replace_backspace(&mut self) {
+        self.replace_prompt.backspace(self.document.content());
+    }
+
+    /// Open the picker over the markdown files next to the current one
+    pub fn open_file_picker(&mut self) {
+        let dir = self.current_file_path.parent().unwrap_or(Path::new(""));
+        match file_picker::markdown_files(dir) {
+            Ok(files) => {
+                self.file_picker = FilePicker::new(files, &self.current_file_path);
+                self.mode = AppMode::FilePicker;
+            }
+            Err(e) => self.status_message = Some(format!("✗ Cannot list files: {}", e)),
+        }
+    }
+
+    pub fn close_file_picker(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    /// Open the file highlighted in the picker
+    pub fn open_selected_file(&mut self) -> Result<(), String> {
+        self.mode = AppMode::Normal;
+        let Some(path) = self.file_picker.selected_file().map(Path::to_path_buf) else {
+            return Err("No markdown files here".to_string());
+        };
+        if path.file_name() == self.current_file_path.file_name() {
+            return Ok(());
+        }
+        self.open_path(path)
+    }
+
+    /// Switch to the most recently opened file other than the current one
+    pub fn switch_to_recent(&mut self) -> Result<(), String> {
+        let path = self
+            .recent_files
+            .iter()
+            .find(|p| **p != self.current_file_path)
+            .cloned()
+            .ok_or("No other recently opened file")?;
+        self.open_path(path)
+    }
+
+    /// Parse `path` and show it in place of the current file
+    fn open_path(&mut self, path: PathBuf) -> Result<(), String> {
+        let document = crate::parser::parse_file(&path)
+            .map_err(|e| format!("Failed to load file: {}", e))?;
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        self.save_to_history();
+        self.load_document(document, filename, path);
+        self.status_message = Some(format!("✓ Opened {}", self.filename));
+        Ok(())
+    }
+
     /// Maximum search query length to prevent performance issues
     const MAX_SEARCH_LEN: usize = 256;
 
@@ -581,6 +856,34 @@ impl App {
         }
     }
 
+    /// Collapse every heading, or expand them all if any are collapsed.
+    pub fn toggle_collapse_all(&mut self) {
+        let expansion = if self.collapsed_headings.is_empty() {
+            OutlineExpansion::CollapseAll
+        } else {
+            OutlineExpansion::ExpandAll
+        };
+        let previous = std::mem::take(&mut self.outline_items);
+        self.collapsed_headings = expansion.collapsed(&self.tree);
+        self.outline_items = Self::flatten_tree(&self.tree, &self.collapsed_headings);
+
+        // Keep the selection, or move to the nearest visible heading above it
+        // (its top-level ancestor when collapsing)
+        let i = self.outline_state.selected().unwrap_or(0);
+        let restored = previous
+            .iter()
+            .take(i + 1)
+            .rev()
+            .any(|item| self.select_by_text(&item.text));
+        if !restored && !self.outline_items.is_empty() {
+            self.outline_state.select(Some(0));
+            self.outline_scroll_state = ScrollbarState::new(self.outline_items.len()).position(0);
+        } else {
+            self.outline_scroll_state = ScrollbarState::new(self.outline_items.len())
+                .position(self.outline_state.selected().unwrap_or(0));
+        }
+    }
+
     pub fn toggle_focus(&mut self) {
         if self.show_outline {
             self.focus = match self.focus {
@@ -601,6 +904,12 @@ impl App {
         }
     }
 
+    /// Switch the outline pane to the next layout (indent, tree, compact)
+    pub fn cycle_outline_style(&mut self) {
+        self.outline_style = self.outline_style.next();
+        self.status_message = Some(format!("Outline style: {}", self.outline_style.name()));
+    }
+
     pub fn cycle_outline_width(&mut self, increase: bool) {
         if increase {
             self.outline_width = match self.outline_width {
@@ -639,6 +948,72 @@ impl App {
         }
     }
 
+    /// Finish a pending `m`/`` ` `` command with the key pressed after it.
+    ///
+    /// Anything but a letter cancels the command.
+    pub fn finish_mark(&mut self, key: Option<char>) {
+        let Some(action) = self.pending_mark.take() else {
+            return;
+        };
+        match key {
+            Some(name) if name.is_ascii_alphabetic() => match action {
+                MarkAction::Set => self.set_mark(name),
+                MarkAction::Jump => self.jump_to_mark(name),
+            },
+            _ => {}
+        }
+    }
+
+    /// Mark the selected heading with a letter, replacing any previous mark
+    /// with that letter. The most recent mark is also the `'` bookmark.
+    pub fn set_mark(&mut self, name: char) {
+        let Some(text) = self.selected_heading_text().map(str::to_string) else {
+            return;
+        };
+        let Some(index) = self.document.headings().iter().position(|h| h.text == text) else {
+            return;
+        };
+        self.marks.insert(name, index);
+        self.bookmark_position = Some(text.clone());
+        self.status_message = Some(format!("Mark '{}' set at \"{}\"", name, text));
+    }
+
+    /// Select the heading marked with a letter, expanding its collapsed
+    /// ancestors so it shows in the outline.
+    pub fn jump_to_mark(&mut self, name: char) {
+        let Some(&index) = self.marks.get(&name) else {
+            self.status_message = Some(format!("✗ Mark '{}' not set", name));
+            return;
+        };
+        let Some(heading) = self.document.headings().get(index) else {
+            return;
+        };
+        let text = heading.text.clone();
+
+        if !self.select_by_text(&text) {
+            for ancestor in self.document.ancestors(index) {
+                self.collapsed_headings.remove(&ancestor.text);
+            }
+            self.outline_items = Self::flatten_tree(&self.tree, &self.collapsed_headings);
+            self.outline_scroll_state = ScrollbarState::new(self.outline_items.len());
+            self.select_by_text(&text);
+        }
+    }
+
+    /// Letters of the marks on a heading, in alphabetical order
+    pub fn marks_on(&self, heading_text: &str) -> String {
+        let mut names: Vec<char> = self
+            .marks
+            .iter()
+            .filter(|(_, index)| {
+                self.document.headings().get(**index).map(|h| h.text.as_str()) == Some(heading_text)
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        names.sort_unstable();
+        names.into_iter().collect()
+    }
+
     pub fn selected_heading_text(&self) -> Option<&str> {
         self.outline_state
             .selected()
@@ -646,6 +1021,28 @@ impl App {
             .map(|item| item.text.as_str())
     }
 
+    /// Texts of the selected heading's ancestors followed by the heading
+    /// itself, for the breadcrumb in the title bar.
+    pub fn breadcrumb(&self) -> Vec<&str> {
+        let Some(text) = self.selected_heading_text() else {
+            return Vec::new();
+        };
+        let Some(index) = self.document.headings().iter().position(|h| h.text == text) else {
+            return Vec::new();
+        };
+        let mut path: Vec<&str> =
+            self.document.ancestors(index).iter().map(|h| h.text.as_str()).collect();
+        path.push(text);
+        path
+    }
+
+    /// 1-based line number of the selected heading in the current file
+    pub fn selected_heading_line(&self) -> Option<usize> {
+        let text = self.selected_heading_text()?;
+        let heading = self.document.find_heading(text)?;
+        Some(self.document.line_col(heading.offset).0)
+    }
+
     pub fn toggle_theme_picker(&mut self) {
         self.show_theme_picker = !self.show_theme_picker;
         if self.show_theme_picker {
@@ -676,17 +1073,10 @@ impl App {
     }
 
     pub fn apply_selected_theme(&mut self) {
-        let new_theme = match self.theme_picker_selected {
-            0 => ThemeName::OceanDark,
-            1 => ThemeName::Nord,
-            2 => ThemeName::Dracula,
-            3 => ThemeName::Solarized,
-            4 => ThemeName::Monokai,
-            5 => ThemeName::Gruvbox,
-            6 => ThemeName::TokyoNight,
-            7 => ThemeName::CatppuccinMocha,
-            _ => ThemeName::OceanDark,
-        };
+        let new_theme = ThemeName::ALL
+            .get(self.theme_picker_selected)
+            .copied()
+            .unwrap_or(ThemeName::OceanDark);
 
         self.current_theme = new_theme;
         // Apply color mode when setting theme (also apply custom colors from config)
@@ -763,9 +1153,9 @@ impl App {
         let content = if let Some(heading_text) = self.selected_heading_text() {
             self.document
                 .extract_section(heading_text)
-                .unwrap_or_else(|| self.document.content.clone())
+                .unwrap_or_else(|| self.document.content().to_string())
         } else {
-            self.document.content.clone()
+            self.document.content().to_string()
         };
 
         // Extract all links from the content
@@ -841,9 +1231,9 @@ impl App {
                             let content = if let Some(heading_text) = self.selected_heading_text() {
                                 self.document
                                     .extract_section(heading_text)
-                                    .unwrap_or_else(|| self.document.content.clone())
+                                    .unwrap_or_else(|| self.document.content().to_string())
                             } else {
-                                self.document.content.clone()
+                                self.document.content().to_string()
                             };
                             self.links_in_view = extract_links(&content);
 
@@ -1076,7 +1466,10 @@ impl App {
     /// Load a new document and update all related state
     fn load_document(&mut self, document: Document, filename: String, path: PathBuf) {
         self.document = document;
+        // Marks point into the old document's headings
+        self.marks.clear();
         self.filename = filename;
+        file_picker::push_recent(&mut self.recent_files, &path);
         self.current_file_path = path;
 
         // Rebuild tree and outline
@@ -1093,7 +1486,7 @@ impl App {
 
         // Reset content scroll
         self.content_scroll = 0;
-        let content_lines = self.document.content.lines().count();
+        let content_lines = self.document.content().lines().count();
         self.content_height = content_lines as u16;
         self.content_scroll_state = ScrollbarState::new(content_lines);
 
@@ -1200,20 +1593,8 @@ impl App {
 
     /// Enter interactive mode - build element index and enter mode
     pub fn enter_interactive_mode(&mut self) {
-        // Get current section content to index
-        let content = if let Some(selected) = self.selected_heading_text() {
-            self.document
-                .extract_section(selected)
-                .unwrap_or_else(|| self.document.content.clone())
-        } else {
-            self.document.content.clone()
-        };
-
-        // Parse content into blocks
-        use crate::parser::content::parse_content;
-        let blocks = parse_content(&content, 0);
-
-        // Index interactive elements
+        // Index interactive elements of the current section
+        let blocks = self.document.section_blocks(self.selected_heading_text());
         self.interactive_state.index_elements(&blocks);
 
         // Enter interactive mode
@@ -1307,16 +1688,7 @@ impl App {
 
     /// Re-index interactive elements after state changes
     fn reindex_interactive_elements(&mut self) {
-        let content = if let Some(selected) = self.selected_heading_text() {
-            self.document
-                .extract_section(selected)
-                .unwrap_or_else(|| self.document.content.clone())
-        } else {
-            self.document.content.clone()
-        };
-
-        use crate::parser::content::parse_content;
-        let blocks = parse_content(&content, 0);
+        let blocks = self.document.section_blocks(self.selected_heading_text());
         self.interactive_state.index_elements(&blocks);
     }
 
@@ -1329,16 +1701,7 @@ impl App {
     ) -> Result<(), String> {
         // Get the checkbox content text to use as identifier
         let checkbox_content = {
-            let content = if let Some(selected) = self.selected_heading_text() {
-                self.document
-                    .extract_section(selected)
-                    .unwrap_or_else(|| self.document.content.clone())
-            } else {
-                self.document.content.clone()
-            };
-
-            use crate::parser::content::parse_content;
-            let blocks = parse_content(&content, 0);
+            let blocks = self.document.section_blocks(self.selected_heading_text());
 
             if let Some(crate::parser::output::Block::List { items, .. }) = blocks.get(block_idx) {
                 items.get(item_idx).map(|item| item.content.clone())
@@ -1514,17 +1877,8 @@ impl App {
             if let crate::tui::interactive::ElementType::Table { block_idx, .. } =
                 &element.element_type
             {
-                // Parse current section to get table data
-                let content = if let Some(selected) = self.selected_heading_text() {
-                    self.document
-                        .extract_section(selected)
-                        .unwrap_or_else(|| self.document.content.clone())
-                } else {
-                    self.document.content.clone()
-                };
-
-                use crate::parser::content::parse_content;
-                let blocks = parse_content(&content, 0);
+                // Parsed blocks of the current section to get table data
+                let blocks = self.document.section_blocks(self.selected_heading_text());
 
                 if let Some(crate::parser::output::Block::Table { headers, rows, .. }) =
                     blocks.get(*block_idx)
@@ -1683,19 +2037,8 @@ impl App {
         col: usize,
         new_value: &str,
     ) -> Result<String, String> {
-        use crate::parser::content::parse_content;
-
-        // Get the current section content to find the right table
-        let section_content = if let Some(heading_text) = self.selected_heading_text() {
-            self.document
-                .extract_section(heading_text)
-                .unwrap_or_else(|| self.document.content.clone())
-        } else {
-            self.document.content.clone()
-        };
-
-        // Parse to find the table block
-        let blocks = parse_content(&section_content, 0);
+        // Parsed blocks of the current section, to find the table block
+        let blocks = self.document.section_blocks(self.selected_heading_text());
 
         // Find the block index of the current table element
         if let Some(element) = self.interactive_state.current_element() {
@@ -1791,3 +2134,129 @@ impl App {
         new_parts.join("|")
     }
 }
+
+/// Map a click at (`column`, `row`) to an outline item index.
+///
+/// `area` is the bordered outline pane and `offset` the index of its first
+/// visible item. Returns `None` for clicks on the border or outside the pane.
+fn outline_index_at(area: Rect, offset: usize, column: u16, row: u16) -> Option<usize> {
+    let inner = area.inner(ratatui::layout::Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    if !inner.contains(Position::new(column, row)) {
+        return None;
+    }
+    Some(offset + (row - inner.y) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_index_at() {
+        // Outline pane at (0, 2), 30 wide and 10 tall: rows 3..=10 hold items
+        let area = Rect::new(0, 2, 30, 10);
+
+        assert_eq!(outline_index_at(area, 0, 5, 3), Some(0));
+        assert_eq!(outline_index_at(area, 0, 5, 10), Some(7));
+        // Scrolled list: the first visible row is item 12
+        assert_eq!(outline_index_at(area, 12, 5, 4), Some(13));
+
+        // Borders and outside the pane
+        assert_eq!(outline_index_at(area, 0, 5, 2), None);
+        assert_eq!(outline_index_at(area, 0, 5, 11), None);
+        assert_eq!(outline_index_at(area, 0, 0, 5), None);
+        assert_eq!(outline_index_at(area, 0, 40, 5), None);
+    }
+
+    #[test]
+    fn test_outline_expansion_collapsed_headings() {
+        let doc = crate::parse_markdown("# A\n## B\n### C\n## D\n# E\n## F\n# G\n");
+        let tree = doc.build_tree();
+        let collapsed = |expansion: OutlineExpansion| {
+            let mut texts: Vec<_> = expansion.collapsed(&tree).into_iter().collect();
+            texts.sort();
+            texts
+        };
+        let visible = |expansion: OutlineExpansion| {
+            App::flatten_tree(&tree, &expansion.collapsed(&tree))
+                .into_iter()
+                .map(|item| item.text)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(collapsed(OutlineExpansion::ExpandAll).is_empty());
+        assert_eq!(visible(OutlineExpansion::ExpandAll).len(), 7);
+
+        // Only headings with children are collapsed; leaves like G are left alone
+        assert_eq!(collapsed(OutlineExpansion::CollapseAll), ["A", "B", "E"]);
+        assert_eq!(visible(OutlineExpansion::CollapseAll), ["A", "E", "G"]);
+        assert_eq!(
+            visible(OutlineExpansion::Level(0)),
+            visible(OutlineExpansion::CollapseAll)
+        );
+
+        assert_eq!(collapsed(OutlineExpansion::Level(1)), ["B"]);
+        assert_eq!(visible(OutlineExpansion::Level(1)), ["A", "B", "D", "E", "F", "G"]);
+        assert_eq!(visible(OutlineExpansion::Level(2)).len(), 7);
+    }
+
+    #[test]
+    fn test_marks_set_and_jump() {
+        let doc = crate::parse_markdown("# A\n## B\n### C\n# D\n");
+        let mut app = App::new(
+            doc,
+            "t.md".to_string(),
+            PathBuf::from("t.md"),
+            Config::default(),
+            ColorMode::Rgb,
+            OutlineExpansion::ExpandAll,
+        );
+
+        assert!(app.select_by_text("C"));
+        app.pending_mark = Some(MarkAction::Set);
+        app.finish_mark(Some('a'));
+        assert_eq!(app.marks.get(&'a'), Some(&2));
+        assert_eq!(app.marks_on("C"), "a");
+        assert_eq!(app.bookmark_position.as_deref(), Some("C"));
+
+        // Jumping expands the collapsed ancestors of the marked heading
+        app.collapsed_headings.insert("A".to_string());
+        app.outline_items = App::flatten_tree(&app.tree, &app.collapsed_headings);
+        assert!(app.select_by_text("D"));
+        app.pending_mark = Some(MarkAction::Jump);
+        app.finish_mark(Some('a'));
+        assert_eq!(app.selected_heading_text(), Some("C"));
+        assert!(!app.collapsed_headings.contains("A"));
+
+        // A non-letter cancels, and unknown marks only report
+        app.pending_mark = Some(MarkAction::Set);
+        app.finish_mark(Some('1'));
+        assert!(app.pending_mark.is_none());
+        assert_eq!(app.marks.len(), 1);
+        app.jump_to_mark('z');
+        assert_eq!(app.selected_heading_text(), Some("C"));
+        assert!(app.status_message.as_deref().unwrap().contains("'z' not set"));
+    }
+
+    #[test]
+    fn test_breadcrumb_follows_selection() {
+        let doc =
+            crate::parse_markdown("# Usage\n## Installation\n### Linux\n### macOS\n# FAQ\n");
+        let mut app = App::new(
+            doc,
+            "t.md".to_string(),
+            PathBuf::from("t.md"),
+            Config::default(),
+            ColorMode::Rgb,
+            OutlineExpansion::ExpandAll,
+        );
+
+        assert!(app.select_by_text("macOS"));
+        assert_eq!(app.breadcrumb(), ["Usage", "Installation", "macOS"]);
+        assert!(app.select_by_text("FAQ"));
+        assert_eq!(app.breadcrumb(), ["FAQ"]);
+    }
+}
@@ -0,0 +1,126 @@
+//! External editor integration.
+//!
+//! Builds the command line used to open a file in the user's editor,
+//! positioned at a given line when the editor supports it.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Resolve the editor command line from `$VISUAL` / `$EDITOR`.
+///
+/// Falls back to the system default editor (see [`edit::get_editor`]) when
+/// neither variable is set. Returns `None` if no editor can be found.
+pub fn resolve_editor() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .or_else(|| {
+            edit::get_editor()
+                .ok()
+                .map(|path| path.to_string_lossy().into_owned())
+        })
+}
+
+/// Build the program and arguments to open `path` at `line` in `editor`.
+///
+/// `editor` is a command line such as `"nvim"` or `"code --wait"`. Editors are
+/// recognised by program name:
+///
+/// - vi/vim/nvim/nano/emacs/micro/kak: `+LINE file`
+/// - VS Code and forks: `--goto file:LINE`
+/// - helix/sublime/zed: `file:LINE`
+///
+/// Unknown editors just get the file path.
+pub fn editor_args(editor: &str, path: &Path, line: Option<usize>) -> (String, Vec<String>) {
+    let mut parts = editor.split_whitespace().map(String::from);
+    let program = parts.next().unwrap_or_else(|| "vi".to_string());
+    let mut args: Vec<String> = parts.collect();
+
+    let name = Path::new(&program)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let file = path.to_string_lossy().into_owned();
+
+    match line {
+        Some(line) => match name.as_str() {
+            "vi" | "vim" | "nvim" | "gvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" => {
+                args.push(format!("+{}", line));
+                args.push(file);
+            }
+            "code" | "code-insiders" | "codium" | "cursor" => {
+                args.push("--goto".to_string());
+                args.push(format!("{}:{}", file, line));
+            }
+            "hx" | "helix" | "subl" | "zed" => args.push(format!("{}:{}", file, line)),
+            _ => args.push(file),
+        },
+        None => args.push(file),
+    }
+
+    (program, args)
+}
+
+/// Open `path` in `editor` at `line`, blocking until the editor exits.
+pub fn open_in_editor(editor: &str, path: &Path, line: Option<usize>) -> std::io::Result<()> {
+    let (program, args) = editor_args(editor, path, line);
+    let status = Command::new(&program).args(&args).status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_args_with_line() {
+        let path = Path::new("README.md");
+
+        assert_eq!(
+            editor_args("nvim", path, Some(42)),
+            (
+                "nvim".to_string(),
+                vec!["+42".to_string(), "README.md".to_string()]
+            )
+        );
+        assert_eq!(
+            editor_args("/usr/bin/code --wait", path, Some(7)),
+            (
+                "/usr/bin/code".to_string(),
+                vec![
+                    "--wait".to_string(),
+                    "--goto".to_string(),
+                    "README.md:7".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            editor_args("hx", path, Some(3)).1,
+            vec!["README.md:3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_editor_args_without_line_or_unknown_editor() {
+        let path = Path::new("notes.md");
+
+        assert_eq!(
+            editor_args("vim", path, None).1,
+            vec!["notes.md".to_string()]
+        );
+        assert_eq!(
+            editor_args("ed", path, Some(10)),
+            ("ed".to_string(), vec!["notes.md".to_string()])
+        );
+    }
+}
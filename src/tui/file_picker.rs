@@ -0,0 +1,128 @@
+//! Picker for opening sibling markdown files
+//!
+//! Lists the markdown files next to the open document so another one can be
+//! opened without leaving the TUI.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most files remembered as recently opened
+pub const MAX_RECENT: usize = 10;
+
+/// Markdown files (`.md`, `.markdown`) directly in `dir`, sorted by name.
+///
+/// Paths are `dir` joined with the file name; an empty `dir` means the
+/// working directory. Symlinks are left out, as with followed links.
+pub fn markdown_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let read_from = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(read_from)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let path = dir.join(entry.file_name());
+        let is_markdown = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+            });
+        if is_markdown {
+            files.push(path);
+        }
+    }
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    Ok(files)
+}
+
+/// Move `path` to the front of `recent`, keeping at most [`MAX_RECENT`]
+pub fn push_recent(recent: &mut Vec<PathBuf>, path: &Path) {
+    recent.retain(|p| p != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(MAX_RECENT);
+}
+
+/// File picker state
+#[derive(Debug, Clone, Default)]
+pub struct FilePicker {
+    /// Files to choose from
+    pub files: Vec<PathBuf>,
+    /// Index of the highlighted file
+    pub selected: usize,
+}
+
+impl FilePicker {
+    /// A picker over `files` with `current` highlighted, if listed
+    pub fn new(files: Vec<PathBuf>, current: &Path) -> Self {
+        let selected = files
+            .iter()
+            .position(|f| f.file_name() == current.file_name())
+            .unwrap_or(0);
+        Self { files, selected }
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.files.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// The highlighted file, if there are any
+    pub fn selected_file(&self) -> Option<&Path> {
+        self.files.get(self.selected).map(PathBuf::as_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_files_filters_and_sorts() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.md", "a.markdown", "C.MD", "notes.txt", "md", "z.md.bak"] {
+            std::fs::write(dir.path().join(name), "# x\n").unwrap();
+        }
+        std::fs::create_dir(dir.path().join("sub.md")).unwrap();
+
+        let files = markdown_files(dir.path()).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["C.MD", "a.markdown", "b.md"]);
+
+        let mut picker = FilePicker::new(files.clone(), &files[2]);
+        assert_eq!(picker.selected_file(), Some(files[2].as_path()));
+        picker.next();
+        assert_eq!(picker.selected, 2);
+        picker.previous();
+        picker.previous();
+        picker.previous();
+        assert_eq!(picker.selected_file(), Some(files[0].as_path()));
+    }
+
+    #[test]
+    fn test_push_recent_moves_to_front() {
+        let mut recent = Vec::new();
+        for name in ["a.md", "b.md", "a.md"] {
+            push_recent(&mut recent, Path::new(name));
+        }
+        assert_eq!(recent, vec![PathBuf::from("a.md"), PathBuf::from("b.md")]);
+
+        for i in 0..20 {
+            push_recent(&mut recent, &PathBuf::from(format!("{}.md", i)));
+        }
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0], PathBuf::from("19.md"));
+    }
+}
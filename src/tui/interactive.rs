@@ -8,7 +8,7 @@
 //! - Tables (navigate cells)
 //! - Images (view info)
 
-use crate::parser::output::{Block, InlineElement};
+use crate::parser::output::{Block, InlineElement, ListItem};
 use crate::parser::{Link, LinkTarget};
 use std::collections::HashMap;
 
@@ -118,7 +118,7 @@ impl InteractiveState {
     }
 
     /// Build element index from parsed blocks
-    pub fn index_elements(&mut self, blocks: &[Block]) {
+    pub fn index_elements(&mut self, blocks: &[&Block]) {
         self.elements.clear();
         let mut current_line = 0;
 
@@ -129,6 +129,7 @@ impl InteractiveState {
                 Block::Details {
                     summary,
                     blocks: nested,
+                    open,
                     ..
                 } => {
                     // Add details block as interactive element
@@ -137,6 +138,11 @@ impl InteractiveState {
                         sub_idx: None,
                     };
 
+                    // Initialize state if not exists, honoring the `open` attribute
+                    self.element_states
+                        .entry(id)
+                        .or_insert(ElementState::Details { expanded: *open });
+
                     // Count lines for this details block
                     let lines = 1 + if self.is_details_expanded(id) {
                         count_block_lines(nested)
@@ -153,11 +159,6 @@ impl InteractiveState {
                         line_range: (start_line, start_line + lines),
                     });
 
-                    // Initialize state if not exists
-                    self.element_states
-                        .entry(id)
-                        .or_insert(ElementState::Details { expanded: false });
-
                     current_line += lines;
                 }
                 Block::Paragraph { inline, .. } => {
@@ -218,7 +219,7 @@ impl InteractiveState {
                                 line_range: (current_line, current_line + 1),
                             });
                         }
-                        current_line += 1;
+                        current_line += count_list_item_lines(item);
                     }
                 }
                 Block::Code {
@@ -620,16 +621,27 @@ fn count_block_lines(blocks: &[Block]) -> usize {
     blocks.iter().map(count_single_block_lines).sum()
 }
 
+/// Count lines for a list item including its nested items
+fn count_list_item_lines(item: &ListItem) -> usize {
+    1 + item
+        .children
+        .iter()
+        .map(count_list_item_lines)
+        .sum::<usize>()
+}
+
 /// Count lines for a single block
 fn count_single_block_lines(block: &Block) -> usize {
     match block {
         Block::Paragraph { .. } => 1,
         Block::Code { content, .. } => 2 + content.lines().count(),
-        Block::List { items, .. } => items.len(),
+        Block::List { items, .. } => items.iter().map(count_list_item_lines).sum(),
         Block::Blockquote { blocks, .. } => count_block_lines(blocks),
         Block::Table { rows, .. } => 3 + rows.len(),
         Block::Image { .. } => 1,
         Block::HorizontalRule => 1,
+        Block::Html { raw } => raw.lines().count(),
         Block::Details { blocks, .. } => 1 + count_block_lines(blocks),
+        Block::Footnote { blocks, .. } => count_block_lines(blocks),
     }
 }
@@ -1,36 +1,58 @@
 mod app;
+mod editor;
+mod file_picker;
 mod interactive;
+mod preview;
+mod query_bar;
+mod replace;
 mod syntax;
 pub mod terminal_compat;
 pub mod theme;
 pub mod tty;  // Public module for TTY handling
 mod ui;
+mod wrap;
 
-pub use app::App;
+pub use app::{App, OutlineExpansion};
 pub use interactive::InteractiveState;
+pub use preview::theme_preview;
+pub use query_bar::QueryBar;
 pub use terminal_compat::{ColorMode, TerminalCapabilities};
-pub use theme::ThemeName;
+pub use theme::{HeadingStyle, OutlineStyle, ThemeName};
 
 use color_eyre::Result;
 use crossterm::ExecutableCommand;
-use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+};
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 use ratatui::DefaultTerminal;
 use std::io::stdout;
 
-/// Suspend the TUI, run an external editor, then restore the TUI
-fn run_editor(terminal: &mut DefaultTerminal, file_path: &std::path::PathBuf) -> Result<()> {
+/// Suspend the TUI, run an external editor at `line`, then restore the TUI
+fn run_editor(
+    terminal: &mut DefaultTerminal,
+    editor: &str,
+    file_path: &std::path::Path,
+    line: Option<usize>,
+    mouse: bool,
+) -> Result<()> {
     // Leave alternate screen and disable raw mode to give editor full terminal control
+    if mouse {
+        stdout().execute(DisableMouseCapture)?;
+    }
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
 
     // Open file in editor (blocks until editor closes)
-    let result = edit::edit_file(file_path);
+    let result = editor::open_in_editor(editor, file_path, line);
 
     // Restore terminal state
     stdout().execute(EnterAlternateScreen)?;
+    if mouse {
+        stdout().execute(EnableMouseCapture)?;
+    }
     enable_raw_mode()?;
     terminal.clear()?;
 
@@ -57,7 +79,30 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
     loop {
         terminal.draw(|frame| ui::render(frame, &mut app))?;
 
-        if let Event::Key(key) = tty::read_event()? {
+        // A query typed in the query bar runs once typing pauses
+        if let Some(wait) = app.query_bar.debounce_remaining(std::time::Instant::now()) {
+            if !tty::poll_event(wait)? {
+                app.run_query();
+                continue;
+            }
+        }
+
+        let event = tty::read_event()?;
+
+        // Mouse input only applies to the main view, not popups or modes
+        if let Event::Mouse(mouse) = event {
+            if app.mouse
+                && app.mode == app::AppMode::Normal
+                && !app.show_help
+                && !app.show_theme_picker
+                && !app.show_search
+            {
+                app.handle_mouse(mouse);
+            }
+            continue;
+        }
+
+        if let Event::Key(key) = event {
             if key.kind == KeyEventKind::Press {
                 // Handle help mode scrolling
                 if app.show_help {
@@ -310,6 +355,55 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
                         _ => {}
                     }
                 }
+                // Handle the query bar: typing, then reading the results
+                else if app.mode == app::AppMode::Query {
+                    if app.query_bar.editing {
+                        match key.code {
+                            KeyCode::Esc => app.close_query_bar(),
+                            KeyCode::Enter => app.submit_query(),
+                            KeyCode::Char(c) => app.query_bar.input(c),
+                            KeyCode::Backspace => app.query_bar.backspace(),
+                            _ => {}
+                        }
+                    } else {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') => app.close_query_bar(),
+                            KeyCode::Char(':') => app.open_query_bar(None),
+                            KeyCode::Char('j') | KeyCode::Down => app.query_bar.scroll_down(),
+                            KeyCode::Char('k') | KeyCode::Up => app.query_bar.scroll_up(),
+                            _ => {}
+                        }
+                    }
+                }
+                // Handle the find/replace preview: nothing is written to disk
+                else if app.mode == app::AppMode::Replace {
+                    match key.code {
+                        KeyCode::Esc => app.close_replace(),
+                        KeyCode::Tab | KeyCode::BackTab => app.replace_prompt.toggle_field(),
+                        KeyCode::Enter => app.replace_prompt.toggle_result(),
+                        KeyCode::Down | KeyCode::PageDown => app.replace_prompt.scroll_down(),
+                        KeyCode::Up | KeyCode::PageUp => app.replace_prompt.scroll_up(),
+                        KeyCode::Char(c) => app.replace_input(c),
+                        KeyCode::Backspace => app.replace_backspace(),
+                        _ => {}
+                    }
+                }
+                // Handle the file picker
+                else if app.mode == app::AppMode::FilePicker {
+                    match key.code {
+                        KeyCode::Esc => app.close_file_picker(),
+                        KeyCode::Enter => {
+                            if let Err(e) = app.open_selected_file() {
+                                app.status_message = Some(format!("✗ Error: {}", e));
+                            }
+                            app.update_content_metrics();
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.file_picker.next(),
+                        KeyCode::Char('k') | KeyCode::Up => app.file_picker.previous(),
+                        KeyCode::Char('q') => return Ok(()),
+                        _ => {}
+                    }
+                }
                 // Handle link follow mode
                 else if app.mode == app::AppMode::LinkFollow {
                     // Clear status message on any key press in link mode
@@ -356,8 +450,8 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
                     match key.code {
                         KeyCode::Esc => app.toggle_search(),
                         KeyCode::Enter => {
-                            app.toggle_search();
-                            // Keep the filtered results
+                            // Keep the filtered results and highlight matches
+                            app.confirm_search();
                         }
                         KeyCode::Char(c) => app.search_input(c),
                         KeyCode::Backspace => app.search_backspace(),
@@ -370,9 +464,20 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
                     }
 
                     match key.code {
+                        // The letter after `m` or `` ` `` names the mark
+                        code if app.pending_mark.is_some() => {
+                            let name = match code {
+                                KeyCode::Char(c) => Some(c),
+                                _ => None,
+                            };
+                            app.finish_mark(name);
+                            app.update_content_metrics();
+                        }
                         KeyCode::Char('q') | KeyCode::Esc if !app.show_help => return Ok(()),
                         KeyCode::Char('?') => app.toggle_help(),
                         KeyCode::Char('/') => app.toggle_search(),
+                        KeyCode::Char(':') => app.open_query_bar(None),
+                        KeyCode::Char('R') => app.open_replace(),
                         KeyCode::Esc if app.show_help => app.toggle_help(),
                         KeyCode::Char('j') | KeyCode::Down => app.next(),
                         KeyCode::Char('k') | KeyCode::Up => app.previous(),
@@ -385,11 +490,22 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
                         KeyCode::Tab => app.toggle_focus(),
                         KeyCode::Char('h') | KeyCode::Left => app.collapse(),
                         KeyCode::Char('l') | KeyCode::Right => app.expand(),
+                        KeyCode::Char('z') => app.toggle_collapse_all(),
                         // New UX features
                         KeyCode::Char('w') => app.toggle_outline(),
+                        KeyCode::Char('O') => app.cycle_outline_style(),
+                        // Open another file
+                        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            if let Err(e) = app.switch_to_recent() {
+                                app.status_message = Some(format!("✗ {}", e));
+                            }
+                            app.update_content_metrics();
+                        }
+                        KeyCode::Char('o') => app.open_file_picker(),
                         KeyCode::Char('[') => app.cycle_outline_width(false),
                         KeyCode::Char(']') => app.cycle_outline_width(true),
-                        KeyCode::Char('m') => app.set_bookmark(),
+                        KeyCode::Char('m') => app.pending_mark = Some(app::MarkAction::Set),
+                        KeyCode::Char('`') => app.pending_mark = Some(app::MarkAction::Jump),
                         KeyCode::Char('\'') => app.jump_to_bookmark(),
                         KeyCode::Char('1') => app.jump_to_heading(0),
                         KeyCode::Char('2') => app.jump_to_heading(1),
@@ -406,8 +522,22 @@ pub fn run(terminal: &mut DefaultTerminal, app: App) -> Result<()> {
                         KeyCode::Char('Y') => app.copy_anchor(),
                         // Edit file
                         KeyCode::Char('e') => {
-                            // Run editor with proper terminal suspend/restore
-                            match run_editor(terminal, &app.current_file_path) {
+                            // Run editor with proper terminal suspend/restore,
+                            // positioned at the selected section when possible
+                            let Some(editor) = editor::resolve_editor() else {
+                                app.status_message = Some(
+                                    "✗ No editor found: set $EDITOR or $VISUAL".to_string(),
+                                );
+                                continue;
+                            };
+                            let line = app.selected_heading_line();
+                            match run_editor(
+                                terminal,
+                                &editor,
+                                &app.current_file_path,
+                                line,
+                                app.mouse,
+                            ) {
                                 Ok(_) => {
                                     // Reload file after successful edit
                                     if let Err(e) = app.reload_current_file() {
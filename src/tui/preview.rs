@@ -0,0 +1,120 @@
+//! Theme previews for `--theme-preview`, printed as ANSI-colored text.
+
+use crate::tui::syntax::SyntaxHighlighter;
+use crate::tui::terminal_compat::ColorMode;
+use crate::tui::theme::{Theme, ThemeName};
+use crate::tui::ui::theme_preview_lines;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+
+/// Render a sample document in every built-in theme, one after another.
+pub fn theme_preview(color_mode: ColorMode) -> String {
+    let highlighter = SyntaxHighlighter::new();
+
+    ThemeName::ALL
+        .iter()
+        .map(|&name| {
+            let theme = Theme::from_name(name).with_color_mode(color_mode, name);
+            to_ansi(&theme_preview_lines(&theme, &highlighter))
+        })
+        .collect()
+}
+
+/// Convert styled lines to text with ANSI escape sequences.
+fn to_ansi(lines: &[Line]) -> String {
+    let mut out = String::new();
+    for line in lines {
+        for span in &line.spans {
+            // Highlighted code keeps each line's newline in its last span
+            let content = span.content.trim_end_matches('\n');
+            let codes = sgr_codes(line.style.patch(span.style));
+            if codes.is_empty() || content.is_empty() {
+                out.push_str(content);
+            } else {
+                out.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), content));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// SGR parameters for a style.
+fn sgr_codes(style: Style) -> Vec<String> {
+    let mut codes = Vec::new();
+
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    for (modifier, code) in modifiers {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+
+    codes.extend(style.fg.and_then(|color| color_code(color, false)));
+    codes.extend(style.bg.and_then(|color| color_code(color, true)));
+    codes
+}
+
+/// SGR parameter for a foreground or background color.
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let (prefix, offset) = if background { ("48", 10) } else { ("38", 0) };
+    let basic = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{};{};{}", prefix, r, g, b)),
+        Color::Indexed(i) => return Some(format!("{};5;{}", prefix, i)),
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+    };
+    Some((basic + offset).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_renders_every_theme() {
+        for mode in [ColorMode::Rgb, ColorMode::Indexed256] {
+            let preview = theme_preview(mode);
+            for name in ThemeName::ALL {
+                let theme = Theme::from_name(name);
+                assert!(preview.contains(theme.name), "{} missing", theme.name);
+            }
+            assert!(preview.contains("inline code"));
+            assert!(preview.contains("Hello, treemd!"));
+            assert!(preview.contains("\x1b["));
+        }
+    }
+
+    #[test]
+    fn test_sgr_codes() {
+        let style = Style::default()
+            .fg(Color::Rgb(1, 2, 3))
+            .bg(Color::Indexed(236))
+            .add_modifier(Modifier::BOLD);
+        assert_eq!(sgr_codes(style), ["1", "38;2;1;2;3", "48;5;236"]);
+        assert_eq!(color_code(Color::Red, true), Some("41".to_string()));
+        assert!(sgr_codes(Style::default()).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! Live query bar
+//!
+//! Runs tql queries against the open document while they are typed. A
+//! keystroke only schedules a run; the event loop performs it once typing
+//! pauses for [`DEBOUNCE`], so a fast typist doesn't re-run the query for
+//! every character of a long document.
+
+use crate::parser::Document;
+use crate::query::{self, OutputFormat};
+use std::time::{Duration, Instant};
+
+/// Pause in typing after which a scheduled query runs
+pub const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Outcome of the last query run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryOutput {
+    /// Results as plain text, one per line, with how many there were
+    Results { text: String, count: usize },
+    /// The query failed; the error formatted with its source snippet
+    Error(String),
+}
+
+/// Query bar state
+///
+/// While `editing`, keys go to the input. Submitting a query that runs
+/// without error stops editing but keeps the results up for scrolling.
+#[derive(Debug, Clone, Default)]
+pub struct QueryBar {
+    /// The query text
+    pub input: String,
+    /// Whether keys are typed into the input
+    pub editing: bool,
+    /// Result of the last run, if any
+    pub output: Option<QueryOutput>,
+    /// Lines scrolled in the results
+    pub scroll: u16,
+    /// When the input changed without the query being run since
+    pending_since: Option<Instant>,
+}
+
+impl QueryBar {
+    /// Maximum query length
+    const MAX_LEN: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start editing, keeping the previous query so it can be refined.
+    pub fn open(&mut self) {
+        self.editing = true;
+        if !self.input.is_empty() && self.output.is_none() {
+            self.schedule();
+        }
+    }
+
+    /// Stop editing and drop the results. The query text is kept for the next `open`.
+    pub fn close(&mut self) {
+        self.editing = false;
+        self.output = None;
+        self.scroll = 0;
+        self.pending_since = None;
+    }
+
+    pub fn input(&mut self, c: char) {
+        if self.input.len() >= Self::MAX_LEN || (c.is_control() && c != '\t') {
+            return;
+        }
+        self.input.push(c);
+        self.schedule();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.input.pop().is_some() {
+            self.schedule();
+        }
+    }
+
+    fn schedule(&mut self) {
+        self.pending_since = Some(Instant::now());
+    }
+
+    /// How long until the scheduled run is due (zero if overdue), or `None`
+    /// if no run is scheduled.
+    pub fn debounce_remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending_since
+            .map(|since| DEBOUNCE.saturating_sub(now.saturating_duration_since(since)))
+    }
+
+    /// Run the query now. An empty query clears the results.
+    pub fn run(&mut self, doc: &Document) {
+        self.pending_since = None;
+        self.scroll = 0;
+        self.output = if self.input.trim().is_empty() {
+            None
+        } else {
+            Some(match query::execute(doc, &self.input) {
+                Ok(values) => QueryOutput::Results {
+                    text: query::format_output(&values, OutputFormat::Plain),
+                    count: values.len(),
+                },
+                Err(e) => QueryOutput::Error(e.format()),
+            })
+        };
+    }
+
+    /// Run the query and stop editing, unless it failed (so it can be fixed)
+    /// or is empty.
+    pub fn submit(&mut self, doc: &Document) {
+        self.run(doc);
+        if matches!(self.output, Some(QueryOutput::Results { .. })) {
+            self.editing = false;
+        }
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_bar_state_machine() {
+        let doc = Document::parse("# Guide\n\n## Install\n\n## Usage\n");
+        let mut bar = QueryBar::new();
+
+        // Open and type: each key schedules a run instead of running it
+        bar.open();
+        assert!(bar.editing);
+        for c in ".h2 | text".chars() {
+            bar.input(c);
+        }
+        assert_eq!(bar.input, ".h2 | text");
+        assert!(bar.output.is_none());
+        let later = Instant::now() + DEBOUNCE;
+        assert_eq!(bar.debounce_remaining(later), Some(Duration::ZERO));
+
+        // The scheduled run shows the results live
+        bar.run(&doc);
+        assert_eq!(bar.debounce_remaining(later), None);
+        assert_eq!(
+            bar.output,
+            Some(QueryOutput::Results {
+                text: "Install\nUsage".to_string(),
+                count: 2
+            })
+        );
+
+        // A broken query shows the formatted error, and submitting it keeps editing
+        bar.input.clear();
+        for c in "[.h2] | nope".chars() {
+            bar.input(c);
+        }
+        bar.submit(&doc);
+        assert!(bar.editing);
+        match &bar.output {
+            Some(QueryOutput::Error(message)) => assert!(message.starts_with("error: ")),
+            other => panic!("expected an error, got {:?}", other),
+        }
+
+        // Fixing and submitting leaves the results up without editing
+        for _ in 0..4 {
+            bar.backspace();
+        }
+        for c in "count".chars() {
+            bar.input(c);
+        }
+        bar.submit(&doc);
+        assert!(!bar.editing);
+        assert_eq!(
+            bar.output,
+            Some(QueryOutput::Results {
+                text: "2".to_string(),
+                count: 1
+            })
+        );
+
+        // Closing drops the results but remembers the query
+        bar.close();
+        assert!(!bar.editing);
+        assert!(bar.output.is_none());
+        assert_eq!(bar.debounce_remaining(later), None);
+        bar.open();
+        assert_eq!(bar.input, "[.h2] | count");
+        assert!(bar.debounce_remaining(later).is_some());
+    }
+}
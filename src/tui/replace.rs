@@ -0,0 +1,306 @@
+//! Search-and-replace preview
+//!
+//! Shows what a regex find/replace would do to the whole document, hunk by
+//! hunk, without touching the file. Replacements are computed over the raw
+//! markdown, so the preview shows exactly the text that would change.
+
+use regex::Regex;
+use std::ops::Range;
+
+/// Largest compiled pattern accepted, so a runaway pattern can't stall the UI
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// One match and the text that would replace it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Byte range of the match in the content
+    pub range: Range<usize>,
+    /// Replacement with `$1`/`${name}` references expanded
+    pub text: String,
+}
+
+/// Every match of `pattern` in `content`, with `replacement` expanded for it
+pub fn replacement_ranges(content: &str, pattern: &Regex, replacement: &str) -> Vec<Replacement> {
+    pattern
+        .captures_iter(content)
+        .map(|caps| {
+            let mut text = String::new();
+            caps.expand(replacement, &mut text);
+            Replacement {
+                range: caps.get(0).map_or(0..0, |m| m.range()),
+                text,
+            }
+        })
+        .collect()
+}
+
+/// The content with every replacement applied
+pub fn apply_replacements(content: &str, replacements: &[Replacement]) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for replacement in replacements {
+        result.push_str(&content[last..replacement.range.start]);
+        result.push_str(&replacement.text);
+        last = replacement.range.end;
+    }
+    result.push_str(&content[last..]);
+    result
+}
+
+/// A piece of a previewed hunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Text left as it is
+    Same(&'a str),
+    /// Matched text that would be removed
+    Removed(&'a str),
+    /// Text that would be inserted in its place
+    Added(&'a str),
+}
+
+/// The whole lines touched by one or more neighbouring replacements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    /// 1-based number of the first line
+    pub line: usize,
+    pub segments: Vec<Segment<'a>>,
+}
+
+/// Group replacements into hunks of the lines they touch. Replacements
+/// touching the same line share a hunk.
+pub fn preview_hunks<'a>(content: &'a str, replacements: &'a [Replacement]) -> Vec<Hunk<'a>> {
+    let line_start = |offset: usize| content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = |offset: usize| {
+        content[offset..]
+            .find('\n')
+            .map_or(content.len(), |i| offset + i)
+    };
+
+    let mut hunks: Vec<(Range<usize>, Vec<&Replacement>)> = Vec::new();
+    for replacement in replacements {
+        let start = line_start(replacement.range.start);
+        let end = line_end(replacement.range.end);
+        match hunks.last_mut() {
+            // Replacements on the same or the next line join the hunk
+            Some((lines, members)) if start <= lines.end + 1 => {
+                lines.end = lines.end.max(end);
+                members.push(replacement);
+            }
+            _ => hunks.push((start..end, vec![replacement])),
+        }
+    }
+
+    let mut line = 1;
+    let mut counted = 0;
+    hunks
+        .into_iter()
+        .map(|(lines, members)| {
+            line += content[counted..lines.start].matches('\n').count();
+            counted = lines.start;
+
+            let mut segments = Vec::new();
+            let mut last = lines.start;
+            for replacement in members {
+                let Range { start, end } = replacement.range;
+                if last < start {
+                    segments.push(Segment::Same(&content[last..start]));
+                }
+                if start < end {
+                    segments.push(Segment::Removed(&content[start..end]));
+                }
+                if !replacement.text.is_empty() {
+                    segments.push(Segment::Added(&replacement.text));
+                }
+                last = end;
+            }
+            if last < lines.end {
+                segments.push(Segment::Same(&content[last..lines.end]));
+            }
+            Hunk { line, segments }
+        })
+        .collect()
+}
+
+/// Which prompt field keys are typed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaceField {
+    Pattern,
+    Replacement,
+}
+
+/// Find/replace prompt state and the replacements it would make
+#[derive(Debug, Clone)]
+pub struct ReplacePrompt {
+    /// Regex to find
+    pub pattern: String,
+    /// Replacement, with `$1`/`${name}` for capture groups
+    pub replacement: String,
+    pub field: ReplaceField,
+    /// Replacements for the current pattern, or why the pattern is invalid.
+    /// Empty when there is no pattern.
+    pub result: Result<Vec<Replacement>, String>,
+    /// Show the whole document as it would be, instead of the changed hunks
+    pub show_result: bool,
+    /// Lines scrolled in the preview
+    pub scroll: u16,
+}
+
+impl ReplacePrompt {
+    /// Maximum length of each field
+    const MAX_LEN: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            pattern: String::new(),
+            replacement: String::new(),
+            field: ReplaceField::Pattern,
+            result: Ok(Vec::new()),
+            show_result: false,
+            scroll: 0,
+        }
+    }
+
+    fn field_mut(&mut self) -> &mut String {
+        match self.field {
+            ReplaceField::Pattern => &mut self.pattern,
+            ReplaceField::Replacement => &mut self.replacement,
+        }
+    }
+
+    /// Type into the current field and refresh the replacements
+    pub fn input(&mut self, c: char, content: &str) {
+        let field = self.field_mut();
+        if field.len() >= Self::MAX_LEN || c.is_control() {
+            return;
+        }
+        field.push(c);
+        self.update(content);
+    }
+
+    pub fn backspace(&mut self, content: &str) {
+        if self.field_mut().pop().is_some() {
+            self.update(content);
+        }
+    }
+
+    pub fn toggle_field(&mut self) {
+        self.field = match self.field {
+            ReplaceField::Pattern => ReplaceField::Replacement,
+            ReplaceField::Replacement => ReplaceField::Pattern,
+        };
+    }
+
+    /// Switch between the changed hunks and the whole result
+    pub fn toggle_result(&mut self) {
+        self.show_result = !self.show_result;
+        self.scroll = 0;
+    }
+
+    /// Recompute the replacements for `content`
+    pub fn update(&mut self, content: &str) {
+        self.scroll = 0;
+        self.result = if self.pattern.is_empty() {
+            Ok(Vec::new())
+        } else {
+            regex::RegexBuilder::new(&self.pattern)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .build()
+                .map(|re| replacement_ranges(content, &re, &self.replacement))
+                .map_err(|e| e.to_string())
+        };
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement_ranges_and_preview() {
+        let content = "# Setup\n\nRun `cargo build`.\nThen cargo test.\n\nDone.\n";
+        let re = Regex::new(r"cargo (\w+)").unwrap();
+        let replacements = replacement_ranges(content, &re, "just $1");
+
+        assert_eq!(
+            replacements,
+            vec![
+                Replacement {
+                    range: 14..25,
+                    text: "just build".to_string()
+                },
+                Replacement {
+                    range: 33..43,
+                    text: "just test".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            apply_replacements(content, &replacements),
+            "# Setup\n\nRun `just build`.\nThen just test.\n\nDone.\n"
+        );
+
+        // Adjacent lines with matches form one hunk, from line 3
+        let hunks = preview_hunks(content, &replacements);
+        assert_eq!(
+            hunks,
+            vec![Hunk {
+                line: 3,
+                segments: vec![
+                    Segment::Same("Run `"),
+                    Segment::Removed("cargo build"),
+                    Segment::Added("just build"),
+                    Segment::Same("`.\nThen "),
+                    Segment::Removed("cargo test"),
+                    Segment::Added("just test"),
+                    Segment::Same("."),
+                ],
+            }]
+        );
+
+        // Lines further apart are separate hunks, with their own line numbers
+        let re = Regex::new("(?m)^(# |Done)").unwrap();
+        let replacements = replacement_ranges(content, &re, "");
+        let hunks = preview_hunks(content, &replacements);
+        assert_eq!(hunks.iter().map(|h| h.line).collect::<Vec<_>>(), vec![1, 6]);
+        assert_eq!(
+            hunks[0].segments,
+            vec![Segment::Removed("# "), Segment::Same("Setup")]
+        );
+        assert_eq!(
+            hunks[1].segments,
+            vec![Segment::Removed("Done"), Segment::Same(".")]
+        );
+    }
+
+    #[test]
+    fn test_replace_prompt() {
+        let content = "alpha beta alpha";
+        let mut prompt = ReplacePrompt::new();
+        for c in "alp(".chars() {
+            prompt.input(c, content);
+        }
+        assert!(prompt.result.is_err());
+
+        prompt.backspace(content);
+        prompt.toggle_field();
+        for c in "omega".chars() {
+            prompt.input(c, content);
+        }
+        assert_eq!(prompt.pattern, "alp");
+        assert_eq!(prompt.replacement, "omega");
+        let replacements = prompt.result.clone().unwrap();
+        assert_eq!(replacements.len(), 2);
+        assert_eq!(
+            apply_replacements(content, &replacements),
+            "omegaha beta omegaha"
+        );
+    }
+}
@@ -29,6 +29,15 @@ impl TerminalCapabilities {
 
         let macos_version = Self::detect_macos_version();
 
+        Self::from_environment(is_terminal_app, supports_rgb, macos_version)
+    }
+
+    /// Recommend a color mode from detected terminal facts
+    fn from_environment(
+        is_terminal_app: bool,
+        supports_rgb: bool,
+        macos_version: Option<u32>,
+    ) -> Self {
         // Determine if we should warn and which color mode to use
         let (should_warn, recommended_color_mode) = if is_terminal_app {
             match macos_version {
@@ -191,4 +200,32 @@ mod tests {
         let mode_copy = mode;
         assert_eq!(mode, mode_copy);
     }
+
+    #[test]
+    fn test_old_terminal_app_warns_and_falls_back_to_256() {
+        let caps = TerminalCapabilities::from_environment(true, false, Some(24));
+        assert!(caps.should_warn);
+        assert_eq!(caps.recommended_color_mode, ColorMode::Indexed256);
+
+        let message = caps.warning_message().unwrap();
+        assert!(message.starts_with("⚠️  Terminal Compatibility Notice\n\n"));
+        assert!(message.contains("Terminal.app on macOS Sequoia (Darwin 24) has limited"));
+        assert!(message.contains("Switching to 256-color mode"));
+        assert!(message.ends_with("Press any key to continue..."));
+
+        // Unknown macOS versions are treated as old
+        let caps = TerminalCapabilities::from_environment(true, true, None);
+        assert!(caps.warning_message().unwrap().contains("macOS < 26"));
+    }
+
+    #[test]
+    fn test_other_terminals_do_not_warn() {
+        let caps = TerminalCapabilities::from_environment(true, false, Some(26));
+        assert!(caps.warning_message().is_none());
+        assert_eq!(caps.recommended_color_mode, ColorMode::Rgb);
+
+        let caps = TerminalCapabilities::from_environment(false, false, None);
+        assert!(caps.warning_message().is_none());
+        assert_eq!(caps.recommended_color_mode, ColorMode::Indexed256);
+    }
 }
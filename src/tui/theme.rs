@@ -13,6 +13,104 @@ pub enum ThemeName {
     CatppuccinMocha,
 }
 
+/// How the focused H1/H2 title is drawn at the top of the content pane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeadingStyle {
+    /// No banner; the title only appears in the pane border
+    #[default]
+    Plain,
+    /// Title followed by a rule as wide as the title
+    Underline,
+    /// Title inside a box drawn with the border color
+    Boxed,
+}
+
+impl HeadingStyle {
+    /// Rows the banner takes up (including the blank line after it) for a
+    /// heading at `level`. Only H1 and H2 get a banner.
+    pub fn banner_height(self, level: usize) -> usize {
+        if level > 2 {
+            return 0;
+        }
+        match self {
+            HeadingStyle::Plain => 0,
+            HeadingStyle::Underline => 3,
+            HeadingStyle::Boxed => 4,
+        }
+    }
+}
+
+/// How headings are laid out in the TUI outline pane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutlineStyle {
+    /// Two spaces of indentation per level
+    #[default]
+    Indent,
+    /// Box-drawing connectors, like `--tree`
+    Tree,
+    /// No indentation; the `#` markers alone show the level
+    Compact,
+}
+
+impl OutlineStyle {
+    /// The style after this one, for cycling at runtime
+    pub fn next(self) -> Self {
+        match self {
+            OutlineStyle::Indent => OutlineStyle::Tree,
+            OutlineStyle::Tree => OutlineStyle::Compact,
+            OutlineStyle::Compact => OutlineStyle::Indent,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutlineStyle::Indent => "indent",
+            OutlineStyle::Tree => "tree",
+            OutlineStyle::Compact => "compact",
+        }
+    }
+}
+
+impl std::str::FromStr for OutlineStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "indent" => Ok(Self::Indent),
+            "tree" => Ok(Self::Tree),
+            "compact" => Ok(Self::Compact),
+            _ => Err(format!("Unknown outline style: {}", s)),
+        }
+    }
+}
+
+impl ThemeName {
+    /// Every built-in theme, in theme picker order
+    pub const ALL: [ThemeName; 8] = [
+        ThemeName::OceanDark,
+        ThemeName::Nord,
+        ThemeName::Dracula,
+        ThemeName::Solarized,
+        ThemeName::Monokai,
+        ThemeName::Gruvbox,
+        ThemeName::TokyoNight,
+        ThemeName::CatppuccinMocha,
+    ];
+}
+
+impl std::str::FromStr for HeadingStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "underline" => Ok(Self::Underline),
+            "boxed" => Ok(Self::Boxed),
+            _ => Err(format!("Unknown heading style: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub name: &'static str,
@@ -37,6 +135,7 @@ pub struct Theme {
     pub blockquote_border: Color,
     pub blockquote_fg: Color,
     pub code_fence: Color,
+    pub search_highlight: Color,
 }
 
 impl Theme {
@@ -91,6 +190,7 @@ impl Theme {
             blockquote_border: Color::Rgb(150, 150, 150),
             blockquote_fg: Color::Rgb(150, 150, 150),
             code_fence: Color::Rgb(150, 180, 200),
+            search_highlight: Color::Rgb(235, 203, 139),
         }
     }
 
@@ -119,6 +219,7 @@ impl Theme {
             blockquote_border: Color::Rgb(76, 86, 106),
             blockquote_fg: Color::Rgb(76, 86, 106),
             code_fence: Color::Rgb(143, 188, 187),
+            search_highlight: Color::Rgb(235, 203, 139),
         }
     }
 
@@ -147,6 +248,7 @@ impl Theme {
             blockquote_border: Color::Rgb(98, 114, 164),
             blockquote_fg: Color::Rgb(98, 114, 164),
             code_fence: Color::Rgb(189, 147, 249),
+            search_highlight: Color::Rgb(241, 250, 140),
         }
     }
 
@@ -175,6 +277,7 @@ impl Theme {
             blockquote_border: Color::Rgb(88, 110, 117),
             blockquote_fg: Color::Rgb(88, 110, 117),
             code_fence: Color::Rgb(42, 161, 152),
+            search_highlight: Color::Rgb(181, 137, 0),
         }
     }
 
@@ -203,6 +306,7 @@ impl Theme {
             blockquote_border: Color::Rgb(117, 113, 94),
             blockquote_fg: Color::Rgb(117, 113, 94),
             code_fence: Color::Rgb(102, 217, 239),
+            search_highlight: Color::Rgb(230, 219, 116),
         }
     }
 
@@ -231,6 +335,7 @@ impl Theme {
             blockquote_border: Color::Rgb(146, 131, 116),
             blockquote_fg: Color::Rgb(146, 131, 116),
             code_fence: Color::Rgb(131, 165, 152),
+            search_highlight: Color::Rgb(250, 189, 47),
         }
     }
 
@@ -259,6 +364,7 @@ impl Theme {
             blockquote_border: Color::Rgb(86, 95, 137), // Comment
             blockquote_fg: Color::Rgb(169, 177, 214),   // Fg dark
             code_fence: Color::Rgb(125, 207, 255),      // Cyan
+            search_highlight: Color::Rgb(224, 175, 104),
         }
     }
 
@@ -287,6 +393,7 @@ impl Theme {
             blockquote_border: Color::Rgb(108, 112, 134), // Overlay 0
             blockquote_fg: Color::Rgb(147, 153, 178),     // Overlay 2
             code_fence: Color::Rgb(116, 199, 236),        // Sapphire
+            search_highlight: Color::Rgb(249, 226, 175),
         }
     }
 
@@ -317,6 +424,7 @@ impl Theme {
             blockquote_border: Color::Indexed(246),
             blockquote_fg: Color::Indexed(246),
             code_fence: Color::Indexed(152),
+            search_highlight: Color::Indexed(222),
         }
     }
 
@@ -345,6 +453,7 @@ impl Theme {
             blockquote_border: Color::Indexed(240),
             blockquote_fg: Color::Indexed(240),
             code_fence: Color::Indexed(109),
+            search_highlight: Color::Indexed(222),
         }
     }
 
@@ -373,6 +482,7 @@ impl Theme {
             blockquote_border: Color::Indexed(61),
             blockquote_fg: Color::Indexed(61),
             code_fence: Color::Indexed(141), // Purple
+            search_highlight: Color::Indexed(228),
         }
     }
 
@@ -401,6 +511,7 @@ impl Theme {
             blockquote_border: Color::Indexed(240),
             blockquote_fg: Color::Indexed(240),
             code_fence: Color::Indexed(37), // Cyan
+            search_highlight: Color::Indexed(136),
         }
     }
 
@@ -429,6 +540,7 @@ impl Theme {
             blockquote_border: Color::Indexed(241),
             blockquote_fg: Color::Indexed(241),
             code_fence: Color::Indexed(81), // Cyan
+            search_highlight: Color::Indexed(186),
         }
     }
 
@@ -457,6 +569,7 @@ impl Theme {
             blockquote_border: Color::Indexed(243),
             blockquote_fg: Color::Indexed(243),
             code_fence: Color::Indexed(108), // Aqua
+            search_highlight: Color::Indexed(214),
         }
     }
 
@@ -485,6 +598,7 @@ impl Theme {
             blockquote_border: Color::Indexed(243),
             blockquote_fg: Color::Indexed(189),
             code_fence: Color::Indexed(117), // Cyan
+            search_highlight: Color::Indexed(179),
         }
     }
 
@@ -513,6 +627,7 @@ impl Theme {
             blockquote_border: Color::Indexed(242),
             blockquote_fg: Color::Indexed(245),
             code_fence: Color::Indexed(116), // Sapphire
+            search_highlight: Color::Indexed(223),
         }
     }
 
@@ -577,6 +692,14 @@ impl Theme {
         Style::default().fg(self.code_fence)
     }
 
+    /// Style patched onto text matching the current search
+    pub fn search_highlight_style(&self) -> Style {
+        Style::default()
+            .fg(Color::Black)
+            .bg(self.search_highlight)
+            .add_modifier(Modifier::BOLD)
+    }
+
     // Modal/popup color helpers (already respects color mode since theme is converted)
     pub fn modal_bg(&self) -> Color {
         self.selection_bg
@@ -621,12 +744,7 @@ impl Theme {
             ($field:ident) => {
                 if let Some(ref color_value) = custom.$field {
                     if let Some(color) = color_value.to_color() {
-                        // Quantize custom RGB colors if in 256-color mode
-                        self.$field = if matches!(mode, ColorMode::Indexed256) {
-                            rgb_to_256(color)
-                        } else {
-                            color
-                        };
+                        self.$field = color;
                     }
                 }
             };
@@ -653,8 +771,10 @@ impl Theme {
         apply_color!(blockquote_border);
         apply_color!(blockquote_fg);
         apply_color!(code_fence);
+        apply_color!(search_highlight);
 
-        self
+        // Quantize custom RGB colors if in 256-color mode
+        self.to_color_mode(mode)
     }
 
     /// Apply color mode to theme (use optimized 256-color variants or convert RGB)
@@ -668,9 +788,11 @@ impl Theme {
         }
     }
 
-    /// Apply color mode to custom theme (convert RGB to 256-color if needed)
-    /// This is used for custom themes that don't have optimized variants
-    pub fn with_color_mode_custom(mut self, mode: ColorMode) -> Self {
+    /// Adjust every color to the color mode, mapping RGB colors to the nearest
+    /// 256-color palette entry in [`ColorMode::Indexed256`].
+    ///
+    /// Used for custom colors, which have no optimized 256-color variants.
+    pub fn to_color_mode(mut self, mode: ColorMode) -> Self {
         match mode {
             ColorMode::Rgb => self,
             ColorMode::Indexed256 => {
@@ -695,6 +817,7 @@ impl Theme {
                 self.blockquote_border = rgb_to_256(self.blockquote_border);
                 self.blockquote_fg = rgb_to_256(self.blockquote_fg);
                 self.code_fence = rgb_to_256(self.code_fence);
+                self.search_highlight = rgb_to_256(self.search_highlight);
                 self
             }
         }
@@ -704,28 +827,75 @@ impl Theme {
 /// Convert RGB color to nearest 256-color palette entry
 fn rgb_to_256(color: Color) -> Color {
     match color {
-        Color::Rgb(r, g, b) => {
-            // Check if it's grayscale
-            if r == g && g == b {
-                // Map to grayscale ramp (232-255)
-                if r < 8 {
-                    return Color::Indexed(16); // Black
-                }
-                if r > 247 {
-                    return Color::Indexed(231); // White
-                }
-                let gray_index = ((r as f32 - 8.0) / 10.0).round() as u8;
-                return Color::Indexed(232 + gray_index);
-            }
-
-            // Map to 6x6x6 RGB cube (16-231)
-            let r_index = (r as f32 / 51.0).round() as u8;
-            let g_index = (g as f32 / 51.0).round() as u8;
-            let b_index = (b as f32 / 51.0).round() as u8;
-
-            Color::Indexed(16 + 36 * r_index + 6 * g_index + b_index)
-        }
+        Color::Rgb(r, g, b) => Color::Indexed(quantize_256(r, g, b)),
         // Already indexed or named color - pass through
         other => other,
     }
 }
+
+/// Channel levels of the xterm 6x6x6 color cube (palette 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Nearest xterm-256 palette index for an RGB color.
+///
+/// Picks whichever is closer of the nearest color cube entry (16-231) and the
+/// nearest step of the grayscale ramp (232-255). The first 16 colors are left
+/// out since terminals redefine them freely.
+pub fn quantize_256(r: u8, g: u8, b: u8) -> u8 {
+    let nearest_level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(c))
+            .unwrap_or(0)
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|&(a, b)| (a.abs_diff(b) as u32).pow(2))
+            .sum::<u32>()
+    };
+
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    // Grayscale ramp runs 8, 18, ..., 238
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23);
+    let gray = (8 + 10 * gray_step) as u8;
+
+    if distance((gray, gray, gray)) < distance(cube) {
+        232 + gray_step as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize_primaries_and_grays() {
+        assert_eq!(quantize_256(255, 0, 0), 196);
+        assert_eq!(quantize_256(0, 255, 0), 46);
+        assert_eq!(quantize_256(0, 0, 255), 21);
+        assert_eq!(quantize_256(0, 0, 0), 16);
+        assert_eq!(quantize_256(255, 255, 255), 231);
+        assert_eq!(quantize_256(128, 128, 128), 244);
+        // Just below white used to overflow the grayscale ramp
+        assert_eq!(quantize_256(247, 247, 247), 231);
+        // Off-cube colors land on the closest cube level, not a uniform step
+        assert_eq!(quantize_256(100, 200, 255), 81);
+    }
+
+    #[test]
+    fn test_to_color_mode_leaves_no_rgb() {
+        let theme = Theme::from_name(ThemeName::Nord).to_color_mode(ColorMode::Indexed256);
+        assert!(!matches!(theme.heading_1, Color::Rgb(..)));
+        assert!(!matches!(theme.background, Color::Rgb(..)));
+        assert!(!matches!(theme.search_highlight, Color::Rgb(..)));
+
+        let theme = Theme::from_name(ThemeName::Nord).to_color_mode(ColorMode::Rgb);
+        assert!(matches!(theme.heading_1, Color::Rgb(..)));
+    }
+}
@@ -8,9 +8,10 @@
 //! - Validates file descriptors before use
 //! - Proper cleanup on error paths
 
-use crossterm::event::{Event, read};
+use crossterm::event::{Event, poll, read};
 use std::io;
 use std::fs::File;
+use std::time::Duration;
 
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
@@ -148,11 +149,25 @@ pub fn disable_raw_mode() -> io::Result<()> {
 ///
 /// On Unix systems, when stdin is piped, this temporarily redirects
 /// stdin to /dev/tty for reading events, then restores it.
+#[cfg(unix)]
+pub fn read_event() -> io::Result<Event> {
+    with_tty_stdin(read)
+}
+
+/// Wait up to `timeout` for an event, handling piped stdin like [`read_event`]
+///
+/// Returns whether an event is ready to be read.
+#[cfg(unix)]
+pub fn poll_event(timeout: Duration) -> io::Result<bool> {
+    with_tty_stdin(|| poll(timeout))
+}
+
+/// Run a crossterm event call with stdin pointing at the terminal
 ///
 /// # Safety
 /// Uses unsafe libc calls for file descriptor manipulation with proper cleanup.
 #[cfg(unix)]
-pub fn read_event() -> io::Result<Event> {
+fn with_tty_stdin<T>(f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
     use std::os::unix::io::{AsRawFd, IntoRawFd};
 
     // Check if stdin is a TTY
@@ -161,7 +176,7 @@ pub fn read_event() -> io::Result<Event> {
     // SAFETY: isatty is safe to call with any fd
     if unsafe { libc::isatty(stdin_fd) } == 1 {
         // Stdin is a TTY, use normal event reading
-        return read();
+        return f();
     }
 
     // Stdin is piped - we need to read from /dev/tty
@@ -213,7 +228,7 @@ pub fn read_event() -> io::Result<Event> {
         libc::close(tty_fd);
 
         // Now read the event (crossterm will use the redirected stdin)
-        let result = read();
+        let result = f();
 
         // Restore original stdin (always, even if read failed)
         libc::dup2(saved_stdin, 0);
@@ -227,3 +242,8 @@ pub fn read_event() -> io::Result<Event> {
 pub fn read_event() -> io::Result<Event> {
     read()
 }
+
+#[cfg(not(unix))]
+pub fn poll_event(timeout: Duration) -> io::Result<bool> {
+    poll(timeout)
+}
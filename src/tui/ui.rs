@@ -1,5 +1,5 @@
-use crate::tui::app::{App, Focus};
-use crate::tui::theme::Theme;
+use crate::tui::app::{App, Focus, OutlineItem};
+use crate::tui::theme::{HeadingStyle, OutlineStyle, Theme};
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Flex, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
@@ -10,9 +10,6 @@ use ratatui::widgets::{
 };
 
 pub fn render(frame: &mut Frame, app: &mut App) {
-    // Update content metrics before rendering to ensure content height and scroll are correct
-    app.update_content_metrics();
-
     let area = frame.area();
 
     // Create main layout with title bar and content
@@ -34,6 +31,20 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         Layout::horizontal([Constraint::Percentage(100)]).split(main_chunks[1])
     };
 
+    // Update content metrics before rendering to ensure content height and scroll are correct.
+    // Wrapping depends on the width of the text column (minus borders and padding).
+    let content_area = content_chunks[content_chunks.len() - 1];
+    app.content_width = content_text_area(content_area, app.padding, app.max_width).width;
+    app.update_content_metrics();
+
+    // Remember pane positions for mouse hit-testing
+    app.outline_area = if app.show_outline {
+        content_chunks[0]
+    } else {
+        Rect::default()
+    };
+    app.content_area = content_area;
+
     // Render outline (left pane) only if visible
     if app.show_outline {
         render_outline(frame, app, content_chunks[0]);
@@ -63,11 +74,21 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         render_search_overlay(frame, app, area);
     }
 
+    // Render query bar and its results if open
+    if matches!(app.mode, crate::tui::app::AppMode::Query) {
+        render_query_bar(frame, app, area);
+    }
+
     // Render theme picker if shown
     if app.show_theme_picker {
         render_theme_picker(frame, app, area);
     }
 
+    // Render file picker if open
+    if matches!(app.mode, crate::tui::app::AppMode::FilePicker) {
+        render_file_picker(frame, app, area);
+    }
+
     // Render cell edit overlay if in cell edit mode
     if matches!(app.mode, crate::tui::app::AppMode::CellEdit) {
         render_cell_edit_overlay(frame, app, area);
@@ -77,12 +98,24 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     if matches!(app.mode, crate::tui::app::AppMode::LinkFollow) && !app.links_in_view.is_empty() {
         render_link_picker(frame, app, area);
     }
+
+    // Strip all colors when running without color (--no-color, NO_COLOR or config)
+    if app.no_color {
+        for cell in frame.buffer_mut().content.iter_mut() {
+            cell.set_fg(Color::Reset);
+            cell.set_bg(Color::Reset);
+        }
+    }
 }
 
 fn render_title_bar(frame: &mut Frame, app: &App, area: Rect) {
-    let heading_count = app.document.headings.len();
+    let heading_count = app.document.headings().len();
     let title_text = format!("treemd - {} - {} headings", app.filename, heading_count);
 
+    // Path to the selected heading fills the rest of the row, right-aligned
+    let room = (area.width as usize).saturating_sub(title_text.width() + 3);
+    let breadcrumb = format_breadcrumb(&app.breadcrumb(), room);
+
     let title = Paragraph::new(title_text)
         .style(
             Style::default()
@@ -91,44 +124,69 @@ fn render_title_bar(frame: &mut Frame, app: &App, area: Rect) {
         )
         .block(Block::default().borders(Borders::BOTTOM));
     frame.render_widget(title, area);
-}
 
-fn render_outline(frame: &mut Frame, app: &mut App, area: Rect) {
-    let theme = &app.theme;
-
-    let items: Vec<ListItem> = app
-        .outline_items
-        .iter()
-        .map(|item| {
-            let indent = "  ".repeat(item.level.saturating_sub(1));
-            let prefix = "#".repeat(item.level);
+    if !breadcrumb.is_empty() {
+        let row = Rect { height: 1, ..area };
+        let crumbs = Paragraph::new(breadcrumb)
+            .style(Style::default().fg(Color::Gray))
+            .alignment(ratatui::layout::Alignment::Right);
+        frame.render_widget(crumbs, row);
+    }
+}
 
-            // Show expand/collapse indicator if heading has children
-            let expand_indicator = if item.has_children {
-                if item.expanded { "▼ " } else { "▶ " }
-            } else {
-                "  "
-            };
+/// Join a heading path as `A › B › C` in at most `max_width` columns.
+///
+/// Outer headings are dropped first and replaced by `…`, so the selected
+/// heading stays visible; if even that doesn't fit it is cut with `…`.
+fn format_breadcrumb(path: &[&str], max_width: usize) -> String {
+    const SEPARATOR: &str = " › ";
 
-            // Show bookmark indicator if this item's text matches the bookmark
-            let bookmark_indicator = if app.bookmark_position.as_deref() == Some(&item.text) {
-                "⚑ "
-            } else {
-                ""
-            };
+    if max_width == 0 {
+        return String::new();
+    }
+    for skip in 0..path.len() {
+        let mut crumbs = path[skip..].to_vec();
+        if skip > 0 {
+            crumbs.insert(0, "…");
+        }
+        let joined = crumbs.join(SEPARATOR);
+        if joined.width() <= max_width {
+            return joined;
+        }
+    }
+    match path.last() {
+        Some(last) => format!("{}…", truncate_to_width(last, max_width - 1)),
+        None => String::new(),
+    }
+}
 
-            // Color headings by level using theme
-            let color = theme.heading_color(item.level);
+fn render_outline(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
 
-            let text = format!(
-                "{}{}{}{} {}",
-                indent, expand_indicator, bookmark_indicator, prefix, item.text
-            );
-            let line = Line::from(Span::styled(text, Style::default().fg(color)));
+    // Show bookmark indicator if an item's text matches the bookmark,
+    // followed by the letters of any marks on it
+    let bookmark_indicator = |item: &OutlineItem| {
+        let marks = app.marks_on(&item.text);
+        match (
+            app.bookmark_position.as_deref() == Some(&item.text),
+            marks.is_empty(),
+        ) {
+            (true, _) => format!("⚑{} ", marks),
+            (false, false) => format!("'{} ", marks),
+            (false, true) => String::new(),
+        }
+    };
 
-            ListItem::new(line)
-        })
-        .collect();
+    let items: Vec<ListItem> =
+        outline_lines(&app.outline_items, app.outline_style, bookmark_indicator)
+            .into_iter()
+            .zip(&app.outline_items)
+            .map(|(text, item)| {
+                // Color headings by level using theme
+                let color = theme.heading_color(item.level);
+                ListItem::new(Line::from(Span::styled(text, Style::default().fg(color))))
+            })
+            .collect();
 
     let block_style = theme.border_style(app.focus == Focus::Outline);
 
@@ -161,9 +219,107 @@ fn render_outline(frame: &mut Frame, app: &mut App, area: Rect) {
     );
 }
 
+/// Text of each outline row in `style`: indentation or tree connectors, the
+/// expand/collapse indicator, the `indicator` for the item (bookmarks and
+/// marks), then the `#` level marker and heading text.
+fn outline_lines(
+    items: &[OutlineItem],
+    style: OutlineStyle,
+    indicator: impl Fn(&OutlineItem) -> String,
+) -> Vec<String> {
+    // Parent of each item: the nearest earlier item at a higher level
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(items.len());
+    for (idx, item) in items.iter().enumerate() {
+        let mut parent = idx.checked_sub(1);
+        while let Some(p) = parent.filter(|&p| items[p].level >= item.level) {
+            parent = parents[p];
+        }
+        parents.push(parent);
+    }
+
+    // An item is its parent's last child when the next item at its level or
+    // above is outside the parent (or there is none)
+    let is_last: Vec<bool> = items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let next = items[idx + 1..].iter().find(|n| n.level <= item.level);
+            match (next, parents[idx]) {
+                (None, _) => true,
+                (Some(next), Some(parent)) => next.level <= items[parent].level,
+                (Some(_), None) => false,
+            }
+        })
+        .collect();
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let lead = match style {
+                OutlineStyle::Indent => "  ".repeat(item.level.saturating_sub(1)),
+                OutlineStyle::Compact => String::new(),
+                OutlineStyle::Tree => {
+                    let mut ancestors = Vec::new();
+                    let mut parent = parents[idx];
+                    while let Some(p) = parent {
+                        ancestors.push(if is_last[p] { "    " } else { "│   " });
+                        parent = parents[p];
+                    }
+                    ancestors.reverse();
+                    let connector = if is_last[idx] { "└─ " } else { "├─ " };
+                    ancestors.concat() + connector
+                }
+            };
+
+            // Show expand/collapse indicator if heading has children
+            let expand_indicator = match (item.has_children, item.expanded, style) {
+                (true, true, _) => "▼ ",
+                (true, false, _) => "▶ ",
+                (false, _, OutlineStyle::Tree) => "",
+                (false, _, _) => "  ",
+            };
+
+            format!(
+                "{}{}{}{} {}",
+                lead,
+                expand_indicator,
+                indicator(item),
+                "#".repeat(item.level),
+                item.text
+            )
+        })
+        .collect()
+}
+
+/// Where the text goes inside the content pane: within the borders, `padding`
+/// columns in from each side, and at most `max_width` wide, centered.
+///
+/// At least one column is always left for the text.
+pub fn content_text_area(pane: Rect, padding: u16, max_width: Option<u16>) -> Rect {
+    let inner = pane.inner(ratatui::layout::Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    let padding = padding.min(inner.width.saturating_sub(1) / 2);
+    let padded_width = inner.width - 2 * padding;
+    let width = max_width.map_or(padded_width, |max| padded_width.min(max));
+
+    Rect {
+        x: inner.x + padding + (padded_width - width) / 2,
+        width,
+        ..inner
+    }
+}
+
 fn render_content(frame: &mut Frame, app: &App, area: Rect) {
     use crate::tui::app::AppMode;
 
+    if app.mode == AppMode::Replace {
+        render_replace_preview(frame, app, area);
+        return;
+    }
+
     let theme = &app.theme;
     let block_style = theme.border_style(app.focus == Focus::Content);
 
@@ -172,7 +328,7 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         let content = app
             .document
             .extract_section(heading_text)
-            .unwrap_or_else(|| app.document.content.clone());
+            .unwrap_or_else(|| app.document.content().to_string());
 
         // Add link count to title if in link follow mode
         let title = if app.mode == AppMode::LinkFollow && !app.links_in_view.is_empty() {
@@ -188,8 +344,9 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         } else {
             " Content ".to_string()
         };
-        (app.document.content.clone(), title)
+        (app.document.content().to_string(), title)
     };
+    let content_text = app.expand_tabs(content_text);
 
     // Enhanced markdown rendering with syntax highlighting
     // Pass interactive state if in interactive mode
@@ -202,7 +359,7 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         (None, None)
     };
 
-    let rendered_text = render_markdown_enhanced(
+    let mut rendered_text = render_markdown_enhanced(
         &content_text,
         &app.highlighter,
         theme,
@@ -210,18 +367,54 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         interactive_state_ref,
     );
 
+    // Banner for the focused H1/H2 (see --heading-style)
+    if let Some(heading) = app
+        .selected_heading_text()
+        .and_then(|text| app.document.find_heading(text))
+    {
+        let banner = heading_banner(
+            &heading.text,
+            heading.level,
+            app.heading_style,
+            theme,
+            app.content_width as usize,
+        );
+        rendered_text.lines.splice(0..0, banner);
+    }
+
+    // Highlight search matches (case-insensitive, like the outline filter)
+    if let Some(matcher) = app.content_highlight().and_then(|query| {
+        regex::RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(true)
+            .build()
+            .ok()
+    }) {
+        let style = theme.search_highlight_style();
+        rendered_text.lines = std::mem::take(&mut rendered_text.lines)
+            .into_iter()
+            .map(|line| highlight_matches(line, &matcher, style))
+            .collect();
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(block_style)
+        .title(title)
+        .style(theme.content_style());
+    frame.render_widget(block, area);
+
+    // Wrap with the same rules as the height in `update_content_metrics`
+    let text_area = content_text_area(area, app.padding, app.max_width);
+    rendered_text.lines = crate::tui::wrap::wrap_lines(
+        std::mem::take(&mut rendered_text.lines),
+        text_area.width as usize,
+    );
+
     let paragraph = Paragraph::new(rendered_text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(block_style)
-                .title(title),
-        )
         .style(theme.content_style())
-        .wrap(Wrap { trim: false })
         .scroll((app.content_scroll, 0));
 
-    frame.render_widget(paragraph, area);
+    frame.render_widget(paragraph, text_area);
 
     // Render scrollbar
     let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
@@ -254,7 +447,28 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let status_text = if app.mode == AppMode::LinkFollow {
+    let status_text = if app.mode == AppMode::Replace {
+        use crate::tui::replace::ReplaceField;
+
+        // The prompt lives in the status bar so the preview keeps the pane
+        let prompt = &app.replace_prompt;
+        let (find_cursor, with_cursor) = match prompt.field {
+            ReplaceField::Pattern => ("_", ""),
+            ReplaceField::Replacement => ("", "_"),
+        };
+        format!(
+            " [REPLACE PREVIEW] Find: {}{} → With: {}{} • Tab:Field • Enter:{} • ↑↓:Scroll • Esc:Close ",
+            prompt.pattern,
+            find_cursor,
+            prompt.replacement,
+            with_cursor,
+            if prompt.show_result {
+                "Changes"
+            } else {
+                "Result"
+            }
+        )
+    } else if app.mode == AppMode::LinkFollow {
         // Link follow mode status
         let link_count = app.links_in_view.len();
         let selected = app.selected_link_idx.map(|i| i + 1).unwrap_or(0);
@@ -323,10 +537,14 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
             "Outline:Hidden".to_string()
         };
 
-        let bookmark_indicator = if app.bookmark_position.is_some() {
-            " ⚑"
+        let bookmark_indicator = if !app.marks.is_empty() {
+            let mut names: Vec<char> = app.marks.keys().copied().collect();
+            names.sort_unstable();
+            format!(" ⚑{}", names.into_iter().collect::<String>())
+        } else if app.bookmark_position.is_some() {
+            " ⚑".to_string()
         } else {
-            ""
+            String::new()
         };
 
         let history_indicator = if !app.file_history.is_empty() {
@@ -434,6 +652,10 @@ fn render_help_popup(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled("  h/←      ", Style::default().fg(theme.modal_key_fg())),
             Span::raw("Collapse (or parent if no children)"),
         ]),
+        Line::from(vec![
+            Span::styled("  z        ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Collapse all (or expand all)"),
+        ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "General",
@@ -447,6 +669,22 @@ fn render_help_popup(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled("  /        ", Style::default().fg(theme.modal_key_fg())),
             Span::raw("Search/filter headings"),
         ]),
+        Line::from(vec![
+            Span::styled("  :        ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Run a query (results update as you type)"),
+        ]),
+        Line::from(vec![
+            Span::styled("  o        ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Open another markdown file in this directory"),
+        ]),
+        Line::from(vec![
+            Span::styled("  Ctrl-o   ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Switch to the previously opened file"),
+        ]),
+        Line::from(vec![
+            Span::styled("  R        ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Preview a regex find/replace (nothing is saved)"),
+        ]),
         Line::from(vec![
             Span::styled("  ?        ", Style::default().fg(theme.modal_key_fg())),
             Span::raw("Toggle this help"),
@@ -464,6 +702,10 @@ fn render_help_popup(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled("  w        ", Style::default().fg(theme.modal_key_fg())),
             Span::raw("Toggle outline visibility (full-width content)"),
         ]),
+        Line::from(vec![
+            Span::styled("  O        ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Cycle outline style (indent, tree, compact)"),
+        ]),
         Line::from(vec![
             Span::styled("  [ ]      ", Style::default().fg(theme.modal_key_fg())),
             Span::raw("Decrease/increase outline width (20%, 30%, 40%)"),
@@ -473,12 +715,16 @@ fn render_help_popup(frame: &mut Frame, app: &App, area: Rect) {
             Span::raw("Jump to heading 1-9"),
         ]),
         Line::from(vec![
-            Span::styled("  m        ", Style::default().fg(theme.modal_key_fg())),
-            Span::raw("Set bookmark (shows ⚑ indicator)"),
+            Span::styled("  m{a-z}   ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Mark heading with a letter (shows ⚑ indicator)"),
+        ]),
+        Line::from(vec![
+            Span::styled("  `{a-z}   ", Style::default().fg(theme.modal_key_fg())),
+            Span::raw("Jump to marked heading"),
         ]),
         Line::from(vec![
             Span::styled("  '        ", Style::default().fg(theme.modal_key_fg())),
-            Span::raw("Jump to bookmarked position"),
+            Span::raw("Jump to the last mark"),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled(
@@ -569,7 +815,7 @@ fn render_help_popup(frame: &mut Frame, app: &App, area: Rect) {
         ]),
         Line::from(vec![
             Span::styled("  e        ", Style::default().fg(theme.modal_key_fg())),
-            Span::raw("Edit file in default editor ($VISUAL or $EDITOR)"),
+            Span::raw("Edit file at the selected section ($VISUAL or $EDITOR)"),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -756,10 +1002,178 @@ fn render_link_picker(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 use crate::parser::content::parse_content;
-use crate::parser::output::{Alignment, Block as ContentBlock, InlineElement};
+use crate::parser::output::{
+    Alignment, Block as ContentBlock, InlineElement, ListItem as ContentListItem,
+};
 use crate::tui::syntax::SyntaxHighlighter;
+use crate::tui::wrap::truncate_to_width;
 use unicode_width::UnicodeWidthStr;
 
+/// Patch `style` onto the parts of `line` that match `matcher`.
+///
+/// Matches are found in the line's full text, so they can span several
+/// differently-styled spans; each span is split at the match boundaries and
+/// keeps its own style underneath the highlight.
+fn highlight_matches(line: Line<'static>, matcher: &regex::Regex, style: Style) -> Line<'static> {
+    let text: String = line
+        .spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    let matches: Vec<std::ops::Range<usize>> = matcher
+        .find_iter(&text)
+        .map(|m| m.range())
+        .filter(|range| !range.is_empty())
+        .collect();
+    if matches.is_empty() {
+        return line;
+    }
+
+    let mut spans = Vec::with_capacity(line.spans.len() + matches.len() * 2);
+    let mut offset = 0;
+    for span in &line.spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+
+        // Cut points inside this span, relative to its start
+        let mut cuts = vec![0, content.len()];
+        for m in &matches {
+            for point in [m.start, m.end] {
+                if point > offset && point < end {
+                    cuts.push(point - offset);
+                }
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        for piece in cuts.windows(2) {
+            let absolute = offset + piece[0];
+            let highlighted = matches.iter().any(|m| m.contains(&absolute));
+            let piece_style = if highlighted {
+                span.style.patch(style)
+            } else {
+                span.style
+            };
+            spans.push(Span::styled(
+                content[piece[0]..piece[1]].to_string(),
+                piece_style,
+            ));
+        }
+        offset = end;
+    }
+
+    Line { spans, ..line }
+}
+
+/// Display width of the banner for `title`, capped at `max_width`.
+///
+/// An underline is as wide as the title; a box adds a border and a space of
+/// padding on each side.
+fn banner_width(title: &str, style: HeadingStyle, max_width: usize) -> usize {
+    let width = match style {
+        HeadingStyle::Plain => 0,
+        HeadingStyle::Underline => title.width(),
+        HeadingStyle::Boxed => title.width() + 4,
+    };
+    width.min(max_width)
+}
+
+/// Lines drawing a heading banner at the top of the content pane, followed by
+/// a blank line. H1 banners use double lines, H2 single; other levels and the
+/// plain style get no banner.
+fn heading_banner(
+    title: &str,
+    level: usize,
+    style: HeadingStyle,
+    theme: &Theme,
+    max_width: usize,
+) -> Vec<Line<'static>> {
+    if style.banner_height(level) == 0 {
+        return Vec::new();
+    }
+
+    let title_style = Style::default()
+        .fg(theme.heading_color(level))
+        .add_modifier(Modifier::BOLD);
+    let border_style = Style::default().fg(theme.border_focused);
+    let width = banner_width(title, style, max_width);
+    let (horizontal, vertical, corners) = if level == 1 {
+        ("═", "║", ["╔", "╗", "╚", "╝"])
+    } else {
+        ("─", "│", ["┌", "┐", "└", "┘"])
+    };
+
+    let mut lines = if style == HeadingStyle::Boxed {
+        let inner = width.saturating_sub(4);
+        let title = truncate_to_width(title, inner);
+        // Truncation can stop a column short before a double-width character
+        let padding = " ".repeat(inner - title.width());
+        vec![
+            Line::from(Span::styled(
+                format!(
+                    "{}{}{}",
+                    corners[0],
+                    horizontal.repeat(inner + 2),
+                    corners[1]
+                ),
+                border_style,
+            )),
+            Line::from(vec![
+                Span::styled(format!("{} ", vertical), border_style),
+                Span::styled(title, title_style),
+                Span::raw(padding),
+                Span::styled(format!(" {}", vertical), border_style),
+            ]),
+            Line::from(Span::styled(
+                format!(
+                    "{}{}{}",
+                    corners[2],
+                    horizontal.repeat(inner + 2),
+                    corners[3]
+                ),
+                border_style,
+            )),
+        ]
+    } else {
+        let title = truncate_to_width(title, width);
+        let rule = horizontal.repeat(title.width());
+        vec![
+            Line::from(Span::styled(title, title_style)),
+            Line::from(Span::styled(rule, border_style)),
+        ]
+    };
+
+    lines.push(Line::default());
+    lines
+}
+
+/// Markdown shown by `--theme-preview`, covering the styles a theme sets.
+const PREVIEW_SAMPLE: &str = "\
+Some **bold** and *italic* text with `inline code`.
+
+- A list item
+- [x] A finished task
+
+> A blockquote
+
+```rust
+fn main() {
+    println!(\"Hello, treemd!\");
+}
+```
+";
+
+/// The preview sample in `theme`, headed by the theme's name as an H1 banner.
+pub(crate) fn theme_preview_lines(
+    theme: &Theme,
+    highlighter: &SyntaxHighlighter,
+) -> Vec<Line<'static>> {
+    let mut lines = heading_banner(theme.name, 1, HeadingStyle::Underline, theme, 80);
+    lines.extend(render_markdown_enhanced(PREVIEW_SAMPLE, highlighter, theme, None, None).lines);
+    lines
+}
+
 fn render_markdown_enhanced(
     content: &str,
     highlighter: &SyntaxHighlighter,
@@ -832,118 +1246,14 @@ fn render_markdown_enhanced(
                     theme.code_fence_style(),
                 )]));
             }
-            ContentBlock::List { ordered, items } => {
+            ContentBlock::List { items, .. } => {
                 for (idx, item) in items.iter().enumerate() {
                     // Check if this specific list item is selected
                     let is_item_selected = selected_element_id
                         .map(|id| id.block_idx == block_idx && id.sub_idx == Some(idx))
                         .unwrap_or(false);
 
-                    // Check if content has nested items (contains newlines with indentation)
-                    let has_nested = item.content.contains('\n');
-
-                    if has_nested {
-                        // Render multi-line item with nested items
-                        let content_lines = item.content.lines();
-                        for (line_idx, line) in content_lines.enumerate() {
-                            if line_idx == 0 {
-                                // First line: use regular list marker
-                                let mut spans = vec![];
-
-                                // Add selection indicator for checkboxes
-                                if is_item_selected {
-                                    spans.push(Span::styled(
-                                        "→ ",
-                                        Style::default()
-                                            .fg(Color::Rgb(100, 200, 255))
-                                            .add_modifier(Modifier::BOLD),
-                                    ));
-                                }
-
-                                let prefix = if let Some(checked) = item.checked {
-                                    let checkbox = if checked { "☑" } else { "☐" };
-                                    format!("  {} ", checkbox)
-                                } else if *ordered {
-                                    format!("  {}. ", idx + 1)
-                                } else {
-                                    "  • ".to_string()
-                                };
-                                let formatted = format_inline_markdown(line, theme);
-                                spans.push(Span::styled(
-                                    prefix,
-                                    Style::default().fg(theme.list_bullet),
-                                ));
-                                spans.extend(formatted);
-                                lines.push(Line::from(spans));
-                            } else {
-                                // Nested items: detect indentation and add bullet/checkbox
-                                let trimmed = line.trim_start();
-                                let indent_count = line.len() - trimmed.len();
-                                if indent_count > 0 {
-                                    // Check if this is a task list item by looking for checkbox in text
-                                    let (is_task, checked, text_after_marker) =
-                                        detect_checkbox_in_text(trimmed);
-
-                                    let indent = " ".repeat(indent_count + 2); // Base indent + 2
-
-                                    let marker = if is_task {
-                                        // Task list item with checkbox
-                                        if checked { "☑ " } else { "☐ " }
-                                    } else {
-                                        // Regular bullet
-                                        "• "
-                                    };
-
-                                    let formatted =
-                                        format_inline_markdown(text_after_marker, theme);
-                                    let mut spans = vec![
-                                        Span::raw(indent),
-                                        Span::styled(
-                                            marker,
-                                            Style::default().fg(theme.list_bullet),
-                                        ),
-                                    ];
-                                    spans.extend(formatted);
-                                    lines.push(Line::from(spans));
-                                } else {
-                                    // Empty line or continuation
-                                    lines.push(Line::from(line.to_string()));
-                                }
-                            }
-                        }
-                    } else {
-                        // Simple single-line item
-                        let formatted = if !item.inline.is_empty() {
-                            render_inline_elements(&item.inline, theme)
-                        } else {
-                            format_inline_markdown(&item.content, theme)
-                        };
-
-                        let mut spans = vec![];
-
-                        // Add selection indicator for checkboxes
-                        if is_item_selected {
-                            spans.push(Span::styled(
-                                "→ ",
-                                Style::default()
-                                    .fg(Color::Rgb(100, 200, 255))
-                                    .add_modifier(Modifier::BOLD),
-                            ));
-                        }
-
-                        let prefix = if let Some(checked) = item.checked {
-                            let checkbox = if checked { "☑" } else { "☐" };
-                            format!("  {} ", checkbox)
-                        } else if *ordered {
-                            format!("  {}. ", idx + 1)
-                        } else {
-                            "  • ".to_string()
-                        };
-
-                        spans.push(Span::styled(prefix, Style::default().fg(theme.list_bullet)));
-                        spans.extend(formatted);
-                        lines.push(Line::from(spans));
-                    }
+                    render_list_item(item, 0, is_item_selected, theme, &mut lines);
                 }
             }
             ContentBlock::Blockquote {
@@ -1047,56 +1357,26 @@ fn render_markdown_enhanced(
             ContentBlock::Details {
                 summary,
                 blocks: nested,
+                open,
                 ..
             } => {
-                // Check if this details block is expanded
+                // Check if this details block is expanded, falling back to the `open` attribute
                 let element_id = crate::tui::interactive::ElementId {
                     block_idx,
                     sub_idx: None,
                 };
                 let is_expanded = interactive_state
                     .map(|state| state.is_details_expanded(element_id))
-                    .unwrap_or(false);
-
-                // Render details block with expand/collapse indicator
-                let mut summary_spans = vec![];
-
-                // Add selection indicator
-                if is_block_selected {
-                    summary_spans.push(Span::styled(
-                        "→ ",
-                        Style::default()
-                            .fg(Color::Rgb(100, 200, 255))
-                            .add_modifier(Modifier::BOLD),
-                    ));
-                }
+                    .unwrap_or(*open);
 
-                // Show ▼ when expanded, ▶ when collapsed
-                let indicator = if is_expanded { "▼ " } else { "▶ " };
-                summary_spans.push(Span::styled(
-                    indicator,
-                    Style::default().fg(theme.list_bullet),
-                ));
-                summary_spans.push(Span::styled(
-                    summary.clone(),
-                    Style::default()
-                        .fg(theme.heading_color(3))
-                        .add_modifier(Modifier::BOLD),
+                lines.extend(render_details(
+                    summary,
+                    nested,
+                    is_expanded,
+                    is_block_selected,
+                    highlighter,
+                    theme,
                 ));
-
-                lines.push(Line::from(summary_spans));
-
-                // Only render nested content if expanded
-                if is_expanded {
-                    for nested_block in nested {
-                        let nested_lines = render_block_to_lines(nested_block, highlighter, theme);
-                        for nested_line in nested_lines {
-                            let mut spans = vec![Span::raw("  ")]; // Indent
-                            spans.extend(nested_line.spans);
-                            lines.push(Line::from(spans));
-                        }
-                    }
-                }
             }
             ContentBlock::HorizontalRule => {
                 lines.push(Line::from(vec![Span::styled(
@@ -1104,6 +1384,44 @@ fn render_markdown_enhanced(
                     Style::default().fg(Color::Rgb(80, 80, 100)),
                 )]));
             }
+            ContentBlock::Html { raw } => {
+                // Raw HTML is shown as written, dimmed
+                for line in raw.lines() {
+                    lines.push(Line::from(Span::styled(line.to_string(), html_style())));
+                }
+            }
+            ContentBlock::Footnote {
+                label,
+                blocks: nested,
+                ..
+            } => {
+                // The label leads the first line, the rest is indented under it
+                let marker = format!("[^{}]: ", label);
+                let indent = " ".repeat(marker.chars().count());
+                let nested_lines = nested.iter().flat_map(|nested_block| {
+                    render_block_to_lines(nested_block, highlighter, theme)
+                });
+                for (idx, nested_line) in nested_lines.enumerate() {
+                    let mut spans = if idx == 0 {
+                        vec![Span::styled(marker.clone(), footnote_style())]
+                    } else {
+                        vec![Span::raw(indent.clone())]
+                    };
+                    if idx == 0 && is_block_selected {
+                        spans.insert(
+                            0,
+                            Span::styled(
+                                "→ ",
+                                Style::default()
+                                    .fg(Color::Rgb(100, 200, 255))
+                                    .add_modifier(Modifier::BOLD),
+                            ),
+                        );
+                    }
+                    spans.extend(nested_line.spans);
+                    lines.push(Line::from(spans));
+                }
+            }
         }
 
         // Add blank line after most blocks for spacing
@@ -1133,35 +1451,36 @@ fn render_block_to_lines(
             language, content, ..
         } => {
             let lang_str = language.as_deref().unwrap_or("");
+            lines.push(Line::from(vec![Span::styled(
+                format!("```{}", lang_str),
+                theme.code_fence_style(),
+            )]));
             let highlighted = highlighter.highlight_code(content, lang_str);
             lines.extend(highlighted);
+            lines.push(Line::from(vec![Span::styled(
+                "```".to_string(),
+                theme.code_fence_style(),
+            )]));
+        }
+        ContentBlock::List { items, .. } => {
+            for item in items {
+                render_list_item(item, 0, false, theme, &mut lines);
+            }
         }
         ContentBlock::Details {
             summary,
             blocks: nested,
             ..
         } => {
-            // Render details with collapsed indicator
-            let summary_spans = vec![
-                Span::styled("▶ ", Style::default().fg(theme.list_bullet)),
-                Span::styled(
-                    summary.clone(),
-                    Style::default()
-                        .fg(theme.heading_color(3))
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ];
-            lines.push(Line::from(summary_spans));
-
-            // Render nested content (indented)
-            for nested_block in nested {
-                let nested_lines = render_block_to_lines(nested_block, highlighter, theme);
-                for nested_line in nested_lines {
-                    let mut spans = vec![Span::raw("  ")];
-                    spans.extend(nested_line.spans);
-                    lines.push(Line::from(spans));
-                }
-            }
+            // Nested details have no toggle of their own, so always show their content
+            lines.extend(render_details(
+                summary,
+                nested,
+                true,
+                false,
+                highlighter,
+                theme,
+            ));
         }
         _ => {
             // For other blocks, use simple text rendering
@@ -1172,6 +1491,56 @@ fn render_block_to_lines(
     lines
 }
 
+/// Render a details block: the summary line with a disclosure triangle, followed
+/// by the indented nested blocks only when expanded
+fn render_details(
+    summary: &str,
+    nested: &[ContentBlock],
+    expanded: bool,
+    is_selected: bool,
+    highlighter: &SyntaxHighlighter,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut summary_spans = vec![];
+
+    // Add selection indicator
+    if is_selected {
+        summary_spans.push(Span::styled(
+            "→ ",
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Show ▼ when expanded, ▶ when collapsed
+    let indicator = if expanded { "▼ " } else { "▶ " };
+    summary_spans.push(Span::styled(
+        indicator,
+        Style::default().fg(theme.list_bullet),
+    ));
+    summary_spans.push(Span::styled(
+        summary.to_string(),
+        Style::default()
+            .fg(theme.heading_color(3))
+            .add_modifier(Modifier::BOLD),
+    ));
+
+    let mut lines = vec![Line::from(summary_spans)];
+
+    if expanded {
+        for nested_block in nested {
+            for nested_line in render_block_to_lines(nested_block, highlighter, theme) {
+                let mut spans = vec![Span::raw("  ")]; // Indent
+                spans.extend(nested_line.spans);
+                lines.push(Line::from(spans));
+            }
+        }
+    }
+
+    lines
+}
+
 fn render_inline_elements(elements: &[InlineElement], theme: &Theme) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
 
@@ -1211,6 +1580,12 @@ fn render_inline_elements(elements: &[InlineElement], theme: &Theme) -> Vec<Span
                     Style::default().fg(Color::Rgb(150, 150, 180)),
                 ));
             }
+            InlineElement::Html { raw } => {
+                spans.push(Span::styled(raw.clone(), html_style()));
+            }
+            InlineElement::FootnoteReference { label } => {
+                spans.push(Span::styled(format!("[^{}]", label), footnote_style()));
+            }
         }
     }
 
@@ -1221,6 +1596,18 @@ fn render_inline_elements(elements: &[InlineElement], theme: &Theme) -> Vec<Span
     spans
 }
 
+/// Style for footnote references and definition labels
+fn footnote_style() -> Style {
+    Style::default().fg(Color::Rgb(100, 150, 255))
+}
+
+/// Style for raw HTML, which the terminal can't render
+fn html_style() -> Style {
+    Style::default()
+        .fg(Color::Rgb(120, 120, 140))
+        .add_modifier(Modifier::DIM)
+}
+
 fn render_table(
     headers: &[String],
     alignments: &[Alignment],
@@ -1431,39 +1818,117 @@ fn render_table_row(
     Line::from(spans)
 }
 
-/// Detect checkbox markers in text and return (is_task, checked, text_after_marker)
-fn detect_checkbox_in_text(text: &str) -> (bool, bool, &str) {
-    let trimmed = text.trim_start();
+/// Render a list item and its nested items, indenting two spaces per level
+fn render_list_item(
+    item: &ContentListItem,
+    depth: usize,
+    is_selected: bool,
+    theme: &Theme,
+    lines: &mut Vec<Line<'static>>,
+) {
+    let mut spans = vec![];
 
-    // Check for [x] or [X] (checked)
-    if let Some(stripped) = trimmed
-        .strip_prefix("[x]")
-        .or_else(|| trimmed.strip_prefix("[X]"))
-    {
-        return (true, true, stripped.trim_start());
+    // Add selection indicator for checkboxes
+    if is_selected {
+        spans.push(Span::styled(
+            "→ ",
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD),
+        ));
     }
 
-    // Check for [ ] (unchecked)
-    if let Some(stripped) = trimmed.strip_prefix("[ ]") {
-        return (true, false, stripped.trim_start());
+    let indent = "  ".repeat(depth + 1);
+    let prefix = if let Some(checked) = item.checked {
+        let checkbox = if checked { "☑" } else { "☐" };
+        format!("{}{} ", indent, checkbox)
+    } else if let Some(number) = item.number {
+        format!("{}{} ", indent, ordered_list_marker(number, depth))
+    } else {
+        format!("{}• ", indent)
+    };
+
+    let formatted = if !item.inline.is_empty() {
+        render_inline_elements(&item.inline, theme)
+    } else {
+        format_inline_markdown(&item.content, theme)
+    };
+
+    spans.push(Span::styled(prefix, Style::default().fg(theme.list_bullet)));
+    spans.extend(formatted);
+    lines.push(Line::from(spans));
+
+    for child in &item.children {
+        render_list_item(child, depth + 1, false, theme, lines);
     }
+}
 
-    // Not a task list item
-    (false, false, text)
+/// Format an ordered list marker, cycling `1.`, `a.`, `i.` by nesting depth
+fn ordered_list_marker(number: u64, depth: usize) -> String {
+    match depth % 3 {
+        0 => format!("{}.", number),
+        1 => format!("{}.", to_alphabetic(number)),
+        _ => format!("{}.", to_roman(number)),
+    }
+}
+
+/// Convert a number to lowercase letters (1 → a, 26 → z, 27 → aa)
+fn to_alphabetic(mut number: u64) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Convert a number to lowercase roman numerals
+fn to_roman(mut number: u64) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
 }
 
 fn align_text(text: &str, width: usize, alignment: &Alignment) -> String {
     // Use Unicode display width instead of character/byte length
     let text_width = text.width();
 
-    // If text is longer than width, truncate it
+    // If text is longer than width, truncate it by display columns
     if text_width >= width {
-        // TODO: Proper Unicode-aware truncation
         if width > 5 {
-            // Approximate truncation - not perfect but better than nothing
-            let approx_chars = width.saturating_sub(5);
-            let truncated = text.chars().take(approx_chars).collect::<String>();
-            return format!(" {}... ", truncated);
+            let truncated = truncate_to_width(text, width.saturating_sub(5));
+            let pad = width.saturating_sub(truncated.width() + 5);
+            return format!(" {}...{} ", truncated, " ".repeat(pad));
         }
         return format!(" {} ", text);
     }
@@ -1590,6 +2055,179 @@ fn render_search_overlay(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, search_area);
 }
 
+/// What the find/replace prompt would change across the whole document: each
+/// changed line with the match struck through and its replacement after it,
+/// or (after Enter) the document as it would read. Nothing is written.
+fn render_replace_preview(frame: &mut Frame, app: &App, area: Rect) {
+    use crate::tui::replace::{Segment, apply_replacements, preview_hunks};
+
+    let theme = &app.theme;
+    let prompt = &app.replace_prompt;
+    let content = &app.document.content();
+    let error_style = Style::default().fg(Color::Rgb(255, 100, 100));
+    let removed = error_style.add_modifier(Modifier::CROSSED_OUT);
+    let added = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(theme.modal_description());
+
+    let (title, lines) = match &prompt.result {
+        Err(error) => (
+            " Replace preview: invalid pattern ".to_string(),
+            error
+                .lines()
+                .map(|l| Line::styled(l.to_string(), error_style))
+                .collect(),
+        ),
+        Ok(_) if prompt.pattern.is_empty() => (
+            " Replace preview ".to_string(),
+            vec![Line::styled(
+                "Type a regex to find; Tab moves to the replacement",
+                dim,
+            )],
+        ),
+        Ok(replacements) if prompt.show_result => (
+            format!(
+                " Result of {} replacements (not saved) ",
+                replacements.len()
+            ),
+            apply_replacements(content, replacements)
+                .lines()
+                .map(|l| Line::from(l.to_string()))
+                .collect(),
+        ),
+        Ok(replacements) => {
+            let mut lines = Vec::new();
+            for hunk in preview_hunks(content, replacements) {
+                lines.push(Line::styled(format!("@@ line {}", hunk.line), dim));
+                let mut spans = Vec::new();
+                for segment in hunk.segments {
+                    let (text, style) = match segment {
+                        Segment::Same(text) => (text, Style::default()),
+                        Segment::Removed(text) => (text, removed),
+                        Segment::Added(text) => (text, added),
+                    };
+                    // Segments can span lines; each newline starts a new row
+                    for (idx, part) in text.split('\n').enumerate() {
+                        if idx > 0 {
+                            lines.push(Line::from(std::mem::take(&mut spans)));
+                        }
+                        if !part.is_empty() {
+                            spans.push(Span::styled(part.to_string(), style));
+                        }
+                    }
+                }
+                lines.push(Line::from(spans));
+                lines.push(Line::from(""));
+            }
+            if lines.is_empty() {
+                lines.push(Line::styled("No matches", dim));
+            }
+            let s = if replacements.len() == 1 { "" } else { "es" };
+            (
+                format!(
+                    " Replace preview: {} match{} (not saved) ",
+                    replacements.len(),
+                    s
+                ),
+                lines,
+            )
+        }
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border_style(true))
+        .title(title)
+        .style(theme.content_style());
+    frame.render_widget(block, area);
+
+    let paragraph = Paragraph::new(lines)
+        .style(theme.content_style())
+        .wrap(Wrap { trim: false })
+        .scroll((prompt.scroll, 0));
+    frame.render_widget(
+        paragraph,
+        content_text_area(area, app.padding, app.max_width),
+    );
+}
+
+/// Query input at the top, with the results (or the error) in a popup below it
+fn render_query_bar(frame: &mut Frame, app: &App, area: Rect) {
+    use crate::tui::query_bar::QueryOutput;
+
+    let bar = &app.query_bar;
+    let input_area = Rect {
+        x: area.x + 2,
+        y: area.y + 2,
+        width: area.width.saturating_sub(4).max(40),
+        height: 3,
+    };
+
+    frame.render_widget(Clear, input_area);
+
+    let (cursor, hint) = if bar.editing {
+        ("_", " Query (Enter: submit, Esc: close) ")
+    } else {
+        ("", " Query (j/k: scroll, ':' edit, Esc: close) ")
+    };
+    let input = Paragraph::new(format!(": {}{}", bar.input, cursor))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(hint)
+                .style(Style::default().bg(Color::Rgb(30, 30, 50))),
+        )
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(input, input_area);
+
+    let Some(output) = &bar.output else {
+        return;
+    };
+
+    // Results fill the space down to the status bar
+    let results_area = Rect {
+        y: input_area.y + input_area.height,
+        height: area
+            .height
+            .saturating_sub(input_area.y + input_area.height + 1),
+        ..input_area
+    };
+    if results_area.height < 3 {
+        return;
+    }
+
+    let (title, text, style) = match output {
+        QueryOutput::Results { text, count } => {
+            let s = if *count == 1 { "" } else { "s" };
+            (
+                format!(" {} result{} ", count, s),
+                text.as_str(),
+                Style::default().fg(app.theme.modal_text()),
+            )
+        }
+        QueryOutput::Error(message) => (
+            " Error ".to_string(),
+            message.as_str(),
+            Style::default().fg(Color::Rgb(255, 100, 100)),
+        ),
+    };
+
+    frame.render_widget(Clear, results_area);
+    let results = Paragraph::new(text.to_string())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(title)
+                .style(Style::default().bg(Color::Rgb(30, 30, 50))),
+        )
+        .style(style)
+        .scroll((bar.scroll, 0));
+    frame.render_widget(results, results_area);
+}
+
 fn render_theme_picker(frame: &mut Frame, app: &App, area: Rect) {
     use crate::tui::theme::ThemeName;
 
@@ -1694,6 +2332,72 @@ fn render_theme_picker(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, popup_area);
 }
 
+fn render_file_picker(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let picker = &app.file_picker;
+
+    let popup_area = centered_area(area, 60, 60);
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines = vec![
+        Line::from(vec![Span::styled(
+            "Open File (j/k to navigate, Enter to open, Esc to cancel)",
+            Style::default()
+                .fg(theme.modal_description())
+                .add_modifier(Modifier::ITALIC),
+        )]),
+        Line::from(""),
+    ];
+
+    if picker.files.is_empty() {
+        lines.push(Line::from(vec![Span::styled(
+            "  No markdown files here",
+            Style::default().fg(theme.modal_text()),
+        )]));
+    }
+
+    for (idx, path) in picker.files.iter().enumerate() {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let is_current = path.file_name() == app.current_file_path.file_name();
+
+        let (prefix, style) = if idx == picker.selected {
+            (
+                "▶ ",
+                Style::default()
+                    .fg(theme.modal_selected_fg())
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            ("  ", Style::default().fg(theme.modal_text()))
+        };
+        let current_marker = if is_current { " ✓" } else { "" };
+
+        lines.push(Line::from(vec![Span::styled(
+            format!("{}{}{}", prefix, name, current_marker),
+            style,
+        )]));
+    }
+
+    // Keep the highlighted file in view (2 header lines, 2 border rows)
+    let visible = popup_area.height.saturating_sub(2) as usize;
+    let scroll = (picker.selected + 3).saturating_sub(visible) as u16;
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.modal_border()))
+                .title(format!(" {} markdown files ", picker.files.len()))
+                .style(Style::default().bg(theme.modal_bg())),
+        )
+        .scroll((scroll, 0));
+
+    frame.render_widget(paragraph, popup_area);
+}
+
 fn render_cell_edit_overlay(frame: &mut Frame, app: &App, area: Rect) {
     let theme = &app.theme;
 
@@ -1743,3 +2447,216 @@ fn render_cell_edit_overlay(frame: &mut Frame, app: &App, area: Rect) {
 
     frame.render_widget(paragraph, edit_area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_text_truncates_wide_characters_by_width() {
+        let cell = align_text("日本語のテキストです", 10, &Alignment::Left);
+        assert_eq!(cell.width(), 10);
+        assert_eq!(cell, " 日本...  ");
+    }
+
+    #[test]
+    fn test_ordered_list_marker_by_depth() {
+        assert_eq!(ordered_list_marker(2, 0), "2.");
+        assert_eq!(ordered_list_marker(1, 1), "a.");
+        assert_eq!(ordered_list_marker(28, 1), "ab.");
+        assert_eq!(ordered_list_marker(4, 2), "iv.");
+        assert_eq!(ordered_list_marker(3, 3), "3.");
+    }
+
+    #[test]
+    fn test_highlight_splits_spans_at_match_boundaries() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let code = Style::default().fg(Color::Green);
+        let highlight = Style::default().bg(Color::Yellow);
+        let line = Line::from(vec![
+            Span::raw("Hello "),
+            Span::styled("World", bold),
+            Span::styled(" code", code),
+        ]);
+
+        // "o wo" spans the plain and bold spans; matching ignores case
+        let matcher = regex::RegexBuilder::new(&regex::escape("O WO"))
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let result = highlight_matches(line.clone(), &matcher, highlight);
+
+        let pieces: Vec<(&str, Style)> = result
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                ("Hell", Style::default()),
+                ("o ", Style::default().patch(highlight)),
+                ("Wo", bold.patch(highlight)),
+                ("rld", bold),
+                (" code", code),
+            ]
+        );
+        assert_eq!(result.to_string(), line.to_string());
+
+        // No match leaves the line untouched
+        let matcher = regex::Regex::new("absent").unwrap();
+        assert_eq!(highlight_matches(line.clone(), &matcher, highlight), line);
+    }
+
+    #[test]
+    fn test_outline_styles() {
+        let item = |level, text: &str, has_children, expanded| OutlineItem {
+            level,
+            text: text.to_string(),
+            expanded,
+            has_children,
+        };
+        let items = [
+            item(1, "Guide", true, true),
+            item(2, "Install", true, true),
+            item(3, "Linux", false, false),
+            item(3, "macOS", false, false),
+            item(2, "Usage", true, false),
+            item(1, "Appendix", false, false),
+        ];
+        let render = |style| {
+            outline_lines(&items, style, |item| {
+                if item.text == "Usage" {
+                    "⚑ ".to_string()
+                } else {
+                    String::new()
+                }
+            })
+        };
+
+        assert_eq!(
+            render(OutlineStyle::Indent),
+            [
+                "▼ # Guide",
+                "  ▼ ## Install",
+                "      ### Linux",
+                "      ### macOS",
+                "  ▶ ⚑ ## Usage",
+                "  # Appendix",
+            ]
+        );
+        assert_eq!(
+            render(OutlineStyle::Tree),
+            [
+                "├─ ▼ # Guide",
+                "│   ├─ ▼ ## Install",
+                "│   │   ├─ ### Linux",
+                "│   │   └─ ### macOS",
+                "│   └─ ▶ ⚑ ## Usage",
+                "└─ # Appendix",
+            ]
+        );
+        assert_eq!(
+            render(OutlineStyle::Compact),
+            [
+                "▼ # Guide",
+                "▼ ## Install",
+                "  ### Linux",
+                "  ### macOS",
+                "▶ ⚑ ## Usage",
+                "  # Appendix",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_breadcrumb_truncates_outer_headings() {
+        let path = ["Usage", "Installation", "macOS"];
+        assert_eq!(format_breadcrumb(&path, 80), "Usage › Installation › macOS");
+        assert_eq!(format_breadcrumb(&path, 24), "… › Installation › macOS");
+        assert_eq!(format_breadcrumb(&path, 10), "… › macOS");
+        assert_eq!(format_breadcrumb(&path, 4), "mac…");
+        assert_eq!(format_breadcrumb(&path, 0), "");
+        assert_eq!(format_breadcrumb(&[], 80), "");
+    }
+
+    #[test]
+    fn test_banner_width_uses_display_width() {
+        assert_eq!(banner_width("Guide", HeadingStyle::Underline, 80), 5);
+        assert_eq!(banner_width("Guide", HeadingStyle::Boxed, 80), 9);
+        assert_eq!(banner_width("日本語", HeadingStyle::Boxed, 80), 10);
+        assert_eq!(banner_width("A long title", HeadingStyle::Boxed, 8), 8);
+        assert_eq!(banner_width("Guide", HeadingStyle::Plain, 80), 0);
+    }
+
+    #[test]
+    fn test_heading_banner_lines() {
+        let theme = Theme::from_name(crate::tui::theme::ThemeName::OceanDark);
+
+        let boxed = heading_banner("日本語", 1, HeadingStyle::Boxed, &theme, 80);
+        let rows: Vec<String> = boxed.iter().map(|line| line.to_string()).collect();
+        assert_eq!(rows, vec!["╔════════╗", "║ 日本語 ║", "╚════════╝", ""]);
+        assert_eq!(boxed[1].spans[1].style.fg, Some(theme.heading_1));
+        assert_eq!(boxed[0].spans[0].style.fg, Some(theme.border_focused));
+
+        let underline = heading_banner("Usage", 2, HeadingStyle::Underline, &theme, 80);
+        let rows: Vec<String> = underline.iter().map(|line| line.to_string()).collect();
+        assert_eq!(rows, vec!["Usage", "─────", ""]);
+        assert_eq!(underline[0].spans[0].style.fg, Some(theme.heading_2));
+
+        // Narrow panes truncate the title inside the box
+        let narrow = heading_banner("Installation", 2, HeadingStyle::Boxed, &theme, 9);
+        assert_eq!(narrow[1].to_string(), "│ Insta │");
+
+        assert!(heading_banner("Deep", 3, HeadingStyle::Boxed, &theme, 80).is_empty());
+        assert!(heading_banner("Guide", 1, HeadingStyle::Plain, &theme, 80).is_empty());
+    }
+
+    #[test]
+    fn test_details_collapsed_hides_nested_blocks() {
+        let highlighter = SyntaxHighlighter::new();
+        let theme = Theme::from_name(crate::tui::theme::ThemeName::OceanDark);
+        let nested = parse_content("Hidden text\n\n- one\n- two\n", 0);
+
+        let collapsed = render_details("More", &nested, false, false, &highlighter, &theme);
+        assert_eq!(collapsed.len(), 1);
+        assert!(collapsed[0].to_string().contains("▶ More"));
+
+        let expanded = render_details("More", &nested, true, false, &highlighter, &theme);
+        assert_eq!(expanded.len(), 4);
+        assert!(expanded[0].to_string().contains("▼ More"));
+        assert!(expanded[1].to_string().contains("Hidden text"));
+        assert!(expanded[3].to_string().contains("two"));
+    }
+
+    #[test]
+    fn test_content_text_area_padding_and_max_width() {
+        // 100-column terminal, content pane next to a 30% outline
+        let pane = Rect::new(30, 2, 70, 20);
+
+        assert_eq!(content_text_area(pane, 0, None), Rect::new(31, 3, 68, 18));
+        assert_eq!(content_text_area(pane, 2, None), Rect::new(33, 3, 64, 18));
+
+        // Narrower than the cap: the pane width wins
+        assert_eq!(
+            content_text_area(pane, 2, Some(80)),
+            Rect::new(33, 3, 64, 18)
+        );
+
+        // Ultra-wide terminal: the column is centered in the pane
+        let wide = Rect::new(0, 0, 300, 50);
+        assert_eq!(
+            content_text_area(wide, 0, Some(100)),
+            Rect::new(100, 1, 100, 48)
+        );
+        assert_eq!(
+            content_text_area(wide, 4, Some(100)),
+            Rect::new(100, 1, 100, 48)
+        );
+
+        // Padding never swallows the whole pane
+        let narrow = Rect::new(0, 0, 8, 10);
+        assert_eq!(content_text_area(narrow, 10, None).width, 2);
+        assert_eq!(content_text_area(Rect::new(0, 0, 2, 2), 3, None).width, 0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Display-width aware line wrapping.
+//!
+//! CJK characters and most emoji occupy two terminal cells, so wrapping and
+//! truncation are computed in display columns (via `unicode-width`) rather
+//! than `char` counts.
+
+use ratatui::text::{Line, Span};
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Wrap a single line to at most `max_width` display columns.
+///
+/// Breaks at spaces where possible; words wider than the line (including runs
+/// of CJK text, which has no spaces) are broken between characters. A
+/// double-width character is never split across rows.
+pub fn wrap_line(line: &str, max_width: usize) -> Vec<String> {
+    row_ranges(line, max_width)
+        .into_iter()
+        .map(|row| line[row].to_string())
+        .collect()
+}
+
+/// Wrap styled lines like [`wrap_line`], keeping each span's style.
+///
+/// The content pane is drawn from these rows, so they break by the same
+/// rules as [`wrapped_height`] counts with.
+pub fn wrap_lines<'a>(lines: Vec<Line<'a>>, max_width: usize) -> Vec<Line<'a>> {
+    let mut rows = Vec::with_capacity(lines.len());
+    for line in lines {
+        let text: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        let ranges = row_ranges(&text, max_width);
+        if ranges.len() == 1 {
+            rows.push(line);
+            continue;
+        }
+
+        for row in ranges {
+            let mut spans = Vec::new();
+            let mut start = 0;
+            for span in &line.spans {
+                let end = start + span.content.len();
+                let (from, to) = (row.start.max(start), row.end.min(end));
+                if from < to {
+                    spans.push(Span::styled(text[from..to].to_string(), span.style));
+                }
+                start = end;
+            }
+            rows.push(Line {
+                spans,
+                style: line.style,
+                alignment: line.alignment,
+            });
+        }
+    }
+    rows
+}
+
+/// Byte ranges of the rows [`wrap_line`] splits `line` into, without the
+/// spaces at the end of each row.
+fn row_ranges(line: &str, max_width: usize) -> Vec<Range<usize>> {
+    if max_width == 0 || line.width() <= max_width {
+        return std::iter::once(0..line.len()).collect();
+    }
+
+    let trimmed = |row: Range<usize>| row.start..row.start + line[row].trim_end_matches(' ').len();
+    let mut rows = Vec::new();
+    // The current row is `line[start..end]`
+    let mut start = 0;
+    let mut end = 0;
+    let mut width = 0;
+    let mut offset = 0;
+
+    for segment in line.split_inclusive(' ') {
+        let word_width = segment.trim_end_matches(' ').width();
+
+        if width + word_width <= max_width {
+            width += segment.width();
+            offset += segment.len();
+            end = offset;
+            continue;
+        }
+
+        // Doesn't fit: start a new row, then hard-break if still too wide
+        if end > start {
+            rows.push(trimmed(start..end));
+            start = offset;
+            width = 0;
+        }
+
+        for (i, ch) in segment.char_indices() {
+            let ch_width = ch.width().unwrap_or(0);
+            if width + ch_width > max_width && end > start {
+                rows.push(trimmed(start..end));
+                start = offset + i;
+                width = 0;
+            }
+            end = offset + i + ch.len_utf8();
+            width += ch_width;
+        }
+        offset += segment.len();
+    }
+
+    if end > start || rows.is_empty() {
+        rows.push(trimmed(start..end));
+    }
+
+    rows
+}
+
+/// Number of terminal rows `text` occupies when wrapped to `max_width` columns.
+pub fn wrapped_height(text: &str, max_width: usize) -> usize {
+    text.lines()
+        .map(|line| wrap_line(line, max_width).len())
+        .sum()
+}
+
+/// Truncate `text` to at most `max_width` display columns.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width > max_width {
+            break;
+        }
+        result.push(ch);
+        width += ch_width;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_mixed_ascii_and_full_width() {
+        // "ab世" is 4 columns wide, so the break falls before "界"
+        assert_eq!(wrap_line("ab世界cd", 4), vec!["ab世", "界cd"]);
+
+        // A double-width character that would straddle the edge moves down
+        assert_eq!(wrap_line("a世界", 2), vec!["a", "世", "界"]);
+    }
+
+    #[test]
+    fn test_wrap_emoji_and_words() {
+        assert_eq!(wrap_line("👍👍👍", 5), vec!["👍👍", "👍"]);
+        assert_eq!(
+            wrap_line("hello 世界 world", 8),
+            vec!["hello", "世界", "world"]
+        );
+        assert_eq!(wrap_line("short", 10), vec!["short"]);
+    }
+
+    #[test]
+    fn test_wrap_lines_keeps_styles() {
+        use ratatui::style::{Style, Stylize};
+
+        let bold = Style::new().bold();
+        let line = Line::from(vec![Span::raw("see "), Span::styled("世界 docs", bold)]);
+        let rows = wrap_lines(vec![line, Line::from("ok")], 6);
+
+        let texts: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        assert_eq!(texts, ["see", "世界", "docs", "ok"]);
+        assert_eq!(texts[..3], wrap_line("see 世界 docs", 6));
+        assert_eq!(rows[1].spans, [Span::styled("世界", bold)]);
+    }
+
+    #[test]
+    fn test_wrapped_height_and_truncation() {
+        assert_eq!(wrapped_height("日本語のテキスト\n\nascii", 6), 5);
+        assert_eq!(truncate_to_width("日本語", 5), "日本");
+        assert_eq!(truncate_to_width("abc", 5), "abc");
+    }
+}
@@ -0,0 +1,948 @@
+//! Integration tests for the treemd command-line interface.
+
+use std::io::Write;
+use std::process::{Command, Output};
+
+const SAMPLE: &str = "# Project\n\n## Installation\n\nRun it.\n\n## Usage\n\nUse it.\n";
+
+fn run(args: &[&str]) -> Output {
+    run_with(SAMPLE, args)
+}
+
+fn run_with(markdown: &str, args: &[&str]) -> Output {
+    let mut file = tempfile::Builder::new().suffix(".md").tempfile().unwrap();
+    file.write_all(markdown.as_bytes()).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .args(args)
+        .arg(file.path())
+        .output()
+        .expect("failed to run treemd")
+}
+
+#[test]
+fn fail_on_empty_exits_nonzero_for_missing_section() {
+    let output = run(&["-l", "--filter", "Security", "--fail-on-empty"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn fail_on_empty_exits_zero_when_headings_match() {
+    let output = run(&["-l", "--filter", "Usage", "--fail-on-empty"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "## Usage");
+}
+
+#[test]
+fn unknown_property_is_null_unless_strict() {
+    let output = run(&["-q", ".h1 | .levle"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+
+    // Strict mode keeps the error unknown properties used to raise
+    let output = run(&["-q", ".h1 | .levle", "--strict"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Property 'levle' not found on heading"),
+        "got: {}",
+        stderr
+    );
+    assert!(stderr.contains("did you mean 'level'?"), "got: {}", stderr);
+}
+
+#[test]
+fn filter_regex_matches_heading_text() {
+    let output = run(&["-l", "--filter-regex", "^(Inst|Us)"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "## Installation\n## Usage"
+    );
+
+    // Case-sensitive unless asked otherwise
+    let output = run(&["-l", "--filter-regex", "^usage$"]);
+    assert!(output.stdout.is_empty());
+    let output = run(&["-l", "--filter-regex", "(?i)^usage$"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "## Usage");
+}
+
+#[test]
+fn filter_regex_reports_invalid_pattern() {
+    let output = run(&["-l", "--filter-regex", "Inst("]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Invalid regex 'Inst('"), "got: {}", stderr);
+}
+
+#[test]
+fn count_unique_reports_duplicate_headings() {
+    let markdown = "# Guide\n\n## Usage\n\n## Install\n\n### Usage\n\n## usage!\n\n## Install\n";
+    let output = run_with(markdown, &["--count=unique"]);
+    assert_eq!(output.status.code(), Some(0));
+    // "usage!" is a new text but has the same slug as "Usage"
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Headings: 6\nDistinct texts: 4 (2 duplicates)\nDistinct slugs: 3 (3 duplicates)\n"
+    );
+
+    let output = run_with(markdown, &["--count=total"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "6\n");
+
+    // Without a mode, counts by level as before
+    let output = run_with(markdown, &["--count"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Heading counts:\n"), "got: {}", stdout);
+    assert!(stdout.ends_with("Total: 6\n"), "got: {}", stdout);
+}
+
+#[test]
+fn fail_on_empty_applies_to_queries() {
+    let output = run(&["-q", ".h2[Security]", "--fail-on-empty"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&["-q", ".h2[Security]"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn locate_prints_heading_position() {
+    let output = run(&["--locate", ".h2[Usage]"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim().ends_with(".md:7:1: Usage"), "got: {}", stdout);
+}
+
+#[test]
+fn locate_counts_columns_in_characters() {
+    let output = run_with(
+        "# Title\n\nÜber café: [docs](https://example.com)\n",
+        &["--locate", ".link"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim().ends_with(".md:3:12: docs"), "got: {}", stdout);
+}
+
+#[test]
+fn locate_prints_code_block_position() {
+    let output = run_with(
+        "# Title\n\n```sh\ncargo build\n```\n\n> ```rust\n> fn main() {}\n> ```\n",
+        &["--locate", ".code"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "got: {}", stdout);
+    assert!(
+        lines[0].ends_with(".md:3:1: cargo build"),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        lines[1].ends_with(".md:7:1: fn main() {}"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn locate_prints_footnote_position() {
+    let output = run_with(
+        "# Title\n\nSee the note.[^1]\n\n[^1]: The note.\n",
+        &["--locate", ".footnote"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.trim().ends_with(".md:5:1: The note."),
+        "got: {}",
+        stdout
+    );
+}
+
+const GREP_SAMPLE: &str = "# Guide\n\nIntro text.\n\n## Setup\n\nThe old flag is Deprecated.\n\n## Usage\n\nRun the tool.\n";
+
+#[test]
+fn grep_matches_section_content() {
+    let output = run_with(GREP_SAMPLE, &["--grep", "deprecated"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Setup\n  7: The old flag is Deprecated.\n"
+    );
+}
+
+#[test]
+fn grep_regex_excludes_non_matching_sections() {
+    let output = run_with(GREP_SAMPLE, &["--grep-regex", "^(Intro|Run) "]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("# Guide\n  3: Intro text."));
+    assert!(stdout.contains("## Usage\n  11: Run the tool."));
+    assert!(!stdout.contains("Setup"));
+
+    let output = run_with(GREP_SAMPLE, &["--grep", "nowhere", "--fail-on-empty"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+const CONTEXT_SAMPLE: &str =
+    "# Guide\n\nIntro.\n\n## Setup\nfirst match\nb\nc\nd\nlast match\n## Usage\n\nRun.\n";
+
+#[test]
+fn grep_context_stays_inside_the_section() {
+    // Near the start: the window stops at the line after the heading
+    let output = run_with(CONTEXT_SAMPLE, &["--grep", "first", "-C", "2"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Setup\n  6: first match\n  7- b\n  8- c\n"
+    );
+
+    // Near the end: the window stops before the next heading
+    let output = run_with(CONTEXT_SAMPLE, &["--grep", "last", "--context", "2"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Setup\n  8- c\n  9- d\n  10: last match\n"
+    );
+
+    // Separate windows are split by `--`, overlapping ones merge
+    let output = run_with(CONTEXT_SAMPLE, &["--grep", "match", "-C", "1"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Setup\n  6: first match\n  7- b\n  --\n  9- d\n  10: last match\n"
+    );
+    let output = run_with(CONTEXT_SAMPLE, &["--grep", "match", "-C", "2"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("--"));
+}
+
+#[test]
+fn locate_context_prints_surrounding_lines() {
+    let output = run_with(CONTEXT_SAMPLE, &["--locate", ".h2", "-C", "1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout
+        .lines()
+        .map(|line| line.rsplit(".md").next().unwrap())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "-4- ",
+            ":5:1: Setup",
+            "-6- first match",
+            "--",
+            "-10- last match",
+            ":11:1: Usage",
+            "-12- "
+        ]
+    );
+
+    let output = run(&["-C", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn max_output_truncates_query_results() {
+    let output = run(&["-q", ".h | text", "--max-output", "2"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Project\nInstallation\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 more"));
+}
+
+const PAGED_SAMPLE: &str = "# A\n\n## B\n\n## C\n\n## D\n\n## E\n";
+
+#[test]
+fn page_prints_one_page_with_footer() {
+    let output = run_with(
+        PAGED_SAMPLE,
+        &["-q", ".h | text", "--page", "1", "--page-size", "2"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "A\nB\nPage 1/3\n");
+
+    // The last page is partial
+    let output = run_with(
+        PAGED_SAMPLE,
+        &["-q", ".h | text", "--page", "3", "--page-size", "2"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "E\nPage 3/3\n");
+
+    // An exact multiple has no empty trailing page
+    let output = run_with(
+        PAGED_SAMPLE,
+        &["-q", ".h2 | text", "--page", "2", "--page-size", "2"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "D\nE\nPage 2/2\n");
+}
+
+#[test]
+fn page_past_the_end_is_an_error() {
+    let output = run_with(
+        PAGED_SAMPLE,
+        &["-q", ".h | text", "--page", "4", "--page-size", "2"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("out of range (3 pages)"));
+}
+
+#[test]
+fn page_footer_goes_to_stderr_for_json() {
+    // --page-size defaults to 10
+    let output = run_with(
+        PAGED_SAMPLE,
+        &["-q", ".h | text", "--page", "1", "--query-output", "jsonl"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\"A\"\n\"B\"\n\"C\"\n\"D\"\n\"E\"\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "Page 1/1");
+}
+
+#[test]
+fn flat_splits_array_results() {
+    let output = run(&["-q", "[.h2 | text]", "--query-output", "jsonl"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        r#"["Installation","Usage"]"#
+    );
+
+    let output = run(&["-q", "[.h2 | text]", "--flat", "--query-output", "jsonl"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "\"Installation\"\n\"Usage\""
+    );
+}
+
+#[test]
+fn tree_style_ascii_uses_plain_connectors() {
+    let output = run(&["--tree", "--tree-style", "ascii"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "`-- # Project\n    |-- ## Installation\n    `-- ## Usage\n"
+    );
+}
+
+#[test]
+fn diff_reports_structural_changes() {
+    let mut old = tempfile::Builder::new().suffix(".md").tempfile().unwrap();
+    old.write_all(b"# Project\n\n## Instalation\n\n## Legacy\n")
+        .unwrap();
+
+    let output = run(&["--diff", old.path().to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("- ## Legacy"));
+    assert!(stdout.contains("+ ## Usage"));
+    assert!(stdout.contains("~ ## Instalation → ## Installation"));
+    assert!(stdout.contains("1 added, 1 removed, 1 renamed"));
+}
+
+#[test]
+fn section_matching_prints_section_containing_pattern() {
+    let output = run(&["--section-matching", "use IT"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Usage\n\nUse it.\n"
+    );
+
+    let output = run(&["--section-matching", "nowhere"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&["-s", "Usage", "--section-matching", "Run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn multiple_files_list_in_argument_order() {
+    let mut first = tempfile::Builder::new().suffix(".md").tempfile().unwrap();
+    first.write_all(b"# Zebra\n\n## Stripes\n").unwrap();
+    let mut second = tempfile::Builder::new().suffix(".md").tempfile().unwrap();
+    second.write_all(b"# Aardvark\n").unwrap();
+
+    let first_path = first.path().to_str().unwrap();
+    let second_path = second.path().to_str().unwrap();
+    let expected = format!(
+        "{0}:# Zebra\n{0}:## Stripes\n{1}:# Aardvark\n",
+        first_path, second_path
+    );
+
+    for extra in [None, Some("--parallel")] {
+        let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+            .arg("-l")
+            .args(extra)
+            .args([first_path, second_path])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .args(["-l", "-o", "json", second_path, first_path])
+        .output()
+        .unwrap();
+    // Keys follow argument order (not sorted)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let key = |path: &str| stdout.find(&format!("\"{}\":", path)).unwrap();
+    assert!(key(second_path) < key(first_path));
+}
+
+#[test]
+fn number_prefixes_section_numbers() {
+    let output = run(&["-l", "--number"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# 1 Project\n## 1.1 Installation\n## 1.2 Usage\n"
+    );
+
+    let output = run(&["--tree", "--number", "--tree-style", "ascii"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "`-- # 1 Project\n    |-- ## 1.1 Installation\n    `-- ## 1.2 Usage\n"
+    );
+
+    let output = run(&["-l", "--number", "--raw0"]);
+    assert_eq!(output.stdout, b"1 Project\x001.1 Installation\x001.2 Usage");
+
+    // Numbers are only added to the printed text
+    let output = run(&["--tree", "--number", "-o", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[1]["text"], "Installation");
+}
+
+#[test]
+fn no_content_omits_section_content() {
+    let output = run(&["-l", "-o", "json", "--no-content"]);
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let project = &json["document"]["sections"][0];
+    assert_eq!(project["title"], "Project");
+    assert!(project.get("content").is_none());
+    assert_eq!(project["children"][1]["title"], "Usage");
+    assert!(project["children"][1].get("content").is_none());
+
+    let output = run(&["-l", "-o", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json["document"]["sections"][0]["children"][0]["content"]["raw"],
+        "Run it."
+    );
+}
+
+#[test]
+fn from_json_rebuilds_document() {
+    let json = run(&["-l", "-o", "json"]);
+    let json = String::from_utf8(json.stdout).unwrap();
+
+    let output = run_with(&json, &["--from-json", "--tree", "--tree-style", "ascii"]);
+    assert!(output.status.success());
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        tree.contains("Installation") && tree.contains("Usage"),
+        "got: {}",
+        tree
+    );
+
+    // Without another mode, piped output is the rebuilt markdown
+    let output = run_with(&json, &["--from-json"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), SAMPLE);
+
+    let output = run_with("# Not JSON\n", &["--from-json"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn raw0_separates_values_with_nul() {
+    let output = run(&["-l", "--raw0"]);
+    assert_eq!(output.stdout, b"Project\0Installation\0Usage");
+
+    let output = run(&["-q", ".h2", "--raw0"]);
+    assert_eq!(output.stdout, b"Installation\0Usage");
+
+    let output = run_with("# Two words\n\n## Three more words\n", &["-l", "--raw0"]);
+    assert_eq!(output.stdout, b"Two words\0Three more words");
+}
+
+const ELEMENTS: &str = "# Doc\n\nSee [the site](https://example.com) and [install](#install).\n\n```rust\nfn main() {}\n```\n\n## Install\n\n![Logo](logo.png)\n\n[Guide](guide.md)\n";
+
+#[test]
+fn code_lists_code_blocks_with_line_ranges() {
+    let output = run_with(ELEMENTS, &["--code"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5-7: rust\n");
+
+    let output = run_with(ELEMENTS, &["--code", "-o", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["language"], "rust");
+    assert_eq!(json[0]["start_line"], 5);
+}
+
+#[test]
+fn links_lists_links_with_type() {
+    let output = run_with(ELEMENTS, &["--links"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "3: https://example.com (external) the site\n\
+         3: #install (anchor) install\n\
+         13: guide.md (relative) Guide\n"
+    );
+
+    let output = run_with(ELEMENTS, &["--links-external-only", "-o", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["url"], "https://example.com");
+}
+
+#[test]
+fn images_lists_images() {
+    let output = run_with(ELEMENTS, &["--images"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "11: logo.png Logo\n"
+    );
+}
+
+#[test]
+fn check_links_reports_broken_links() {
+    let markdown =
+        "# Doc\n\n## Setup\n\n[ok](#setup) [gone](#nowhere) [missing](missing-file.md)\n";
+    let output = run_with(markdown, &["--check-links"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "5: ok #setup [anchor]\n\
+         5: BROKEN (no heading with this anchor) #nowhere [anchor]\n\
+         5: BROKEN (file not found) missing-file.md [relative]\n\
+         \n\
+         3 links, 2 broken\n"
+    );
+
+    let output = run_with(
+        "# Doc\n\n[top](#doc) [web](https://example.com)\n",
+        &["--check-links"],
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn check_duplicates_reports_shared_slugs() {
+    let markdown = "# Doc\n\n## Setup\n\nText\n\n## Usage\n\n### Setup\n";
+    let output = run_with(markdown, &["--check-duplicates"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "#setup: lines 3, 9\n\n1 duplicate slug\n"
+    );
+
+    let output = run_with("# Doc\n\n## Setup\n", &["--check-duplicates"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No duplicate headings\n"
+    );
+}
+
+#[test]
+fn theme_preview_prints_every_theme() {
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .args(["--theme-preview", "--color-mode", "256"])
+        .env_remove("NO_COLOR")
+        .output()
+        .expect("failed to run treemd");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for name in ["Ocean Dark", "Nord", "Dracula", "Catppuccin Mocha"] {
+        assert!(stdout.contains(name), "{} missing", name);
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .args(["--theme-preview", "--no-color"])
+        .output()
+        .expect("failed to run treemd");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn error_format_json_reports_unknown_function() {
+    let output = run(&["-q", ".h | lenght", "--error-format", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("stderr should be one JSON object");
+    assert_eq!(error["kind"], "unknown_function");
+    assert_eq!(error["message"], "Unknown function 'lenght'");
+    assert_eq!(error["span"], serde_json::json!({"start": 5, "end": 11}));
+    assert_eq!(error["suggestions"], serde_json::json!(["length"]));
+    assert!(error["help"].is_null());
+
+    // The readable diagnostic stays the default
+    let output = run(&["-q", ".h | lenght"]);
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+}
+
+#[test]
+fn shift_headings_rewrites_levels_and_warns_on_clamp() {
+    let output = run(&["--shift-headings", "1"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Project\n\n### Installation\n\nRun it.\n\n### Usage\n\nUse it.\n"
+    );
+    assert!(output.stderr.is_empty());
+
+    let output = run(&["--shift-headings", "-1"]);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("# Project\n\n# Installation\n"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("clamped 1 heading "), "got: {}", stderr);
+}
+
+#[test]
+fn shift_headings_conflicts_with_other_modes() {
+    for mode in ["--list", "--tree", "--interactive-query=.h"] {
+        let output = run(&["--shift-headings", "1", mode]);
+        assert!(!output.status.success(), "{} was accepted", mode);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+    }
+}
+
+#[test]
+fn with_content_previews_section_text() {
+    let markdown = "# Project\n\nA **tool** for viewing [markdown](https://example.com) files.\n\n## Usage\n\nUse it.\n";
+    let output = run_with(markdown, &["-l", "--with-content=20"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Project\n    A tool for viewing m…\n## Usage\n    Use it.\n"
+    );
+
+    let output = run_with(markdown, &["-l", "--with-content"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("    A tool for viewing markdown files.\n"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn template_fills_placeholders() {
+    let output = run(&[
+        "--template",
+        "{{ .h1 | text }}: {{ [.h2] | count }} sections",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Project: 2 sections\n"
+    );
+
+    let mut template = tempfile::NamedTempFile::new().unwrap();
+    template
+        .write_all(b"Last: {{ [.h2] | last | text }}\n")
+        .unwrap();
+    let arg = format!("@{}", template.path().display());
+    let output = run(&["--template", &arg]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Last: Usage\n");
+
+    let output = run(&["--template", "{{ .h2 | nope }}"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("{{ .h2 | nope }}"), "got: {}", stderr);
+}
+
+#[test]
+fn sort_orders_listed_headings() {
+    let markdown = "# Glossary\n\n## beta\n\n### Zeta function\n\n## Alpha\n\n### ab\n";
+    let list = |args: &[&str]| {
+        let output = run_with(markdown, args);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        list(&["-l", "--sort", "text"]),
+        "### ab\n## Alpha\n## beta\n# Glossary\n### Zeta function\n"
+    );
+    assert_eq!(
+        list(&["-l", "--sort", "level"]),
+        "# Glossary\n## beta\n## Alpha\n### Zeta function\n### ab\n"
+    );
+    assert_eq!(
+        list(&["-l", "--sort", "length"]),
+        "### ab\n## beta\n## Alpha\n# Glossary\n### Zeta function\n"
+    );
+    assert_eq!(
+        list(&["-l", "--sort", "text", "--reverse"]),
+        "### Zeta function\n# Glossary\n## beta\n## Alpha\n### ab\n"
+    );
+    // Without --sort, --reverse flips document order
+    assert_eq!(list(&["-l", "-L", "2", "--reverse"]), "## Alpha\n## beta\n");
+}
+
+#[test]
+fn missing_file_reports_not_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nope.md");
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .arg("-l")
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr).trim(),
+        format!("Error: {}: no such file", path.display())
+    );
+}
+
+#[test]
+fn directory_argument_suggests_a_file_inside_it() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("README.md"), SAMPLE).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .arg("-l")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("is a directory; did you mean"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.trim_end().ends_with("README.md?"), "{}", stderr);
+}
+
+fn run_stdin(input: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to run treemd");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn raw_input_queries_each_line() {
+    let output = run_stdin("alpha\nbeta\ngamma\n", &["-R", "-q", ". | upper"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ALPHA\nBETA\nGAMMA\n"
+    );
+
+    // Lines that look like markdown stay plain strings
+    let output = run_stdin("# not a heading\n", &["--raw-input", "-q", "length"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "15");
+}
+
+#[test]
+fn raw_input_slurp_collects_lines() {
+    let output = run_stdin("alpha\nbeta\ngamma\n", &["-R", "--slurp", "-q", "count"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3");
+}
+
+#[test]
+fn tasks_reports_progress_per_section() {
+    let markdown = "# Plan\n\n- [x] one\n- [ ] two\n\n## Setup\n\n- [x] a\n- [x] b\n- [ ] c\n\n## Notes\n\nNo tasks here.\n";
+    let output = run_with(markdown, &["--tasks"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Plan  [##########----------] 1/2 (50%)\n\
+         ## Setup  [#############-------] 2/3 (67%)\n\
+         \n\
+         Total: [############--------] 3/5 (60%)\n"
+    );
+}
+
+#[test]
+fn frontmatter_prints_yaml_as_json() {
+    let markdown =
+        "---\ntitle: Release notes\ndraft: false\ntags: [news, rust]\nweight: 3\n---\n\n# Notes\n";
+    let output = run_with(markdown, &["--frontmatter", "-o", "json"]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "title": "Release notes",
+            "draft": false,
+            "tags": ["news", "rust"],
+            "weight": 3
+        })
+    );
+
+    // Plain output is the block as written
+    let output = run_with(markdown, &["--frontmatter"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "title: Release notes\ndraft: false\ntags: [news, rust]\nweight: 3\n"
+    );
+}
+
+#[test]
+fn frontmatter_without_front_matter_is_empty() {
+    let output = run(&["--frontmatter", "-o", "json"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "{}");
+
+    let output = run(&["--frontmatter"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+const MESSY_SECTION: &str = "# Intro\n\nHi.\n\n# Guide\nSome _emphasis_   here.\n\n## Steps\n* one\n* two\n\n```sh\nmake\n```\n\n# Appendix\n";
+
+const SETEXT: &str = "Title\n=====\n\nIntro.\n\nSetup\n-----\n\nRun it.\n\n## Usage\n\nUse it.\n";
+
+#[test]
+fn setext_sections_extract_and_list() {
+    let output = run_with(SETEXT, &["-s", "Setup"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Setup\n-----\n\nRun it.\n"
+    );
+
+    let output = run_with(SETEXT, &["-s", "Title"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Title\n=====\n\nIntro."));
+    assert!(stdout.ends_with("Use it.\n"));
+
+    let output = run_with(SETEXT, &["-s", "Setup", "-o", "markdown"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## Setup\n\nRun it.\n"
+    );
+
+    // The outline shows setext headings with `#` prefixes
+    let output = run_with(SETEXT, &["-l"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Title\n## Setup\n## Usage\n"
+    );
+}
+
+#[test]
+fn section_markdown_output_round_trips() {
+    let output = run_with(MESSY_SECTION, &["-s", "Guide", "-o", "markdown"]);
+    assert_eq!(output.status.code(), Some(0));
+    let markdown = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert_eq!(
+        markdown,
+        "# Guide\n\nSome *emphasis*   here.\n\n## Steps\n\n- one\n- two\n\n```sh\nmake\n```\n"
+    );
+
+    // Re-serializing the output again changes nothing
+    let again = run_with(&markdown, &["-s", "Guide", "-o", "markdown"]);
+    assert_eq!(String::from_utf8_lossy(&again.stdout), markdown);
+
+    // And it has the same structure as the original section
+    let structure = |md: &str| {
+        let query = "[.h | text], [.list | text], [.code | lang], [.p | text]";
+        run_with(md, &["-q", query, "--query-output", "jsonl"]).stdout
+    };
+    let section = run_with(MESSY_SECTION, &["-s", "Guide"]).stdout;
+    assert_eq!(
+        structure(&markdown),
+        structure(&String::from_utf8_lossy(&section))
+    );
+}
+
+#[test]
+fn list_markdown_output_is_heading_lines() {
+    let output = run(&["-l", "-o", "markdown"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Project\n## Installation\n## Usage\n"
+    );
+}
+
+#[test]
+fn list_slugs_number_duplicates_in_order() {
+    let md = "# Guide\n\n## Usage\n\n### Usage\n\n## Usage\n\n## Setup Notes!\n";
+    let output = run_with(md, &["-l", "--slugs"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "guide\nusage\nusage-1\nusage-2\nsetup-notes\n"
+    );
+
+    // Filtering keeps each heading's slug from the whole document
+    let output = run_with(md, &["-l", "-L", "2", "--slugs=anchor"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "#usage\n#usage-2\n#setup-notes\n"
+    );
+
+    let output = run_with(md, &["--slugs"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn schema_describes_block_variants() {
+    let output = Command::new(env!("CARGO_BIN_EXE_treemd"))
+        .arg("--schema")
+        .output()
+        .expect("failed to run treemd");
+    assert_eq!(output.status.code(), Some(0));
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let defs = &schema["$defs"];
+
+    // Each block type is a oneOf variant tagged by a constant `type`
+    let blocks = defs["Block"]["oneOf"].as_array().unwrap();
+    let types: Vec<&str> = blocks
+        .iter()
+        .map(|b| b["properties"]["type"]["const"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "paragraph",
+            "code",
+            "list",
+            "blockquote",
+            "table",
+            "image",
+            "horizontal_rule",
+            "html",
+            "details",
+            "footnote"
+        ]
+    );
+    let code = &blocks[1]["required"];
+    for field in ["type", "content", "startLine", "endLine"] {
+        assert!(
+            code.as_array().unwrap().iter().any(|f| f == field),
+            "{}",
+            field
+        );
+    }
+
+    assert_eq!(schema["required"], serde_json::json!(["document"]));
+    let section = defs["Section"]["required"].as_array().unwrap();
+    assert!(section.iter().any(|f| f == "children"));
+    assert_eq!(
+        defs["Alignment"]["enum"],
+        serde_json::json!(["left", "center", "right", "none"])
+    );
+}